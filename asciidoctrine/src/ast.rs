@@ -1,12 +1,16 @@
-use std::{fmt::Debug, marker::PhantomPinned, pin::Pin, ptr::NonNull};
+use std::{borrow::Cow, fmt::Debug};
 use serde::{Deserialize, Deserializer, Serialize};
 use crate::util::Env;
 
 #[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
 pub struct AST<'a> {
-  pub content: &'a str,
+  #[serde(borrow)]
+  pub content: Cow<'a, str>,
   pub elements: Vec<ElementSpan<'a>>,
   pub attributes: Vec<Attribute<'a>>,
+  /// The metadata from the header of the document
+  #[serde(default)]
+  pub header: DocumentHeader,
 }
 
 impl AST<'_> {
@@ -22,18 +26,63 @@ impl AST<'_> {
 
     None
   }
+
+  /// Copies the borrowed text so the AST no longer depends on its
+  /// input
+  pub fn into_owned(self) -> AST<'static> {
+    AST {
+      content: Cow::Owned(self.content.into_owned()),
+      elements: self
+        .elements
+        .into_iter()
+        .map(ElementSpan::into_owned)
+        .collect(),
+      attributes: self
+        .attributes
+        .into_iter()
+        .map(Attribute::into_owned)
+        .collect(),
+      header: self.header,
+    }
+  }
 }
 
 impl Default for AST<'_> {
   fn default() -> Self {
     Self {
-      content: "",
+      content: Cow::Borrowed(""),
       elements: vec![],
       attributes: vec![],
+      header: DocumentHeader::default(),
     }
   }
 }
+/// The metadata of a document
+#[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize)]
+pub struct DocumentHeader {
+  /// The title of the document (`doctitle`)
+  pub title: Option<String>,
+  pub authors: Vec<Author>,
+  pub revision: Option<Revision>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize)]
+pub struct Author {
+  /// The full name as written in the document
+  pub name: String,
+  pub firstname: String,
+  pub middlename: Option<String>,
+  pub lastname: Option<String>,
+  pub initials: String,
+  pub email: Option<String>,
+}
 
+#[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize)]
+pub struct Revision {
+  pub number: Option<String>,
+  pub date: Option<String>,
+  pub remark: Option<String>,
+}
 /// The basic element of a document
 ///
 /// This is meant to form a tree of document element.
@@ -45,8 +94,10 @@ pub struct ElementSpan<'a> {
   // e.g. it's the same as the source of it's
   // parent
   pub source: Option<String>,
-  // A string reference to the source
-  pub content: &'a str,
+  // A string reference to the source or the text
+  // of a generated element
+  #[serde(borrow)]
+  pub content: Cow<'a, str>,
   // TODO Add start and end point
   pub start: usize,
   pub end: usize,
@@ -81,6 +132,36 @@ impl<'a> ElementSpan<'a> {
     None
   }
 
+  /// Copies the borrowed text of the element and its children
+  pub fn into_owned(self) -> ElementSpan<'static> {
+    ElementSpan {
+      source: self.source,
+      content: Cow::Owned(self.content.into_owned()),
+      start: self.start,
+      end: self.end,
+      start_line: self.start_line,
+      start_col: self.start_col,
+      end_line: self.end_line,
+      end_col: self.end_col,
+      element: self.element.into_owned(),
+      children: self
+        .children
+        .into_iter()
+        .map(ElementSpan::into_owned)
+        .collect(),
+      positional_attributes: self
+        .positional_attributes
+        .into_iter()
+        .map(AttributeValue::into_owned)
+        .collect(),
+      attributes: self
+        .attributes
+        .into_iter()
+        .map(Attribute::into_owned)
+        .collect(),
+    }
+  }
+
   pub fn add_offset(self, other: &ElementSpan<'_>) -> Self {
     let mut base = self;
 
@@ -138,6 +219,12 @@ pub enum Element<'a> {
   Title {
     level: u32,
   },
+  /// A section with its title as first child and the blocks
+  /// up to the next title of the same or a higher level. Only
+  /// built by the `SectionTree` extension
+  Section {
+    level: u32,
+  },
   Table,
   List(ListType),
   Image,
@@ -156,6 +243,8 @@ pub enum Element<'a> {
   /// Holds a reference to the include statement
   /// and a document inside
   IncludeElement(IncludeElement<'a>),
+  /// The place of the table of contents (`toc::[]`)
+  TableOfContents,
 
   /// The following variants are inline elements nested
   /// inside a conainer element
@@ -170,7 +259,28 @@ pub enum Element<'a> {
   Link,
   /// An inline footnote
   Footnote,
-  /// A list item
+  /// An image inside of a text
+  InlineImage,
+  /// A keyboard shortcut. The keys are the positional attributes
+  Keyboard,
+  /// A button of a user interface
+  Button,
+  /// A menu selection. The items are the positional attributes
+  Menu,
+  /// Text that goes to the output without any substitutions
+  InlinePassthrough,
+  /// A formula in the notation given by the `notation` attribute
+  Stem,
+  /// A term for the index. The positional attributes are the
+  /// primary, secondary and tertiary term
+  IndexTerm,
+  /// A hard line break inside of a text
+  LineBreak,
+  /// A callout marker at the end of a listing line with its
+  /// number. The line inside the listing is an attribute
+  Callout(u32),
+  /// A list item with the nesting level of its list. The term of
+  /// a description list and the state of a checkbox are attributes
   ListItem(u32),
   /// A table row
   TableRow,
@@ -180,10 +290,54 @@ pub enum Element<'a> {
   Error(String),
 }
 
+impl Element<'_> {
+  pub fn into_owned(self) -> Element<'static> {
+    match self {
+      Element::Attribute(attribute) => Element::Attribute(attribute.into_owned()),
+      Element::Comment => Element::Comment,
+      Element::Paragraph => Element::Paragraph,
+      Element::Title { level } => Element::Title { level },
+      Element::Section { level } => Element::Section { level },
+      Element::Table => Element::Table,
+      Element::List(kind) => Element::List(kind),
+      Element::Image => Element::Image,
+      Element::Anchor => Element::Anchor,
+      Element::TypedBlock { kind } => Element::TypedBlock { kind },
+      Element::ExternalContent => Element::ExternalContent,
+      Element::IncludeElement(include) => Element::IncludeElement(include.into_owned()),
+      Element::TableOfContents => Element::TableOfContents,
+      Element::Styled => Element::Styled,
+      Element::Text => Element::Text,
+      Element::XRef => Element::XRef,
+      Element::Link => Element::Link,
+      Element::Footnote => Element::Footnote,
+      Element::InlineImage => Element::InlineImage,
+      Element::Keyboard => Element::Keyboard,
+      Element::Button => Element::Button,
+      Element::Menu => Element::Menu,
+      Element::InlinePassthrough => Element::InlinePassthrough,
+      Element::Stem => Element::Stem,
+      Element::IndexTerm => Element::IndexTerm,
+      Element::LineBreak => Element::LineBreak,
+      Element::Callout(number) => Element::Callout(number),
+      Element::ListItem(number) => Element::ListItem(number),
+      Element::TableRow => Element::TableRow,
+      Element::TableCell => Element::TableCell,
+      Element::Error(msg) => Element::Error(msg),
+    }
+  }
+}
+
 #[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
 pub enum ListType {
   Bullet,
   Number,
+  /// A bullet list with checkboxes
+  Checklist,
+  /// A list of terms and their descriptions
+  Description,
+  /// The explanations of the callouts in the preceding listing
+  Callout,
 }
 
 #[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
@@ -194,10 +348,57 @@ pub enum BlockType {
   Literal,
   Sidebar,
   Quote,
+  Verse,
   Example,
+  Open,
+  Admonition(AdmonitionType),
 }
 
 #[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub enum AdmonitionType {
+  Note,
+  Tip,
+  Important,
+  Warning,
+  Caution,
+}
+
+impl AdmonitionType {
+  pub fn from_keyword(keyword: &str) -> Option<Self> {
+    match keyword {
+      "NOTE" => Some(AdmonitionType::Note),
+      "TIP" => Some(AdmonitionType::Tip),
+      "IMPORTANT" => Some(AdmonitionType::Important),
+      "WARNING" => Some(AdmonitionType::Warning),
+      "CAUTION" => Some(AdmonitionType::Caution),
+      _ => None,
+    }
+  }
+
+  /// The keyword used in the asciidoc source (e.g. `NOTE`)
+  pub fn keyword(&self) -> &'static str {
+    match self {
+      AdmonitionType::Note => "NOTE",
+      AdmonitionType::Tip => "TIP",
+      AdmonitionType::Important => "IMPORTANT",
+      AdmonitionType::Warning => "WARNING",
+      AdmonitionType::Caution => "CAUTION",
+    }
+  }
+
+  /// The caption shown to the reader (e.g. `Note`)
+  pub fn caption(&self) -> &'static str {
+    match self {
+      AdmonitionType::Note => "Note",
+      AdmonitionType::Tip => "Tip",
+      AdmonitionType::Important => "Important",
+      AdmonitionType::Warning => "Warning",
+      AdmonitionType::Caution => "Caution",
+    }
+  }
+}
+
+#[derive(Debug, Clone, Serialize)]
 pub enum AttributeValue<'a> {
   String(String),
   Ref(&'a str),
@@ -210,59 +411,71 @@ impl AttributeValue<'_> {
       AttributeValue::String(value) => value.as_str(),
     }
   }
-}
 
-#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
-pub struct Attribute<'a> {
-  pub key: String,
-  #[serde(borrow)]
-  pub value: AttributeValue<'a>,
+  pub fn into_owned(self) -> AttributeValue<'static> {
+    match self {
+      AttributeValue::Ref(value) => AttributeValue::String(value.to_string()),
+      AttributeValue::String(value) => AttributeValue::String(value),
+    }
+  }
 }
 
-#[derive(Clone)]
-struct UnmovableString {
-  pub content: String,
-  pub content_ref: NonNull<str>,
-  _pin: PhantomPinned,
+impl<'a> From<Cow<'a, str>> for AttributeValue<'a> {
+  fn from(value: Cow<'a, str>) -> Self {
+    match value {
+      Cow::Borrowed(value) => AttributeValue::Ref(value),
+      Cow::Owned(value) => AttributeValue::String(value),
+    }
+  }
 }
 
-impl PartialEq for UnmovableString {
+// An owned copy of a value is the same value
+impl PartialEq for AttributeValue<'_> {
   fn eq(&self, other: &Self) -> bool {
-    self.content == other.content
+    self.as_str() == other.as_str()
   }
 }
 
-impl Debug for UnmovableString {
-  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-    write!(f, "UnmovableString(\"{}\")", self.content)
+impl<'de: 'a, 'a> Deserialize<'de> for AttributeValue<'a> {
+  fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+  where
+    D: Deserializer<'de>,
+  {
+    #[derive(Deserialize)]
+    enum Value<'a> {
+      String(String),
+      Ref(#[serde(borrow)] Cow<'a, str>),
+    }
+
+    // Strings with escapes can't be borrowed from the input
+    Ok(match Value::deserialize(deserializer)? {
+      Value::String(value) => AttributeValue::String(value),
+      Value::Ref(value) => value.into(),
+    })
   }
 }
 
-impl UnmovableString {
-  fn new(content: String) -> Pin<Box<Self>> {
-    let res = Self {
-      content,
-      content_ref: "".into(),
-      _pin: PhantomPinned,
-    };
-    let mut boxed = Box::new(res);
-    boxed.content_ref = NonNull::from(boxed.content.as_str());
-    let pin = Box::into_pin(boxed);
-    pin
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct Attribute<'a> {
+  pub key: String,
+  #[serde(borrow)]
+  pub value: AttributeValue<'a>,
+}
+
+impl Attribute<'_> {
+  pub fn into_owned(self) -> Attribute<'static> {
+    Attribute {
+      key: self.key,
+      value: self.value.into_owned(),
+    }
   }
 }
 
 #[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
 pub struct IncludeElement<'a> {
-  #[serde(
-    serialize_with = "serialize_unmovable_string",
-    deserialize_with = "deserialize_unmovable_string"
-  )]
-  content: Pin<Box<UnmovableString>>,
+  content: String,
   #[serde(borrow)]
   pub inner: AST<'a>,
-  #[serde(skip)]
-  _pin: PhantomPinned,
 }
 
 impl IncludeElement<'_> {
@@ -271,42 +484,21 @@ impl IncludeElement<'_> {
     env: &mut Env,
     parser: &dyn for<'a> Fn(&'a str, &mut Env) -> Result<AST<'a>, String>,
   ) -> Result<Self, String> {
-    let content = UnmovableString::new(content);
-    let content_ref = unsafe { content.content_ref.as_ref() };
-    let inner_ast = parser(content_ref, env)?;
-
-    Ok(IncludeElement {
-      content,
-      inner: inner_ast,
-      _pin: PhantomPinned,
-    })
+    // The included text only lives as long as this element. So the
+    // inner AST gets its own copy of it
+    let inner = parser(&content, env)?.into_owned();
+
+    Ok(IncludeElement { content, inner })
   }
 
   pub fn from_data(content: String, inner: AST<'static>) -> Self {
+    IncludeElement { content, inner }
+  }
+
+  pub fn into_owned(self) -> IncludeElement<'static> {
     IncludeElement {
-      content: UnmovableString::new(content),
-      inner,
-      _pin: PhantomPinned,
+      content: self.content,
+      inner: self.inner.into_owned(),
     }
   }
 }
-
-fn serialize_unmovable_string<S>(
-  string: &Pin<Box<UnmovableString>>,
-  serializer: S,
-) -> Result<S::Ok, S::Error>
-where
-  S: serde::Serializer,
-{
-  serializer.serialize_str(&string.content)
-}
-
-fn deserialize_unmovable_string<'de, D>(
-  deserializer: D,
-) -> Result<Pin<Box<UnmovableString>>, D::Error>
-where
-  D: Deserializer<'de>,
-{
-  let string = String::deserialize(deserializer)?;
-  Ok(UnmovableString::new(string))
-}
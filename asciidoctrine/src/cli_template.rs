@@ -38,6 +38,16 @@ pub fn cli_template(
   };
   let ast = reader.parse(&input, &opts, &mut env)?;
 
+  // The extensions built into asciidoctrine. All others are
+  // left to `handle_extensions`
+  let ast = opts
+    .extensions
+    .iter()
+    .try_fold(ast, |ast, extension| match extension.as_str() {
+      "sections" => sections::SectionTree::new().transform(ast),
+      _ => Ok(ast),
+    })?;
+
   let ast = handle_extensions(&opts, &mut env, ast)?;
 
   let output: Box<dyn Write> = if opts.dry_run {
@@ -51,8 +61,20 @@ pub fn cli_template(
 
   match opts.writerfmt {
     options::Writer::Html5 => HtmlWriter::new().write(ast, &opts, output)?,
+    options::Writer::Docbook => DocbookWriter::new().write(ast, &opts, output)?,
     options::Writer::Json => JsonWriter::new().write(ast, &opts, output)?,
     options::Writer::Asciidoc => AsciidocWriter::new().write(ast, &opts, output)?,
+    options::Writer::Markdown => MarkdownWriter::new().write(ast, &opts, output)?,
+    options::Writer::Latex => LatexWriter::new().write(ast, &opts, output)?,
+    options::Writer::Manpage => ManpageWriter::new().write(ast, &opts, output)?,
+    options::Writer::Revealjs => RevealjsWriter::new().write(ast, &opts, output)?,
+    options::Writer::Html5Chunked => {
+      // The chunks are written through the environment, so that a
+      // dry run only lists them
+      let mut writer = ChunkedHtmlWriter::with_env(env);
+      writer.write(ast, &opts, output)?;
+      env = writer.into_env();
+    }
     options::Writer::Docx => match &opts.output {
       Some(output) => {
         DocxWriter::new().write(
@@ -63,7 +85,29 @@ pub fn cli_template(
       }
       None => bail!("docx can only be written to file not to stdout"),
     },
-    _ => bail!("not yet supported"),
+    options::Writer::Epub => match &opts.output {
+      Some(output) => {
+        EpubWriter::new().write(
+          ast,
+          &opts,
+          fs::File::create(output).context("Could not open output file")?,
+        )?;
+      }
+      None => bail!("epub can only be written to file not to stdout"),
+    },
+    options::Writer::Pdf => match &opts.output {
+      Some(output) => {
+        PdfWriter::new().write(
+          ast,
+          &opts,
+          fs::File::create(output).context("Could not open output file")?,
+        )?;
+      }
+      None => match seekable_stdout() {
+        Some(stdout) => PdfWriter::new().write(ast, &opts, stdout)?,
+        None => bail!("pdf can only be written to a file or a seekable stdout"),
+      },
+    },
   };
 
   if opts.dry_run {
@@ -84,3 +128,20 @@ fn no_extensions<'a>(
 ) -> Result<AST<'a>> {
   Ok(ast)
 }
+
+/// Returns stdout as a file if it can be seeked, e.g. when it is
+/// redirected to a file and not a pipe
+#[cfg(unix)]
+fn seekable_stdout() -> Option<fs::File> {
+  use std::io::Seek;
+  use std::os::fd::AsFd;
+
+  let mut stdout = fs::File::from(io::stdout().as_fd().try_clone_to_owned().ok()?);
+  stdout.stream_position().ok()?;
+  Some(stdout)
+}
+
+#[cfg(not(unix))]
+fn seekable_stdout() -> Option<fs::File> {
+  None
+}
@@ -1,23 +1,38 @@
 use crate::ast::*;
+use crate::sections::build_sections;
 
+/// Extracts the sections up to the title `level` as a nested tree.
+/// Every section keeps its title and its subsections, all other
+/// content is dropped.
 pub fn extract_table_of_contents<'a>(input: &AST<'a>, level: u32) -> AST<'a> {
-  let elements = input.elements.iter().filter_map(|el| {
-    match el.element {
-      Element::Title { level: l } => if l <= level {
-        Some(el.clone())
-      } else {
-        None
-      },
-      _ => None,
-    }
-  }).collect();
+  let sections = build_sections(input.clone());
 
   AST {
-    elements,
+    elements: toc_entries(&sections.elements, level),
     ..AST::default()
   }
 }
 
+fn toc_entries<'a>(elements: &[ElementSpan<'a>], level: u32) -> Vec<ElementSpan<'a>> {
+  elements
+    .iter()
+    .flat_map(|el| match &el.element {
+      Element::Section { level: l } if *l <= level => {
+        let (title, content) = match el.children.split_first() {
+          Some((title, content)) => (Some(title.clone()), content),
+          None => (None, &el.children[..]),
+        };
+        vec![ElementSpan {
+          children: title.into_iter().chain(toc_entries(content, level)).collect(),
+          ..el.clone()
+        }]
+      }
+      Element::IncludeElement(include) => toc_entries(&include.inner.elements, level),
+      _ => Vec::new(),
+    })
+    .collect()
+}
+
 #[cfg(test)]
 mod tests {
   use pretty_assertions::assert_eq;
@@ -40,23 +55,56 @@ mod tests {
     assert_eq!(
       extract_table_of_contents(&input, 2),
       AST {
-        elements: vec![
-          new_element(Element::Title { level: 1 }, "Main Header"),
-          new_element(Element::Title { level: 2 }, "Subheader"),
-          new_element(Element::Title { level: 2 }, "Next Subheader"),
-        ],
+        elements: vec![new_section(
+          1,
+          "Main Header",
+          "_main_header",
+          vec![
+            new_section(2, "Subheader", "_subheader", vec![]),
+            new_section(2, "Next Subheader", "_next_subheader", vec![]),
+          ]
+        )],
         ..AST::default()
       }
     );
   }
 
+  fn new_section<'a>(
+    level: u32,
+    title: &'a str,
+    id: &str,
+    subsections: Vec<ElementSpan<'a>>,
+  ) -> ElementSpan<'a> {
+    let id = Attribute {
+      key: "id".to_string(),
+      value: AttributeValue::String(id.to_string()),
+    };
+    let mut heading = new_element(Element::Title { level }, title);
+    heading.attributes.push(id.clone());
+    let mut children = vec![heading];
+    children.extend(subsections);
+
+    ElementSpan {
+      children,
+      attributes: vec![id],
+      ..new_element(Element::Section { level }, title)
+    }
+  }
+
   fn new_element<'a>(element: Element<'a>, content: &'a str) -> ElementSpan<'a> {
+    let attributes = match element {
+      Element::Title { .. } => vec![Attribute {
+        key: "name".to_string(),
+        value: AttributeValue::Ref(content),
+      }],
+      _ => vec![],
+    };
     ElementSpan {
       element,
       source: None,
-      content,
+      content: content.into(),
       children: vec![],
-      attributes: vec![],
+      attributes,
       positional_attributes: vec![],
       start: 0,
       end: 0,
@@ -13,13 +13,22 @@ pub mod cli_template;
 pub mod options;
 pub mod util;
 pub mod extract_toc;
+pub mod sections;
 pub mod reader;
 pub use reader::asciidoc::AsciidocReader;
 pub use reader::json::JsonReader;
 pub use reader::markdown::MarkdownReader;
 mod writer;
 pub use writer::html::HtmlWriter;
+pub use writer::chunked::ChunkedHtmlWriter;
+pub use writer::docbook::DocbookWriter;
+pub use writer::pdf::PdfWriter;
 pub use writer::docx::DocxWriter;
+pub use writer::markdown::MarkdownWriter;
+pub use writer::latex::LatexWriter;
+pub use writer::epub::EpubWriter;
+pub use writer::manpage::ManpageWriter;
+pub use writer::revealjs::RevealjsWriter;
 pub use writer::json::JsonWriter;
 pub use writer::asciidoc::AsciidocWriter;
 
@@ -39,6 +48,12 @@ pub enum AsciidoctrineError {
   Utf8(#[from] std::str::Utf8Error),
   #[error(transparent)]
   Docx(#[from] docx_rs::DocxError),
+  #[error("could not read the reference docx")]
+  ReferenceDocx(#[from] docx_rs::ReaderError),
+  #[error("could not read {0}")]
+  Read(String, #[source] std::io::Error),
+  #[error(transparent)]
+  Zip(#[from] zip::result::ZipError),
   #[error("Child process stdin has not been captured!")]
   Childprocess,
   #[error("malformed ast structure")]
@@ -1,22 +1,15 @@
 use clap::{Parser, ValueEnum};
 use std::path::PathBuf;
 
-/// Parse a single key-value pair
-fn parse_key_val<T, U>(s: &str) -> Result<(T, U), String>
-where
-  T: std::str::FromStr,
-  U: std::str::FromStr,
-{
-  let pos = s
-    .find('=')
-    .ok_or_else(|| format!("invalid KEY=value: no `=` found in `{}`", s))?;
-  let key = s[..pos]
-    .parse()
-    .or_else(|_| Err(format!("couldn't parse key in `{}`", s)))?;
-  let value = s[pos + 1..]
-    .parse()
-    .or_else(|_| Err(format!("couldn't parse value in `{}`", s)))?;
-  Ok((key, value))
+/// Parse an attribute definition (`name=value`)
+///
+/// The value is optional, so that `name` just sets the attribute
+/// and `name!` unsets it.
+fn parse_define(s: &str) -> Result<(String, String), String> {
+  match s.find('=') {
+    Some(pos) => Ok((s[..pos].to_string(), s[pos + 1..].to_string())),
+    None => Ok((s.to_string(), String::new())),
+  }
 }
 
 #[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, ValueEnum, Debug)]
@@ -33,12 +26,48 @@ pub enum Writer {
   Pdf,
   Json,
   Docx,
+  Markdown,
+  Latex,
+  Epub,
+  Manpage,
+  Revealjs,
+  // One page per chapter (or section) with
+  // an index page and navigation
+  Html5Chunked,
   // The asciidoc output makes it possible
   // to use this tool as a preprocessor for
   // other asciidoc tools while it is maturing
   Asciidoc,
 }
 
+impl Writer {
+  /// The name of the backend in the `{backend}` attribute
+  pub fn backend(&self) -> &'static str {
+    match self {
+      Writer::Html5 => "html5",
+      Writer::Docbook => "docbook5",
+      Writer::Pdf => "pdf",
+      Writer::Json => "json",
+      Writer::Docx => "docx",
+      Writer::Markdown => "markdown",
+      Writer::Latex => "latex",
+      Writer::Epub => "epub3",
+      Writer::Manpage => "manpage",
+      Writer::Revealjs => "revealjs",
+      Writer::Html5Chunked => "multipage_html5",
+      Writer::Asciidoc => "asciidoc",
+    }
+  }
+
+  /// The default of the `{doctype}` attribute
+  pub fn doctype(&self) -> &'static str {
+    match self {
+      Writer::Manpage => "manpage",
+      _ => "article",
+    }
+  }
+}
+
 #[derive(Parser, Debug)]
 #[clap(author, version, about, long_about = None)]
 pub struct Opts {
@@ -56,9 +85,11 @@ pub struct Opts {
   pub template: Option<PathBuf>,
   #[clap(long)]
   pub stylesheet: Option<PathBuf>,
+  #[clap(long)]
+  pub reference_docx: Option<PathBuf>,
   #[clap(short = 'a', long = "attribute")]
-  #[clap(value_parser = parse_key_val::<String, String>, number_of_values = 1)]
-  defines: Vec<(String, String)>,
+  #[clap(value_parser = parse_define, number_of_values = 1)]
+  pub defines: Vec<(String, String)>,
   #[clap(name = "FILE")]
   pub input: Option<PathBuf>,
   #[clap(short = 'o')]
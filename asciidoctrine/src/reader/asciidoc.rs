@@ -5,6 +5,8 @@ use crate::reader::*;
 use crate::Result;
 use pest::iterators::Pair;
 use pest::Parser;
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
 
 pub struct AsciidocReader {}
 
@@ -16,37 +18,63 @@ impl AsciidocReader {
 
 impl crate::Reader for AsciidocReader {
   fn parse<'a>(&self, input: &'a str, args: &Opts, env: &mut Env) -> Result<AST<'a>> {
-    let ast = AsciidocParser::parse(Rule::asciidoc, input)?;
-
-    let mut attributes = Vec::new();
-    let source = if let Some(path) = &args.input {
-      if let Some(path) = path.to_str() {
-        attributes.push(Attribute {
-          key: "source".to_string(),
-          value: AttributeValue::String(path.to_string()),
-        });
-        Some(path)
-      } else {
-        None
-      }
+    match preprocess(input, args) {
+      // The preprocessed text only lives during the parsing. So the
+      // AST gets its own copy of the content
+      Some((text, lines)) => Ok(parse_document(&text, input, Some(lines), args, env)?.into_owned()),
+      None => parse_document(input, input, None, args, env),
+    }
+  }
+}
+
+/// Parses the (preprocessed) text. The positions refer to the input.
+fn parse_document<'a>(
+  text: &'a str,
+  input: &'a str,
+  lines: Option<Vec<SourceLine>>,
+  args: &Opts,
+  env: &mut Env,
+) -> Result<AST<'a>> {
+  let ast = AsciidocParser::parse(Rule::document, text)?;
+
+  let mut attributes = Vec::new();
+  let source = if let Some(path) = &args.input {
+    if let Some(path) = path.to_str() {
+      attributes.push(Attribute {
+        key: "source".to_string(),
+        value: AttributeValue::String(path.to_string()),
+      });
+      Some(path)
     } else {
       None
-    };
+    }
+  } else {
+    None
+  };
 
-    let mut elements = Vec::new();
+  let mut elements = Vec::new();
 
-    for element in ast {
-      if let Some(element) = process_element(element, source.unwrap_or(""), env) {
-        elements.push(element);
-      }
+  for element in ast {
+    if let Some(element) = process_element(element, source.unwrap_or(""), env) {
+      elements.push(element);
     }
-
-    Ok(AST {
-      content: input,
-      elements,
-      attributes,
-    })
   }
+  if let Some(lines) = lines {
+    elements = elements
+      .into_iter()
+      .map(|element| restore_position(element, &lines, input))
+      .collect();
+  }
+  let mut header = DocumentHeader::default();
+  let elements = substitute_attributes(elements, args, &mut attributes, &mut header);
+  let elements = bind_callouts(elements, &mut 0);
+
+  Ok(AST {
+    content: Cow::Borrowed(input),
+    elements,
+    attributes,
+    header,
+  })
 }
 
 #[derive(Parser, Debug, Copy, Clone)]
@@ -62,53 +90,40 @@ fn process_element<'a>(
 
   let element = match element.as_rule() {
     Rule::delimited_block => Some(process_delimited_block(element, source, env)),
+    Rule::attribute_entry_block => element
+      .into_inner()
+      .find(|sub| sub.as_rule() == Rule::attribute_entry)
+      .map(|sub| base.element(Element::Attribute(process_attribute_entry(sub)))),
     Rule::title => Some(process_title(element, base)),
     Rule::header | Rule::title_block => {
       Some(element.into_inner().fold(base, |base, subelement| {
         match subelement.as_rule() {
           Rule::title => process_title(subelement, base.clone()),
           Rule::anchor => process_anchor(subelement, base),
+          Rule::attribute_list => process_attribute_list(subelement, base),
+          Rule::author_info => base.add_attribute(Attribute {
+            key: "authors".to_string(),
+            value: AttributeValue::Ref(subelement.as_str()),
+          }),
+          Rule::revision_info => base.add_attribute(Attribute {
+            key: "revision".to_string(),
+            value: AttributeValue::Ref(subelement.as_str()),
+          }),
           // We just take the attributes at the beginning
           // of the element.
           _ => base,
         }
       }))
     }
+    Rule::toc_macro => Some(base.element(Element::TableOfContents)),
     Rule::paragraph => Some(process_paragraph(element)),
-    Rule::list => extract_inner_rule(element, source, env),
+    Rule::list => Some(process_list(element, source, env)),
     Rule::list_paragraph => Some(process_paragraph(element)),
     Rule::other_list_inline => Some(from_element(&element, Element::Text)),
     Rule::continuation => None,
-    Rule::bullet_list => Some(process_children(
-      element.clone(),
-      set_span(&element).element(Element::List(ListType::Bullet)),
-      source,
-      env,
-    )),
-    Rule::numbered_list => Some(process_children(
-      element.clone(),
-      set_span(&element).element(Element::List(ListType::Number)),
-      source,
-      env,
-    )),
-    Rule::bullet_list_element | Rule::number_bullet_list_element => Some(
-      element
-        .clone()
-        .into_inner()
-        .fold(set_span(&element), |base, sub| match sub.as_rule() {
-          Rule::bullet | Rule::number_bullet => {
-            base.element(Element::ListItem(sub.as_str().trim().len() as u32))
-          }
-          Rule::list_element => process_children(sub, base, source, env),
-          Rule::EOI => base,
-          _ => {
-            let mut base = base;
-            base.children.push(set_span(&sub));
-            base
-          }
-        }),
-    ),
     Rule::image_block => Some(process_image(element, env)),
+    Rule::styled_paragraph => Some(process_styled_paragraph(element)),
+    Rule::admonition => Some(process_admonition(element)),
     Rule::include_macro => Some(process_include(element, source, env)),
     Rule::block => extract_inner_rule(element, source, env),
     Rule::inline => Some(process_inline(element, base)),
@@ -151,33 +166,42 @@ fn process_inline_attribute_list<'a>(
   element
     .into_inner()
     .fold(base, |base, sub| match sub.as_rule() {
-      Rule::attribute => sub
-        .into_inner()
-        .fold(base, |base, sub| match sub.as_rule() {
-          Rule::attribute_value => base.add_positional_attribute(AttributeValue::Ref(sub.as_str())),
-          Rule::named_attribute => {
-            let mut rules = sub.into_inner();
-            let key = rules
-              .find_map(|sub| match sub.as_rule() {
-                Rule::identifier => Some(sub.as_str()),
-                _ => None,
-              })
-              .unwrap()
-              .to_string();
-            let value = rules
-              .find_map(|sub| match sub.as_rule() {
-                Rule::attribute_value => Some(sub.into_inner().concat()),
-                _ => None,
-              })
-              .unwrap();
+      Rule::attribute => process_attribute(sub, base),
+      _ => base.add_child(set_span(&sub)),
+    })
+}
 
-            base.add_attribute(Attribute {
-              key: key,
-              value: AttributeValue::String(value),
-            })
-          }
-          _ => base.add_child(set_span(&sub)),
-        }),
+fn process_attribute<'a>(element: Pair<'a, asciidoc::Rule>, base: ElementSpan<'a>) -> ElementSpan<'a> {
+  element
+    .into_inner()
+    .fold(base, |base, sub| match sub.as_rule() {
+      Rule::attribute_value => base.add_positional_attribute(AttributeValue::Ref(sub.as_str())),
+      Rule::named_attribute => {
+        let mut rules = sub.into_inner();
+        let key = rules
+          .find_map(|sub| match sub.as_rule() {
+            Rule::identifier => Some(sub.as_str()),
+            _ => None,
+          })
+          .unwrap()
+          .to_string();
+        let value = rules
+          .find_map(|sub| match sub.as_rule() {
+            // Only quoted values have an inner value
+            Rule::attribute_value => Some(if sub.as_str().starts_with('"') {
+              sub.into_inner().concat()
+            } else {
+              sub.as_str().to_string()
+            }),
+            _ => None,
+          })
+          .unwrap();
+
+        base.add_attribute(Attribute {
+          key: key,
+          value: AttributeValue::String(value),
+        })
+      }
       _ => base.add_child(set_span(&sub)),
     })
 }
@@ -186,12 +210,57 @@ fn process_attribute_list<'a>(
   element: Pair<'a, asciidoc::Rule>,
   base: ElementSpan<'a>,
 ) -> ElementSpan<'a> {
-  element
+  let first = base.positional_attributes.len();
+  let base = element
     .into_inner()
     .fold(base, |base, sub| match sub.as_rule() {
       Rule::inline_attribute_list => process_inline_attribute_list(sub, base),
       _ => base.add_child(set_span(&sub)),
-    })
+    });
+
+  match base.positional_attributes.get(first) {
+    Some(&AttributeValue::Ref(value)) if !value.starts_with('"') && value.contains(['#', '.']) => {
+      process_shorthands(base, first, value)
+    }
+    _ => base,
+  }
+}
+
+/// Splits a first positional attribute like `quote#id.role%option`
+/// into the style with the options, the anchor and the roles
+fn process_shorthands<'a>(mut base: ElementSpan<'a>, index: usize, value: &'a str) -> ElementSpan<'a> {
+  let value = value.trim();
+  let end = value.find(['#', '.', '%']).unwrap_or(value.len());
+  let mut style = value[..end].to_string();
+  let mut roles = Vec::new();
+  let mut rest = &value[end..];
+
+  while let Some(marker) = rest.chars().next() {
+    let end = rest[1..].find(['#', '.', '%']).map_or(rest.len(), |end| end + 1);
+    let name = &rest[1..end];
+    match marker {
+      '#' if !name.is_empty() => {
+        base = base.add_attribute(Attribute {
+          key: "anchor".to_string(),
+          value: AttributeValue::Ref(name),
+        })
+      }
+      '.' if !name.is_empty() => roles.push(name),
+      // The options stay with the style
+      '%' => style.push_str(&rest[..end]),
+      _ => (),
+    }
+    rest = &rest[end..];
+  }
+  if !roles.is_empty() {
+    base = base.add_attribute(Attribute {
+      key: "role".to_string(),
+      value: AttributeValue::String(roles.join(" ")),
+    });
+  }
+  base.positional_attributes[index] = AttributeValue::String(style);
+
+  base
 }
 
 fn process_blocktitle<'a>(
@@ -226,19 +295,31 @@ fn process_delimited_block<'a>(
       Rule::delimited_comment
       | Rule::delimited_source
       | Rule::delimited_literal
-      | Rule::delimited_example => process_delimited_inner(
-        sub.clone(),
-        base.element(Element::TypedBlock {
-          kind: match sub.as_rule() {
-            Rule::delimited_comment => BlockType::Comment,
-            Rule::delimited_source | Rule::delimited_literal => BlockType::Listing,
-            Rule::delimited_example => BlockType::Example,
-            _ => unreachable!(),
-          },
-        }),
-        source,
-        env,
-      ),
+      | Rule::delimited_fenced
+      | Rule::delimited_example
+      | Rule::delimited_sidebar
+      | Rule::delimited_quote
+      | Rule::delimited_open
+      | Rule::delimited_passthrough => {
+        let kind = match sub.as_rule() {
+          Rule::delimited_comment => BlockType::Comment,
+          Rule::delimited_source | Rule::delimited_literal | Rule::delimited_fenced => {
+            BlockType::Listing
+          }
+          Rule::delimited_example => styled_kind(&base, BlockType::Example),
+          Rule::delimited_sidebar => BlockType::Sidebar,
+          Rule::delimited_quote => styled_kind(&base, BlockType::Quote),
+          Rule::delimited_open => styled_kind(&base, BlockType::Open),
+          Rule::delimited_passthrough => BlockType::Passtrough,
+          _ => unreachable!(),
+        };
+        process_delimited_inner(
+          sub.clone(),
+          base.element(Element::TypedBlock { kind }),
+          source,
+          env,
+        )
+      }
       _ => base.add_child(set_span(&sub)),
     })
 }
@@ -249,26 +330,79 @@ fn process_delimited_inner<'a>(
   source: &str,
   env: &mut Env,
 ) -> ElementSpan<'a> {
+  // Listings include other files as plain text
+  let listing = matches!(
+    element.as_rule(),
+    Rule::delimited_source | Rule::delimited_fenced
+  );
+
   element.into_inner().fold(base, |base, element| {
     let mut base = base;
 
     match element.as_rule() {
+      Rule::fenced_language => {
+        if base.positional_attributes.is_empty() {
+          base
+            .add_positional_attribute(AttributeValue::Ref("source"))
+            .add_positional_attribute(AttributeValue::Ref(element.as_str()))
+        } else {
+          base
+        }
+      }
       Rule::delimited_inner => {
         if let Element::TypedBlock {
-          kind: BlockType::Example,
+          kind:
+            BlockType::Example
+            | BlockType::Admonition(_)
+            | BlockType::Sidebar
+            | BlockType::Quote
+            | BlockType::Open,
         } = base.element
         {
+          let inner = set_span(&element);
           let ast = AsciidocParser::parse(Rule::asciidoc, element.as_str()).unwrap();
 
           for element in ast {
             if let Some(e) = process_element(element, source, env) {
-              base.children.push(e);
+              base.children.push(shift_element(e, &inner));
+            }
+          }
+        }
+        let included = if listing {
+          include_plain_text(element.as_str(), source, env)
+        } else {
+          None
+        };
+        if let Element::TypedBlock {
+          kind: BlockType::Listing,
+        } = base.element
+        {
+          let content = included.as_deref().unwrap_or_else(|| element.as_str());
+          let subs = block_substitutions(&base, VERBATIM);
+          if subs.iter().all(|sub| VERBATIM.contains(sub))
+            && subs.contains(&Substitution::SpecialCharacters)
+          {
+            if subs.contains(&Substitution::Callouts) {
+              base.children = find_callouts(content, &set_span(&element));
             }
+          } else if included.is_none() {
+            // With further substitutions the text of the listing
+            // becomes inline elements
+            let inner = set_span(&element);
+            base.children = parse_paragraph(element.as_str())
+              .into_iter()
+              .map(|child| child.add_offset(&inner))
+              .collect();
+          } else {
+            warn!("listings with included files only support verbatim substitutions");
           }
         }
         base.add_attribute(Attribute {
           key: "content".to_string(),
-          value: AttributeValue::Ref(element.as_str()),
+          value: match included {
+            Some(content) => AttributeValue::String(content),
+            None => AttributeValue::Ref(element.as_str()),
+          },
         })
       }
       _ => base,
@@ -276,6 +410,510 @@ fn process_delimited_inner<'a>(
   })
 }
 
+/// Parses the author line of the header. Several authors are
+/// separated by `;`.
+fn parse_authors(line: &str) -> Vec<Author> {
+  line
+    .split(';')
+    .map(str::trim)
+    .filter(|author| !author.is_empty())
+    .map(|author| match author.split_once('<') {
+      Some((name, email)) => {
+        let email = email.trim().trim_end_matches('>').trim();
+        parse_author(name, Some(email.to_string()).filter(|email| !email.is_empty()))
+      }
+      None => parse_author(author, None),
+    })
+    .collect()
+}
+
+/// Splits a name into its parts. Underscores join the words of a part
+/// (`Mary_Sue Brontë`). Names with more than three parts stay whole.
+fn parse_author(name: &str, email: Option<String>) -> Author {
+  let name = name.split_whitespace().collect::<Vec<_>>().join(" ");
+  let parts: Vec<String> = name.split(' ').map(|part| part.replace('_', " ")).collect();
+  let (firstname, middlename, lastname) = match parts.as_slice() {
+    [first, last] => (first.clone(), None, Some(last.clone())),
+    [first, middle, last] => (first.clone(), Some(middle.clone()), Some(last.clone())),
+    _ => (name.replace('_', " "), None, None),
+  };
+  let initials = [Some(&firstname), middlename.as_ref(), lastname.as_ref()]
+    .iter()
+    .flatten()
+    .filter_map(|part| part.chars().next())
+    .collect();
+
+  Author {
+    name: name.replace('_', " "),
+    firstname,
+    middlename,
+    lastname,
+    initials,
+    email,
+  }
+}
+
+/// Parses the revision line of the header
+/// (`v1.0, 2024-01-01: Remark`)
+fn parse_revision(line: &str) -> Revision {
+  let (rest, remark) = match line.split_once(": ") {
+    Some((rest, remark)) => (rest, Some(remark)),
+    None => (line.strip_suffix(':').unwrap_or(line), None),
+  };
+  let (number, date) = match rest.split_once(',') {
+    Some((number, date)) => (Some(number), Some(date)),
+    // A single value is the date unless it looks like a version
+    None if rest.trim_start().starts_with(['v', 'V']) => (Some(rest), None),
+    None => (None, Some(rest)),
+  };
+  let value = |value: Option<&str>| {
+    value
+      .map(|value| value.trim().to_string())
+      .filter(|value| !value.is_empty())
+  };
+
+  Revision {
+    number: value(number.map(|number| {
+      number
+        .trim()
+        .trim_start_matches(|c: char| !c.is_ascii_digit() && c != '{')
+    })),
+    date: value(date),
+    remark: value(remark),
+  }
+}
+
+/// The document attributes defined by the authors and the revision
+fn header_attributes(header: &DocumentHeader) -> Vec<(String, String)> {
+  let mut attributes = Vec::new();
+
+  if let Some(title) = &header.title {
+    attributes.push(("doctitle".to_string(), title.clone()));
+  }
+  for (index, author) in header.authors.iter().enumerate() {
+    let suffix = match index {
+      0 => String::new(),
+      index => format!("_{}", index + 1),
+    };
+    attributes.push((format!("author{}", suffix), author.name.clone()));
+    attributes.push((format!("firstname{}", suffix), author.firstname.clone()));
+    if let Some(middlename) = &author.middlename {
+      attributes.push((format!("middlename{}", suffix), middlename.clone()));
+    }
+    if let Some(lastname) = &author.lastname {
+      attributes.push((format!("lastname{}", suffix), lastname.clone()));
+    }
+    attributes.push((format!("authorinitials{}", suffix), author.initials.clone()));
+    if let Some(email) = &author.email {
+      attributes.push((format!("email{}", suffix), email.clone()));
+    }
+  }
+  if !header.authors.is_empty() {
+    let names: Vec<&str> = header.authors.iter().map(|author| author.name.as_str()).collect();
+    attributes.push(("authors".to_string(), names.join(", ")));
+    attributes.push(("authorcount".to_string(), names.len().to_string()));
+  }
+
+  if let Some(revision) = &header.revision {
+    let values = [
+      ("revnumber", &revision.number),
+      ("revdate", &revision.date),
+      ("revremark", &revision.remark),
+    ];
+    for (key, value) in values {
+      if let Some(value) = value {
+        attributes.push((key.to_string(), value.clone()));
+      }
+    }
+  }
+
+  attributes
+}
+
+/// Completes the header with the values of the document attributes
+fn complete_header(header: &mut DocumentHeader, document: &DocumentAttributes) {
+  let value = |key: &str| document.values.get(key).cloned();
+
+  if let Some(title) = value("doctitle") {
+    header.title = Some(title);
+  }
+  if header.authors.is_empty() {
+    if let Some(author) = value("author") {
+      header.authors = vec![parse_author(&author, value("email"))];
+    }
+  }
+  if header.revision.is_none() {
+    let revision = Revision {
+      number: value("revnumber"),
+      date: value("revdate"),
+      remark: value("revremark"),
+    };
+    if revision != Revision::default() {
+      header.revision = Some(revision);
+    }
+  }
+}
+
+fn process_attribute_entry<'a>(element: Pair<'a, asciidoc::Rule>) -> Attribute<'a> {
+  let mut key = String::new();
+  let mut unset = false;
+  let mut value = "";
+
+  for sub in element.into_inner() {
+    match sub.as_rule() {
+      Rule::identifier => key = sub.as_str().to_string(),
+      Rule::attribute_unset => unset = true,
+      Rule::attribute_entry_value => value = sub.as_str(),
+      _ => (),
+    }
+  }
+  if unset {
+    key.push('!');
+  }
+
+  Attribute {
+    key,
+    value: AttributeValue::Ref(value),
+  }
+}
+
+const BUILTIN_ATTRIBUTES: &[(&str, &str)] = &[
+  ("empty", ""),
+  ("sp", " "),
+  ("nbsp", "&#160;"),
+  ("zwsp", "&#8203;"),
+  ("wj", "&#8288;"),
+  ("apos", "&#39;"),
+  ("quot", "&#34;"),
+  ("lsquo", "&#8216;"),
+  ("rsquo", "&#8217;"),
+  ("ldquo", "&#8220;"),
+  ("rdquo", "&#8221;"),
+  ("deg", "&#176;"),
+  ("plus", "&#43;"),
+  ("brvbar", "&#166;"),
+  ("vbar", "|"),
+  ("amp", "&amp;"),
+  ("lt", "&lt;"),
+  ("gt", "&gt;"),
+  ("startsb", "["),
+  ("endsb", "]"),
+  ("caret", "^"),
+  ("asterisk", "*"),
+  ("tilde", "~"),
+  ("backslash", "\\"),
+  ("backtick", "`"),
+  ("two-colons", "::"),
+  ("two-semicolons", ";;"),
+  ("cpp", "C++"),
+  ("pp", "&#43;&#43;"),
+];
+
+/// The attributes defined at the current position of the document
+struct DocumentAttributes {
+  values: HashMap<String, String>,
+  /// Attributes defined on the command line can't be changed
+  /// by the document
+  locked: HashSet<String>,
+}
+
+impl DocumentAttributes {
+  fn new(args: &Opts) -> Self {
+    let mut values: HashMap<String, String> = BUILTIN_ATTRIBUTES
+      .iter()
+      .map(|(key, value)| (key.to_string(), value.to_string()))
+      .collect();
+
+    if let Some(input) = &args.input {
+      // The directory is absolute even for a relative input
+      let docdir = std::path::absolute(input)
+        .ok()
+        .and_then(|path| path.parent().and_then(|path| path.to_str()).map(str::to_string))
+        .unwrap_or_default();
+      values.insert("docdir".to_string(), docdir);
+      if let Some(docfile) = input.to_str() {
+        values.insert("docfile".to_string(), docfile.to_string());
+      }
+      if let Some(docname) = input.file_stem().and_then(|name| name.to_str()) {
+        values.insert("docname".to_string(), docname.to_string());
+      }
+    } else {
+      values.insert("docdir".to_string(), String::new());
+    }
+    let now = chrono::Local::now();
+    values.insert("localdate".to_string(), now.format("%Y-%m-%d").to_string());
+    values.insert("localtime".to_string(), now.format("%H:%M:%S %z").to_string());
+    values.insert("localyear".to_string(), now.format("%Y").to_string());
+    values.insert("backend".to_string(), args.writerfmt.backend().to_string());
+    values.insert("doctype".to_string(), args.writerfmt.doctype().to_string());
+
+    let mut attributes = DocumentAttributes {
+      values,
+      locked: HashSet::new(),
+    };
+    for (key, value) in args.defines.iter() {
+      // A trailing `@` makes it a soft set, which the document
+      // can override
+      let (key, soft) = match key.strip_suffix('@') {
+        Some(key) => (key, true),
+        None => (key.as_str(), false),
+      };
+      let (value, soft) = match value.strip_suffix('@') {
+        Some(value) => (value, true),
+        None => (value.as_str(), soft),
+      };
+      attributes.apply(key, value);
+      if !soft {
+        attributes.locked.insert(key.trim_end_matches('!').to_string());
+      }
+    }
+
+    attributes
+  }
+
+  /// Sets an attribute or unsets it if the key ends with `!`.
+  /// Returns `false` if the attribute is locked.
+  fn apply(&mut self, key: &str, value: &str) -> bool {
+    let (name, unset) = match key.strip_suffix('!') {
+      Some(name) => (name, true),
+      None => (key, false),
+    };
+    if self.locked.contains(name) {
+      return false;
+    }
+
+    if unset {
+      self.values.remove(name);
+    } else {
+      let value = self.substitute(value).unwrap_or_else(|| value.to_string());
+      self.values.insert(name.to_string(), value);
+    }
+    true
+  }
+
+  /// Replaces all attribute references (`{name}`) in a text.
+  /// Returns `None` if nothing was replaced.
+  fn substitute(&self, text: &str) -> Option<String> {
+    if !text.contains('{') {
+      return None;
+    }
+
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    let mut changed = false;
+
+    while let Some(start) = rest.find('{') {
+      let (before, reference) = rest.split_at(start);
+      let name = reference
+        .find('}')
+        .map(|end| &reference[1..end])
+        .filter(|name| is_attribute_name(name));
+
+      match name {
+        Some(name) => {
+          let reference_end = name.len() + 2;
+          if let Some(before) = before.strip_suffix('\\') {
+            // Escaped references are kept as they are
+            out.push_str(before);
+            out.push_str(&reference[..reference_end]);
+            changed = true;
+          } else {
+            out.push_str(before);
+            match self.values.get(name) {
+              Some(value) => {
+                out.push_str(value);
+                changed = true;
+              }
+              None => {
+                warn!("skipping reference to missing attribute {{{}}}", name);
+                out.push_str(&reference[..reference_end]);
+              }
+            }
+          }
+          rest = &reference[reference_end..];
+        }
+        None => {
+          out.push_str(before);
+          out.push('{');
+          rest = &reference[1..];
+        }
+      }
+    }
+    out.push_str(rest);
+
+    if changed {
+      Some(out)
+    } else {
+      None
+    }
+  }
+}
+
+fn is_attribute_name(name: &str) -> bool {
+  let mut chars = name.chars();
+  match chars.next() {
+    Some(c) if c.is_alphanumeric() || c == '_' => {
+      chars.all(|c| c.is_alphanumeric() || c == '_' || c == '-')
+    }
+    _ => false,
+  }
+}
+
+/// Resolves the attribute references in all elements. The header
+/// entries are moved into the document attributes and the metadata
+/// of the header is collected.
+fn substitute_attributes<'a>(
+  elements: Vec<ElementSpan<'a>>,
+  args: &Opts,
+  attributes: &mut Vec<Attribute<'a>>,
+  metadata: &mut DocumentHeader,
+) -> Vec<ElementSpan<'a>> {
+  let mut document = DocumentAttributes::new(args);
+  let mut header: Vec<String> = args
+    .defines
+    .iter()
+    .map(|(key, _)| key.trim_end_matches('@').to_string())
+    .collect();
+
+  let mut elements = elements.into_iter().peekable();
+  let title = match elements.peek() {
+    Some(element) if element.element == Element::Title { level: 1 } => elements.next(),
+    _ => None,
+  };
+  if let Some(title) = &title {
+    let substitute = |text: &str| document.substitute(text).unwrap_or_else(|| text.to_string());
+    metadata.title = title.get_attribute("name").map(substitute);
+    if let Some(line) = title.get_attribute("authors") {
+      metadata.authors = parse_authors(&substitute(line));
+    }
+    if let Some(line) = title.get_attribute("revision") {
+      metadata.revision = Some(parse_revision(&substitute(line)));
+    }
+    for (key, value) in header_attributes(metadata) {
+      document.apply(&key, &value);
+    }
+  }
+  while let Some(Element::Attribute(attribute)) = elements.peek().map(|element| &element.element) {
+    if document.apply(&attribute.key, attribute.value.as_str()) {
+      header.push(attribute.key.clone());
+    }
+    elements.next();
+  }
+  complete_header(metadata, &document);
+
+  // Only the last entry of an attribute counts. Unset attributes
+  // are kept (with a trailing `!`) to be able to write them back.
+  let mut keys: Vec<String> = Vec::new();
+  for key in header {
+    let name = key.trim_end_matches('!');
+    keys.retain(|other| other.trim_end_matches('!') != name);
+    keys.push(key);
+  }
+  for key in keys {
+    let value = match key.strip_suffix('!') {
+      Some(name) if !document.values.contains_key(name) => Some(String::new()),
+      Some(_) => None,
+      None => document.values.get(&key).cloned(),
+    };
+    if let Some(value) = value {
+      attributes.push(Attribute {
+        key,
+        value: AttributeValue::String(value),
+      });
+    }
+  }
+
+  title
+    .into_iter()
+    .chain(elements)
+    .flat_map(|element| substitute_element(element, &mut document, NORMAL, false))
+    .collect()
+}
+
+/// Applies the substitutions to an element and its children. Hard
+/// line breaks split a text into several elements.
+fn substitute_element<'a>(
+  element: ElementSpan<'a>,
+  document: &mut DocumentAttributes,
+  subs: &[Substitution],
+  hardbreaks: bool,
+) -> Vec<ElementSpan<'a>> {
+  let mut element = element;
+
+  let disabled = match element.element {
+    Element::Styled => !subs.contains(&Substitution::Quotes),
+    Element::Link
+    | Element::XRef
+    | Element::Footnote
+    | Element::InlineImage
+    | Element::Keyboard
+    | Element::Button
+    | Element::Menu
+    | Element::InlinePassthrough
+    | Element::Stem
+    | Element::IndexTerm => !subs.contains(&Substitution::Macros),
+    _ => false,
+  };
+  if disabled {
+    // The markup stays in the text
+    element = ElementSpan {
+      element: Element::Text,
+      attributes: Vec::new(),
+      positional_attributes: Vec::new(),
+      children: Vec::new(),
+      ..element
+    };
+  }
+  if element.element == Element::Text {
+    let hardbreaks = hardbreaks || document.values.contains_key("hardbreaks-option");
+    return substitute_text(element, document, subs, hardbreaks);
+  }
+
+  // Only blocks with text content substitute it. The others
+  // substitute their children.
+  let substitute_content = match &mut element.element {
+    Element::Attribute(attribute) => {
+      document.apply(&attribute.key, attribute.value.as_str());
+      return vec![element];
+    }
+    Element::IncludeElement(include) => {
+      include.inner.elements = std::mem::take(&mut include.inner.elements)
+        .into_iter()
+        .flat_map(|element| substitute_element(element, document, subs, hardbreaks))
+        .collect();
+      return vec![element];
+    }
+    Element::TypedBlock { kind } => matches!(
+      kind,
+      BlockType::Verse | BlockType::Listing | BlockType::Literal | BlockType::Passtrough
+    ),
+    _ => true,
+  };
+  let subs = block_substitutions(&element, subs);
+  let substitute_content = substitute_content && subs.contains(&Substitution::Attributes);
+  let hardbreaks = hardbreaks || has_option(&element, "hardbreaks");
+
+  for attribute in element.attributes.iter_mut() {
+    if attribute.key == "content" && !substitute_content {
+      continue;
+    }
+    if let Some(value) = document.substitute(attribute.value.as_str()) {
+      attribute.value = AttributeValue::String(value);
+    }
+  }
+  for attribute in element.positional_attributes.iter_mut() {
+    if let Some(value) = document.substitute(attribute.as_str()) {
+      *attribute = AttributeValue::String(value);
+    }
+  }
+  element.children = std::mem::take(&mut element.children)
+    .into_iter()
+    .flat_map(|child| substitute_element(child, document, &subs, hardbreaks))
+    .collect();
+
+  vec![element]
+}
+
 fn process_title<'a>(element: Pair<'a, asciidoc::Rule>, base: ElementSpan<'a>) -> ElementSpan<'a> {
   match element.as_rule() {
     Rule::title => {
@@ -295,10 +933,19 @@ fn process_title<'a>(element: Pair<'a, asciidoc::Rule>, base: ElementSpan<'a>) -
               }
             },
           }),
-          Rule::line => base.add_attribute(Attribute {
-            key: "name".to_string(),
-            value: AttributeValue::Ref(subelement.as_str()),
-          }),
+          Rule::line => {
+            // The title is parsed like the text of a paragraph
+            let line = from_element(&subelement, Element::Text);
+            let mut base = base.add_attribute(Attribute {
+              key: "name".to_string(),
+              value: AttributeValue::Ref(subelement.as_str()),
+            });
+            base.children = parse_paragraph(subelement.as_str())
+              .into_iter()
+              .map(|child| child.add_offset(&line))
+              .collect();
+            base
+          }
           // We just take the attributes at the beginning
           // of the element.
           _ => base.error("Unsupported title formatting"),
@@ -331,20 +978,26 @@ fn parse_paragraph<'a>(content: &'a str) -> Vec<ElementSpan<'a>> {
 
 fn process_paragraph<'a>(element: Pair<'a, asciidoc::Rule>) -> ElementSpan<'a> {
   let mut base = from_element(&element, Element::Paragraph);
+  let mut text = element.as_str();
 
   for subelement in element.clone().into_inner() {
     match subelement.as_rule() {
       Rule::anchor => base = process_anchor(subelement, base),
+      Rule::attribute_list => base = process_attribute_list(subelement, base),
+      Rule::blocktitle => base = process_blocktitle(subelement, base),
       Rule::paragraph_content => {
         let attributes = base.attributes;
+        let positional_attributes = base.positional_attributes;
+        text = subelement.as_str();
         base = from_element(&subelement, Element::Paragraph);
         base.attributes = attributes;
+        base.positional_attributes = positional_attributes;
       }
       _ => (),
     }
   }
 
-  base.children = parse_paragraph(base.content)
+  base.children = parse_paragraph(text)
     .into_iter()
     .map(|child| child.add_offset(&base))
     .collect();
@@ -356,13 +1009,19 @@ fn process_inline<'a>(element: Pair<'a, asciidoc::Rule>, base: ElementSpan<'a>)
   element
     .into_inner()
     .fold(base, |base, element| match element.as_rule() {
-      Rule::monospaced | Rule::strong | Rule::emphasized => {
+      Rule::monospaced
+      | Rule::strong
+      | Rule::emphasized
+      | Rule::double_curved_quote
+      | Rule::single_curved_quote => {
         let base = base.element(Element::Styled).add_attribute(Attribute {
           key: "style".to_string(),
           value: AttributeValue::Ref(match element.as_rule() {
             Rule::monospaced => "monospaced",
             Rule::strong => "strong",
             Rule::emphasized => "em",
+            Rule::double_curved_quote => "double_quoted",
+            Rule::single_curved_quote => "single_quoted",
             _ => "not_supported",
           }),
         });
@@ -383,55 +1042,758 @@ fn process_inline<'a>(element: Pair<'a, asciidoc::Rule>, base: ElementSpan<'a>)
       }
       Rule::link => process_link(element, base),
       Rule::xref => process_xref(element, base),
+      Rule::text_anchor => process_text_anchor(element, base),
       Rule::footnote | Rule::footnoteref => process_footnote(element, base),
+      Rule::inline_image => process_inline_image(element, base),
+      Rule::keyboard | Rule::button | Rule::menu => process_ui_macro(element, base),
+      Rule::inline_passthrough => process_inline_passthrough(element, base),
+      Rule::stem => process_stem(element, base),
+      Rule::index_term => process_index_term(element, base),
+      Rule::comment => base.element(Element::Comment),
       _ => base,
     })
 }
 
-fn process_link<'a>(element: Pair<'a, asciidoc::Rule>, base: ElementSpan<'a>) -> ElementSpan<'a> {
-  element
-    .into_inner()
-    .fold(base.element(Element::Link), |base, element| {
-      match element.as_rule() {
-        Rule::url => {
-          let base = base.add_attribute(Attribute {
-            key: "url".to_string(),
-            value: AttributeValue::Ref(element.as_str()),
-          });
-          let element = element.into_inner().next().unwrap(); // TODO Fehler möglich?
-          base.add_attribute(Attribute {
-            key: "protocol".to_string(),
-            value: AttributeValue::Ref(element.as_str()),
-          })
-        }
-        Rule::inline_attribute_list => process_inline_attribute_list(element, base),
-        _ => base.add_child(set_span(&element)),
-      }
-    })
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Substitution {
+  SpecialCharacters,
+  Quotes,
+  Attributes,
+  Replacements,
+  Macros,
+  PostReplacements,
+  Callouts,
 }
 
-fn process_xref<'a>(element: Pair<'a, asciidoc::Rule>, base: ElementSpan<'a>) -> ElementSpan<'a> {
-  let base = element
-    .clone()
-    .into_inner()
-    .fold(base.element(Element::XRef), |base, element| {
-      match element.as_rule() {
-        Rule::identifier => base.add_attribute(Attribute {
-          key: "id".to_string(),
-          value: AttributeValue::Ref(element.as_str()),
-        }),
-        Rule::word => base,
-        _ => base,
+const NORMAL: &[Substitution] = &[
+  Substitution::SpecialCharacters,
+  Substitution::Quotes,
+  Substitution::Attributes,
+  Substitution::Replacements,
+  Substitution::Macros,
+  Substitution::PostReplacements,
+];
+const VERBATIM: &[Substitution] = &[Substitution::SpecialCharacters, Substitution::Callouts];
+
+/// Resolves the value of a `subs` attribute. Starting with an
+/// incremental change (`+name`, `name+` or `-name`) modifies the
+/// default substitutions.
+fn resolve_substitutions(subs: &str, default: &[Substitution]) -> Vec<Substitution> {
+  let mut resolved: Vec<Substitution> = Vec::new();
+
+  for (index, sub) in subs.split(',').map(str::trim).enumerate() {
+    let (name, modifier) = if let Some(name) = sub.strip_prefix('+') {
+      (name, Some('+'))
+    } else if let Some(name) = sub.strip_suffix('+') {
+      (name, Some('+'))
+    } else if let Some(name) = sub.strip_prefix('-') {
+      (name, Some('-'))
+    } else {
+      (sub, None)
+    };
+    if index == 0 && modifier.is_some() {
+      resolved = default.to_vec();
+    }
+
+    let group = match substitution_group(name) {
+      Some(group) => group,
+      None => {
+        warn!("unknown substitution {}", name);
+        continue;
       }
-    });
+    };
+    if modifier == Some('-') {
+      resolved.retain(|sub| !group.contains(sub));
+    } else {
+      for sub in group {
+        if !resolved.contains(sub) {
+          resolved.push(*sub);
+        }
+      }
+    }
+  }
+
+  resolved
+}
+
+fn substitution_group(name: &str) -> Option<&'static [Substitution]> {
+  Some(match name {
+    "none" => &[],
+    "normal" | "n" => NORMAL,
+    "verbatim" | "v" => VERBATIM,
+    "specialchars" | "specialcharacters" | "c" => &[Substitution::SpecialCharacters],
+    "quotes" | "q" => &[Substitution::Quotes],
+    "attributes" | "a" => &[Substitution::Attributes],
+    "replacements" | "r" => &[Substitution::Replacements],
+    "macros" | "m" => &[Substitution::Macros],
+    "post_replacements" | "p" => &[Substitution::PostReplacements],
+    "callouts" => &[Substitution::Callouts],
+    _ => return None,
+  })
+}
+
+/// The substitutions for the content of an element. Blocks use their
+/// `subs` attribute or their default, all other elements the ones of
+/// their parent.
+fn block_substitutions(element: &ElementSpan, inherited: &[Substitution]) -> Vec<Substitution> {
+  let default = match &element.element {
+    Element::TypedBlock {
+      kind: BlockType::Listing | BlockType::Literal,
+    } => VERBATIM,
+    Element::TypedBlock {
+      kind: BlockType::Passtrough | BlockType::Comment,
+    } => &[],
+    // The `subs` of an inline passthrough only apply to its content
+    Element::InlinePassthrough => return inherited.to_vec(),
+    _ => inherited,
+  };
+
+  match element.get_attribute("subs") {
+    Some(subs) => resolve_substitutions(subs, default),
+    None => default.to_vec(),
+  }
+}
+
+/// Checks if the option is set with `%name` or in the `options`
+/// attribute of an element
+fn has_option(element: &ElementSpan, name: &str) -> bool {
+  element
+    .positional_attributes
+    .iter()
+    .any(|attribute| attribute.as_str().split('%').skip(1).any(|option| option == name))
+    || element
+      .get_attribute("options")
+      .or_else(|| element.get_attribute("opts"))
+      .is_some_and(|options| options.split(',').any(|option| option.trim() == name))
+}
+
+fn substitute_text<'a>(
+  element: ElementSpan<'a>,
+  document: &DocumentAttributes,
+  subs: &[Substitution],
+  hardbreaks: bool,
+) -> Vec<ElementSpan<'a>> {
+  let breaks = match element.get_attribute("content") {
+    None if subs.contains(&Substitution::PostReplacements) => {
+      hard_breaks(&element.content, hardbreaks)
+    }
+    _ => Vec::new(),
+  };
+
+  let parts = if breaks.is_empty() {
+    vec![element]
+  } else {
+    let mut parts = Vec::new();
+    let mut start = 0;
+    for line_break in breaks {
+      if start < line_break.start {
+        parts.push(sub_span(&element, start..line_break.start));
+      }
+      parts.push(sub_span(&element, line_break.clone()).element(Element::LineBreak));
+      start = line_break.end;
+    }
+    if start < element.content.len() {
+      parts.push(sub_span(&element, start..element.content.len()));
+    }
+    parts
+  };
+
+  parts
+    .into_iter()
+    .map(|part| match part.element {
+      Element::Text => substitute_text_part(part, document, subs),
+      _ => part,
+    })
+    .collect()
+}
+
+fn substitute_text_part<'a>(
+  part: ElementSpan<'a>,
+  document: &DocumentAttributes,
+  subs: &[Substitution],
+) -> ElementSpan<'a> {
+  let mut part = part;
+  let text = part.get_attribute("content").unwrap_or(&part.content);
 
-  match concat_elements(element, Rule::word, " ") {
-    Some(content) => base.add_attribute(Attribute {
+  let mut replaced = None;
+  if subs.contains(&Substitution::Attributes) {
+    replaced = document.substitute(text);
+  }
+  if subs.contains(&Substitution::Replacements) {
+    if let Some(text) = replace_typography(replaced.as_deref().unwrap_or(text)) {
+      replaced = Some(text);
+    }
+  }
+  if let Some(text) = replaced {
+    part.attributes.retain(|attribute| attribute.key != "content");
+    part = part.add_attribute(Attribute {
       key: "content".to_string(),
-      value: AttributeValue::String(content),
-    }),
-    None => base,
+      value: AttributeValue::String(text),
+    });
+  }
+
+  if !subs.contains(&Substitution::SpecialCharacters) {
+    // Without special characters the text goes to the output as it is
+    if part.get_attribute("content").is_none() {
+      let content = part.content.clone();
+      part = part.add_attribute(Attribute {
+        key: "content".to_string(),
+        value: content.into(),
+      });
+    }
+    part.element = Element::InlinePassthrough;
+  }
+
+  part
+}
+
+/// The byte ranges of the hard line breaks in a text. With
+/// `all` every end of a line is a line break.
+fn hard_breaks(text: &str, all: bool) -> Vec<std::ops::Range<usize>> {
+  let mut breaks = Vec::new();
+  let mut offset = 0;
+
+  for line in text.split_inclusive('\n') {
+    if let Some(content) = line.strip_suffix('\n') {
+      let content = content.strip_suffix('\r').unwrap_or(content);
+      let end = offset + content.len();
+      if content.ends_with(" +") || content.ends_with("\t+") {
+        breaks.push(end - 2..end);
+      } else if all {
+        breaks.push(end..end);
+      }
+    }
+    offset += line.len();
+  }
+
+  breaks
+}
+
+/// The part of an element with the byte range of its content
+fn sub_span<'a>(element: &ElementSpan<'a>, range: std::ops::Range<usize>) -> ElementSpan<'a> {
+  let position = |offset: usize| {
+    let before = &element.content[..offset];
+    match before.rfind('\n') {
+      Some(newline) => (
+        element.start_line + before.matches('\n').count(),
+        before[newline + 1..].chars().count() + 1,
+      ),
+      None => (element.start_line, element.start_col + before.chars().count()),
+    }
+  };
+  let (start_line, start_col) = position(range.start);
+  let (end_line, end_col) = position(range.end);
+
+  ElementSpan {
+    content: match &element.content {
+      Cow::Borrowed(content) => Cow::Borrowed(&content[range.clone()]),
+      Cow::Owned(content) => Cow::Owned(content[range.clone()].to_string()),
+    },
+    start: element.start + range.start,
+    end: element.start + range.end,
+    start_line,
+    start_col,
+    end_line,
+    end_col,
+    ..element.clone()
+  }
+}
+
+/// Replaces the ASCII forms of typographic characters. Returns `None`
+/// if nothing was replaced.
+fn replace_typography(text: &str) -> Option<String> {
+  let chars: Vec<char> = text.chars().collect();
+  let mut out = String::with_capacity(text.len());
+  let mut changed = false;
+  let mut index = 0;
+
+  while index < chars.len() {
+    if chars[index] == '\\' {
+      if let Some((length, _)) = typographic_replacement(&chars, index + 1) {
+        // Escaped replacements keep their ASCII form
+        out.extend(&chars[index + 1..index + 1 + length]);
+        index += 1 + length;
+        changed = true;
+        continue;
+      }
+    }
+    match typographic_replacement(&chars, index) {
+      Some((length, replacement)) => {
+        out.push_str(replacement);
+        index += length;
+        changed = true;
+      }
+      None => {
+        out.push(chars[index]);
+        index += 1;
+      }
+    }
+  }
+
+  if changed {
+    Some(out)
+  } else {
+    None
+  }
+}
+
+/// The length and the replacement of the typographic character at
+/// the index
+fn typographic_replacement(chars: &[char], index: usize) -> Option<(usize, &'static str)> {
+  let is_word = |index: usize| chars.get(index).is_some_and(|c| c.is_alphanumeric() || *c == '_');
+  let after_word = index > 0 && is_word(index - 1);
+  let line_start = index == 0 || chars.get(index - 1) == Some(&'\n');
+
+  Some(match &chars[index.min(chars.len())..] {
+    ['(', 'C', ')', ..] => (3, "\u{a9}"),
+    ['(', 'R', ')', ..] => (3, "\u{ae}"),
+    ['(', 'T', 'M', ')', ..] => (4, "\u{2122}"),
+    [' ', '-', '-', ' ', ..] => (4, "\u{2009}\u{2014}\u{2009}"),
+    [' ', '-', '-'] | [' ', '-', '-', '\n', ..] => (3, "\u{2009}\u{2014}\u{2009}"),
+    ['-', '-', ' ', ..] if line_start => (3, "\u{2009}\u{2014}\u{2009}"),
+    ['-', '-', ..] if after_word && is_word(index + 2) => (2, "\u{2014}\u{200b}"),
+    ['.', '.', '.', ..] => (3, "\u{2026}\u{200b}"),
+    ['`', '\'', ..] => (2, "\u{2019}"),
+    ['\'', c, ..] if after_word && c.is_alphabetic() => (1, "\u{2019}"),
+    ['-', '>', ..] => (2, "\u{2192}"),
+    ['=', '>', ..] => (2, "\u{21d2}"),
+    ['<', '-', ..] => (2, "\u{2190}"),
+    ['<', '=', ..] => (2, "\u{21d0}"),
+    _ => return None,
+  })
+}
+
+fn process_list<'a>(
+  element: Pair<'a, asciidoc::Rule>,
+  source: &str,
+  env: &mut Env,
+) -> ElementSpan<'a> {
+  let text = element.as_str();
+  let offset = element.as_span().start();
+  let mut base = set_span(&element);
+  // All open lists with the marker of their items
+  let mut lists: Vec<(&'a str, ElementSpan<'a>)> = Vec::new();
+
+  for sub in element.into_inner() {
+    match sub.as_rule() {
+      Rule::anchor => base = process_anchor(sub, base),
+      Rule::attribute_list => base = process_attribute_list(sub, base),
+      Rule::blocktitle => base = process_blocktitle(sub, base),
+      Rule::bullet_list_element
+      | Rule::number_bullet_list_element
+      | Rule::labeled_list_element
+      | Rule::callout_list_element => {
+        let (marker, list_type, item) = process_list_item(sub, source, env);
+
+        match lists.iter().position(|(open, _)| *open == marker) {
+          Some(position) => {
+            while lists.len() > position + 1 {
+              close_list(&mut lists, text, offset);
+            }
+          }
+          None => {
+            let list = if lists.is_empty() {
+              base.clone()
+            } else {
+              // The span of a nested list is set when it is closed
+              ElementSpan {
+                children: Vec::new(),
+                attributes: Vec::new(),
+                positional_attributes: Vec::new(),
+                ..item.clone()
+              }
+            };
+            lists.push((marker, list.element(Element::List(list_type.clone()))));
+          }
+        }
+
+        let depth = lists.len() as u32;
+        if let Some((_, list)) = lists.last_mut() {
+          // `<.>` numbers the callouts in order
+          let item = if list_type == ListType::Callout && item.get_attribute("callout").is_none() {
+            item.add_attribute(Attribute {
+              key: "callout".to_string(),
+              value: AttributeValue::String((list.children.len() + 1).to_string()),
+            })
+          } else {
+            item
+          };
+          list.children.push(item.element(Element::ListItem(depth)));
+        }
+      }
+      _ => (),
+    }
+  }
+  while lists.len() > 1 {
+    close_list(&mut lists, text, offset);
+  }
+
+  match lists.pop() {
+    Some((_, list)) => with_checklist_type(list),
+    None => base.error("list without items"),
+  }
+}
+
+/// Closes the innermost open list and appends it to the last item
+/// of its parent list
+fn close_list<'a>(lists: &mut Vec<(&'a str, ElementSpan<'a>)>, text: &'a str, offset: usize) {
+  let mut list = match lists.pop() {
+    Some((_, list)) => with_checklist_type(list),
+    None => return,
+  };
+  if let (Some(first), Some(last)) = (list.children.first(), list.children.last()) {
+    list.start = first.start;
+    list.start_line = first.start_line;
+    list.start_col = first.start_col;
+    list.end = last.end;
+    list.end_line = last.end_line;
+    list.end_col = last.end_col;
+    list.content = Cow::Borrowed(&text[list.start - offset..list.end - offset]);
+  }
+
+  if let Some(item) = lists
+    .last_mut()
+    .and_then(|(_, parent)| parent.children.last_mut())
+  {
+    item.end = list.end;
+    item.end_line = list.end_line;
+    item.end_col = list.end_col;
+    item.content = Cow::Borrowed(&text[item.start - offset..item.end - offset]);
+    item.children.push(list);
+  }
+}
+
+/// A bullet list with checkboxes is a checklist
+fn with_checklist_type(list: ElementSpan) -> ElementSpan {
+  let checklist = list.element == Element::List(ListType::Bullet)
+    && list
+      .children
+      .iter()
+      .any(|item| item.get_attribute("checked").is_some());
+
+  if checklist {
+    list.element(Element::List(ListType::Checklist))
+  } else {
+    list
+  }
+}
+
+/// Returns the marker and the kind of list of the item together with
+/// the item itself
+fn process_list_item<'a>(
+  element: Pair<'a, asciidoc::Rule>,
+  source: &str,
+  env: &mut Env,
+) -> (&'a str, ListType, ElementSpan<'a>) {
+  let mut marker = "";
+  let mut list_type = ListType::Bullet;
+
+  let item = element
+    .clone()
+    .into_inner()
+    .fold(set_span(&element), |base, sub| match sub.as_rule() {
+      Rule::bullet => {
+        marker = sub.as_str();
+        base
+      }
+      Rule::number_bullet => {
+        marker = sub.as_str();
+        list_type = ListType::Number;
+        base
+      }
+      Rule::label_bullet => {
+        marker = sub.as_str();
+        list_type = ListType::Description;
+        base
+      }
+      Rule::callout_bullet => {
+        // All callouts belong to the same list
+        marker = "<>";
+        list_type = ListType::Callout;
+        match &sub.as_str()[1..sub.as_str().len() - 1] {
+          "." => base,
+          number => base.add_attribute(Attribute {
+            key: "callout".to_string(),
+            value: AttributeValue::Ref(number),
+          }),
+        }
+      }
+      Rule::label => base.add_attribute(Attribute {
+        key: "term".to_string(),
+        value: AttributeValue::Ref(sub.as_str().trim()),
+      }),
+      Rule::checkbox => base.add_attribute(Attribute {
+        key: "checked".to_string(),
+        value: AttributeValue::String((sub.as_str() != "[ ]").to_string()),
+      }),
+      Rule::list_element => process_children(sub, base, source, env),
+      Rule::EOI => base,
+      _ => {
+        let mut base = base;
+        base.children.push(set_span(&sub));
+        base
+      }
+    });
+
+  (marker, list_type, item)
+}
+
+/// Finds the callouts at the end of the lines of a listing
+fn find_callouts<'a>(content: &str, inner: &ElementSpan<'a>) -> Vec<ElementSpan<'a>> {
+  // Only without included files the callouts have a position in the source
+  let exact = content == inner.content;
+  let mut callouts = Vec::new();
+  let mut auto_number = 0;
+  let mut offset = 0;
+
+  for (index, line) in content.split('\n').enumerate() {
+    if let Some((column, numbers)) = line_callouts(line) {
+      for number in numbers {
+        let number = number.unwrap_or_else(|| {
+          auto_number += 1;
+          auto_number
+        });
+        let span = if exact {
+          // The span starts at the comment with the callouts
+          let comment = line.len() - line[column..].trim_start().len();
+          sub_span(inner, offset + comment..offset + line.len())
+        } else {
+          inner.clone()
+        };
+        callouts.push(
+          span
+            .element(Element::Callout(number))
+            .add_attribute(Attribute {
+              key: "line".to_string(),
+              value: AttributeValue::String(index.to_string()),
+            })
+            .add_attribute(Attribute {
+              key: "column".to_string(),
+              value: AttributeValue::String(column.to_string()),
+            }),
+        );
+      }
+    }
+    offset += line.len() + 1;
+  }
+
+  callouts
+}
+
+/// Returns the byte offset where the code before the callouts at the
+/// end of the line ends together with their numbers. `<.>` has no
+/// number.
+fn line_callouts(line: &str) -> Option<(usize, Vec<Option<u32>>)> {
+  let mut rest = line.trim_end();
+  let mut numbers = Vec::new();
+
+  while let Some(start) = rest.rfind('<') {
+    let number = match &rest[start..] {
+      "<.>" => None,
+      marker => {
+        let number = marker
+          .strip_prefix("<!--")
+          .and_then(|marker| marker.strip_suffix("-->"))
+          .or_else(|| marker.strip_prefix('<')?.strip_suffix('>'))
+          .filter(|number| !number.is_empty() && number.bytes().all(|byte| byte.is_ascii_digit()))
+          .and_then(|number| number.parse().ok());
+        match number {
+          Some(number) => Some(number),
+          None => break,
+        }
+      }
+    };
+    // A callout is separated from the code before it
+    if !rest[..start].is_empty() && !rest[..start].ends_with(char::is_whitespace) {
+      break;
+    }
+    numbers.push(number);
+    rest = rest[..start].trim_end();
+  }
+  if numbers.is_empty() {
+    return None;
   }
+  numbers.reverse();
+
+  // The callouts are usually hidden in a line comment
+  for comment in ["//", "#", ";;", "--"].iter() {
+    if let Some(code) = rest.strip_suffix(comment) {
+      if code.is_empty() || code.ends_with(char::is_whitespace) {
+        rest = code.trim_end();
+        break;
+      }
+    }
+  }
+  // A line with only a comment keeps its indentation
+  if rest.is_empty() {
+    rest = &line[..line.len() - line.trim_start().len()];
+  }
+
+  Some((rest.len(), numbers))
+}
+
+/// Binds each callout list to the listing before it
+fn bind_callouts<'a>(elements: Vec<ElementSpan<'a>>, listings: &mut usize) -> Vec<ElementSpan<'a>> {
+  // The number of the previous listing and its callouts
+  let mut previous: Option<(usize, HashSet<u32>)> = None;
+
+  elements
+    .into_iter()
+    .map(|element| {
+      let mut element = element;
+
+      match &mut element.element {
+        Element::TypedBlock {
+          kind: BlockType::Listing,
+        } if element
+          .children
+          .iter()
+          .any(|child| matches!(child.element, Element::Callout(_))) =>
+        {
+          *listings += 1;
+          let mut numbers = HashSet::new();
+          for callout in element.children.iter_mut() {
+            if let Element::Callout(number) = callout.element {
+              // Only the first callout with a number is a link target
+              if numbers.insert(number) {
+                callout.attributes.push(Attribute {
+                  key: "anchor".to_string(),
+                  value: AttributeValue::String(format!("CO{}-{}", listings, number)),
+                });
+              }
+            }
+          }
+          previous = Some((*listings, numbers));
+          return element;
+        }
+        Element::List(ListType::Callout) => match previous.take() {
+          Some((listing, numbers)) => {
+            for item in element.children.iter_mut() {
+              let number = item
+                .get_attribute("callout")
+                .and_then(|number| number.parse().ok());
+              match number {
+                Some(number) if numbers.contains(&number) => {
+                  item.attributes.push(Attribute {
+                    key: "id".to_string(),
+                    value: AttributeValue::String(format!("CO{}-{}", listing, number)),
+                  });
+                }
+                _ => warn!(
+                  "callout {} not found in the listing",
+                  item.get_attribute("callout").unwrap_or("")
+                ),
+              }
+            }
+          }
+          None => warn!("callout list without a listing before it"),
+        },
+        Element::IncludeElement(include) => {
+          include.inner.elements =
+            bind_callouts(std::mem::take(&mut include.inner.elements), listings);
+          previous = None;
+        }
+        _ => previous = None,
+      }
+      element.children = bind_callouts(std::mem::take(&mut element.children), listings);
+
+      element
+    })
+    .collect()
+}
+
+fn process_link<'a>(element: Pair<'a, asciidoc::Rule>, base: ElementSpan<'a>) -> ElementSpan<'a> {
+  element
+    .into_inner()
+    .fold(base.element(Element::Link), |base, element| {
+      match element.as_rule() {
+        Rule::url => {
+          let base = base.add_attribute(Attribute {
+            key: "url".to_string(),
+            value: AttributeValue::Ref(element.as_str()),
+          });
+          let element = element.into_inner().next().unwrap(); // TODO Fehler möglich?
+          base.add_attribute(Attribute {
+            key: "protocol".to_string(),
+            value: AttributeValue::Ref(element.as_str()),
+          })
+        }
+        Rule::link_target => base.add_attribute(Attribute {
+          key: "url".to_string(),
+          value: AttributeValue::Ref(element.as_str()),
+        }),
+        Rule::mailto_url => base
+          .add_attribute(Attribute {
+            key: "url".to_string(),
+            value: AttributeValue::Ref(element.as_str()),
+          })
+          .add_attribute(Attribute {
+            key: "protocol".to_string(),
+            value: AttributeValue::Ref("mailto"),
+          }),
+        Rule::inline_attribute_list => process_inline_attribute_list(element, base),
+        _ => base.add_child(set_span(&element)),
+      }
+    })
+}
+
+fn process_xref<'a>(element: Pair<'a, asciidoc::Rule>, base: ElementSpan<'a>) -> ElementSpan<'a> {
+  element
+    .into_inner()
+    .fold(base.element(Element::XRef), |base, element| {
+      match element.as_rule() {
+        Rule::xref_target => element.into_inner().fold(base, |base, element| {
+          let key = match element.as_rule() {
+            // Without an id we reference the whole document
+            Rule::identifier if !element.as_str().ends_with(".adoc") => "id",
+            _ => "path",
+          };
+          base.add_attribute(Attribute {
+            key: key.to_string(),
+            value: AttributeValue::Ref(element.as_str()),
+          })
+        }),
+        Rule::xref_text => base.add_attribute(Attribute {
+          key: "content".to_string(),
+          value: AttributeValue::String(
+            element.as_str().split_whitespace().collect::<Vec<_>>().join(" "),
+          ),
+        }),
+        Rule::inline_attribute_list => {
+          let base = process_inline_attribute_list(element, base);
+          match base.positional_attributes.first() {
+            Some(text) => {
+              let text = text.as_str().trim().to_string();
+              base.add_attribute(Attribute {
+                key: "content".to_string(),
+                value: AttributeValue::String(text),
+              })
+            }
+            None => base,
+          }
+        }
+        _ => base,
+      }
+    })
+}
+
+fn process_text_anchor<'a>(
+  element: Pair<'a, asciidoc::Rule>,
+  base: ElementSpan<'a>,
+) -> ElementSpan<'a> {
+  element
+    .into_inner()
+    .fold(base.element(Element::Anchor), |base, element| match element.as_rule() {
+      Rule::identifier => base.add_attribute(Attribute {
+        key: "anchor".to_string(),
+        value: AttributeValue::Ref(element.as_str()),
+      }),
+      Rule::anchor_reftext => base.add_attribute(Attribute {
+        key: "reftext".to_string(),
+        value: AttributeValue::Ref(element.as_str().trim()),
+      }),
+      _ => base,
+    })
 }
 
 fn process_footnote<'a>(element: Pair<'a, asciidoc::Rule>, base: ElementSpan<'a>) -> ElementSpan<'a> {
@@ -460,7 +1822,13 @@ fn process_footnote<'a>(element: Pair<'a, asciidoc::Rule>, base: ElementSpan<'a>
 }
 
 fn process_image<'a>(element: Pair<'a, asciidoc::Rule>, env: &mut Env) -> ElementSpan<'a> {
-  let base = element.clone().into_inner().flatten().fold(
+  // The attributes of the macro come first. This way the alternative
+  // text stays the first positional attribute.
+  let (images, metadata): (Vec<_>, Vec<_>) = element
+    .clone()
+    .into_inner()
+    .partition(|sub| sub.as_rule() == Rule::image);
+  let base = images.into_iter().flat_map(|image| image.into_inner()).fold(
     set_span(&element).element(Element::Image),
     |base, element| match element.as_rule() {
       Rule::url => base.add_attribute(Attribute {
@@ -475,6 +1843,12 @@ fn process_image<'a>(element: Pair<'a, asciidoc::Rule>, env: &mut Env) -> Elemen
       _ => base,
     },
   );
+  let base = metadata.into_iter().fold(base, |base, sub| match sub.as_rule() {
+    Rule::anchor => process_anchor(sub, base),
+    Rule::attribute_list => process_attribute_list(sub, base),
+    Rule::blocktitle => process_blocktitle(sub, base),
+    _ => base,
+  });
 
   match base.get_attribute("opts") {
     Some("inline") => match base.get_attribute("path") {
@@ -494,32 +1868,235 @@ fn process_image<'a>(element: Pair<'a, asciidoc::Rule>, env: &mut Env) -> Elemen
   }
 }
 
-#[derive(Debug, PartialEq)]
-enum ColKind {
+fn process_inline_image<'a>(element: Pair<'a, asciidoc::Rule>, base: ElementSpan<'a>) -> ElementSpan<'a> {
+  element
+    .into_inner()
+    .fold(base.element(Element::InlineImage), |base, element| match element.as_rule() {
+      Rule::url | Rule::path => base.add_attribute(Attribute {
+        key: "path".to_string(),
+        value: AttributeValue::Ref(element.as_str()),
+      }),
+      Rule::inline_attribute_list => process_inline_attribute_list(element, base),
+      _ => base,
+    })
+}
+
+fn process_ui_macro<'a>(element: Pair<'a, asciidoc::Rule>, base: ElementSpan<'a>) -> ElementSpan<'a> {
+  let kind = element.as_rule();
+  let base = base.element(match kind {
+    Rule::keyboard => Element::Keyboard,
+    Rule::button => Element::Button,
+    _ => Element::Menu,
+  });
+
+  element
+    .into_inner()
+    .fold(base, |base, element| match (element.as_rule(), kind) {
+      (Rule::menu_name, _) => base.add_attribute(Attribute {
+        key: "menu".to_string(),
+        value: AttributeValue::Ref(element.as_str()),
+      }),
+      (Rule::macro_content, Rule::keyboard) => split_keys(element.as_str())
+        .into_iter()
+        .fold(base, |base, key| base.add_positional_attribute(AttributeValue::Ref(key))),
+      (Rule::macro_content, Rule::menu) => {
+        let separator = if element.as_str().contains('>') { '>' } else { ',' };
+        split_trimmed(element.as_str(), separator)
+          .into_iter()
+          .fold(base, |base, item| base.add_positional_attribute(AttributeValue::Ref(item)))
+      }
+      (Rule::macro_content, _) => base.add_attribute(Attribute {
+        key: "content".to_string(),
+        value: macro_content(element.as_str()),
+      }),
+      _ => base,
+    })
+}
+
+/// Splits a key combination like `Ctrl+Shift+N` or `Ctrl,T` into
+/// its keys
+fn split_keys(keys: &str) -> Vec<&str> {
+  let keys = keys.trim();
+
+  if keys.contains(',') {
+    split_trimmed(keys, ',')
+  } else if keys == "+" {
+    vec![keys]
+  } else {
+    // `Ctrl++` ends with the plus key
+    match keys.strip_suffix("++") {
+      Some(keys) => {
+        let mut out = split_trimmed(keys, '+');
+        out.push("+");
+        out
+      }
+      None => split_trimmed(keys, '+'),
+    }
+  }
+}
+
+fn split_trimmed(text: &str, separator: char) -> Vec<&str> {
+  text
+    .split(separator)
+    .map(str::trim)
+    .filter(|part| !part.is_empty())
+    .collect()
+}
+
+/// The content of a macro without the escapes of `]`
+fn macro_content<'a>(content: &'a str) -> AttributeValue<'a> {
+  if content.contains("\\]") {
+    AttributeValue::String(content.replace("\\]", "]"))
+  } else {
+    AttributeValue::Ref(content)
+  }
+}
+
+fn process_inline_passthrough<'a>(
+  element: Pair<'a, asciidoc::Rule>,
+  base: ElementSpan<'a>,
+) -> ElementSpan<'a> {
+  element
+    .into_inner()
+    .fold(base.element(Element::InlinePassthrough), |base, element| {
+      match element.as_rule() {
+        Rule::pass_subs => base.add_attribute(Attribute {
+          key: "subs".to_string(),
+          value: AttributeValue::Ref(element.as_str()),
+        }),
+        Rule::triple_plus_content => base.add_attribute(Attribute {
+          key: "content".to_string(),
+          value: AttributeValue::Ref(element.as_str()),
+        }),
+        Rule::macro_content => base.add_attribute(Attribute {
+          key: "content".to_string(),
+          value: macro_content(element.as_str()),
+        }),
+        _ => base,
+      }
+    })
+}
+
+fn process_stem<'a>(element: Pair<'a, asciidoc::Rule>, base: ElementSpan<'a>) -> ElementSpan<'a> {
+  element
+    .into_inner()
+    .fold(base.element(Element::Stem), |base, element| match element.as_rule() {
+      Rule::stem_notation => match element.as_str() {
+        // The notation of `stem` is set by the document
+        "stem" => base,
+        notation => base.add_attribute(Attribute {
+          key: "notation".to_string(),
+          value: AttributeValue::Ref(notation),
+        }),
+      },
+      Rule::macro_content => base.add_attribute(Attribute {
+        key: "content".to_string(),
+        value: macro_content(element.as_str()),
+      }),
+      _ => base,
+    })
+}
+
+fn process_index_term<'a>(element: Pair<'a, asciidoc::Rule>, base: ElementSpan<'a>) -> ElementSpan<'a> {
+  let visible = element
+    .clone()
+    .into_inner()
+    .any(|element| element.as_rule() == Rule::visible_index_term);
+  let base = base.element(Element::IndexTerm).add_attribute(Attribute {
+    key: "visible".to_string(),
+    value: AttributeValue::String(visible.to_string()),
+  });
+
+  element
+    .into_inner()
+    .fold(base, |base, element| match element.as_rule() {
+      Rule::inline_attribute_list => process_inline_attribute_list(element, base),
+      Rule::visible_index_term => {
+        base.add_positional_attribute(AttributeValue::Ref(element.as_str().trim()))
+      }
+      Rule::concealed_index_terms => split_trimmed(element.as_str(), ',')
+        .into_iter()
+        .fold(base, |base, term| base.add_positional_attribute(AttributeValue::Ref(term))),
+      _ => base,
+    })
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ColKind {
   Default,
   Asciidoc,
 }
 
-#[derive(Debug, PartialEq)]
-struct ColumnFormat {
-  length: usize,
-  kind: ColKind,
+/// The horizontal alignment of a column
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum HAlign {
+  Left,
+  Center,
+  Right,
+}
+
+/// The format of a column as given in the `cols` attribute of a
+/// table (e.g. `2*^.^3a`)
+#[derive(Clone, Debug, PartialEq)]
+pub struct ColumnFormat {
+  /// The relative width or the width in percent
+  pub width: Option<usize>,
+  pub halign: HAlign,
+  pub kind: ColKind,
+}
+
+impl Default for ColumnFormat {
+  fn default() -> Self {
+    ColumnFormat {
+      width: None,
+      halign: HAlign::Left,
+      kind: ColKind::Default,
+    }
+  }
 }
 
 fn parse_column_format(input: &str) -> ColumnFormat {
-  ColumnFormat {
-    length: 1,
-    kind: match input {
-      "a" => ColKind::Asciidoc,
-      _ => ColKind::Default,
-    },
+  let mut format = ColumnFormat::default();
+  let mut rest = input;
+
+  let halign = match rest.chars().next() {
+    Some('<') => Some(HAlign::Left),
+    Some('^') => Some(HAlign::Center),
+    Some('>') => Some(HAlign::Right),
+    _ => None,
+  };
+  if let Some(halign) = halign {
+    format.halign = halign;
+    rest = &rest[1..];
+  }
+  // The vertical alignment is not used
+  if let Some(valign) = rest.strip_prefix('.') {
+    rest = valign.get(1..).unwrap_or("");
+  }
+  let digits = rest.chars().take_while(char::is_ascii_digit).count();
+  format.width = rest[..digits].parse().ok();
+  if rest[digits..].trim_start_matches(['%', '~']) == "a" {
+    format.kind = ColKind::Asciidoc;
   }
+
+  format
 }
 
-fn parse_columns_format(input: &str) -> Vec<ColumnFormat> {
+/// Parses the `cols` attribute of a table. A multiplier (e.g. `3*`)
+/// repeats the format.
+pub fn parse_columns_format(input: &str) -> Vec<ColumnFormat> {
   input
-    .split(',')
-    .map(|input| parse_column_format(input.trim()))
+    .split([',', ';'])
+    .flat_map(|input| {
+      let input = input.trim();
+      match input.split_once('*') {
+        Some((count, format)) => vec![
+          parse_column_format(format.trim());
+          count.trim().parse().unwrap_or(1).max(1)
+        ],
+        None => vec![parse_column_format(input)],
+      }
+    })
     .collect()
 }
 
@@ -529,10 +2106,7 @@ fn parse_columns_format_from_content(input: &str, delimiter: char) -> Vec<Column
     .next()
     .unwrap_or("")
     .matches(delimiter)
-    .map(|_| ColumnFormat {
-      length: 1,
-      kind: ColKind::Default,
-    })
+    .map(|_| ColumnFormat::default())
     .collect()
 }
 
@@ -656,7 +2230,7 @@ fn process_table_content<'a>(
     rows.push(ElementSpan {
       element: Element::TableRow,
       source: None,
-      content: "",
+      content: Cow::Borrowed(""),
       start: 0,
       end: 0,
       start_line: 0,
@@ -681,7 +2255,7 @@ fn process_table_cell<'a>(
   let mut base = ElementSpan {
     element: Element::TableCell,
     source: None,
-    content,
+    content: Cow::Borrowed(content),
     start: 0,
     end: 0,
     start_line: 0,
@@ -718,25 +2292,365 @@ fn process_table_cell<'a>(
   base
 }
 
-fn process_include<'a>(
-  element: Pair<'a, asciidoc::Rule>,
-  source: &str,
-  env: &mut Env,
-) -> ElementSpan<'a> {
+fn process_styled_paragraph<'a>(element: Pair<'a, asciidoc::Rule>) -> ElementSpan<'a> {
   let base = set_span(&element);
 
-  let base = element
-    .clone()
+  element
     .into_inner()
-    .flatten()
-    .fold(base, |base, element| match element.as_rule() {
-      Rule::path => base.add_attribute(Attribute {
-        key: "path".to_string(),
-        value: AttributeValue::Ref(element.as_str()),
-      }),
-      Rule::inline_attribute_list => process_inline_attribute_list(element, base),
+    .fold(base, |base, sub| match sub.as_rule() {
+      Rule::anchor => process_anchor(sub, base),
+      Rule::attribute_list => process_attribute_list(sub, base),
+      Rule::blocktitle => process_blocktitle(sub, base),
+      Rule::paragraph_content => {
+        let kind = styled_kind(&base, BlockType::Quote);
+        let base = base
+          .element(Element::TypedBlock { kind: kind.clone() })
+          .add_attribute(Attribute {
+            key: "content".to_string(),
+            value: AttributeValue::Ref(sub.as_str()),
+          });
+
+        if kind == BlockType::Verse {
+          // The lines of a verse are kept as they are
+          base
+        } else {
+          let mut paragraph = from_element(&sub, Element::Paragraph);
+          paragraph.children = parse_paragraph(sub.as_str())
+            .into_iter()
+            .map(|child| child.add_offset(&paragraph))
+            .collect();
+          base.add_child(paragraph)
+        }
+      }
+      _ => base,
+    })
+}
+
+/// Determines the kind of a block by its style (the first positional
+/// attribute). Example and open blocks can become admonitions (e.g.
+/// `[NOTE]`) and quotes can become verses.
+fn styled_kind(base: &ElementSpan, default: BlockType) -> BlockType {
+  let style = match base.positional_attributes.first() {
+    Some(style) => style.as_str(),
+    None => return default,
+  };
+
+  if let Some(kind) = AdmonitionType::from_keyword(style) {
+    return match default {
+      BlockType::Example | BlockType::Open => BlockType::Admonition(kind),
+      _ => default,
+    };
+  }
+
+  match (style, &default) {
+    ("verse", BlockType::Quote | BlockType::Open) => BlockType::Verse,
+    ("quote", BlockType::Open) => BlockType::Quote,
+    ("sidebar", BlockType::Open) => BlockType::Sidebar,
+    ("example", BlockType::Open) => BlockType::Example,
+    ("pass", BlockType::Open) => BlockType::Passtrough,
+    _ => default,
+  }
+}
+
+fn process_admonition<'a>(element: Pair<'a, asciidoc::Rule>) -> ElementSpan<'a> {
+  let base = set_span(&element);
+
+  element
+    .into_inner()
+    .fold(base, |base, sub| match sub.as_rule() {
+      Rule::anchor => process_anchor(sub, base),
+      Rule::attribute_list => process_attribute_list(sub, base),
+      Rule::blocktitle => process_blocktitle(sub, base),
+      Rule::admonition_label | Rule::admonition_style => {
+        let keyword = concat_elements(sub.clone(), Rule::admonition_type, "").unwrap_or_default();
+        let base = match AdmonitionType::from_keyword(&keyword) {
+          Some(kind) => base.element(Element::TypedBlock {
+            kind: BlockType::Admonition(kind),
+          }),
+          None => base.error("Unsupported admonition type"),
+        };
+        // The style can have further attributes like a caption
+        sub
+          .into_inner()
+          .filter(|sub| sub.as_rule() == Rule::attribute)
+          .fold(base, |base, sub| process_attribute(sub, base))
+      }
+      Rule::paragraph_content => {
+        let mut paragraph = from_element(&sub, Element::Paragraph);
+        paragraph.children = parse_paragraph(sub.as_str())
+          .into_iter()
+          .map(|child| child.add_offset(&paragraph))
+          .collect();
+        base.add_child(paragraph)
+      }
       _ => base,
+    })
+}
+
+/// Where a line of the preprocessed text comes from
+struct SourceLine {
+  /// Byte offset of the line in the preprocessed text
+  offset: usize,
+  /// Byte offset of the line in the original text
+  source_offset: usize,
+  /// Line number in the original text
+  line: usize,
+  /// Number of characters removed from the start of the line
+  col_offset: usize,
+}
+
+enum Directive<'a> {
+  Ifdef {
+    negate: bool,
+    target: &'a str,
+    content: &'a str,
+  },
+  Ifeval(&'a str),
+  /// The target has to match the open conditional if it's given
+  Endif(&'a str),
+  /// A directive with a missing or forbidden target or content
+  Malformed,
+}
+
+fn parse_directive(line: &str) -> Option<Directive<'_>> {
+  let (name, rest) = line.trim_end().split_once("::")?;
+  let (target, rest) = rest.split_once('[')?;
+  let content = rest.strip_suffix(']')?;
+  // A target with whitespace makes the directive malformed
+  let valid = !target.contains(char::is_whitespace);
+
+  match name {
+    "ifdef" | "ifndef" if valid && !target.is_empty() => Some(Directive::Ifdef {
+      negate: name == "ifndef",
+      target,
+      content,
+    }),
+    "ifeval" if target.is_empty() => Some(Directive::Ifeval(content)),
+    "endif" if valid && content.is_empty() => Some(Directive::Endif(target)),
+    "ifdef" | "ifndef" | "ifeval" | "endif" => Some(Directive::Malformed),
+    _ => None,
+  }
+}
+
+/// Resolves the conditional directives. Returns `None` if the
+/// input has none.
+fn preprocess(input: &str, args: &Opts) -> Option<(String, Vec<SourceLine>)> {
+  if !["ifdef::", "ifndef::", "ifeval::", "endif::"]
+    .iter()
+    .any(|directive| input.contains(directive))
+  {
+    return None;
+  }
+
+  let mut attributes = DocumentAttributes::new(args);
+  let mut out = String::with_capacity(input.len());
+  let mut lines = Vec::new();
+  // Every open conditional remembers if its content is included
+  // and its target
+  let mut conditions: Vec<(bool, &str)> = Vec::new();
+  let mut source_offset = 0;
+
+  for (index, line) in input.split_inclusive('\n').enumerate() {
+    let line_offset = source_offset;
+    source_offset += line.len();
+    let content = line.trim_end_matches(['\n', '\r']);
+    let active = conditions.iter().all(|(included, _)| *included);
+
+    match parse_directive(content) {
+      Some(Directive::Endif(target)) => {
+        match conditions.last() {
+          None => warn!("line {}: endif without a matching condition", index + 1),
+          // A mismatched endif doesn't close the conditional
+          Some((_, open)) if !target.is_empty() && target != *open => warn!(
+            "line {}: mismatched endif::{}[], expected endif::{}[]",
+            index + 1,
+            target,
+            open
+          ),
+          Some(_) => {
+            conditions.pop();
+          }
+        }
+        continue;
+      }
+      Some(Directive::Malformed) => {
+        warn!("line {}: malformed directive `{}`", index + 1, content);
+        continue;
+      }
+      Some(Directive::Ifdef {
+        negate,
+        target,
+        content: single_line,
+      }) => {
+        let defined = |name: &str| attributes.values.contains_key(name);
+        let matches = if target.contains('+') {
+          target.split('+').all(defined)
+        } else {
+          target.split(',').any(defined)
+        };
+        let included = active && matches != negate;
+
+        if single_line.is_empty() {
+          conditions.push((included, target));
+        } else if included {
+          let col_offset = content.len() - single_line.len() - 1;
+          lines.push(SourceLine {
+            offset: out.len(),
+            source_offset: line_offset + col_offset,
+            line: index + 1,
+            col_offset,
+          });
+          out.push_str(single_line);
+          out.push('\n');
+        }
+        continue;
+      }
+      Some(Directive::Ifeval(expression)) => {
+        let included = active
+          && evaluate_expression(expression, &attributes).unwrap_or_else(|| {
+            warn!("line {}: couldn't evaluate `{}`", index + 1, expression);
+            false
+          });
+        conditions.push((included, ""));
+        continue;
+      }
+      None => (),
+    }
+    if !active {
+      continue;
+    }
+
+    if content.starts_with(':') {
+      if let Ok(mut entry) = AsciidocParser::parse(Rule::attribute_entry, content) {
+        if let Some(entry) = entry.next() {
+          let attribute = process_attribute_entry(entry);
+          attributes.apply(&attribute.key, attribute.value.as_str());
+        }
+      }
+    }
+
+    // An escaped directive is kept as text
+    let col_offset = match content.strip_prefix('\\').and_then(parse_directive) {
+      Some(_) => 1,
+      None => 0,
+    };
+    lines.push(SourceLine {
+      offset: out.len(),
+      source_offset: line_offset + col_offset,
+      line: index + 1,
+      col_offset,
     });
+    out.push_str(&line[col_offset..]);
+  }
+  if !conditions.is_empty() {
+    warn!("{} conditional(s) without endif", conditions.len());
+  }
+
+  Some((out, lines))
+}
+
+fn evaluate_expression(expression: &str, attributes: &DocumentAttributes) -> Option<bool> {
+  use std::cmp::Ordering;
+
+  let expression = attributes
+    .substitute(expression)
+    .unwrap_or_else(|| expression.to_string());
+  let (position, operator) = ["==", "!=", "<=", ">=", "<", ">"]
+    .iter()
+    .find_map(|operator| expression.find(operator).map(|position| (position, *operator)))?;
+  let lhs = expression[..position].trim();
+  let rhs = expression[position + operator.len()..].trim();
+  if lhs.is_empty() || rhs.is_empty() {
+    return None;
+  }
+
+  let ordering = match (lhs.parse::<f64>(), rhs.parse::<f64>()) {
+    (Ok(lhs), Ok(rhs)) => lhs.partial_cmp(&rhs)?,
+    _ => unquote(lhs).cmp(unquote(rhs)),
+  };
+
+  Some(match operator {
+    "==" => ordering == Ordering::Equal,
+    "!=" => ordering != Ordering::Equal,
+    "<" => ordering == Ordering::Less,
+    "<=" => ordering != Ordering::Greater,
+    ">" => ordering == Ordering::Greater,
+    _ => ordering != Ordering::Less,
+  })
+}
+
+fn unquote(value: &str) -> &str {
+  ["\"", "'"]
+    .iter()
+    .find_map(|quote| value.strip_prefix(quote)?.strip_suffix(quote))
+    .unwrap_or(value)
+}
+
+fn restore_position<'a>(
+  element: ElementSpan<'a>,
+  lines: &[SourceLine],
+  source: &str,
+) -> ElementSpan<'a> {
+  let mut element = element;
+
+  let (start, start_line, start_col) =
+    source_position(lines, source, element.start, element.start_line, element.start_col);
+  let (end, end_line, end_col) =
+    source_position(lines, source, element.end, element.end_line, element.end_col);
+  element.start = start;
+  element.start_line = start_line;
+  element.start_col = start_col;
+  element.end = end;
+  element.end_line = end_line;
+  element.end_col = end_col;
+
+  // Included documents have their own positions
+  if let Element::IncludeElement(_) = element.element {
+    return element;
+  }
+  element.children = std::mem::take(&mut element.children)
+    .into_iter()
+    .map(|child| restore_position(child, lines, source))
+    .collect();
+
+  element
+}
+
+fn source_position(
+  lines: &[SourceLine],
+  source: &str,
+  offset: usize,
+  line: usize,
+  col: usize,
+) -> (usize, usize, usize) {
+  // Generated elements don't have a position at all
+  if line == 0 {
+    return (offset, line, col);
+  }
+
+  match lines.get(line - 1) {
+    // Not all nested elements have absolute positions yet, so we
+    // can't rely on the offset being inside of the line
+    Some(source_line) => (
+      source_line.source_offset + offset.saturating_sub(source_line.offset),
+      source_line.line,
+      col + source_line.col_offset,
+    ),
+    // The position is behind the last line
+    None => (
+      source.len(),
+      lines.last().map(|line| line.line + 1).unwrap_or(1),
+      1,
+    ),
+  }
+}
+
+fn process_include<'a>(
+  element: Pair<'a, asciidoc::Rule>,
+  source: &str,
+  env: &mut Env,
+) -> ElementSpan<'a> {
+  let base = process_include_macro(element);
 
   let path = match base.get_attribute("path") {
     Some(path) => path,
@@ -765,13 +2679,30 @@ fn process_include<'a>(
   let content = match env.read_to_string(&path) {
     Ok(content) => content,
     Err(e) => {
-      return base
-        .clone()
-        .error(&format!("couldn't read included file {} ({})", path, e));
+      warn!("couldn't read included file {} ({})", path, e);
+      // The directive stays visible in the document
+      let text = ElementSpan {
+        element: Element::Text,
+        attributes: Vec::new(),
+        positional_attributes: Vec::new(),
+        ..base.clone()
+      }
+      .add_attribute(Attribute {
+        key: "content".to_string(),
+        value: AttributeValue::String(unresolved_directive(source, base.content.trim_end())),
+      });
+      return ElementSpan {
+        element: Element::Paragraph,
+        attributes: Vec::new(),
+        positional_attributes: Vec::new(),
+        children: vec![text],
+        ..base
+      };
     }
   };
+  let content = select_include_content(&content, &base);
 
-  let include_el =
+  let mut include_el =
     match IncludeElement::from_parser(
       content,
       env,
@@ -784,12 +2715,13 @@ fn process_include<'a>(
             }
           }
           Ok(AST {
-            content: content_ref,
+            content: Cow::Borrowed(content_ref),
             elements,
             attributes: vec![Attribute {
               key: "source".to_string(),
               value: AttributeValue::String(path.to_string()),
             }],
+            header: DocumentHeader::default(),
           })
         }
         Err(e) => Err(format!("couldn't parse included file {} ({})", path, e)),
@@ -803,9 +2735,309 @@ fn process_include<'a>(
       }
     };
 
+  if let Some(offset) = base.get_attribute("leveloffset") {
+    match offset.parse::<i32>() {
+      Ok(offset) => {
+        include_el.inner.elements = std::mem::take(&mut include_el.inner.elements)
+          .into_iter()
+          .map(|element| shift_level(element, offset))
+          .collect();
+      }
+      Err(_) => warn!("invalid leveloffset {} for {}", offset, path),
+    }
+  }
+
   base.element(Element::IncludeElement(include_el))
 }
 
+fn process_include_macro<'a>(element: Pair<'a, asciidoc::Rule>) -> ElementSpan<'a> {
+  let base = set_span(&element);
+
+  element
+    .clone()
+    .into_inner()
+    .flatten()
+    .fold(base, |base, element| match element.as_rule() {
+      Rule::path => base.add_attribute(Attribute {
+        key: "path".to_string(),
+        value: AttributeValue::Ref(element.as_str()),
+      }),
+      Rule::inline_attribute_list => process_inline_attribute_list(element, base),
+      _ => base,
+    })
+}
+
+/// Selects the part of an included document requested by the
+/// `lines`, `tag`/`tags` and `indent` attributes
+fn select_include_content(content: &str, options: &ElementSpan) -> String {
+  let content = if let Some(lines) = options.get_attribute("lines") {
+    select_lines(content, lines)
+  } else if let Some(tags) = options
+    .get_attribute("tags")
+    .or_else(|| options.get_attribute("tag"))
+  {
+    select_tags(content, tags)
+  } else {
+    content.to_string()
+  };
+
+  match options.get_attribute("indent") {
+    Some(indent) => match indent.parse() {
+      Ok(indent) => reindent(&content, indent),
+      Err(_) => {
+        warn!("invalid indent {}", indent);
+        content
+      }
+    },
+    None => content,
+  }
+}
+
+fn select_lines(content: &str, lines: &str) -> String {
+  let ranges: Vec<(usize, usize)> = lines
+    .split([';', ','])
+    .map(str::trim)
+    .filter(|range| !range.is_empty())
+    .filter_map(|range| {
+      let parsed = parse_line_range(range);
+      if parsed.is_none() {
+        warn!("invalid line range {}", range);
+      }
+      parsed
+    })
+    .collect();
+
+  content
+    .split_inclusive('\n')
+    .enumerate()
+    .filter(|(index, _)| {
+      ranges
+        .iter()
+        .any(|(start, end)| (*start..=*end).contains(&(index + 1)))
+    })
+    .map(|(_, line)| line)
+    .collect()
+}
+
+fn parse_line_range(range: &str) -> Option<(usize, usize)> {
+  match range.split_once("..") {
+    Some((start, end)) => {
+      let start = start.parse().ok()?;
+      match end {
+        "" | "-1" => Some((start, usize::MAX)),
+        end => Some((start, end.parse().ok()?)),
+      }
+    }
+    None => {
+      let line = range.parse().ok()?;
+      Some((line, line))
+    }
+  }
+}
+
+fn select_tags(content: &str, tags: &str) -> String {
+  let mut selection: HashMap<&str, bool> = HashMap::new();
+  // Applies to all tagged regions without an explicit selection
+  let mut wildcard = None;
+  // Applies to the lines outside of all tagged regions
+  let mut untagged = None;
+
+  for tag in tags.split([';', ',']).map(str::trim) {
+    let (name, selected) = match tag.strip_prefix('!') {
+      Some(name) => (name, false),
+      None => (tag, true),
+    };
+    match name {
+      "" => (),
+      "**" => {
+        wildcard = Some(selected);
+        untagged = Some(selected);
+      }
+      "*" => wildcard = Some(selected),
+      name => {
+        selection.insert(name, selected);
+      }
+    }
+  }
+  // If we only exclude regions the rest of the document is included
+  let untagged = untagged
+    .unwrap_or_else(|| !selection.values().any(|selected| *selected) && wildcard != Some(true));
+
+  let mut open: Vec<&str> = Vec::new();
+  let mut found = HashSet::new();
+  let mut out = String::with_capacity(content.len());
+
+  for line in content.split_inclusive('\n') {
+    match tag_directive(line) {
+      Some((true, name)) => {
+        found.insert(name);
+        open.push(name);
+      }
+      Some((false, name)) => match open.iter().rposition(|tag| *tag == name) {
+        Some(position) => open.truncate(position),
+        None => warn!("end::{}[] without a matching tag", name),
+      },
+      None => {
+        let selected = open
+          .iter()
+          .rev()
+          .find_map(|tag| selection.get(tag).copied().or(wildcard))
+          .unwrap_or(untagged);
+        if selected {
+          out.push_str(line);
+        }
+      }
+    }
+  }
+
+  for (name, selected) in selection {
+    if selected && !found.contains(name) {
+      warn!("tag {} not found in included document", name);
+    }
+  }
+
+  out
+}
+
+/// Returns if the line starts or ends the tagged region with the name
+fn tag_directive(line: &str) -> Option<(bool, &str)> {
+  [("tag::", true), ("end::", false)]
+    .iter()
+    .find_map(|&(prefix, start)| {
+      let position = line.find(prefix)?;
+      let (name, rest) = line[position + prefix.len()..].split_once("[]")?;
+      if name.is_empty() || name.contains(char::is_whitespace) || !rest.trim().is_empty() {
+        return None;
+      }
+      Some((start, name))
+    })
+}
+
+fn reindent(content: &str, indent: usize) -> String {
+  let common = content
+    .lines()
+    .filter(|line| !line.trim().is_empty())
+    .map(|line| line.len() - line.trim_start().len())
+    .min()
+    .unwrap_or(0);
+  let prefix = " ".repeat(indent);
+
+  content
+    .split_inclusive('\n')
+    .map(|line| {
+      if line.trim().is_empty() {
+        line.trim_start_matches([' ', '\t']).to_string()
+      } else {
+        format!("{}{}", prefix, &line[common..])
+      }
+    })
+    .collect()
+}
+
+fn shift_level(element: ElementSpan, offset: i32) -> ElementSpan {
+  let mut element = element;
+
+  match &mut element.element {
+    Element::Title { level } => {
+      *level = (*level as i32 + offset).clamp(1, 6) as u32;
+    }
+    Element::IncludeElement(include) => {
+      include.inner.elements = std::mem::take(&mut include.inner.elements)
+        .into_iter()
+        .map(|element| shift_level(element, offset))
+        .collect();
+    }
+    _ => (),
+  }
+  element.children = std::mem::take(&mut element.children)
+    .into_iter()
+    .map(|child| shift_level(child, offset))
+    .collect();
+
+  element
+}
+
+/// Replaces the include directives in a listing with the content
+/// of the included files. Returns `None` if there is nothing to
+/// replace.
+fn include_plain_text(content: &str, source: &str, env: &mut Env) -> Option<String> {
+  if !content
+    .lines()
+    .any(|line| line.starts_with("include::") || line.starts_with("\\include::"))
+  {
+    return None;
+  }
+
+  let mut out = String::with_capacity(content.len());
+  for line in content.split_inclusive('\n') {
+    if line.starts_with("\\include::") {
+      out.push_str(&line[1..]);
+      continue;
+    }
+
+    let directive = line.trim_end_matches(['\n', '\r']);
+    let options = match AsciidocParser::parse(Rule::include_macro, directive)
+      .ok()
+      .and_then(|mut pairs| pairs.next())
+      .filter(|pair| pair.as_str() == directive)
+    {
+      Some(include) => process_include_macro(include),
+      None => {
+        out.push_str(line);
+        continue;
+      }
+    };
+    let path = options.get_attribute("path").unwrap_or("");
+    // Resolve the path relative to the current source file
+    let path = {
+      use std::path::Path;
+      if let Some(parent) = Path::new(source).parent() {
+        if let Some(parent_str) = parent.to_str() {
+          if !parent_str.is_empty() {
+            format!("{}/{}", parent_str, path)
+          } else {
+            path.to_string()
+          }
+        } else {
+          path.to_string()
+        }
+      } else {
+        path.to_string()
+      }
+    };
+
+    match env.read_to_string(&path) {
+      Ok(included) => {
+        let included = select_include_content(&included, &options);
+        out.push_str(&included);
+        if line.ends_with('\n') && !included.is_empty() && !included.ends_with('\n') {
+          out.push('\n');
+        }
+      }
+      Err(e) => {
+        warn!("couldn't read included file {} ({})", path, e);
+        out.push_str(&unresolved_directive(source, directive));
+        if line.ends_with('\n') {
+          out.push('\n');
+        }
+      }
+    }
+  }
+  // The listing content doesn't end with a newline
+  if !content.ends_with('\n') && out.ends_with('\n') {
+    out.pop();
+  }
+
+  Some(out)
+}
+
+/// The line which replaces an include directive whose file can't be
+/// read (like in asciidoctor)
+fn unresolved_directive(source: &str, directive: &str) -> String {
+  let source = if source.is_empty() { "<stdin>" } else { source };
+  format!("Unresolved directive in {} - {}", source, directive)
+}
+
 // Helper functions
 
 fn concat_elements<'a>(
@@ -868,7 +3100,7 @@ fn from_element<'a>(rule: &Pair<'a, asciidoc::Rule>, element: Element<'a>) -> El
   ElementSpan {
     element,
     source: None, // TODO
-    content: rule.as_str(),
+    content: Cow::Borrowed(rule.as_str()),
     children: Vec::new(),
     attributes: Vec::new(),
     positional_attributes: Vec::new(),
@@ -880,3 +3112,15 @@ fn from_element<'a>(rule: &Pair<'a, asciidoc::Rule>, element: Element<'a>) -> El
     end_col,
   }
 }
+
+/// Moves an element (and all of its children) parsed from a sub slice
+/// into the coordinates of the surrounding document
+fn shift_element<'a>(element: ElementSpan<'a>, base: &ElementSpan<'a>) -> ElementSpan<'a> {
+  let mut element = element.add_offset(base);
+  element.children = element
+    .children
+    .into_iter()
+    .map(|child| shift_element(child, base))
+    .collect();
+  element
+}
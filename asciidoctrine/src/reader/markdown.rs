@@ -2,6 +2,7 @@ pub use crate::ast::*;
 use crate::options::Opts;
 use crate::util::Env;
 use crate::Result;
+use std::borrow::Cow;
 use pulldown_cmark::{Event, Parser, Tag, TagEnd, HeadingLevel, CodeBlockKind, Options};
 
 pub struct MarkdownReader {}
@@ -86,7 +87,7 @@ impl MarkdownReader {
 
               let mut elem_span = ElementSpan {
                 source: None,
-                content: &input[range.clone()],
+                content: Cow::Borrowed(&input[range.clone()]),
                 element: Element::TypedBlock {
                   kind: BlockType::Listing,
                 },
@@ -138,7 +139,7 @@ impl MarkdownReader {
             Tag::Emphasis => {
               let elem_span = ElementSpan {
                 source: None,
-                content: &input[range.clone()],
+                content: Cow::Borrowed(&input[range.clone()]),
                 element: Element::Styled,
                 start: range.start,
                 end: range.end,
@@ -160,7 +161,7 @@ impl MarkdownReader {
             Tag::Strong => {
               let elem_span = ElementSpan {
                 source: None,
-                content: &input[range.clone()],
+                content: Cow::Borrowed(&input[range.clone()]),
                 element: Element::Styled,
                 start: range.start,
                 end: range.end,
@@ -182,7 +183,7 @@ impl MarkdownReader {
             Tag::Strikethrough => {
               let elem_span = ElementSpan {
                 source: None,
-                content: &input[range.clone()],
+                content: Cow::Borrowed(&input[range.clone()]),
                 element: Element::Styled,
                 start: range.start,
                 end: range.end,
@@ -209,7 +210,7 @@ impl MarkdownReader {
 
               let mut elem_span = ElementSpan {
                 source: None,
-                content: &input[range.clone()],
+                content: Cow::Borrowed(&input[range.clone()]),
                 element: Element::Link,
                 start: range.start,
                 end: range.end,
@@ -253,7 +254,7 @@ impl MarkdownReader {
 
               let mut elem_span = ElementSpan {
                 source: None,
-                content: &input[range.clone()],
+                content: Cow::Borrowed(&input[range.clone()]),
                 element: Element::Image,
                 start: range.start,
                 end: range.end,
@@ -294,7 +295,7 @@ impl MarkdownReader {
 
           let elem_span = ElementSpan {
             source: None,
-            content: &input[range.clone()],
+            content: Cow::Borrowed(&input[range.clone()]),
             element,
             start: range.start,
             end: range.end,
@@ -357,7 +358,7 @@ impl MarkdownReader {
 
           let text_elem = ElementSpan {
             source: None,
-            content: &input[range.clone()],
+            content: Cow::Borrowed(&input[range.clone()]),
             element: Element::Text,
             start: range.start,
             end: range.end,
@@ -383,7 +384,7 @@ impl MarkdownReader {
 
           let code_elem = ElementSpan {
             source: None,
-            content: &input[range.clone()],
+            content: Cow::Borrowed(&input[range.clone()]),
             element: Element::Styled,
             start: range.start,
             end: range.end,
@@ -419,7 +420,7 @@ impl MarkdownReader {
 
           let html_elem = ElementSpan {
             source: None,
-            content: &input[range.clone()],
+            content: Cow::Borrowed(&input[range.clone()]),
             element: Element::TypedBlock {
               kind: BlockType::Passtrough,
             },
@@ -450,11 +451,11 @@ impl MarkdownReader {
 
           let break_elem = ElementSpan {
             source: None,
-            content: if matches!(event, Event::SoftBreak) {
+            content: Cow::Borrowed(if matches!(event, Event::SoftBreak) {
               "\n"
             } else {
               "\n"
-            },
+            }),
             element: Element::Text,
             start: range.start,
             end: range.end,
@@ -478,7 +479,7 @@ impl MarkdownReader {
 
           let rule_elem = ElementSpan {
             source: None,
-            content: &input[range.clone()],
+            content: Cow::Borrowed(&input[range.clone()]),
             element: Element::ExternalContent,
             start: range.start,
             end: range.end,
@@ -531,9 +532,10 @@ impl crate::Reader for MarkdownReader {
     }
 
     Ok(AST {
-      content: input,
+      content: Cow::Borrowed(input),
       elements: self.convert_events(input),
       attributes,
+      header: DocumentHeader::default(),
     })
   }
 }
@@ -0,0 +1,397 @@
+use crate::ast::*;
+use std::borrow::Cow;
+use std::collections::HashSet;
+use std::iter::Peekable;
+
+/// Turns the flat titles of a document into a tree of sections
+///
+/// Every `Section` holds its title as the first child followed by
+/// all blocks up to the next title of the same or a higher level.
+/// The sections get an `id` (unless `sectids` is unset) and a
+/// `sectnum` attribute if `sectnums` is set.
+#[derive(Default)]
+pub struct SectionTree {}
+
+impl SectionTree {
+  pub fn new() -> Self {
+    SectionTree {}
+  }
+}
+
+impl crate::Extension for SectionTree {
+  fn transform<'a>(&mut self, input: AST<'a>) -> anyhow::Result<AST<'a>> {
+    Ok(build_sections(input))
+  }
+}
+
+pub fn build_sections(input: AST<'_>) -> AST<'_> {
+  let mut ast = input;
+  let mut builder = SectionBuilder::new(&ast);
+  let mut elements = std::mem::take(&mut ast.elements).into_iter().peekable();
+
+  let mut out = Vec::new();
+  // The document title is not a section
+  if ast.header.title.is_some() {
+    if let Some(title) = elements.next_if(|element| element.element == Element::Title { level: 1 })
+    {
+      out.push(title);
+    }
+  }
+  out.extend(builder.nest(&mut elements, 0, &ast.content));
+  ast.elements = out;
+
+  ast
+}
+
+/// Sets the `id` and `sectnum` attributes on the titles like
+/// [`build_sections`] but keeps the titles flat
+pub fn number_titles(input: AST<'_>) -> AST<'_> {
+  let mut ast = input;
+  let mut builder = SectionBuilder::new(&ast);
+  let mut elements = std::mem::take(&mut ast.elements);
+
+  // The document title is not a section
+  let skip = match elements.first() {
+    Some(title) if ast.header.title.is_some() && title.element == Element::Title { level: 1 } => 1,
+    _ => 0,
+  };
+  builder.number_all(&mut elements[skip..]);
+  ast.elements = elements;
+
+  ast
+}
+
+/// Generates the id of a section from its title
+///
+/// Like asciidoctor we drop all characters which are not part of a
+/// word and replace spaces, dots and hyphens by the separator.
+pub fn generate_id(title: &str, idprefix: &str, idseparator: &str) -> String {
+  let separator = idseparator.chars().next();
+  let mut id = String::new();
+  let mut after_separator = false;
+
+  for c in title.to_lowercase().chars() {
+    if c == ' ' || c == '.' || c == '-' {
+      if let Some(separator) = separator {
+        if !after_separator {
+          id.push(separator);
+          after_separator = true;
+        }
+      }
+    } else if c.is_alphanumeric() || c == '_' {
+      id.push(c);
+      after_separator = false;
+    }
+  }
+  if let Some(separator) = separator {
+    if id.ends_with(separator) {
+      id.pop();
+    }
+    if idprefix.is_empty() {
+      id = id.trim_start_matches(separator).to_string();
+    }
+  }
+
+  format!("{}{}", idprefix, id)
+}
+
+struct SectionBuilder {
+  /// All ids already used in the document
+  ids: HashSet<String>,
+  sectids: bool,
+  idprefix: String,
+  idseparator: String,
+  sectnums: bool,
+  sectnumlevels: u32,
+  /// The current number on each section level
+  numbers: Vec<u32>,
+}
+
+impl SectionBuilder {
+  fn new(ast: &AST) -> Self {
+    let mut ids = HashSet::new();
+    collect_ids(&ast.elements, &mut ids);
+
+    let mut builder = SectionBuilder {
+      ids,
+      sectids: true,
+      idprefix: "_".to_string(),
+      idseparator: "_".to_string(),
+      sectnums: false,
+      sectnumlevels: 3,
+      numbers: Vec::new(),
+    };
+    for attribute in ast.attributes.iter() {
+      builder.set_attribute(attribute);
+    }
+
+    builder
+  }
+
+  fn set_attribute(&mut self, attribute: &Attribute) {
+    let value = match &attribute.value {
+      AttributeValue::Ref(value) => value,
+      AttributeValue::String(value) => value.as_str(),
+    };
+    match attribute.key.as_str() {
+      "sectids" => self.sectids = true,
+      "sectids!" => self.sectids = false,
+      "idprefix" => self.idprefix = value.to_string(),
+      "idseparator" => self.idseparator = value.to_string(),
+      "sectnums" => self.sectnums = true,
+      "sectnums!" => self.sectnums = false,
+      "sectnumlevels" => match value.parse() {
+        Ok(levels) => self.sectnumlevels = levels,
+        Err(_) => warn!("invalid sectnumlevels {}", value),
+      },
+      _ => (),
+    }
+  }
+
+  /// Collects the elements up to the next title with a level of
+  /// at most `level` and nests the sections inside of them
+  fn nest<'a, I: Iterator<Item = ElementSpan<'a>>>(
+    &mut self,
+    elements: &mut Peekable<I>,
+    level: u32,
+    document: &Cow<'a, str>,
+  ) -> Vec<ElementSpan<'a>> {
+    let mut out = Vec::new();
+
+    while let Some(element) = elements
+      .next_if(|element| !matches!(element.element, Element::Title { level: l } if l <= level))
+    {
+      match element.element {
+        Element::Title { level } => {
+          let mut element = element;
+          self.number_title(&mut element, level);
+          let mut section = self.section(&element, level);
+          let mut children = vec![element];
+          children.extend(self.nest(elements, level, document));
+          section = extend_span(section, children.last(), document);
+          section.children = children;
+          out.push(section);
+        }
+        Element::Attribute(ref attribute) => {
+          self.set_attribute(attribute);
+          out.push(element);
+        }
+        Element::IncludeElement(mut include) => {
+          let inner = std::mem::take(&mut include.inner.elements);
+          include.inner.elements =
+            self.nest(&mut inner.into_iter().peekable(), 0, &include.inner.content);
+          out.push(ElementSpan {
+            element: Element::IncludeElement(include),
+            ..element
+          });
+        }
+        _ => out.push(element),
+      }
+    }
+
+    out
+  }
+
+  /// Sets the ids and numbers of the titles in document order
+  fn number_all(&mut self, elements: &mut [ElementSpan]) {
+    for element in elements.iter_mut() {
+      match &mut element.element {
+        Element::Title { level } => {
+          let level = *level;
+          self.number_title(element, level);
+        }
+        Element::Attribute(attribute) => self.set_attribute(attribute),
+        Element::IncludeElement(include) => self.number_all(&mut include.inner.elements),
+        _ => self.number_all(&mut element.children),
+      }
+    }
+  }
+
+  fn number_title(&mut self, title: &mut ElementSpan, level: u32) {
+    let id = match title
+      .get_attribute("id")
+      .or_else(|| title.get_attribute("anchor"))
+    {
+      Some(id) => Some(id.to_string()),
+      None if self.sectids => {
+        let name = title.get_attribute("name").unwrap_or("");
+        Some(self.unique_id(generate_id(name, &self.idprefix, &self.idseparator)))
+      }
+      None => None,
+    };
+    if let Some(id) = id {
+      replace_attribute(title, "id", id);
+    }
+
+    // The sections of the first level are numbered from level 2
+    // on, because level 1 are the parts of a book
+    if self.sectnums && level > 1 && level - 1 <= self.sectnumlevels {
+      let depth = (level - 1) as usize;
+      self.numbers.truncate(depth);
+      self.numbers.resize(depth, 0);
+      self.numbers[depth - 1] += 1;
+      let sectnum: String = self
+        .numbers
+        .iter()
+        .map(|number| format!("{}.", number))
+        .collect();
+      replace_attribute(title, "sectnum", sectnum);
+    }
+  }
+
+  /// Creates a section for the title with its id and number
+  fn section<'a>(&self, title: &ElementSpan<'a>, level: u32) -> ElementSpan<'a> {
+    let attributes = title
+      .attributes
+      .iter()
+      .filter(|attribute| attribute.key == "id" || attribute.key == "sectnum")
+      .cloned()
+      .collect();
+
+    ElementSpan {
+      element: Element::Section { level },
+      children: Vec::new(),
+      positional_attributes: Vec::new(),
+      attributes,
+      ..title.clone()
+    }
+  }
+
+  fn unique_id(&mut self, id: String) -> String {
+    let separator = self.idseparator.chars().next().unwrap_or('_');
+    let mut unique = id.clone();
+    let mut counter = 2;
+    while self.ids.contains(&unique) {
+      unique = format!("{}{}{}", id, separator, counter);
+      counter += 1;
+    }
+    self.ids.insert(unique.clone());
+
+    unique
+  }
+}
+
+/// Lets the section span up to the end of its last child
+fn extend_span<'a>(
+  section: ElementSpan<'a>,
+  last: Option<&ElementSpan<'a>>,
+  document: &Cow<'a, str>,
+) -> ElementSpan<'a> {
+  let mut section = section;
+
+  if let Some(last) = last {
+    if last.source == section.source && last.end > section.end {
+      section.end = last.end;
+      section.end_line = last.end_line;
+      section.end_col = last.end_col;
+      let range = section.start..section.end;
+      let content = match document {
+        Cow::Borrowed(document) => document.get(range).map(Cow::Borrowed),
+        Cow::Owned(document) => document
+          .get(range)
+          .map(|content| Cow::Owned(content.to_string())),
+      };
+      if let Some(content) = content {
+        section.content = content;
+      }
+    }
+  }
+
+  section
+}
+
+fn replace_attribute(element: &mut ElementSpan, key: &str, value: String) {
+  element.attributes.retain(|attribute| attribute.key != key);
+  element.attributes.push(Attribute {
+    key: key.to_string(),
+    value: AttributeValue::String(value),
+  });
+}
+
+pub(crate) fn collect_ids(elements: &[ElementSpan], ids: &mut HashSet<String>) {
+  for element in elements {
+    // The id of a cross reference is its target and not an anchor
+    if element.element == Element::XRef {
+      collect_ids(&element.children, ids);
+      continue;
+    }
+    for key in ["anchor", "id"] {
+      if let Some(id) = element.get_attribute(key) {
+        ids.insert(id.to_string());
+      }
+    }
+    if let Element::IncludeElement(include) = &element.element {
+      collect_ids(&include.inner.elements, ids);
+    }
+    collect_ids(&element.children, ids);
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::{options, util, AsciidocReader, Reader};
+  use clap::Parser;
+
+  #[test]
+  fn nest_and_number_sections() -> crate::Result<()> {
+    let content = "= Document\n:sectnums:\n\nPreamble\n\n== One\n\n=== Sub\n\n[[two]]\n== Two\n";
+    let opts = options::Opts::parse_from(vec!["asciidoctrine"]);
+    let mut env = util::Env::Cache(util::Cache::new());
+    let ast = build_sections(AsciidocReader::new().parse(content, &opts, &mut env)?);
+
+    let outline: Vec<_> = ast
+      .elements
+      .iter()
+      .map(|element| {
+        (
+          element.element.clone(),
+          element.get_attribute("id"),
+          element.get_attribute("sectnum"),
+          element.children.len(),
+        )
+      })
+      .collect();
+    assert_eq!(
+      outline,
+      vec![
+        (Element::Title { level: 1 }, None, None, 1),
+        (Element::Paragraph, None, None, 1),
+        (Element::Section { level: 2 }, Some("_one"), Some("1."), 2),
+        (Element::Section { level: 2 }, Some("two"), Some("2."), 1),
+      ]
+    );
+    let sub = &ast.elements[2].children[1];
+    assert_eq!(sub.element, Element::Section { level: 3 });
+    assert_eq!(sub.get_attribute("sectnum"), Some("1.1."));
+    assert_eq!(ast.elements[2].content, "== One\n\n=== Sub");
+
+    Ok(())
+  }
+
+  #[test]
+  fn references_do_not_reserve_ids() -> crate::Result<()> {
+    let content = "== First\n\nSee <<_first>>.\n";
+    let opts = options::Opts::parse_from(vec!["asciidoctrine"]);
+    let mut env = util::Env::Cache(util::Cache::new());
+    let ast = build_sections(AsciidocReader::new().parse(content, &opts, &mut env)?);
+
+    assert_eq!(ast.elements[0].get_attribute("id"), Some("_first"));
+
+    Ok(())
+  }
+
+  #[test]
+  fn generate_ids() {
+    assert_eq!(generate_id("A Chapter", "_", "_"), "_a_chapter");
+    assert_eq!(
+      generate_id("Version 1.2 - Notes", "_", "_"),
+      "_version_1_2_notes"
+    );
+    assert_eq!(generate_id("What's new?", "", "-"), "whats-new");
+    assert_eq!(
+      generate_id("Überschriften verschieben", "_", "_"),
+      "_überschriften_verschieben"
+    );
+  }
+}
@@ -6,6 +6,10 @@ use std::process::{Command, Stdio};
 
 pub trait Environment {
   fn read_to_string(&mut self, path: &str) -> crate::Result<String>;
+  /// Reads a binary file like an image
+  fn read(&mut self, path: &str) -> crate::Result<Vec<u8>> {
+    self.read_to_string(path).map(String::into_bytes)
+  }
   fn write(&mut self, path: &str, content: &str) -> crate::Result<()>;
   fn eval(&mut self, interpreter: &str, content: &str) -> crate::Result<(bool, String, String)>; // success, Stdout, Stderr
 }
@@ -27,6 +31,10 @@ impl Environment for Io {
     fs_read_to_string(path)
   }
 
+  fn read(&mut self, path: &str) -> crate::Result<Vec<u8>> {
+    Ok(fs::read(path)?)
+  }
+
   fn write(&mut self, path: &str, content: &str) -> crate::Result<()> {
     let path = Path::new(path);
     if let Some(path) = path.parent() {
@@ -83,7 +91,7 @@ pub struct EvalData {
 }
 
 pub struct Cache {
-  files: HashMap<String, String>,
+  files: HashMap<String, Vec<u8>>,
   evaluations: HashMap<(String, String), EvalData>,
 }
 
@@ -95,21 +103,39 @@ impl Cache {
     }
   }
 
+  /// Stores a binary file like an image
+  pub fn insert(&mut self, path: &str, content: Vec<u8>) {
+    self.files.insert(path.to_string(), content);
+  }
+
   pub fn get_files(self) -> HashMap<String, String> {
-    self.files
+    self
+      .files
+      .into_iter()
+      .map(|(path, content)| (path, String::from_utf8_lossy(&content).into_owned()))
+      .collect()
   }
 }
 
 impl Environment for Cache {
   fn read_to_string(&mut self, path: &str) -> crate::Result<String> {
-    Ok(self.files.remove(path).ok_or(io::Error::new(
+    let content = self.files.remove(path).ok_or(io::Error::new(
+      ErrorKind::NotFound,
+      "file not found in cache",
+    ))?;
+    Ok(String::from_utf8(content).map_err(|err| io::Error::new(ErrorKind::InvalidData, err))?)
+  }
+
+  fn read(&mut self, path: &str) -> crate::Result<Vec<u8>> {
+    // Unlike included files the same image can be used several times
+    Ok(self.files.get(path).cloned().ok_or(io::Error::new(
       ErrorKind::NotFound,
       "file not found in cache",
     ))?)
   }
 
   fn write(&mut self, path: &str, content: &str) -> crate::Result<()> {
-    self.files.insert(path.to_string(), content.to_string());
+    self.insert(path, content.as_bytes().to_vec());
 
     Ok(())
   }
@@ -130,7 +156,7 @@ impl Environment for Cache {
           self.files.remove(path);
         }
         for (path, content) in add_files.into_iter() {
-          self.files.insert(path, content);
+          self.files.insert(path, content.into_bytes());
         }
         Ok((success, out, err))
       }
@@ -160,6 +186,10 @@ impl Environment for FakeOutput {
     fs_read_to_string(path)
   }
 
+  fn read(&mut self, path: &str) -> crate::Result<Vec<u8>> {
+    Ok(fs::read(path)?)
+  }
+
   fn write(&mut self, path: &str, content: &str) -> crate::Result<()> {
     let real_path = Path::new(path);
     if real_path.exists() {
@@ -205,6 +235,14 @@ impl Environment for Env {
     }
   }
 
+  fn read(&mut self, path: &str) -> crate::Result<Vec<u8>> {
+    match self {
+      Env::Io(env) => env.read(path),
+      Env::Cache(env) => env.read(path),
+      Env::FakeOutput(env) => env.read(path),
+    }
+  }
+
   fn write(&mut self, path: &str, content: &str) -> crate::Result<()> {
     match self {
       Env::Io(env) => env.write(path, content),
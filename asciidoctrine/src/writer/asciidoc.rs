@@ -20,10 +20,13 @@ impl AsciidocWriter {
       Element::Title { level } => {
         self.write_title(element, *level, out)?;
       }
+      Element::Section { .. } => {
+        self.write_blocks(&element.children, out)?;
+      }
       Element::Paragraph => {
         self.write_paragraph(element, out)?;
       }
-      Element::Text => {
+      Element::Text | Element::LineBreak => {
         self.write_text(element, out)?;
       }
       Element::Styled => {
@@ -56,6 +59,26 @@ impl AsciidocWriter {
       Element::Footnote => {
         self.write_footnote(element, out)?;
       }
+      Element::InlineImage => {
+        self.write_inline_image(element, out)?;
+      }
+      Element::Keyboard | Element::Button | Element::Menu => {
+        self.write_ui_macro(element, out)?;
+      }
+      Element::InlinePassthrough => {
+        self.write_inline_passthrough(element, out)?;
+      }
+      Element::Stem => {
+        let notation = element.get_attribute("notation").unwrap_or("stem");
+        let content = element.get_attribute("content").unwrap_or("");
+        write!(out, "{}:[{}]", notation, content.replace(']', "\\]"))?;
+      }
+      Element::IndexTerm => {
+        self.write_index_term(element, out)?;
+      }
+      Element::Callout(_) => {
+        // Callouts are written as part of the listing content
+      }
       Element::Image => {
         self.write_image(element, out)?;
       }
@@ -65,8 +88,11 @@ impl AsciidocWriter {
       Element::Comment => {
         self.write_comment(element, out)?;
       }
-      Element::Attribute(_) => {
-        // Document attributes are handled separately
+      Element::Attribute(attribute) => {
+        self.write_attribute_entry(attribute, out)?;
+      }
+      Element::TableOfContents => {
+        write!(out, "toc::[]")?;
       }
       Element::IncludeElement(_) => {
         // TODO: Implement include element writing
@@ -92,14 +118,46 @@ impl AsciidocWriter {
     // AsciiDoc uses = for level 1, == for level 2, etc.
     let prefix = "=".repeat(level as usize);
 
+    if let Some(attrs) = Self::attribute_list(element, &["name", "authors", "revision", "id"]) {
+      writeln!(out, "{}", attrs)?;
+    }
+
     // The title text is stored in the "name" attribute
     let title = element.get_attribute("name").unwrap_or("");
     write!(out, "{} {}", prefix, title)?;
 
+    // The author and revision lines of the document header
+    if level == 1 {
+      for key in ["authors", "revision"] {
+        if let Some(line) = element.get_attribute(key) {
+          write!(out, "\n{}", line)?;
+        }
+      }
+    }
+
+    Ok(())
+  }
+
+  fn write_attribute_entry<W: Write>(
+    &mut self,
+    attribute: &crate::Attribute,
+    out: &mut W,
+  ) -> crate::Result<()> {
+    if attribute.key.ends_with('!') {
+      write!(out, ":{}:", attribute.key)?;
+    } else {
+      let value = Self::attribute_value_to_string(&attribute.value);
+      if value.is_empty() {
+        write!(out, ":{}:", attribute.key)?;
+      } else {
+        write!(out, ":{}: {}", attribute.key, value)?;
+      }
+    }
     Ok(())
   }
 
   fn write_paragraph<W: Write>(&mut self, element: &ElementSpan, out: &mut W) -> crate::Result<()> {
+    self.write_block_attributes(element, out)?;
     for child in &element.children {
       self.write_element(child, out)?;
     }
@@ -127,6 +185,12 @@ impl AsciidocWriter {
       Some("monospaced") | Some("monospace") | Some("code") => {
         write!(out, "`{}`", content)?;
       }
+      Some("double_quoted") => {
+        write!(out, "\"`{}`\"", content)?;
+      }
+      Some("single_quoted") => {
+        write!(out, "'`{}`'", content)?;
+      }
       _ => {
         // Unknown style, just write content
         write!(out, "{}", content)?;
@@ -142,20 +206,47 @@ impl AsciidocWriter {
     list_type: &ListType,
     out: &mut W,
   ) -> crate::Result<()> {
+    self.write_block_attributes(element, out)?;
+    self.write_nested_list(element, list_type, &[], out)
+  }
+
+  fn write_nested_list<W: Write>(
+    &mut self,
+    element: &ElementSpan,
+    list_type: &ListType,
+    parents: &[&ListType],
+    out: &mut W,
+  ) -> crate::Result<()> {
+    // The marker only gets longer if the list is nested in a list
+    // of the same kind
+    let level = parents
+      .iter()
+      .filter(|parent| Self::same_marker(parent, list_type))
+      .count() as u32
+      + 1;
+
     // Process list items directly to maintain context about list type
     for (i, child) in element.children.iter().enumerate() {
-      if let Element::ListItem(level) = &child.element {
-        self.write_list_item_with_type(child, *level, list_type, out)?;
-      }
-
-      if i < element.children.len() - 1 {
-        let next_child = &element.children[i + 1];
-        self.add_blank_lines(&child, &next_child, out)?;
+      if i > 0 {
+        writeln!(out)?;
       }
+      self.write_list_item_with_type(child, level, list_type, parents, out)?;
     }
     Ok(())
   }
 
+  fn same_marker(a: &ListType, b: &ListType) -> bool {
+    matches!(
+      (a, b),
+      (ListType::Number, ListType::Number)
+        | (ListType::Description, ListType::Description)
+        | (
+          ListType::Bullet | ListType::Checklist,
+          ListType::Bullet | ListType::Checklist
+        )
+    )
+  }
+
   fn write_list_item<W: Write>(
     &mut self,
     element: &ElementSpan,
@@ -164,7 +255,7 @@ impl AsciidocWriter {
   ) -> crate::Result<()> {
     // This shouldn't be called directly in list context, but keep for safety
     // Default to bullet list
-    self.write_list_item_with_type(element, level, &ListType::Bullet, out)
+    self.write_list_item_with_type(element, level, &ListType::Bullet, &[], out)
   }
 
   fn write_list_item_with_type<W: Write>(
@@ -172,6 +263,7 @@ impl AsciidocWriter {
     element: &ElementSpan,
     level: u32,
     list_type: &ListType,
+    parents: &[&ListType],
     out: &mut W,
   ) -> crate::Result<()> {
     // Write the appropriate marker based on list type
@@ -180,26 +272,51 @@ impl AsciidocWriter {
         let prefix = ".".repeat(level as usize);
         write!(out, "{} ", prefix)?;
       }
-      ListType::Bullet => {
+      ListType::Bullet | ListType::Checklist => {
         let prefix = "*".repeat(level as usize);
         write!(out, "{} ", prefix)?;
       }
+      ListType::Description => {
+        let term = element.get_attribute("term").unwrap_or("");
+        let marker = match level {
+          1..=3 => ":".repeat(level as usize + 1),
+          _ => ";;".to_string(),
+        };
+        write!(out, "{}{}", term, marker)?;
+        if !element.children.is_empty() {
+          write!(out, " ")?;
+        }
+      }
+      ListType::Callout => {
+        let number = element.get_attribute("callout").unwrap_or(".");
+        write!(out, "<{}> ", number)?;
+      }
+    }
+    match element.get_attribute("checked") {
+      Some("true") => write!(out, "[x] ")?,
+      Some(_) => write!(out, "[ ] ")?,
+      None => (),
     }
 
     // Write list item content
     // Don't add extra newline - let children handle it
-    for child in element.children.iter() {
+    for (i, child) in element.children.iter().enumerate() {
       match &child.element {
         Element::List(nested_type) => {
-          self.write_list(child, nested_type, out)?;
+          writeln!(out)?;
+          let mut parents = parents.to_vec();
+          parents.push(list_type);
+          self.write_nested_list(child, nested_type, &parents, out)?;
         }
-        Element::Paragraph => {
+        Element::Paragraph if i == 0 => {
           // For paragraphs in lists, write content inline (without the paragraph wrapper newline)
           for grandchild in &child.children {
             self.write_element(grandchild, out)?;
           }
         }
         _ => {
+          // Further blocks are attached with a list continuation
+          write!(out, "\n+\n")?;
           self.write_element(child, out)?;
         }
       }
@@ -208,6 +325,76 @@ impl AsciidocWriter {
     Ok(())
   }
 
+  /// Writes the anchor, the title and the attribute list of a block
+  fn write_block_attributes<W: Write>(
+    &mut self,
+    element: &ElementSpan,
+    out: &mut W,
+  ) -> crate::Result<()> {
+    if let Some(title) = element.get_attribute("title") {
+      writeln!(out, ".{}", title)?;
+    }
+    if let Some(attrs) = Self::attribute_list(element, &["content", "title"]) {
+      writeln!(out, "{}", attrs)?;
+    }
+    Ok(())
+  }
+
+  /// The attribute list of a block without the skipped attributes. The
+  /// id and the roles are written in the shorthand after the style.
+  fn attribute_list(element: &ElementSpan, skip: &[&str]) -> Option<String> {
+    let mut style = element
+      .positional_attributes
+      .first()
+      .map(Self::attribute_value_to_string)
+      .unwrap_or_default();
+    if let Some(anchor) = element.get_attribute("anchor") {
+      style.push_str(&format!("#{}", anchor));
+    }
+    if let Some(role) = element.get_attribute("role") {
+      for role in role.split_whitespace() {
+        style.push_str(&format!(".{}", role));
+      }
+    }
+
+    // An empty style only keeps the place of the further positional
+    // attributes
+    let mut attrs = Vec::new();
+    if !style.is_empty() || element.positional_attributes.len() > 1 {
+      attrs.push(style);
+    }
+    attrs.extend(
+      element
+        .positional_attributes
+        .iter()
+        .skip(1)
+        .map(Self::attribute_value_to_string),
+    );
+    attrs.extend(
+      element
+        .attributes
+        .iter()
+        .filter(|attr| {
+          !skip.contains(&attr.key.as_str()) && !["anchor", "role"].contains(&attr.key.as_str())
+        })
+        .map(|attr| {
+          let value = Self::attribute_value_to_string(&attr.value);
+          // Values with a separator have to be quoted
+          if value.contains(',') {
+            format!("{}=\"{}\"", attr.key, value)
+          } else {
+            format!("{}={}", attr.key, value)
+          }
+        }),
+    );
+
+    if attrs.is_empty() {
+      None
+    } else {
+      Some(format!("[{}]", attrs.join(",")))
+    }
+  }
+
   fn write_typed_block<W: Write>(
     &mut self,
     element: &ElementSpan,
@@ -216,44 +403,27 @@ impl AsciidocWriter {
   ) -> crate::Result<()> {
     use crate::BlockType;
 
-    // Write block attributes if present (exclude "content" attribute)
-    let has_positional_attrs = !element.positional_attributes.is_empty();
-    let named_attrs: Vec<_> = element
-      .attributes
-      .iter()
-      .filter(|attr| attr.key != "content")
-      .collect();
-
-    if has_positional_attrs || !named_attrs.is_empty() {
-      write!(out, "[")?;
-
-      // Write positional attributes (like source, language)
-      for (i, attr) in element.positional_attributes.iter().enumerate() {
-        if i > 0 {
-          write!(out, ",")?;
-        }
-        match attr {
-          crate::AttributeValue::String(s) => write!(out, "{}", s)?,
-          crate::AttributeValue::Ref(r) => write!(out, "{}", r)?,
+    // Admonition paragraphs are written in their short form
+    if let BlockType::Admonition(admonition) = kind {
+      if element.get_attribute("content").is_none() {
+        write!(out, "{}: ", admonition.keyword())?;
+        for child in &element.children {
+          self.write_paragraph(child, out)?;
         }
+        return Ok(());
       }
-
-      // Write named attributes (except "content")
-      for attr in named_attrs {
-        let value = Self::attribute_value_to_string(&attr.value);
-        write!(out, ",{}={}", attr.key, value)?;
-      }
-
-      writeln!(out, "]")?;
     }
 
+    self.write_block_attributes(element, out)?;
+
     // Determine delimiter based on block type
     let delimiter = match kind {
       BlockType::Listing => "----",
       BlockType::Literal => "....",
-      BlockType::Example => "====",
+      BlockType::Example | BlockType::Admonition(_) => "====",
       BlockType::Sidebar => "****",
-      BlockType::Quote => "____",
+      BlockType::Quote | BlockType::Verse => "____",
+      BlockType::Open => "--",
       BlockType::Passtrough => "++++",
       BlockType::Comment => "////",
     };
@@ -261,7 +431,7 @@ impl AsciidocWriter {
     writeln!(out, "{}", delimiter)?;
 
     // Write block content from the "content" attribute or element.content
-    let content = element.get_attribute("content").unwrap_or(element.content);
+    let content = element.get_attribute("content").unwrap_or(&element.content);
     write!(out, "{}", content.trim())?;
 
     writeln!(out)?;
@@ -319,15 +489,109 @@ impl AsciidocWriter {
   fn write_link<W: Write>(&mut self, element: &ElementSpan, out: &mut W) -> crate::Result<()> {
     let url = element.get_attribute("url").unwrap_or("");
 
-    write!(out, "link:{}[", url)?;
+    // Relative targets need the link macro
+    if element.content.starts_with("link:") || element.get_attribute("protocol").is_none() {
+      write!(out, "link:{}[", url)?;
+    } else if element.positional_attributes.is_empty() {
+      write!(out, "{}", url)?;
+      return Ok(());
+    } else {
+      write!(out, "{}[", url)?;
+    }
+
+    // The link text and the subject and body of a mail
+    let attributes: Vec<String> = element
+      .positional_attributes
+      .iter()
+      .map(Self::attribute_value_to_string)
+      .collect();
+    write!(out, "{}]", attributes.join(","))?;
+    Ok(())
+  }
+
+  fn write_inline_image<W: Write>(
+    &mut self,
+    element: &ElementSpan,
+    out: &mut W,
+  ) -> crate::Result<()> {
+    let path = element.get_attribute("path").unwrap_or("");
+
+    let mut attrs: Vec<String> = element
+      .positional_attributes
+      .iter()
+      .map(Self::attribute_value_to_string)
+      .collect();
+    attrs.extend(
+      element
+        .attributes
+        .iter()
+        .filter(|attr| attr.key != "path")
+        .map(|attr| format!("{}={}", attr.key, attr.value.as_str())),
+    );
+
+    write!(out, "image:{}[{}]", path, attrs.join(","))?;
+    Ok(())
+  }
+
+  fn write_ui_macro<W: Write>(&mut self, element: &ElementSpan, out: &mut W) -> crate::Result<()> {
+    let items: Vec<&str> = element
+      .positional_attributes
+      .iter()
+      .map(|item| item.as_str())
+      .collect();
+
+    match element.element {
+      Element::Keyboard => write!(out, "kbd:[{}]", items.join("+"))?,
+      Element::Button => write!(
+        out,
+        "btn:[{}]",
+        element.get_attribute("content").unwrap_or("")
+      )?,
+      _ => write!(
+        out,
+        "menu:{}[{}]",
+        element.get_attribute("menu").unwrap_or(""),
+        items.join(" > ")
+      )?,
+    }
+    Ok(())
+  }
+
+  fn write_inline_passthrough<W: Write>(
+    &mut self,
+    element: &ElementSpan,
+    out: &mut W,
+  ) -> crate::Result<()> {
+    let content = element.get_attribute("content").unwrap_or("");
 
-    // Link text is in positional_attributes[0]
-    if let Some(link_text) = element.positional_attributes.get(0) {
-      let text = Self::attribute_value_to_string(link_text);
-      write!(out, "{}", text)?;
+    if element.content.starts_with("+++") {
+      write!(out, "+++{}+++", content)?;
+    } else if element.content.starts_with("pass:") {
+      let subs = element.get_attribute("subs").unwrap_or("");
+      write!(out, "pass:{}[{}]", subs, content.replace(']', "\\]"))?;
+    } else {
+      // A text of a block without special characters substitution
+      write!(out, "{}", element.content)?;
     }
+    Ok(())
+  }
+
+  fn write_index_term<W: Write>(
+    &mut self,
+    element: &ElementSpan,
+    out: &mut W,
+  ) -> crate::Result<()> {
+    let terms: Vec<&str> = element
+      .positional_attributes
+      .iter()
+      .map(|term| term.as_str().trim())
+      .collect();
 
-    write!(out, "]")?;
+    if element.get_attribute("visible") == Some("true") {
+      write!(out, "(({}))", terms.join(", "))?;
+    } else {
+      write!(out, "((({})))", terms.join(", "))?;
+    }
     Ok(())
   }
 
@@ -343,48 +607,73 @@ impl AsciidocWriter {
   }
 
   fn write_xref<W: Write>(&mut self, element: &ElementSpan, out: &mut W) -> crate::Result<()> {
-    let anchor = element.get_attribute("anchor").unwrap_or("");
-
-    write!(out, "<<{}", anchor)?;
+    let target = match (element.get_attribute("path"), element.get_attribute("id")) {
+      (Some(path), Some(id)) => format!("{}#{}", path, id),
+      (Some(path), None) => format!("{}#", path),
+      (None, id) => id.unwrap_or("").to_string(),
+    };
+    let content = element.get_attribute("content");
 
-    if !element.children.is_empty() {
-      write!(out, ",")?;
-      for child in &element.children {
-        self.write_element(child, out)?;
+    if element.content.starts_with("xref:") {
+      write!(out, "xref:{}[{}]", target, content.unwrap_or(""))?;
+    } else {
+      match content {
+        Some(content) => write!(out, "<<{},{}>>", target, content)?,
+        None => write!(out, "<<{}>>", target)?,
       }
     }
-
-    write!(out, ">>")?;
     Ok(())
   }
 
   fn write_image<W: Write>(&mut self, element: &ElementSpan, out: &mut W) -> crate::Result<()> {
     let path = element.get_attribute("path").unwrap_or("");
 
-    write!(out, "image::{}[", path)?;
-
-    // Write alt text from children or attributes
-    if let Some(alt) = element.get_attribute("alt") {
-      write!(out, "{}", alt)?;
-    } else {
-      for child in &element.children {
-        self.write_element(child, out)?;
-      }
-    }
+    // The alternative text and the size belong to the macro, the
+    // other attributes to the block
+    let mut block = element.clone();
+    block.positional_attributes.clear();
+    block
+      .attributes
+      .retain(|attr| !["path", "content"].contains(&attr.key.as_str()));
+    self.write_block_attributes(&block, out)?;
 
-    write!(out, "]")?;
+    let attrs: Vec<String> = element
+      .positional_attributes
+      .iter()
+      .map(Self::attribute_value_to_string)
+      .collect();
+    write!(out, "image::{}[{}]", path, attrs.join(","))?;
     Ok(())
   }
 
   fn write_anchor<W: Write>(&mut self, element: &ElementSpan, out: &mut W) -> crate::Result<()> {
-    if let Some(name) = element.get_attribute("name") {
-      writeln!(out, "[[{}]]", name)?;
+    if let Some(id) = element.get_attribute("anchor") {
+      match element.get_attribute("reftext") {
+        Some(reftext) => write!(out, "[[{},{}]]", id, reftext)?,
+        None => write!(out, "[[{}]]", id)?,
+      }
     }
     Ok(())
   }
 
   fn write_comment<W: Write>(&mut self, element: &ElementSpan, out: &mut W) -> crate::Result<()> {
-    writeln!(out, "// {}", element.content.trim())?;
+    // The comment lines inside of a paragraph start with a line break
+    write!(out, "{}", element.content)?;
+    Ok(())
+  }
+
+  fn write_blocks<W: Write>(&mut self, elements: &[ElementSpan], out: &mut W) -> crate::Result<()> {
+    for (i, element) in elements.iter().enumerate() {
+      self.write_element(element, out)?;
+
+      // Add blank line between top-level elements
+      // Don't add blank line after list items or within lists
+      if i < elements.len() - 1 {
+        let next_element = &elements[i + 1];
+        self.add_blank_lines(element, next_element, out)?;
+      }
+    }
+
     Ok(())
   }
 
@@ -395,14 +684,17 @@ impl AsciidocWriter {
     out: &mut W,
   ) -> crate::Result<()> {
     match (&element.element, &next_element.element) {
-      (_, Element::Title { level: _ }) => {
+      (_, Element::Title { level: _ })
+      | (_, Element::Section { .. })
+      | (Element::Section { .. }, _) => {
         writeln!(out)?;
         writeln!(out)?;
       }
       (Element::List(_), _) => {
         writeln!(out)?;
+        writeln!(out)?;
       }
-      (Element::Paragraph, _) => {
+      (Element::Paragraph, _) | (Element::Image, _) => {
         writeln!(out)?;
         writeln!(out)?;
       }
@@ -413,6 +705,17 @@ impl AsciidocWriter {
         writeln!(out)?;
         writeln!(out)?;
       }
+      (Element::Attribute(_), Element::Attribute(_)) => {
+        writeln!(out)?;
+      }
+      (Element::Attribute(_), _) => {
+        writeln!(out)?;
+        writeln!(out)?;
+      }
+      // A callout list directly follows its listing
+      (Element::TypedBlock { kind: _ }, Element::List(ListType::Callout)) => {
+        writeln!(out)?;
+      }
       (Element::TypedBlock { kind: _ }, _) => {
         writeln!(out)?;
         writeln!(out)?;
@@ -430,27 +733,29 @@ impl<W: io::Write> crate::Writer<W> for AsciidocWriter {
   fn write<'a>(&mut self, ast: AST, _args: &options::Opts, out: W) -> crate::Result<()> {
     let mut out = out;
 
-    // Write document attributes first
+    // The document title and the document attributes form the header
+    let has_title = matches!(
+      ast.elements.first().map(|element| &element.element),
+      Some(Element::Title { level: 1 })
+    );
+    if has_title {
+      self.write_element(&ast.elements[0], &mut out)?;
+      writeln!(out)?;
+    }
     for attr in &ast.attributes {
-      let value = Self::attribute_value_to_string(&attr.value);
-      writeln!(out, ":{}: {}", attr.key, value)?;
+      self.write_attribute_entry(attr, &mut out)?;
+      writeln!(out)?;
     }
 
-    if !ast.attributes.is_empty() {
+    // A blank line separates the header from the body
+    let has_header = has_title || !ast.attributes.is_empty();
+    let skip = if has_title { 1 } else { 0 };
+    if has_header && ast.elements.len() > skip {
       writeln!(out)?;
     }
 
     // Write all elements
-    for (i, element) in ast.elements.iter().enumerate() {
-      self.write_element(element, &mut out)?;
-
-      // Add blank line between top-level elements
-      // Don't add blank line after list items or within lists
-      if i < ast.elements.len() - 1 {
-        let next_element = &ast.elements[i + 1];
-        self.add_blank_lines(&element, &next_element, &mut out)?;
-      }
-    }
+    self.write_blocks(&ast.elements[skip..], &mut out)?;
 
     Ok(())
   }
@@ -0,0 +1,242 @@
+pub use crate::ast::*;
+use crate::sections::number_titles;
+use crate::util::Environment;
+use crate::writer::html;
+use crate::{options, Result};
+use serde::Serialize;
+use std::collections::HashSet;
+use std::io;
+use std::path::Path;
+use tera::{Context, Tera};
+
+/// Writes html with a page for every chunk of the document. The
+/// document is split at the sections up to the `chunk-level`.
+///
+/// The output gets the index page with the document title, the
+/// preamble and the table of contents. The chunks and the stylesheet
+/// are written next to it through the environment.
+pub struct ChunkedHtmlWriter {
+  io: crate::util::Env,
+}
+
+impl ChunkedHtmlWriter {
+  pub fn new() -> Self {
+    ChunkedHtmlWriter {
+      io: crate::util::Env::Io(crate::util::Io::new()),
+    }
+  }
+
+  /// Writes the chunks through the environment, e.g. a `Cache` in
+  /// tests or the `FakeOutput` of a dry run
+  pub fn with_env(io: crate::util::Env) -> Self {
+    ChunkedHtmlWriter { io }
+  }
+
+  /// Gives back the environment with the written chunks
+  pub fn into_env(self) -> crate::util::Env {
+    self.io
+  }
+}
+
+impl Default for ChunkedHtmlWriter {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+/// A link of the navigation between the pages
+#[derive(Serialize, Clone)]
+struct Link {
+  href: String,
+  title: String,
+}
+
+struct Page<'b, 'a> {
+  file: String,
+  elements: Vec<&'b ElementSpan<'a>>,
+  /// The level of the heading. The index page has none.
+  level: u32,
+  link: Link,
+}
+
+impl<T: io::Write> crate::Writer<T> for ChunkedHtmlWriter {
+  fn write<'a>(&mut self, ast: AST, args: &options::Opts, mut out: T) -> Result<()> {
+    let ast = number_titles(ast);
+    html::begin_document(&ast);
+    html::embed_images(&ast, args, &mut self.io);
+
+    let chunk_level = match ast.get_attribute("chunk-level").map(str::parse) {
+      Some(Ok(level)) if (1..=5).contains(&level) => level,
+      Some(_) => {
+        warn!("invalid chunk-level, using 1");
+        1
+      }
+      None => 1,
+    };
+    let mut chunks = Vec::new();
+    // The levels of asciidoc start with the document title at 0
+    html::split_parts(&ast.elements, chunk_level + 1, &mut chunks);
+
+    let doctitle = ast.header.title.as_deref();
+    let title_element = ast
+      .elements
+      .first()
+      .filter(|element| doctitle.is_some() && element.element == Element::Title { level: 1 });
+    // The document title and the preamble stay on the index page
+    let index = match chunks.first().map(|chunk| html::part_heading(chunk)) {
+      Some(None) => chunks.remove(0),
+      Some(Some(heading)) if title_element.is_some_and(|title| std::ptr::eq(heading, title)) => {
+        chunks.remove(0)
+      }
+      _ => Vec::new(),
+    };
+
+    let directory = args
+      .output
+      .as_ref()
+      .and_then(|output| output.parent())
+      .unwrap_or_else(|| Path::new(""));
+    let index_file = args
+      .output
+      .as_ref()
+      .and_then(|output| output.file_name())
+      .and_then(|name| name.to_str())
+      .unwrap_or("index.html");
+    let mut files = HashSet::new();
+    files.insert(index_file.to_string());
+    let mut pages = vec![Page {
+      file: index_file.to_string(),
+      elements: index,
+      level: 0,
+      link: Link {
+        href: index_file.to_string(),
+        title: html::escape_attribute(doctitle.unwrap_or("Index")),
+      },
+    }];
+    for (number, chunk) in chunks.into_iter().enumerate() {
+      let heading = html::part_heading(&chunk);
+      // The pages are named after the sections
+      let file = match chunk
+        .first()
+        .and_then(|element| element.get_attribute("id"))
+        .or_else(|| heading.and_then(|heading| heading.get_attribute("id")))
+      {
+        Some(id) if !files.contains(&format!("{}.html", id)) => format!("{}.html", id),
+        _ => format!("chunk-{}.html", number + 1),
+      };
+      files.insert(file.clone());
+      let level = match heading.map(|heading| &heading.element) {
+        Some(Element::Title { level }) => *level,
+        _ => chunk_level + 1,
+      };
+      pages.push(Page {
+        link: Link {
+          href: file.clone(),
+          title: html::escape_attribute(
+            &heading.map(heading_label).unwrap_or_else(|| file.clone()),
+          ),
+        },
+        file,
+        elements: chunk,
+        level,
+      });
+    }
+
+    // The references between the pages need the file of their target
+    let parts: Vec<Vec<&ElementSpan>> = pages.iter().map(|page| page.elements.clone()).collect();
+    let page_files: Vec<String> = pages.iter().map(|page| page.file.clone()).collect();
+    html::set_part_files(&parts, &page_files);
+
+    let mut stylesheet = match &args.stylesheet {
+      Some(path) => {
+        let path = path.to_str().expect("path to stylesheet unreadable");
+        self.io.read_to_string(path)?
+      }
+      None => include_str!("assets/asciidoctor.css").to_string(),
+    };
+    stylesheet.push_str(&html::highlight_stylesheet());
+    self.io.write(
+      &directory.join("stylesheet.css").to_string_lossy(),
+      &stylesheet,
+    )?;
+
+    let mut tera = Tera::default();
+    tera.autoescape_on(vec![]);
+    tera
+      .add_raw_templates(vec![
+        ("chunk.html", include_str!("assets/chunk.html")),
+        ("chunk-nav.html", include_str!("assets/chunk-nav.html")),
+      ])
+      .expect("couldn't load default template");
+    // Own templates can extend `chunk.html` and replace its blocks
+    let template = match &args.template {
+      Some(path) => {
+        let path = path.to_str().expect("path to template unreadable");
+        let template = if path == "-" {
+          "{{body}}{{toc}}".to_string()
+        } else {
+          self.io.read_to_string(path)?
+        };
+        tera.add_raw_template("custom.html", &template)?;
+        "custom.html"
+      }
+      None => "chunk.html",
+    };
+
+    let mut context = Context::new();
+    html::insert_metadata(&ast, &mut context);
+    context.insert("body_class", "article");
+    context.insert("stylesheet_href", "stylesheet.css");
+
+    for (index, page) in pages.iter().enumerate() {
+      let header = match title_element {
+        Some(_) if index == 0 => Some(&ast.header),
+        _ => None,
+      };
+      html::begin_part_file(&page.file);
+      let mut body = Vec::new();
+      html::write_part(&page.elements, header, &mut body)?;
+      let mut toc = Vec::new();
+      if index == 0 {
+        html::write_document_toc(&ast, &mut toc)?;
+      }
+      // The page of the section above or the index page
+      let up = pages[..index]
+        .iter()
+        .rev()
+        .find(|other| other.level < page.level)
+        .map(|other| &other.link);
+
+      context.insert("is_index", &(index == 0));
+      context.insert("title", &page.link.title);
+      context.insert("body", std::str::from_utf8(&body)?);
+      context.insert("toc", std::str::from_utf8(&toc)?);
+      context.insert("prev", &index.checked_sub(1).map(|prev| &pages[prev].link));
+      context.insert("next", &pages.get(index + 1).map(|next| &next.link));
+      context.insert("up", &up);
+      let html = tera.render(template, &context)?;
+
+      match index {
+        0 => out.write_all(html.as_bytes())?,
+        _ => self
+          .io
+          .write(&directory.join(&page.file).to_string_lossy(), &html)?,
+      }
+    }
+    out.flush()?;
+
+    Ok(())
+  }
+}
+
+// Helper Functions
+//----------------------------------------------------
+
+/// The title of a heading with its number
+fn heading_label(heading: &ElementSpan) -> String {
+  let name = heading.get_attribute("name").unwrap_or("");
+  match heading.get_attribute("sectnum") {
+    Some(sectnum) => format!("{} {}", sectnum, name),
+    None => name.to_string(),
+  }
+}
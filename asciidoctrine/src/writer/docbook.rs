@@ -0,0 +1,878 @@
+pub use crate::ast::*;
+use crate::sections::build_sections;
+use crate::{options, AsciidoctrineError, Result};
+use std::io;
+
+#[derive(Default)]
+pub struct DocbookWriter {}
+
+impl DocbookWriter {
+  pub fn new() -> Self {
+    DocbookWriter {}
+  }
+}
+
+impl<T: io::Write> crate::Writer<T> for DocbookWriter {
+  fn write<'a>(&mut self, ast: AST, _args: &options::Opts, mut out: T) -> Result<()> {
+    // DocBook needs the nested sections
+    let ast = build_sections(ast);
+
+    out.write_all(b"<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n")?;
+    if ast.get_attribute("toc").is_some() {
+      out.write_all(b"<?asciidoc-toc?>\n")?;
+    }
+    if ast.get_attribute("sectnums").is_some() {
+      out.write_all(b"<?asciidoc-numbered?>\n")?;
+    }
+    out.write_all(
+      format!(
+        "<article xmlns=\"http://docbook.org/ns/docbook\" xmlns:xl=\"http://www.w3.org/1999/xlink\" version=\"5.0\" xml:lang=\"{}\">\n",
+        escape_attribute(ast.get_attribute("lang").unwrap_or("en"))
+      )
+      .as_bytes(),
+    )?;
+    write_info(&ast, &mut out)?;
+
+    // The document title is already part of the info
+    let skip = match ast.elements.first() {
+      Some(title) if ast.header.title.is_some() && title.element == Element::Title { level: 1 } => {
+        1
+      }
+      _ => 0,
+    };
+    for element in ast.elements.iter().skip(skip) {
+      write_block(element, &mut out)?;
+    }
+    out.write_all(b"</article>\n")?;
+    out.flush()?;
+
+    Ok(())
+  }
+}
+
+/// Writes the metadata of the document header
+fn write_info<T: io::Write>(ast: &AST, out: &mut T) -> Result<()> {
+  let header = &ast.header;
+  out.write_all(b"<info>\n")?;
+  if let Some(title) = &header.title {
+    out.write_all(format!("<title>{}</title>\n", escape_text(title)).as_bytes())?;
+  }
+  if let Some(date) = header.revision.as_ref().and_then(|r| r.date.as_ref()) {
+    out.write_all(format!("<date>{}</date>\n", escape_text(date)).as_bytes())?;
+  }
+
+  if header.authors.len() > 1 {
+    out.write_all(b"<authorgroup>\n")?;
+  }
+  for author in header.authors.iter() {
+    out.write_all(b"<author>\n<personname>\n")?;
+    out.write_all(
+      format!(
+        "<firstname>{}</firstname>\n",
+        escape_text(&author.firstname)
+      )
+      .as_bytes(),
+    )?;
+    if let Some(middlename) = &author.middlename {
+      out.write_all(format!("<othername>{}</othername>\n", escape_text(middlename)).as_bytes())?;
+    }
+    if let Some(lastname) = &author.lastname {
+      out.write_all(format!("<surname>{}</surname>\n", escape_text(lastname)).as_bytes())?;
+    }
+    out.write_all(b"</personname>\n")?;
+    if let Some(email) = &author.email {
+      out.write_all(format!("<email>{}</email>\n", escape_text(email)).as_bytes())?;
+    }
+    out.write_all(b"</author>\n")?;
+  }
+  if header.authors.len() > 1 {
+    out.write_all(b"</authorgroup>\n")?;
+  }
+  if let Some(author) = header.authors.first() {
+    out.write_all(
+      format!(
+        "<authorinitials>{}</authorinitials>\n",
+        escape_text(&author.initials)
+      )
+      .as_bytes(),
+    )?;
+  }
+
+  if let Some(revision) = &header.revision {
+    out.write_all(b"<revhistory>\n<revision>\n")?;
+    out.write_all(
+      format!(
+        "<revnumber>{}</revnumber>\n",
+        escape_text(revision.number.as_deref().unwrap_or(""))
+      )
+      .as_bytes(),
+    )?;
+    out.write_all(
+      format!(
+        "<date>{}</date>\n",
+        escape_text(revision.date.as_deref().unwrap_or(""))
+      )
+      .as_bytes(),
+    )?;
+    if let Some(author) = header.authors.first() {
+      out.write_all(
+        format!(
+          "<authorinitials>{}</authorinitials>\n",
+          escape_text(&author.initials)
+        )
+        .as_bytes(),
+      )?;
+    }
+    if let Some(remark) = &revision.remark {
+      out.write_all(format!("<revremark>{}</revremark>\n", escape_text(remark)).as_bytes())?;
+    }
+    out.write_all(b"</revision>\n</revhistory>\n")?;
+  }
+  out.write_all(b"</info>\n")?;
+
+  Ok(())
+}
+
+fn write_block<T: io::Write>(input: &ElementSpan, out: &mut T) -> Result<()> {
+  match &input.element {
+    Element::Section { .. } => {
+      let mut children = input.children.iter();
+      let title = children.next().ok_or(AsciidoctrineError::MalformedAst)?;
+      out
+        .write_all(format!("<section{}>\n", id_attribute(input.get_attribute("id"))).as_bytes())?;
+      write_title(title.get_attribute("name"), out)?;
+      for element in children {
+        write_block(element, out)?;
+      }
+      out.write_all(b"</section>\n")?;
+    }
+    // Titles which are not part of a section (e.g. inside of blocks)
+    Element::Title { level } => {
+      let name = input.get_attribute("name").unwrap_or("");
+      out.write_all(
+        format!(
+          "<bridgehead{} renderas=\"sect{}\">{}</bridgehead>\n",
+          id_attribute(input.get_attribute("id")),
+          level.saturating_sub(1).clamp(1, 5),
+          escape_text(name)
+        )
+        .as_bytes(),
+      )?;
+    }
+    Element::Paragraph => match input.get_attribute("title") {
+      Some(title) => {
+        out.write_all(format!("<formalpara{}>\n", anchor(input)).as_bytes())?;
+        write_title(Some(title), out)?;
+        out.write_all(b"<para>")?;
+        write_inlines(&input.children, out)?;
+        out.write_all(b"</para>\n</formalpara>\n")?;
+      }
+      None => {
+        out.write_all(format!("<simpara{}>", anchor(input)).as_bytes())?;
+        write_inlines(&input.children, out)?;
+        out.write_all(b"</simpara>\n")?;
+      }
+    },
+    Element::List(list_type) => write_list(list_type, input, out)?,
+    Element::ListItem(_) => {
+      for element in input.children.iter() {
+        write_block(element, out)?;
+      }
+    }
+    Element::TypedBlock { kind } => write_typed_block(kind, input, out)?,
+    Element::Table => write_table(input, out)?,
+    Element::Image => {
+      let path = input.get_attribute("path").unwrap_or("");
+      let alt = positional_attribute(input, 0).unwrap_or(path);
+      let title = input.get_attribute("title");
+      match title {
+        Some(_) => out.write_all(format!("<figure{}>\n", anchor(input)).as_bytes())?,
+        None => out.write_all(format!("<informalfigure{}>\n", anchor(input)).as_bytes())?,
+      }
+      write_title(title, out)?;
+      out.write_all(b"<mediaobject>\n<imageobject>\n")?;
+      out.write_all(
+        format!(
+          "<imagedata fileref=\"{}\"{}/>\n",
+          escape_attribute(path),
+          image_size(input)
+        )
+        .as_bytes(),
+      )?;
+      out.write_all(b"</imageobject>\n")?;
+      out.write_all(
+        format!(
+          "<textobject><phrase>{}</phrase></textobject>\n",
+          escape_text(alt)
+        )
+        .as_bytes(),
+      )?;
+      out.write_all(b"</mediaobject>\n")?;
+      match title {
+        Some(_) => out.write_all(b"</figure>\n")?,
+        None => out.write_all(b"</informalfigure>\n")?,
+      }
+    }
+    Element::Anchor => {
+      if let Some(id) = input.get_attribute("anchor") {
+        out.write_all(format!("<anchor xml:id=\"{}\"/>\n", escape_attribute(id)).as_bytes())?;
+      }
+    }
+    Element::IncludeElement(include) => {
+      for element in include.inner.elements.iter() {
+        write_block(element, out)?;
+      }
+    }
+    // Attribute entries are already resolved by the reader
+    Element::Attribute(_) => (),
+    // The table of contents is generated by the DocBook toolchain
+    Element::TableOfContents => (),
+    Element::Comment => (),
+    Element::ExternalContent => {
+      warn!("external content is not supported in DocBook");
+    }
+    Element::Error(msg) => {
+      warn!("{}", msg);
+      out.write_all(format!("<!-- ERROR: {} -->\n", msg.replace("--", "- -")).as_bytes())?;
+    }
+    Element::Styled
+    | Element::Text
+    | Element::XRef
+    | Element::Link
+    | Element::Footnote
+    | Element::InlineImage
+    | Element::Keyboard
+    | Element::Button
+    | Element::Menu
+    | Element::InlinePassthrough
+    | Element::Stem
+    | Element::IndexTerm
+    | Element::LineBreak
+    | Element::Callout(_)
+    | Element::TableRow
+    | Element::TableCell => return Err(AsciidoctrineError::MalformedAst),
+  }
+
+  Ok(())
+}
+
+fn write_typed_block<T: io::Write>(
+  kind: &BlockType,
+  input: &ElementSpan,
+  out: &mut T,
+) -> Result<()> {
+  let title = input.get_attribute("title");
+  let content = input.get_attribute("content").unwrap_or(&input.content);
+
+  match kind {
+    BlockType::Comment => (),
+    BlockType::Passtrough => {
+      out.write_all(content.as_bytes())?;
+      out.write_all(b"\n")?;
+    }
+    BlockType::Listing => {
+      let tag = match (
+        positional_attribute(input, 0),
+        positional_attribute(input, 1),
+      ) {
+        (Some("source"), Some(language)) => {
+          format!(
+            "programlisting language=\"{}\" linenumbering=\"unnumbered\"",
+            escape_attribute(language)
+          )
+        }
+        (Some("source"), None) => "programlisting".to_string(),
+        _ => "screen".to_string(),
+      };
+      let name = tag.split(' ').next().unwrap_or("");
+      if title.is_some() {
+        out.write_all(format!("<formalpara{}>\n", anchor(input)).as_bytes())?;
+        write_title(title, out)?;
+        out.write_all(b"<para>\n")?;
+        out.write_all(format!("<{}>", tag).as_bytes())?;
+      } else {
+        out.write_all(format!("<{}{}>", tag, anchor(input)).as_bytes())?;
+      }
+      write_listing_content(content, input, out)?;
+      out.write_all(format!("</{}>\n", name).as_bytes())?;
+      if title.is_some() {
+        out.write_all(b"</para>\n</formalpara>\n")?;
+      }
+    }
+    BlockType::Literal => {
+      if title.is_some() {
+        out.write_all(format!("<formalpara{}>\n", anchor(input)).as_bytes())?;
+        write_title(title, out)?;
+        out.write_all(b"<para>\n<literallayout class=\"monospaced\">")?;
+      } else {
+        out
+          .write_all(format!("<literallayout{} class=\"monospaced\">", anchor(input)).as_bytes())?;
+      }
+      out.write_all(escape_text(content).as_bytes())?;
+      out.write_all(b"</literallayout>\n")?;
+      if title.is_some() {
+        out.write_all(b"</para>\n</formalpara>\n")?;
+      }
+    }
+    BlockType::Sidebar => {
+      out.write_all(format!("<sidebar{}>\n", anchor(input)).as_bytes())?;
+      write_title(title, out)?;
+      write_blocks(&input.children, out)?;
+      out.write_all(b"</sidebar>\n")?;
+    }
+    BlockType::Example => {
+      // An example without a title is informal
+      let tag = if title.is_some() {
+        "example"
+      } else {
+        "informalexample"
+      };
+      out.write_all(format!("<{}{}>\n", tag, anchor(input)).as_bytes())?;
+      write_title(title, out)?;
+      write_blocks(&input.children, out)?;
+      out.write_all(format!("</{}>\n", tag).as_bytes())?;
+    }
+    BlockType::Open => {
+      // An open block just groups its content
+      if let Some(id) = input.get_attribute("anchor") {
+        out.write_all(format!("<anchor xml:id=\"{}\"/>\n", escape_attribute(id)).as_bytes())?;
+      }
+      write_blocks(&input.children, out)?;
+    }
+    BlockType::Quote | BlockType::Verse => {
+      out.write_all(format!("<blockquote{}>\n", anchor(input)).as_bytes())?;
+      write_title(title, out)?;
+      // The attribution is given as `[quote, author, source]`
+      let author = input
+        .get_attribute("attribution")
+        .or_else(|| positional_attribute(input, 1));
+      let citetitle = input
+        .get_attribute("citetitle")
+        .or_else(|| positional_attribute(input, 2));
+      if author.is_some() || citetitle.is_some() {
+        out.write_all(b"<attribution>")?;
+        if let Some(author) = author {
+          out.write_all(escape_text(author).as_bytes())?;
+        }
+        if let Some(citetitle) = citetitle {
+          out.write_all(format!("<citetitle>{}</citetitle>", escape_text(citetitle)).as_bytes())?;
+        }
+        out.write_all(b"</attribution>\n")?;
+      }
+      if kind == &BlockType::Verse {
+        // A verse keeps its line breaks
+        out.write_all(
+          format!("<literallayout>{}</literallayout>\n", escape_text(content)).as_bytes(),
+        )?;
+      } else {
+        write_blocks(&input.children, out)?;
+      }
+      out.write_all(b"</blockquote>\n")?;
+    }
+    BlockType::Admonition(admonition) => {
+      let tag = admonition.keyword().to_lowercase();
+      out.write_all(format!("<{}{}>\n", tag, anchor(input)).as_bytes())?;
+      write_title(title, out)?;
+      write_blocks(&input.children, out)?;
+      out.write_all(format!("</{}>\n", tag).as_bytes())?;
+    }
+  }
+
+  Ok(())
+}
+
+/// Writes the lines of a listing with the callout markers
+fn write_listing_content<T: io::Write>(
+  content: &str,
+  input: &ElementSpan,
+  out: &mut T,
+) -> Result<()> {
+  // With further substitutions the text is parsed into inline elements
+  if input
+    .children
+    .iter()
+    .any(|child| !matches!(child.element, Element::Callout(_)))
+  {
+    return write_inlines(&input.children, out);
+  }
+
+  for (index, line) in content.split('\n').enumerate() {
+    if index > 0 {
+      out.write_all(b"\n")?;
+    }
+    let index = index.to_string();
+    let markers: Vec<&ElementSpan> = input
+      .children
+      .iter()
+      .filter(|callout| callout.get_attribute("line") == Some(index.as_str()))
+      .collect();
+    let code = markers
+      .first()
+      .and_then(|callout| callout.get_attribute("column"))
+      .and_then(|column| column.parse().ok())
+      .and_then(|column| line.get(..column))
+      .unwrap_or(line);
+    out.write_all(escape_text(code).as_bytes())?;
+
+    // Indented callouts need no space before them
+    let mut separate = !code.is_empty() && !code.ends_with(char::is_whitespace);
+    for callout in markers {
+      if separate {
+        out.write_all(b" ")?;
+      }
+      separate = true;
+      match callout.get_attribute("anchor") {
+        Some(id) => {
+          out.write_all(format!("<co xml:id=\"{}\"/>", escape_attribute(id)).as_bytes())?
+        }
+        None => out.write_all(b"<co/>")?,
+      }
+    }
+  }
+
+  Ok(())
+}
+
+fn write_list<T: io::Write>(list_type: &ListType, input: &ElementSpan, out: &mut T) -> Result<()> {
+  let title = input.get_attribute("title");
+
+  match list_type {
+    ListType::Bullet | ListType::Checklist => {
+      let role = match list_type {
+        ListType::Checklist => " role=\"checklist\"",
+        _ => "",
+      };
+      out.write_all(format!("<itemizedlist{}{}>\n", anchor(input), role).as_bytes())?;
+      write_title(title, out)?;
+      for item in input.children.iter() {
+        out.write_all(b"<listitem>\n")?;
+        write_list_item(item, out)?;
+        out.write_all(b"</listitem>\n")?;
+      }
+      out.write_all(b"</itemizedlist>\n")?;
+    }
+    ListType::Number => {
+      let level = match input.children.first().map(|item| &item.element) {
+        Some(Element::ListItem(level)) => (*level).max(1) as usize,
+        _ => 1,
+      };
+      let numeration = NUMERATIONS[(level - 1) % NUMERATIONS.len()];
+      let mut attrs = format!("{} numeration=\"{}\"", anchor(input), numeration);
+      if let Some(start) = input.get_attribute("start") {
+        attrs.push_str(&format!(" startingnumber=\"{}\"", escape_attribute(start)));
+      }
+      out.write_all(format!("<orderedlist{}>\n", attrs).as_bytes())?;
+      write_title(title, out)?;
+      for item in input.children.iter() {
+        out.write_all(b"<listitem>\n")?;
+        write_list_item(item, out)?;
+        out.write_all(b"</listitem>\n")?;
+      }
+      out.write_all(b"</orderedlist>\n")?;
+    }
+    ListType::Description => {
+      out.write_all(format!("<variablelist{}>\n", anchor(input)).as_bytes())?;
+      write_title(title, out)?;
+      for item in input.children.iter() {
+        let term = item.get_attribute("term").unwrap_or("");
+        out.write_all(b"<varlistentry>\n")?;
+        out.write_all(format!("<term>{}</term>\n", escape_text(term)).as_bytes())?;
+        out.write_all(b"<listitem>\n")?;
+        write_list_item(item, out)?;
+        out.write_all(b"</listitem>\n</varlistentry>\n")?;
+      }
+      out.write_all(b"</variablelist>\n")?;
+    }
+    ListType::Callout => {
+      out.write_all(format!("<calloutlist{}>\n", anchor(input)).as_bytes())?;
+      write_title(title, out)?;
+      for item in input.children.iter() {
+        let arearefs = item.get_attribute("id").unwrap_or("");
+        out.write_all(
+          format!("<callout arearefs=\"{}\">\n", escape_attribute(arearefs)).as_bytes(),
+        )?;
+        write_list_item(item, out)?;
+        out.write_all(b"</callout>\n")?;
+      }
+      out.write_all(b"</calloutlist>\n")?;
+    }
+  }
+
+  Ok(())
+}
+
+/// The numeration of nested ordered lists
+const NUMERATIONS: &[&str] = &[
+  "arabic",
+  "loweralpha",
+  "lowerroman",
+  "upperalpha",
+  "upperroman",
+];
+
+fn write_list_item<T: io::Write>(item: &ElementSpan, out: &mut T) -> Result<()> {
+  // A list item needs at least one block
+  if item.children.is_empty() {
+    out.write_all(b"<simpara></simpara>\n")?;
+    return Ok(());
+  }
+
+  let marker = match item.get_attribute("checked") {
+    Some("true") => "&#10003; ",
+    Some(_) => "&#10063; ",
+    None => "",
+  };
+  for (index, element) in item.children.iter().enumerate() {
+    if index == 0
+      && element.element == Element::Paragraph
+      && element.get_attribute("title").is_none()
+    {
+      out.write_all(format!("<simpara>{}", marker).as_bytes())?;
+      write_inlines(&element.children, out)?;
+      out.write_all(b"</simpara>\n")?;
+    } else {
+      write_block(element, out)?;
+    }
+  }
+
+  Ok(())
+}
+
+fn write_table<T: io::Write>(input: &ElementSpan, out: &mut T) -> Result<()> {
+  let title = input.get_attribute("title");
+  let tag = if title.is_some() {
+    "table"
+  } else {
+    "informaltable"
+  };
+  let columns = input
+    .children
+    .iter()
+    .map(|row| row.children.len())
+    .max()
+    .unwrap_or(0)
+    .max(1);
+
+  out.write_all(
+    format!(
+      "<{}{} frame=\"all\" rowsep=\"1\" colsep=\"1\">\n",
+      tag,
+      anchor(input)
+    )
+    .as_bytes(),
+  )?;
+  write_title(title, out)?;
+  out.write_all(format!("<tgroup cols=\"{}\">\n", columns).as_bytes())?;
+  for column in 1..=columns {
+    out.write_all(format!("<colspec colname=\"col_{}\" colwidth=\"1*\"/>\n", column).as_bytes())?;
+  }
+  out.write_all(b"<tbody>\n")?;
+  for row in input.children.iter() {
+    if row.element != Element::TableRow {
+      return Err(AsciidoctrineError::MalformedAst);
+    }
+    out.write_all(b"<row>\n")?;
+    for cell in row.children.iter() {
+      if cell.element != Element::TableCell {
+        return Err(AsciidoctrineError::MalformedAst);
+      }
+      out.write_all(b"<entry>")?;
+      for element in cell.children.iter() {
+        write_block(element, out)?;
+      }
+      out.write_all(b"</entry>\n")?;
+    }
+    out.write_all(b"</row>\n")?;
+  }
+  out.write_all(b"</tbody>\n</tgroup>\n")?;
+  out.write_all(format!("</{}>\n", tag).as_bytes())?;
+
+  Ok(())
+}
+
+fn write_blocks<T: io::Write>(elements: &[ElementSpan], out: &mut T) -> Result<()> {
+  for element in elements {
+    write_block(element, out)?;
+  }
+
+  Ok(())
+}
+
+fn write_inlines<T: io::Write>(elements: &[ElementSpan], out: &mut T) -> Result<()> {
+  for element in elements {
+    write_inline(element, out)?;
+  }
+
+  Ok(())
+}
+
+fn write_inline<T: io::Write>(input: &ElementSpan, out: &mut T) -> Result<()> {
+  match &input.element {
+    Element::Text => {
+      let content = input.get_attribute("content").unwrap_or(&input.content);
+      out.write_all(escape_text(content).as_bytes())?;
+    }
+    Element::LineBreak => out.write_all(b"<?asciidoc-br?>")?,
+    Element::Styled => {
+      let content = escape_text(input.get_attribute("content").unwrap_or(""));
+      let styled = match input.get_attribute("style").unwrap_or("") {
+        "strong" => format!("<emphasis role=\"strong\">{}</emphasis>", content),
+        "em" => format!("<emphasis>{}</emphasis>", content),
+        "monospaced" => format!("<literal>{}</literal>", content),
+        "mark" => format!("<emphasis role=\"marked\">{}</emphasis>", content),
+        "sup" => format!("<superscript>{}</superscript>", content),
+        "sub" => format!("<subscript>{}</subscript>", content),
+        "double_quoted" => format!("\u{201c}{}\u{201d}", content),
+        "single_quoted" => format!("\u{2018}{}\u{2019}", content),
+        style => format!(
+          "<phrase role=\"{}\">{}</phrase>",
+          escape_attribute(style),
+          content
+        ),
+      };
+      out.write_all(styled.as_bytes())?;
+    }
+    Element::Link => {
+      let url = input.get_attribute("url").unwrap_or("");
+      let content =
+        positional_attribute(input, 0).unwrap_or_else(|| url.trim_start_matches("mailto:"));
+      out.write_all(
+        format!(
+          "<link xl:href=\"{}\">{}</link>",
+          escape_attribute(url),
+          escape_text(content)
+        )
+        .as_bytes(),
+      )?;
+    }
+    Element::XRef => {
+      let id = input.get_attribute("id");
+      let content = input.get_attribute("content");
+      match (input.get_attribute("path"), id, content) {
+        (Some(path), id, content) => {
+          let href = match id {
+            Some(id) => format!("{}#{}", docbook_path(path), id),
+            None => docbook_path(path),
+          };
+          out.write_all(
+            format!(
+              "<link xl:href=\"{}\">{}</link>",
+              escape_attribute(&href),
+              escape_text(content.or(id).unwrap_or(path))
+            )
+            .as_bytes(),
+          )?;
+        }
+        (None, id, Some(content)) => out.write_all(
+          format!(
+            "<link linkend=\"{}\">{}</link>",
+            escape_attribute(id.unwrap_or("")),
+            escape_text(content)
+          )
+          .as_bytes(),
+        )?,
+        (None, id, None) => out.write_all(
+          format!("<xref linkend=\"{}\"/>", escape_attribute(id.unwrap_or(""))).as_bytes(),
+        )?,
+      }
+    }
+    Element::Footnote => {
+      let content = input.get_attribute("content").unwrap_or("");
+      out.write_all(
+        format!(
+          "<footnote><simpara>{}</simpara></footnote>",
+          escape_text(content)
+        )
+        .as_bytes(),
+      )?;
+    }
+    Element::InlineImage => {
+      let path = input.get_attribute("path").unwrap_or("");
+      let alt = positional_attribute(input, 0).unwrap_or(path);
+      out.write_all(
+        format!(
+          "<inlinemediaobject><imageobject><imagedata fileref=\"{}\"{}/></imageobject><textobject><phrase>{}</phrase></textobject></inlinemediaobject>",
+          escape_attribute(path),
+          image_size(input),
+          escape_text(alt)
+        )
+        .as_bytes(),
+      )?;
+    }
+    Element::Keyboard => {
+      let keys: Vec<String> = input
+        .positional_attributes
+        .iter()
+        .map(|key| format!("<keycap>{}</keycap>", escape_text(key.as_str())))
+        .collect();
+      if keys.len() == 1 {
+        out.write_all(keys[0].as_bytes())?;
+      } else {
+        out.write_all(format!("<keycombo>{}</keycombo>", keys.join("")).as_bytes())?;
+      }
+    }
+    Element::Button => {
+      let content = input.get_attribute("content").unwrap_or("");
+      out.write_all(format!("<guibutton>{}</guibutton>", escape_text(content)).as_bytes())?;
+    }
+    Element::Menu => {
+      let menu = escape_text(input.get_attribute("menu").unwrap_or(""));
+      match input.positional_attributes.split_last() {
+        Some((item, submenus)) => {
+          let mut parts = vec![format!("<guimenu>{}</guimenu>", menu)];
+          for submenu in submenus {
+            parts.push(format!(
+              "<guisubmenu>{}</guisubmenu>",
+              escape_text(submenu.as_str())
+            ));
+          }
+          parts.push(format!(
+            "<guimenuitem>{}</guimenuitem>",
+            escape_text(item.as_str())
+          ));
+          out.write_all(format!("<menuchoice>{}</menuchoice>", parts.join("")).as_bytes())?;
+        }
+        None => out.write_all(format!("<guimenu>{}</guimenu>", menu).as_bytes())?,
+      }
+    }
+    Element::InlinePassthrough => {
+      let content = input.get_attribute("content").unwrap_or("");
+      out.write_all(content.as_bytes())?;
+    }
+    Element::Stem => {
+      let content = input
+        .get_attribute("content")
+        .unwrap_or("")
+        .replace("]]>", "]]]]><![CDATA[>");
+      out.write_all(
+        format!(
+          "<inlineequation><alt><![CDATA[{0}]]></alt><mathphrase><![CDATA[{0}]]></mathphrase></inlineequation>",
+          content
+        )
+        .as_bytes(),
+      )?;
+    }
+    Element::IndexTerm => {
+      let terms: Vec<&str> = input
+        .positional_attributes
+        .iter()
+        .map(|term| term.as_str().trim())
+        .collect();
+      out.write_all(b"<indexterm>")?;
+      for (tag, term) in ["primary", "secondary", "tertiary"]
+        .iter()
+        .zip(terms.iter())
+      {
+        out.write_all(format!("<{0}>{1}</{0}>", tag, escape_text(term)).as_bytes())?;
+      }
+      out.write_all(b"</indexterm>")?;
+      // Concealed terms only appear in the index
+      if input.get_attribute("visible") == Some("true") {
+        if let Some(term) = terms.first() {
+          out.write_all(escape_text(term).as_bytes())?;
+        }
+      }
+    }
+    Element::Anchor => {
+      if let Some(id) = input.get_attribute("anchor") {
+        out.write_all(format!("<anchor xml:id=\"{}\"/>", escape_attribute(id)).as_bytes())?;
+      }
+    }
+    Element::Comment => (),
+    // Callouts are written as part of the listing content
+    Element::Callout(_) => (),
+    _ => return Err(AsciidoctrineError::MalformedAst),
+  }
+
+  Ok(())
+}
+
+// Helper Functions
+//----------------------------------------------------
+
+fn write_title<T: io::Write>(title: Option<&str>, out: &mut T) -> Result<()> {
+  if let Some(title) = title {
+    out.write_all(format!("<title>{}</title>\n", escape_text(title)).as_bytes())?;
+  }
+  Ok(())
+}
+
+/// The `xml:id` of a block with an anchor
+fn anchor(input: &ElementSpan) -> String {
+  id_attribute(input.get_attribute("anchor"))
+}
+
+fn id_attribute(id: Option<&str>) -> String {
+  match id {
+    Some(id) => format!(" xml:id=\"{}\"", escape_attribute(id)),
+    None => String::new(),
+  }
+}
+
+/// The width and height of an image given as attributes or as
+/// positional attributes after the alternative text
+fn image_size(input: &ElementSpan) -> String {
+  [("width", "contentwidth"), ("height", "contentdepth")]
+    .iter()
+    .enumerate()
+    .filter_map(|(index, (key, name))| {
+      input
+        .get_attribute(key)
+        .or_else(|| positional_attribute(input, index + 1))
+        .map(|value| format!(" {}=\"{}\"", name, escape_attribute(value)))
+    })
+    .collect()
+}
+
+/// A positional attribute without surrounding whitespace if it isn't
+/// empty
+fn positional_attribute<'a>(input: &'a ElementSpan, index: usize) -> Option<&'a str> {
+  input
+    .positional_attributes
+    .get(index)
+    .map(|value| value.as_str().trim())
+    .filter(|value| !value.is_empty())
+}
+
+/// The path of the DocBook document generated from an asciidoc document
+fn docbook_path(path: &str) -> String {
+  match path.strip_suffix(".adoc") {
+    Some(path) => format!("{}.xml", path),
+    None => path.to_string(),
+  }
+}
+
+/// Escapes the markup characters of xml. Character references in the
+/// text are kept.
+fn escape_text(input: &str) -> String {
+  let mut out = String::with_capacity(input.len());
+  for (index, c) in input.char_indices() {
+    match c {
+      '<' => out.push_str("&lt;"),
+      '>' => out.push_str("&gt;"),
+      '&' if !is_reference(&input[index..]) => out.push_str("&amp;"),
+      c => out.push(c),
+    }
+  }
+  out
+}
+
+fn escape_attribute(input: &str) -> String {
+  escape_text(input).replace('"', "&quot;")
+}
+
+/// Checks if the text starts with a character reference or one of
+/// the entities predefined by xml
+fn is_reference(text: &str) -> bool {
+  let name = match text[1..].find(';') {
+    Some(end) => &text[1..end + 1],
+    None => return false,
+  };
+  match name.strip_prefix('#') {
+    Some(number) => match number.strip_prefix('x') {
+      Some(hex) => !hex.is_empty() && hex.chars().all(|c| c.is_ascii_hexdigit()),
+      None => !number.is_empty() && number.chars().all(|c| c.is_ascii_digit()),
+    },
+    None => matches!(name, "amp" | "lt" | "gt" | "quot" | "apos"),
+  }
+}
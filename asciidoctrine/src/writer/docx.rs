@@ -1,6 +1,12 @@
 pub use crate::ast::*;
+use crate::reader::asciidoc::{parse_columns_format, ColumnFormat, HAlign};
+use crate::sections::number_titles;
+use crate::writer::highlight::{self, Theme};
+use crate::writer::text::decode_references;
 use crate::{options, Result, AsciidoctrineError};
+use std::collections::HashMap;
 use std::io;
+use std::path::{Path, PathBuf};
 use docx_rs::*;
 
 pub struct DocxWriter {}
@@ -12,68 +18,1030 @@ impl DocxWriter {
 }
 
 impl<T: io::Write + io::Seek> crate::Writer<T> for DocxWriter {
-  fn write<'a>(&mut self, ast: AST, _args: &options::Opts, out: T) -> Result<()> {
+  fn write<'a>(&mut self, ast: AST, args: &options::Opts, out: T) -> Result<()> {
+    let ast = number_titles(ast);
+
+    let doc = match &args.reference_docx {
+      Some(path) => reference_document(path)?,
+      None => Docx::new(),
+    };
+    let doc = default_styles()
+      .into_iter()
+      .fold(doc, |doc, style| {
+        match doc.styles.find_style_by_id(&style.style_id) {
+          Some(_) => doc,
+          None => doc.add_style(style),
+        }
+      })
+      .add_abstract_numbering(list_numbering(BULLET_NUMBERING, &ListType::Bullet))
+      .add_abstract_numbering(list_numbering(ORDERED_NUMBERING, &ListType::Number));
+
+    let mut base = args
+      .input
+      .as_ref()
+      .and_then(|input| input.parent())
+      .map(Path::to_path_buf)
+      .unwrap_or_default();
+    if let Some(imagesdir) = ast.get_attribute("imagesdir") {
+      base.push(imagesdir);
+    }
+    let mut state = State {
+      base,
+      text_width: text_width(&doc),
+      titles: HashMap::new(),
+      footnote_ids: HashMap::new(),
+      footnotes: 0,
+      bookmarks: 0,
+      in_table: false,
+      highlight: ast
+        .get_attribute("source-highlighter")
+        .map(|_| highlight::theme(ast.get_attribute("highlight-theme"))),
+    };
+    collect_titles(&ast.elements, &mut state.titles);
+
     let doc = ast
       .elements
       .iter()
-      .try_fold(Docx::new(), |doc, element| write_doc(element, doc))?;
-    doc.build().pack(out).unwrap();
+      .try_fold(doc, |doc, element| write_doc(element, doc, &mut state))?;
+    let mut xml = doc.build();
+    // docx-rs has no setters for the core properties
+    xml.doc_props.core = core_properties(&ast);
+    xml.pack(out).unwrap();
 
     Ok(())
   }
 }
 
-fn paragraph(input: &ElementSpan, out: Paragraph) -> Result<Paragraph> {
+/// What the writer has to remember between the elements
+struct State {
+  /// The directory of the local images
+  base: PathBuf,
+  /// The width of the text on a page in twips
+  text_width: u32,
+  /// The titles by their ids for references without a text
+  titles: HashMap<String, String>,
+  /// The numbers of the footnotes with an id
+  footnote_ids: HashMap<String, usize>,
+  footnotes: usize,
+  bookmarks: usize,
+  /// Word only finds the footnotes outside of tables
+  in_table: bool,
+  /// The colours of the source listings if they are highlighted
+  highlight: Option<&'static Theme>,
+}
+
+/// Takes the styles and the page setup of the document from an
+/// existing Word document
+fn reference_document(path: &Path) -> Result<Docx> {
+  let data = std::fs::read(path)
+    .map_err(|error| AsciidoctrineError::Read(path.display().to_string(), error))?;
+  let reference = read_docx(&data)?;
+  let mut doc = Docx::new().styles(reference.styles);
+  let page = reference.document.section_property;
+  doc.document.section_property.page_size = page.page_size;
+  doc.document.section_property.page_margin = page.page_margin;
+
+  Ok(doc)
+}
+
+/// The styles the writer uses. Styles of a reference document with
+/// the same id take precedence.
+fn default_styles() -> Vec<Style> {
+  // docx-rs always writes the style `Normal` itself
+  let mut styles = vec![Style::new("Title", StyleType::Paragraph)
+    .name("Title")
+    .based_on("Normal")
+    .next("Normal")
+    .size(56)
+    .bold()];
+  // The sizes are given in half points
+  for (level, size) in [32, 28, 26, 24, 22, 22].iter().enumerate() {
+    styles.push(
+      Style::new(format!("Heading{}", level + 1), StyleType::Paragraph)
+        .name(format!("heading {}", level + 1))
+        .based_on("Normal")
+        .next("Normal")
+        .size(*size)
+        .bold()
+        .outline_lvl(level),
+    );
+  }
+  styles.push(
+    Style::new("SourceCode", StyleType::Paragraph)
+      .name("Source Code")
+      .based_on("Normal")
+      .size(20)
+      .fonts(RunFonts::new().ascii("Courier New").hi_ansi("Courier New")),
+  );
+  styles.push(
+    Style::new("Caption", StyleType::Paragraph)
+      .name("caption")
+      .based_on("Normal")
+      .next("Normal")
+      .size(20)
+      .bold(),
+  );
+  styles.push(
+    Style::new("Quote", StyleType::Paragraph)
+      .name("Quote")
+      .based_on("Normal")
+      .italic()
+      .indent(Some(LIST_INDENT), None, Some(LIST_INDENT), None),
+  );
+  styles.push(
+    Style::new("TableHeading", StyleType::Paragraph)
+      .name("Table Heading")
+      .based_on("Normal")
+      .bold(),
+  );
+  styles.push(
+    Style::new("FootnoteText", StyleType::Paragraph)
+      .name("footnote text")
+      .based_on("Normal")
+      .size(20),
+  );
+  styles.push(
+    Style::new("Hyperlink", StyleType::Character)
+      .name("Hyperlink")
+      .color("0563C1")
+      .underline("single"),
+  );
+  let mut footnote_reference =
+    Style::new("FootnoteReference", StyleType::Character).name("footnote reference");
+  footnote_reference.run_property = footnote_reference
+    .run_property
+    .vert_align(VertAlignType::SuperScript);
+  styles.push(footnote_reference);
+
+  styles
+}
+
+/// Builds `docProps/core.xml` from the document header
+fn core_properties(ast: &AST) -> Vec<u8> {
+  let mut properties = Vec::new();
+  if let Some(title) = &ast.header.title {
+    properties.push(("dc:title", title.clone()));
+  }
+  let authors: Vec<&str> = ast
+    .header
+    .authors
+    .iter()
+    .map(|author| author.name.as_str())
+    .collect();
+  if !authors.is_empty() {
+    properties.push(("dc:creator", authors.join("; ")));
+  }
+  for (key, attribute) in [
+    ("dc:description", "description"),
+    ("cp:keywords", "keywords"),
+    ("dc:language", "lang"),
+  ] {
+    if let Some(value) = ast.get_attribute(attribute) {
+      properties.push((key, value.to_string()));
+    }
+  }
+  if let Some(number) = ast.header.revision.as_ref().and_then(|r| r.number.as_ref()) {
+    properties.push(("cp:version", number.clone()));
+  }
+  // The document is created at its revision date. Without a `revdate`
+  // or a `docdate` it is created today like `localdate`.
+  let date = ast
+    .header
+    .revision
+    .as_ref()
+    .and_then(|revision| revision.date.as_deref())
+    .or_else(|| ast.get_attribute("docdate"));
+  let created = match date {
+    Some(date) => chrono::NaiveDate::parse_from_str(date.trim(), "%Y-%m-%d").ok(),
+    None => Some(chrono::Local::now().date_naive()),
+  };
+
+  let mut xml = String::from(concat!(
+    r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>"#,
+    r#"<cp:coreProperties xmlns:cp="http://schemas.openxmlformats.org/package/2006/metadata/core-properties" "#,
+    r#"xmlns:dc="http://purl.org/dc/elements/1.1/" xmlns:dcterms="http://purl.org/dc/terms/" "#,
+    r#"xmlns:dcmitype="http://purl.org/dc/dcmitype/" xmlns:xsi="http://www.w3.org/2001/XMLSchema-instance">"#,
+  ));
+  if let Some(created) = created {
+    xml.push_str(&format!(
+      r#"<dcterms:created xsi:type="dcterms:W3CDTF">{}T00:00:00Z</dcterms:created>"#,
+      created.format("%Y-%m-%d")
+    ));
+  }
+  for (key, value) in properties {
+    let value = value
+      .replace('&', "&amp;")
+      .replace('<', "&lt;")
+      .replace('>', "&gt;");
+    xml.push_str(&format!("<{0}>{1}</{0}>", key, value));
+  }
+  xml.push_str("</cp:coreProperties>");
+
+  xml.into_bytes()
+}
+
+fn paragraph(input: &ElementSpan, out: Paragraph, state: &mut State) -> Result<Paragraph> {
   match &input.element {
     Element::Text => {
       let r = Run::new();
-      Ok(out.add_run(r.add_text(input.content.to_string())))
+      let content = input.get_attribute("content").unwrap_or(&input.content);
+      Ok(out.add_run(r.add_text(decode_references(content))))
+    }
+    Element::Styled => {
+      let content = decode_references(input.get_attribute("content").unwrap_or(""));
+      let r = Run::new();
+      let r = match input.get_attribute("style").unwrap_or("") {
+        "strong" => r.add_text(content).bold(),
+        "em" => r.add_text(content).italic(),
+        "monospaced" => r
+          .add_text(content)
+          .fonts(RunFonts::new().ascii("Courier New").hi_ansi("Courier New")),
+        "mark" => r.add_text(content).highlight("yellow"),
+        "sup" => vert_align(r.add_text(content), VertAlignType::SuperScript),
+        "sub" => vert_align(r.add_text(content), VertAlignType::SubScript),
+        "double_quoted" => r.add_text(format!("\u{201C}{}\u{201D}", content)),
+        "single_quoted" => r.add_text(format!("\u{2018}{}\u{2019}", content)),
+        _ => r.add_text(content),
+      };
+      Ok(out.add_run(r))
     }
     Element::Link => {
       let url = input.get_attribute("url").unwrap_or("");
-      let content = match input.positional_attributes.get(0) {
-        Some(value) => match value {
-          AttributeValue::Ref(value) => value.to_string(),
-          AttributeValue::String(value) => value.clone(),
-        },
-        None => "".to_string(),
+      let content = match positional_attribute(input, 0) {
+        Some(content) => content,
+        None => url.strip_prefix("mailto:").unwrap_or(url),
       };
+      let r = Run::new().add_text(content).style("Hyperlink");
 
-      Ok(out.add_hyperlink(Hyperlink::new(url, HyperlinkType::External)))
+      Ok(out.add_hyperlink(Hyperlink::new(url, HyperlinkType::External).add_run(r)))
     }
     Element::Footnote => {
-      let r = Run::new();
-      Ok(out.add_run(r.add_text(input.get_attribute("content").unwrap_or("").to_string())))
+      let (id, content) = match input.get_attribute("kind") {
+        Some("ref") => (
+          positional_attribute(input, 0),
+          positional_attribute(input, 1),
+        ),
+        _ => (None, input.get_attribute("content")),
+      };
+      if let Some(number) = id.and_then(|id| state.footnote_ids.get(id)) {
+        // Word can't refer to a footnote twice
+        let r = Run::new()
+          .add_text(number.to_string())
+          .style("FootnoteReference");
+        return Ok(out.add_run(r));
+      }
+      let content = content.unwrap_or("");
+      let content = content.split_whitespace().collect::<Vec<_>>().join(" ");
+      let content = decode_references(&content);
+      if state.in_table {
+        return Ok(out.add_run(Run::new().add_text(format!("({})", content))));
+      }
+
+      state.footnotes += 1;
+      if let Some(id) = id {
+        state.footnote_ids.insert(id.to_string(), state.footnotes);
+      }
+      let footnote = Footnote::new().add_content(
+        Paragraph::new()
+          .style("FootnoteText")
+          .add_run(Run::new().add_text(content)),
+      );
+      Ok(out.add_run(Run::new().add_footnote_reference(footnote)))
+    }
+    Element::XRef => {
+      let id = input.get_attribute("id");
+      let content = input
+        .get_attribute("content")
+        .or_else(|| id.and_then(|id| state.titles.get(id)).map(String::as_str))
+        .or(id)
+        .or_else(|| input.get_attribute("path"))
+        .unwrap_or("");
+      let r = Run::new().add_text(decode_references(content));
+      match (id, input.get_attribute("path")) {
+        // References into other documents can't be followed
+        (Some(id), None) => Ok(
+          out
+            .add_hyperlink(Hyperlink::new(id, HyperlinkType::Anchor).add_run(r.style("Hyperlink"))),
+        ),
+        _ => Ok(out.add_run(r)),
+      }
+    }
+    Element::InlineImage => {
+      let path = input.get_attribute("path").unwrap_or("");
+      let width = input
+        .get_attribute("width")
+        .or_else(|| positional_attribute(input, 1));
+      match picture(path, width, state) {
+        Some(pic) => Ok(out.add_run(Run::new().add_image(pic))),
+        None => {
+          let alt = positional_attribute(input, 0).unwrap_or(path);
+          Ok(out.add_run(Run::new().add_text(format!("[{}]", alt))))
+        }
+      }
+    }
+    Element::Keyboard => {
+      let keys: Vec<&str> = input
+        .positional_attributes
+        .iter()
+        .map(|key| key.as_str())
+        .collect();
+      Ok(
+        out.add_run(
+          Run::new()
+            .add_text(keys.join("+"))
+            .fonts(RunFonts::new().ascii("Courier New")),
+        ),
+      )
+    }
+    Element::Button => {
+      let content = input.get_attribute("content").unwrap_or("");
+      Ok(out.add_run(Run::new().add_text(format!("[{}]", content)).bold()))
+    }
+    Element::Menu => {
+      let mut items = vec![input.get_attribute("menu").unwrap_or("")];
+      items.extend(input.positional_attributes.iter().map(|item| item.as_str()));
+      Ok(out.add_run(Run::new().add_text(items.join(" \u{25B8} ")).bold()))
     }
+    Element::LineBreak => Ok(out.add_run(Run::new().add_break(BreakType::TextWrapping))),
+    Element::InlinePassthrough | Element::Stem => {
+      let content = input.get_attribute("content").unwrap_or("");
+      Ok(out.add_run(Run::new().add_text(content)))
+    }
+    Element::IndexTerm => match input.positional_attributes.first() {
+      Some(term) if input.get_attribute("visible") == Some("true") => {
+        Ok(out.add_run(Run::new().add_text(term.as_str())))
+      }
+      _ => Ok(out),
+    },
+    Element::Anchor => match input.get_attribute("anchor") {
+      Some(id) => {
+        state.bookmarks += 1;
+        Ok(
+          out
+            .add_bookmark_start(state.bookmarks, id)
+            .add_bookmark_end(state.bookmarks),
+        )
+      }
+      None => Ok(out),
+    },
+    // Callouts are part of the listing content
+    Element::Callout(_) | Element::Comment => Ok(out),
     _ => Err(AsciidoctrineError::MalformedAst),
   }
 }
 
-fn write_doc(input: &ElementSpan, out: Docx) -> Result<Docx> {
+fn write_doc(input: &ElementSpan, out: Docx, state: &mut State) -> Result<Docx> {
   match &input.element {
+    Element::Section { .. } => input
+      .children
+      .iter()
+      .try_fold(out, |out, element| write_doc(element, out, state)),
     Element::Title { level } => {
-      let title = input.get_attribute("name").unwrap_or("");
-      let p = Paragraph::new()
-        .add_run(Run::new().add_text(title))
-        .outline_lvl(*level as usize)
-        .style(&format!("Heading{}", level));
+      let title = decode_references(input.get_attribute("name").unwrap_or(""));
+      let title = match input.get_attribute("sectnum") {
+        Some(number) => format!("{} {}", number, title),
+        None => title,
+      };
+      let style = match level {
+        1 => "Title".to_string(),
+        _ => format!("Heading{}", (level - 1).min(6)),
+      };
+      let p = Paragraph::new().style(&style);
+      // References point to the bookmark of the title
+      let p = match input.get_attribute("id") {
+        Some(id) => {
+          state.bookmarks += 1;
+          p.add_bookmark_start(state.bookmarks, id)
+            .add_run(Run::new().add_text(title))
+            .add_bookmark_end(state.bookmarks)
+        }
+        None => p.add_run(Run::new().add_text(title)),
+      };
       Ok(out.add_paragraph(p))
     }
     Element::Paragraph => {
+      let out = write_caption(input, out);
       let p = input
         .children
         .iter()
-        .try_fold(Paragraph::new(), |p, element| paragraph(element, p))?;
+        .try_fold(Paragraph::new(), |p, element| paragraph(element, p, state))?;
       Ok(out.add_paragraph(p))
     }
-    Element::Text | Element::Link | Element::Footnote => Err(AsciidoctrineError::MalformedAst),
-    _ => {
-      error!(
-        "<NOT-YET-SUPPORTED:{:?}>{}</NOT-YET-SUPPORTED>\n",
-        input.element, input.content
-      );
-
+    Element::TypedBlock {
+      kind: BlockType::Admonition(admonition),
+    } => {
+      let label = Run::new()
+        .add_text(format!("{}: ", admonition.caption()))
+        .bold();
+      let (first, rest) = match input.children.split_first() {
+        Some((first, rest)) if first.element == Element::Paragraph => (Some(first), rest),
+        _ => (None, &input.children[..]),
+      };
+      let p = match first {
+        Some(first) => first
+          .children
+          .iter()
+          .try_fold(Paragraph::new().add_run(label), |p, element| {
+            paragraph(element, p, state)
+          })?,
+        None => Paragraph::new().add_run(label),
+      };
+      rest.iter().try_fold(out.add_paragraph(p), |doc, element| {
+        write_doc(element, doc, state)
+      })
+    }
+    Element::TypedBlock {
+      kind: BlockType::Listing,
+    }
+    | Element::TypedBlock {
+      kind: BlockType::Literal,
+    } => {
+      let out = write_caption(input, out);
+      let content = input
+        .get_attribute("content")
+        .unwrap_or(&input.content)
+        .trim_end_matches('\n');
+      let language = match (
+        positional_attribute(input, 0),
+        positional_attribute(input, 1),
+      ) {
+        (Some("source"), Some(language)) => Some(language),
+        _ => None,
+      };
+      let runs = match (state.highlight, language) {
+        (Some(theme), Some(language)) => match highlight::highlight(language, content) {
+          Some(tokens) => highlighted(&tokens, theme),
+          None => {
+            warn!("no syntax highlighting for {}", language);
+            vec![preformatted(content)]
+          }
+        },
+        _ => vec![preformatted(content)],
+      };
+      let p = runs
+        .into_iter()
+        .fold(Paragraph::new().style("SourceCode"), |p, run| {
+          p.add_run(run)
+        });
+      Ok(out.add_paragraph(p))
+    }
+    Element::TypedBlock {
+      kind: BlockType::Verse,
+    } => {
+      let out = write_caption(input, out);
+      let content = input.get_attribute("content").unwrap_or(&input.content);
+      let p = Paragraph::new()
+        .style("Quote")
+        .add_run(preformatted(&decode_references(content.trim())));
+      Ok(write_attribution(input, out.add_paragraph(p)))
+    }
+    Element::TypedBlock {
+      kind: BlockType::Quote,
+    } => {
+      let mut out = write_caption(input, out);
+      let blocks = write_detached(&input.children, &mut out, state)?;
+      let out = blocks.into_iter().fold(out, |out, block| match block {
+        DocumentChild::Paragraph(p) => out.add_paragraph(p.style("Quote")),
+        DocumentChild::Table(table) => out.add_table(*table),
+        _ => out,
+      });
+      Ok(write_attribution(input, out))
+    }
+    Element::TypedBlock {
+      kind: BlockType::Example,
+    }
+    | Element::TypedBlock {
+      kind: BlockType::Sidebar,
+    }
+    | Element::TypedBlock {
+      kind: BlockType::Open,
+    } => {
+      let out = write_caption(input, out);
+      input
+        .children
+        .iter()
+        .try_fold(out, |out, element| write_doc(element, out, state))
+    }
+    Element::TypedBlock {
+      kind: BlockType::Passtrough,
+    } => {
+      warn!("passthrough blocks can't be written into Word documents");
+      Ok(out)
+    }
+    Element::TypedBlock {
+      kind: BlockType::Comment,
+    }
+    | Element::Comment
+    | Element::Anchor => Ok(out),
+    Element::List(list_type) => write_list(input, list_type, 0, out, state),
+    Element::ListItem(_) => input
+      .children
+      .iter()
+      .try_fold(out, |out, element| write_doc(element, out, state)),
+    Element::Table => write_table(input, out, state),
+    Element::Image => {
+      let path = input.get_attribute("path").unwrap_or("");
+      let width = input
+        .get_attribute("width")
+        .or_else(|| positional_attribute(input, 1));
+      let p = match picture(path, width, state) {
+        Some(pic) => Paragraph::new().add_run(Run::new().add_image(pic)),
+        None => {
+          let alt = positional_attribute(input, 0).unwrap_or(path);
+          Paragraph::new().add_run(Run::new().add_text(format!("[{}]", alt)))
+        }
+      };
+      let p = match input.get_attribute("align") {
+        Some("center") => p.align(AlignmentType::Center),
+        Some("right") => p.align(AlignmentType::Right),
+        _ => p,
+      };
+      let out = out.add_paragraph(p);
+      // The title of a figure is placed below it
+      Ok(match input.get_attribute("title") {
+        Some(title) => out.add_paragraph(
+          Paragraph::new()
+            .style("Caption")
+            .add_run(Run::new().add_text(decode_references(title))),
+        ),
+        None => out,
+      })
+    }
+    Element::IncludeElement(include) => include
+      .inner
+      .elements
+      .iter()
+      .try_fold(out, |out, element| write_doc(element, out, state)),
+    // Attribute entries are already resolved by the reader
+    Element::Attribute(_) => Ok(out),
+    // Word documents get no generated table of contents yet
+    Element::TableOfContents => Ok(out),
+    Element::ExternalContent => {
+      warn!("external content can't be written into Word documents");
+      Ok(out)
+    }
+    Element::Error(msg) => {
+      warn!("{}", msg);
       Ok(out)
     }
+    Element::Styled
+    | Element::Text
+    | Element::XRef
+    | Element::Link
+    | Element::Footnote
+    | Element::InlineImage
+    | Element::Keyboard
+    | Element::Button
+    | Element::Menu
+    | Element::InlinePassthrough
+    | Element::Stem
+    | Element::IndexTerm
+    | Element::LineBreak
+    | Element::Callout(_)
+    | Element::TableRow
+    | Element::TableCell => Err(AsciidoctrineError::MalformedAst),
   }
 }
+
+fn write_table(input: &ElementSpan, out: Docx, state: &mut State) -> Result<Docx> {
+  let columns = input
+    .children
+    .iter()
+    .map(|row| row.children.len())
+    .max()
+    .unwrap_or(0);
+  if columns == 0 {
+    return Ok(out);
+  }
+  let mut formats = input
+    .get_attribute("cols")
+    .map(parse_columns_format)
+    .unwrap_or_default();
+  formats.resize(columns.max(formats.len()), ColumnFormat::default());
+  formats.truncate(columns);
+  let header = has_option(input, "header");
+
+  // The relative widths of the columns share the width of the text
+  let widths: Vec<usize> = formats
+    .iter()
+    .map(|format| format.width.unwrap_or(1).max(1))
+    .collect();
+  let total: usize = widths.iter().sum();
+  let grid: Vec<usize> = widths
+    .iter()
+    .map(|width| state.text_width as usize * width / total)
+    .collect();
+
+  let mut out = write_caption(input, out);
+  let mut rows = Vec::new();
+  for (index, row) in input.children.iter().enumerate() {
+    if row.element != Element::TableRow {
+      return Err(AsciidoctrineError::MalformedAst);
+    }
+    let heading = header && index == 0;
+    let mut cells = Vec::new();
+    for (column, cell) in row.children.iter().enumerate() {
+      if cell.element != Element::TableCell {
+        return Err(AsciidoctrineError::MalformedAst);
+      }
+      let align = match formats[column].halign {
+        HAlign::Left => None,
+        HAlign::Center => Some(AlignmentType::Center),
+        HAlign::Right => Some(AlignmentType::Right),
+      };
+
+      state.in_table = true;
+      let blocks = write_detached(&cell.children, &mut out, state);
+      state.in_table = false;
+      let mut table_cell = TableCell::new().width(grid[column], WidthType::Dxa);
+      // Word needs a paragraph at the end of every cell
+      let mut ends_with_paragraph = false;
+      for block in blocks? {
+        match block {
+          DocumentChild::Paragraph(p) => {
+            let p = match heading {
+              true => p.style("TableHeading"),
+              false => *p,
+            };
+            let p = match align {
+              Some(align) => p.align(align),
+              None => p,
+            };
+            table_cell = table_cell.add_paragraph(p);
+            ends_with_paragraph = true;
+          }
+          DocumentChild::Table(table) => {
+            table_cell = table_cell.add_table(*table);
+            ends_with_paragraph = false;
+          }
+          _ => (),
+        }
+      }
+      if !ends_with_paragraph {
+        table_cell = table_cell.add_paragraph(Paragraph::new());
+      }
+      if heading {
+        table_cell = table_cell.shading(Shading::new().fill("D9D9D9"));
+      }
+      cells.push(table_cell);
+    }
+    let missing: Vec<TableCell> = grid[cells.len()..]
+      .iter()
+      .map(|width| {
+        TableCell::new()
+          .width(*width, WidthType::Dxa)
+          .add_paragraph(Paragraph::new())
+      })
+      .collect();
+    cells.extend(missing);
+    rows.push(TableRow::new(cells));
+  }
+  let table = Table::new(rows)
+    .set_grid(grid)
+    .width(state.text_width as usize, WidthType::Dxa);
+
+  Ok(out.add_table(table))
+}
+
+/// Writes blocks into a document of their own, so that they can be
+/// placed elsewhere (e.g. in a table cell). The lists keep their
+/// numberings in `out`.
+fn write_detached(
+  elements: &[ElementSpan],
+  out: &mut Docx,
+  state: &mut State,
+) -> Result<Vec<DocumentChild>> {
+  let mut detached = Docx::new();
+  detached.numberings = std::mem::take(&mut out.numberings);
+  let detached = elements
+    .iter()
+    .try_fold(detached, |doc, element| write_doc(element, doc, state))?;
+  out.numberings = detached.numberings;
+  if detached.document_rels.has_numberings {
+    out.document_rels.has_numberings = true;
+  }
+
+  Ok(detached.document.children)
+}
+
+/// Writes the title of a block above it
+fn write_caption(input: &ElementSpan, out: Docx) -> Docx {
+  match input.get_attribute("title") {
+    Some(title) => out.add_paragraph(
+      Paragraph::new()
+        .style("Caption")
+        .keep_next(true)
+        .add_run(Run::new().add_text(decode_references(title))),
+    ),
+    None => out,
+  }
+}
+
+/// Writes the attribution of a quote given as `[quote, author, source]`
+fn write_attribution(input: &ElementSpan, out: Docx) -> Docx {
+  let author = input
+    .get_attribute("attribution")
+    .or_else(|| positional_attribute(input, 1));
+  let citetitle = input
+    .get_attribute("citetitle")
+    .or_else(|| positional_attribute(input, 2));
+  if author.is_none() && citetitle.is_none() {
+    return out;
+  }
+
+  let mut p = Paragraph::new()
+    .style("Quote")
+    .align(AlignmentType::Right)
+    .add_run(Run::new().add_text("\u{2014} "));
+  if let Some(author) = author {
+    p = p.add_run(Run::new().add_text(decode_references(author)));
+  }
+  if let Some(citetitle) = citetitle {
+    if author.is_some() {
+      p = p.add_run(Run::new().add_text(", "));
+    }
+    p = p.add_run(Run::new().add_text(decode_references(citetitle)).italic());
+  }
+
+  out.add_paragraph(p)
+}
+
+/// Ids of the abstract numberings for lists. The id 1 is already
+/// used by the default numbering of docx-rs.
+const BULLET_NUMBERING: usize = 2;
+const ORDERED_NUMBERING: usize = 3;
+/// Indentation of one list level in twips
+const LIST_INDENT: i32 = 720;
+
+fn list_numbering(id: usize, list_type: &ListType) -> AbstractNumbering {
+  (0..9).fold(AbstractNumbering::new(id), |numbering, level| {
+    let (format, text) = match list_type {
+      ListType::Number => {
+        let format = [
+          "decimal",
+          "lowerLetter",
+          "lowerRoman",
+          "upperLetter",
+          "upperRoman",
+        ][level % 5];
+        (format, format!("%{}.", level + 1))
+      }
+      _ => (
+        "bullet",
+        ["\u{2022}", "\u{25e6}", "\u{25aa}"][level % 3].to_string(),
+      ),
+    };
+    numbering.add_level(
+      Level::new(
+        level,
+        Start::new(1),
+        NumberFormat::new(format),
+        LevelText::new(text),
+        LevelJc::new("left"),
+      )
+      .indent(
+        Some(LIST_INDENT * (level as i32 + 1)),
+        Some(SpecialIndentType::Hanging(LIST_INDENT / 2)),
+        None,
+        None,
+      ),
+    )
+  })
+}
+
+fn write_list(
+  input: &ElementSpan,
+  list_type: &ListType,
+  level: usize,
+  out: Docx,
+  state: &mut State,
+) -> Result<Docx> {
+  if let ListType::Description = list_type {
+    return input
+      .children
+      .iter()
+      .try_fold(out, |out, item| write_description(item, level, out, state));
+  }
+  if let ListType::Callout = list_type {
+    // Callouts keep the numbers of the listing
+    return input.children.iter().try_fold(out, |out, item| {
+      let number = item.get_attribute("callout").unwrap_or("");
+      let p = Paragraph::new()
+        .indent(Some(LIST_INDENT * (level as i32 + 1)), None, None, None)
+        .add_run(Run::new().add_text(format!("({}) ", number)).bold());
+      write_list_item(item, p, level, out, state)
+    });
+  }
+
+  // Every list gets its own numbering, so that numbered lists restart
+  let id = out.numberings.numberings.len() + 2;
+  let abstract_id = match list_type {
+    ListType::Number => ORDERED_NUMBERING,
+    _ => BULLET_NUMBERING,
+  };
+  let mut numbering = Numbering::new(id, abstract_id);
+  if let Some(start) = input
+    .get_attribute("start")
+    .and_then(|start| start.parse().ok())
+  {
+    numbering = numbering.add_override(LevelOverride::new(level).start(start));
+  }
+  let out = out.add_numbering(numbering);
+
+  input.children.iter().try_fold(out, |out, item| {
+    let p = Paragraph::new().numbering(NumberingId::new(id), IndentLevel::new(level));
+    let p = match item.get_attribute("checked") {
+      Some("true") => p.add_run(Run::new().add_text("\u{2612} ")),
+      Some(_) => p.add_run(Run::new().add_text("\u{2610} ")),
+      None => p,
+    };
+    write_list_item(item, p, level, out, state)
+  })
+}
+
+fn write_description(
+  input: &ElementSpan,
+  level: usize,
+  out: Docx,
+  state: &mut State,
+) -> Result<Docx> {
+  let indent = LIST_INDENT * level as i32;
+  let term = Paragraph::new()
+    .add_run(
+      Run::new()
+        .add_text(input.get_attribute("term").unwrap_or(""))
+        .bold(),
+    )
+    .indent(Some(indent), None, None, None);
+  let p = Paragraph::new().indent(Some(indent + LIST_INDENT), None, None, None);
+
+  write_list_item(input, p, level, out.add_paragraph(term), state)
+}
+
+/// Writes the first paragraph of the item into `p` followed by all
+/// other blocks of the item
+fn write_list_item(
+  input: &ElementSpan,
+  p: Paragraph,
+  level: usize,
+  out: Docx,
+  state: &mut State,
+) -> Result<Docx> {
+  let (first, rest) = match input.children.split_first() {
+    Some((first, rest)) if first.element == Element::Paragraph => (Some(first), rest),
+    _ => (None, &input.children[..]),
+  };
+  let out = match first {
+    Some(first) => {
+      let p = first
+        .children
+        .iter()
+        .try_fold(p, |p, element| paragraph(element, p, state))?;
+      out.add_paragraph(p)
+    }
+    None if input.children.is_empty() => out.add_paragraph(p),
+    None => out,
+  };
+
+  rest
+    .iter()
+    .try_fold(out, |out, element| match &element.element {
+      Element::List(list_type) => write_list(element, list_type, level + 1, out, state),
+      _ => write_doc(element, out, state),
+    })
+}
+
+// Helper Functions
+//----------------------------------------------------
+
+/// Keeps the lines of the content in one run
+fn preformatted(content: &str) -> Run {
+  content
+    .split('\n')
+    .enumerate()
+    .fold(Run::new(), |r, (index, line)| {
+      let r = match index {
+        0 => r,
+        _ => r.add_break(BreakType::TextWrapping),
+      };
+      line.split('\t').enumerate().fold(r, |r, (index, part)| {
+        let r = match index {
+          0 => r,
+          _ => r.add_tab(),
+        };
+        match part.is_empty() {
+          true => r,
+          false => r.add_text(part),
+        }
+      })
+    })
+}
+
+/// Colours the tokens of a listing like the theme. Plain text keeps
+/// the colour of the paragraph.
+fn highlighted(tokens: &[highlight::Token], theme: &Theme) -> Vec<Run> {
+  tokens
+    .iter()
+    .map(|(kind, text)| {
+      let run = preformatted(text);
+      match theme.style(*kind) {
+        Some(style) if style.bold => run.color(style.color.trim_start_matches('#')).bold(),
+        Some(style) => run.color(style.color.trim_start_matches('#')),
+        None => run,
+      }
+    })
+    .collect()
+}
+
+fn vert_align(mut r: Run, align: VertAlignType) -> Run {
+  r.run_property = r.run_property.vert_align(align);
+  r
+}
+
+/// Pixels are converted to the EMU of Word with 96 dpi
+const EMU_PER_PX: u32 = 9525;
+const EMU_PER_TWIP: u32 = 635;
+
+/// Loads a local image. It is at most as wide as the text or the
+/// width in pixels if given.
+fn picture(path: &str, width: Option<&str>, state: &State) -> Option<Pic> {
+  if path.contains("://") {
+    warn!("remote image {} can't be embedded in Word documents", path);
+    return None;
+  }
+  let path = state.base.join(path);
+  let image = match std::fs::read(&path)
+    .map_err(|e| e.to_string())
+    .and_then(|data| image::load_from_memory(&data).map_err(|e| e.to_string()))
+  {
+    Ok(image) => image,
+    Err(e) => {
+      warn!("couldn't embed image {} ({})", path.display(), e);
+      return None;
+    }
+  };
+  // Word gets all images as png like docx-rs does itself
+  let mut png = io::Cursor::new(Vec::new());
+  if let Err(e) = image.write_to(&mut png, image::ImageFormat::Png) {
+    warn!("couldn't embed image {} ({})", path.display(), e);
+    return None;
+  }
+
+  let (width_px, height_px) = (image.width().max(1), image.height().max(1));
+  let mut width_emu = width
+    .and_then(|width| width.parse::<u32>().ok())
+    .unwrap_or(width_px)
+    * EMU_PER_PX;
+  width_emu = width_emu.min(state.text_width * EMU_PER_TWIP);
+  let height_emu = (width_emu as u64 * height_px as u64 / width_px as u64) as u32;
+
+  Some(Pic::new_with_dimensions(png.into_inner(), width_px, height_px).size(width_emu, height_emu))
+}
+
+/// The width of the page without the margins in twips
+fn text_width(doc: &Docx) -> u32 {
+  let page = &doc.document.section_property;
+  // docx-rs keeps the size of the page private
+  let page_width = serde_json::to_value(&page.page_size)
+    .ok()
+    .and_then(|size| size.get("w").and_then(|w| w.as_u64()))
+    .unwrap_or(11906) as i32;
+  (page_width - page.page_margin.left - page.page_margin.right).max(LIST_INDENT) as u32
+}
+
+fn collect_titles(elements: &[ElementSpan], titles: &mut HashMap<String, String>) {
+  for element in elements {
+    if let Element::Title { .. } = element.element {
+      if let (Some(id), Some(name)) = (element.get_attribute("id"), element.get_attribute("name")) {
+        titles.insert(id.to_string(), decode_references(name));
+      }
+    }
+    if let Element::IncludeElement(include) = &element.element {
+      collect_titles(&include.inner.elements, titles);
+    }
+    collect_titles(&element.children, titles);
+  }
+}
+
+/// Checks if the option is set with `%name` or in the `options`
+/// attribute of an element
+fn has_option(input: &ElementSpan, name: &str) -> bool {
+  input.positional_attributes.iter().any(|attribute| {
+    attribute
+      .as_str()
+      .split('%')
+      .skip(1)
+      .any(|option| option == name)
+  }) || input
+    .get_attribute("options")
+    .or_else(|| input.get_attribute("opts"))
+    .is_some_and(|options| options.split(',').any(|option| option.trim() == name))
+}
+
+fn positional_attribute<'a>(input: &'a ElementSpan, index: usize) -> Option<&'a str> {
+  input
+    .positional_attributes
+    .get(index)
+    .map(|value| value.as_str().trim())
+    .filter(|value| !value.is_empty())
+}
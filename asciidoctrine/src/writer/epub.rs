@@ -0,0 +1,459 @@
+pub use crate::ast::*;
+use crate::sections::number_titles;
+use crate::util::Environment;
+use crate::writer::html;
+use crate::{options, Result};
+use anyhow::Context;
+use std::io::{self, Write};
+use std::path::{Component, Path};
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipWriter};
+
+/// Writes an EPUB 3 e-book. Every chapter becomes a content document
+/// with the html of the `HtmlWriter`.
+///
+/// The container is a zip file, that's why the output has to be
+/// seekable.
+pub struct EpubWriter {
+  io: crate::util::Env,
+}
+
+impl EpubWriter {
+  pub fn new() -> Self {
+    EpubWriter {
+      io: crate::util::Env::Io(crate::util::Io::new()),
+    }
+  }
+
+  /// Reads the stylesheet and the images through the environment,
+  /// e.g. a `Cache` in tests
+  pub fn with_env(io: crate::util::Env) -> Self {
+    EpubWriter { io }
+  }
+}
+
+impl Default for EpubWriter {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+impl<T: io::Write + io::Seek> crate::Writer<T> for EpubWriter {
+  fn write<'a>(&mut self, ast: AST, args: &options::Opts, out: T) -> Result<()> {
+    let ast = number_titles(ast);
+    html::begin_document(&ast);
+    html::use_xml_syntax();
+    let lang = ast.get_attribute("lang").unwrap_or("en");
+    let class = match ast.get_attribute("doctype") {
+      Some("book") => "book",
+      _ => "article",
+    };
+    let doctitle = ast.header.title.as_deref();
+    // The document title opens the first chapter but isn't part of
+    // the table of contents
+    let title_element = ast
+      .elements
+      .first()
+      .filter(|element| doctitle.is_some() && element.element == Element::Title { level: 1 });
+
+    let mut chapters = Vec::new();
+    html::split_parts(&ast.elements, 2, &mut chapters);
+    if chapters.is_empty() {
+      chapters.push(Vec::new());
+    }
+    let files: Vec<String> = (1..=chapters.len())
+      .map(|number| format!("chapter-{}.xhtml", number))
+      .collect();
+
+    // The references between the chapters need the file of their target
+    html::set_part_files(&chapters, &files);
+
+    let toclevels = html::toclevels(&ast);
+    let mut documents = Vec::new();
+    let mut nav = Vec::new();
+    for (index, (chapter, file)) in chapters.iter().zip(files.iter()).enumerate() {
+      let header = match title_element {
+        Some(_) if index == 0 => Some(&ast.header),
+        _ => None,
+      };
+      html::begin_part_file(file);
+      let mut body = Vec::new();
+      html::write_part(chapter, header, &mut body)?;
+      let body = std::str::from_utf8(&body)?;
+
+      for element in chapter {
+        if !title_element.is_some_and(|title| std::ptr::eq(*element, title)) {
+          collect_nav_entries(element, file, toclevels + 1, &mut nav);
+        }
+      }
+      let title = html::part_title(chapter).or(doctitle).unwrap_or(file);
+      documents.push((
+        file,
+        content_document(title, lang, class, body),
+        body.contains("src=\"http"),
+      ));
+    }
+    if nav.is_empty() {
+      nav.push(NavEntry {
+        level: 1,
+        href: files[0].clone(),
+        label: html::escape_attribute(doctitle.unwrap_or(&files[0])),
+      });
+    }
+
+    let toc_title = ast
+      .get_attribute("toc-title")
+      .unwrap_or("Table of Contents");
+    let mut toc = format!(
+      "<nav epub:type=\"toc\" id=\"toc\">\n<h1>{}</h1>\n",
+      html::escape_attribute(toc_title)
+    );
+    write_nav_list(&nav, &mut toc);
+    toc.push_str("</nav>\n");
+    let nav_document = content_document(toc_title, lang, class, &toc);
+
+    let mut stylesheet = match &args.stylesheet {
+      Some(path) => {
+        let path = path.to_str().expect("path to stylesheet unreadable");
+        self.io.read_to_string(path)?
+      }
+      None => include_str!("assets/asciidoctor.css").to_string(),
+    };
+    stylesheet.push_str(&html::highlight_stylesheet());
+
+    let mut base = args
+      .input
+      .as_ref()
+      .and_then(|input| input.parent())
+      .map(Path::to_path_buf)
+      .unwrap_or_default();
+    if let Some(imagesdir) = ast.get_attribute("imagesdir") {
+      base.push(imagesdir);
+    }
+    let mut paths = Vec::new();
+    html::collect_images(&ast.elements, &mut paths);
+    let images = load_images(&mut self.io, &base, &paths);
+
+    let mut zip = ZipWriter::new(out);
+    // The mimetype comes first and uncompressed so that readers can
+    // recognize the container by its first bytes
+    zip.start_file(
+      "mimetype",
+      SimpleFileOptions::default().compression_method(CompressionMethod::Stored),
+    )?;
+    zip.write_all(b"application/epub+zip")?;
+    let options = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+    zip.start_file("META-INF/container.xml", options)?;
+    zip.write_all(CONTAINER.as_bytes())?;
+    zip.start_file("OEBPS/content.opf", options)?;
+    zip.write_all(package_document(&ast, lang, &documents, &images).as_bytes())?;
+    zip.start_file("OEBPS/nav.xhtml", options)?;
+    zip.write_all(nav_document.as_bytes())?;
+    zip.start_file("OEBPS/stylesheet.css", options)?;
+    zip.write_all(stylesheet.as_bytes())?;
+    for (file, document, _) in documents.iter() {
+      zip.start_file(format!("OEBPS/{}", file), options)?;
+      zip.write_all(document.as_bytes())?;
+    }
+    for image in images.iter() {
+      zip.start_file(format!("OEBPS/{}", image.path), options)?;
+      zip.write_all(&image.data)?;
+    }
+    zip.finish()?.flush()?;
+
+    Ok(())
+  }
+}
+
+const CONTAINER: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<container version="1.0" xmlns="urn:oasis:names:tc:opendocument:xmlns:container">
+  <rootfiles>
+    <rootfile full-path="OEBPS/content.opf" media-type="application/oebps-package+xml"/>
+  </rootfiles>
+</container>
+"#;
+
+// Chapters
+//----------------------------------------------------
+
+fn content_document(title: &str, lang: &str, class: &str, body: &str) -> String {
+  format!(
+    r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE html>
+<html xmlns="http://www.w3.org/1999/xhtml" xmlns:epub="http://www.idpf.org/2007/ops" xml:lang="{lang}" lang="{lang}">
+<head>
+<meta charset="UTF-8"/>
+<title>{title}</title>
+<link rel="stylesheet" type="text/css" href="stylesheet.css"/>
+</head>
+<body class="{class}">
+{body}</body>
+</html>
+"#,
+    lang = lang,
+    title = html::escape_attribute(title),
+    class = class,
+    body = body,
+  )
+}
+
+// Navigation
+//----------------------------------------------------
+
+struct NavEntry {
+  level: u32,
+  href: String,
+  label: String,
+}
+
+fn collect_nav_entries(element: &ElementSpan, file: &str, depth: u32, entries: &mut Vec<NavEntry>) {
+  let (level, title) = match &element.element {
+    Element::Title { level } => (*level, element),
+    Element::Section { level } => match element.children.first() {
+      Some(title) => (*level, title),
+      None => return,
+    },
+    Element::IncludeElement(include) => {
+      for element in include.inner.elements.iter() {
+        collect_nav_entries(element, file, depth, entries);
+      }
+      return;
+    }
+    _ => return,
+  };
+
+  if level <= depth {
+    let name = html::escape_attribute(title.get_attribute("name").unwrap_or(""));
+    entries.push(NavEntry {
+      level,
+      // The html of a part title has no id
+      href: match element.get_attribute("id").filter(|_| level > 1) {
+        Some(id) => format!("{}#{}", file, id),
+        None => file.to_string(),
+      },
+      label: match title.get_attribute("sectnum") {
+        Some(sectnum) => format!("{} {}", sectnum, name),
+        None => name,
+      },
+    });
+  }
+  if let Element::Section { .. } = element.element {
+    for child in element.children.iter().skip(1) {
+      collect_nav_entries(child, file, depth, entries);
+    }
+  }
+}
+
+/// Writes the entries as nested lists. The entries of a deeper level
+/// belong to the entry before them.
+fn write_nav_list(entries: &[NavEntry], out: &mut String) {
+  out.push_str("<ol>\n");
+  let mut rest = entries;
+  while let Some((entry, tail)) = rest.split_first() {
+    let end = tail
+      .iter()
+      .position(|next| next.level <= entry.level)
+      .unwrap_or(tail.len());
+    out.push_str(&format!(
+      "<li><a href=\"{}\">{}</a>",
+      entry.href, entry.label
+    ));
+    if end > 0 {
+      out.push('\n');
+      write_nav_list(&tail[..end], out);
+    }
+    out.push_str("</li>\n");
+    rest = &tail[end..];
+  }
+  out.push_str("</ol>\n");
+}
+
+// Package
+//----------------------------------------------------
+
+/// Builds `content.opf` with the metadata, the files and the reading
+/// order of the book
+fn package_document(
+  ast: &AST,
+  lang: &str,
+  documents: &[(&String, String, bool)],
+  images: &[Image],
+) -> String {
+  let identifier = match ast.get_attribute("uuid") {
+    Some(uuid) => format!("urn:uuid:{}", uuid),
+    None => {
+      let authors: Vec<&str> = ast
+        .header
+        .authors
+        .iter()
+        .map(|author| author.name.as_str())
+        .collect();
+      let key = format!(
+        "{}\n{}",
+        ast.header.title.as_deref().unwrap_or(""),
+        authors.join("\n")
+      );
+      format!("urn:asciidoctrine:{:016x}", fnv1a(key.as_bytes()))
+    }
+  };
+
+  let mut metadata = vec![
+    format!(
+      "<dc:identifier id=\"bookid\">{}</dc:identifier>",
+      html::escape_attribute(&identifier)
+    ),
+    format!(
+      "<dc:title>{}</dc:title>",
+      html::escape_attribute(ast.header.title.as_deref().unwrap_or("Untitled"))
+    ),
+    format!(
+      "<dc:language>{}</dc:language>",
+      html::escape_attribute(lang)
+    ),
+  ];
+  for author in ast.header.authors.iter() {
+    metadata.push(format!(
+      "<dc:creator>{}</dc:creator>",
+      html::escape_attribute(&author.name)
+    ));
+  }
+  // Only a W3C date is allowed
+  if let Some(date) = ast
+    .header
+    .revision
+    .as_ref()
+    .and_then(|revision| revision.date.as_deref())
+    .filter(|date| chrono::NaiveDate::parse_from_str(date, "%Y-%m-%d").is_ok())
+  {
+    metadata.push(format!("<dc:date>{}</dc:date>", date));
+  }
+  if let Some(description) = ast.get_attribute("description") {
+    metadata.push(format!(
+      "<dc:description>{}</dc:description>",
+      html::escape_attribute(description)
+    ));
+  }
+  for keyword in ast
+    .get_attribute("keywords")
+    .unwrap_or("")
+    .split(',')
+    .map(str::trim)
+    .filter(|keyword| !keyword.is_empty())
+  {
+    metadata.push(format!(
+      "<dc:subject>{}</dc:subject>",
+      html::escape_attribute(keyword)
+    ));
+  }
+  metadata.push(format!(
+    "<meta property=\"dcterms:modified\">{}</meta>",
+    chrono::Utc::now().format("%Y-%m-%dT%H:%M:%SZ")
+  ));
+
+  let mut manifest = vec![
+    "<item id=\"nav\" href=\"nav.xhtml\" media-type=\"application/xhtml+xml\" properties=\"nav\"/>"
+      .to_string(),
+    "<item id=\"stylesheet\" href=\"stylesheet.css\" media-type=\"text/css\"/>".to_string(),
+  ];
+  let mut spine = Vec::new();
+  for (index, (file, _, remote)) in documents.iter().enumerate() {
+    let properties = if *remote {
+      " properties=\"remote-resources\""
+    } else {
+      ""
+    };
+    manifest.push(format!(
+      "<item id=\"chapter-{}\" href=\"{}\" media-type=\"application/xhtml+xml\"{}/>",
+      index + 1,
+      file,
+      properties
+    ));
+    spine.push(format!("<itemref idref=\"chapter-{}\"/>", index + 1));
+  }
+  for (index, image) in images.iter().enumerate() {
+    manifest.push(format!(
+      "<item id=\"image-{}\" href=\"{}\" media-type=\"{}\"/>",
+      index + 1,
+      html::escape_attribute(&image.path),
+      image.media_type
+    ));
+  }
+
+  format!(
+    r#"<?xml version="1.0" encoding="UTF-8"?>
+<package xmlns="http://www.idpf.org/2007/opf" version="3.0" unique-identifier="bookid" xml:lang="{}">
+  <metadata xmlns:dc="http://purl.org/dc/elements/1.1/">
+    {}
+  </metadata>
+  <manifest>
+    {}
+  </manifest>
+  <spine>
+    {}
+  </spine>
+</package>
+"#,
+    html::escape_attribute(lang),
+    metadata.join("\n    "),
+    manifest.join("\n    "),
+    spine.join("\n    "),
+  )
+}
+
+/// A stable hash for the identifier of books without a `uuid`
+fn fnv1a(bytes: &[u8]) -> u64 {
+  bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+    (hash ^ u64::from(*byte)).wrapping_mul(0x0100_0000_01b3)
+  })
+}
+
+// Images
+//----------------------------------------------------
+
+struct Image {
+  path: String,
+  media_type: &'static str,
+  data: Vec<u8>,
+}
+
+/// Reads the local images. They keep their path inside the book, so
+/// the html needs no changes.
+fn load_images<E: Environment>(env: &mut E, base: &Path, paths: &[String]) -> Vec<Image> {
+  paths
+    .iter()
+    .filter_map(|path| {
+      if path.contains("://") || path.starts_with("data:") {
+        warn!("remote image {} is not embedded in the book", path);
+        return None;
+      }
+      if !Path::new(path)
+        .components()
+        .all(|component| matches!(component, Component::Normal(_) | Component::CurDir))
+      {
+        warn!("image {} is outside of the book", path);
+        return None;
+      }
+      let media_type = match html::image_media_type(path) {
+        Some(media_type) => media_type,
+        None => {
+          warn!("image {} has no supported format", path);
+          return None;
+        }
+      };
+      match env
+        .read(&base.join(path).to_string_lossy())
+        .with_context(|| format!("reading image {}", path))
+      {
+        Ok(data) => Some(Image {
+          path: path.trim_start_matches("./").to_string(),
+          media_type,
+          data,
+        }),
+        Err(error) => {
+          warn!("{:#}", error);
+          None
+        }
+      }
+    })
+    .collect()
+}
@@ -0,0 +1,938 @@
+//! A small syntax highlighter for source listings. It knows a few
+//! languages well enough to colour their keywords, strings, numbers
+//! and comments and works without any external tool.
+
+use crate::ast::AST;
+
+/// The kinds of tokens. They are named like the classes of
+/// highlight.js so its themes work as well.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum TokenKind {
+  Text,
+  Keyword,
+  Type,
+  BuiltIn,
+  Literal,
+  Number,
+  String,
+  Comment,
+  Meta,
+  Function,
+  Variable,
+  Attr,
+  Section,
+  Symbol,
+}
+
+impl TokenKind {
+  /// The class of highlight.js. Plain text has none.
+  pub(crate) fn class(self) -> Option<&'static str> {
+    match self {
+      TokenKind::Text => None,
+      TokenKind::Keyword => Some("hljs-keyword"),
+      TokenKind::Type => Some("hljs-type"),
+      TokenKind::BuiltIn => Some("hljs-built_in"),
+      TokenKind::Literal => Some("hljs-literal"),
+      TokenKind::Number => Some("hljs-number"),
+      TokenKind::String => Some("hljs-string"),
+      TokenKind::Comment => Some("hljs-comment"),
+      TokenKind::Meta => Some("hljs-meta"),
+      TokenKind::Function => Some("hljs-title function_"),
+      TokenKind::Variable => Some("hljs-variable"),
+      TokenKind::Attr => Some("hljs-attr"),
+      TokenKind::Section => Some("hljs-section"),
+      TokenKind::Symbol => Some("hljs-symbol"),
+    }
+  }
+}
+
+pub(crate) type Token<'a> = (TokenKind, &'a str);
+
+/// Splits the code into tokens. Languages without a grammar give
+/// `None`.
+pub(crate) fn highlight<'a>(language: &str, code: &'a str) -> Option<Vec<Token<'a>>> {
+  let language = language.to_lowercase();
+  if ASCIIDOC.contains(&language.as_str()) {
+    return Some(asciidoc(code));
+  }
+  SYNTAXES
+    .iter()
+    .find(|syntax| syntax.names.contains(&language.as_str()))
+    .map(|syntax| tokenize(syntax, code))
+}
+
+/// How the html writer highlights the listings of a document
+pub(crate) struct Highlighter {
+  /// Inline styles instead of the classes of highlight.js
+  pub inline_styles: bool,
+  pub theme: &'static Theme,
+}
+
+impl Highlighter {
+  /// The highlighter the attributes of the document ask for. Only
+  /// documents with a `source-highlighter` are highlighted.
+  pub(crate) fn from_document(ast: &AST) -> Option<Self> {
+    ast.get_attribute("source-highlighter")?;
+    let inline_styles = match ast.get_attribute("highlight-css") {
+      Some("style") => true,
+      Some("class") | None => false,
+      Some(css) => {
+        warn!("unknown highlight-css {}", css);
+        false
+      }
+    };
+
+    Some(Highlighter {
+      inline_styles,
+      theme: theme(ast.get_attribute("highlight-theme")),
+    })
+  }
+}
+
+/// The colour of a kind of token
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct TokenStyle {
+  pub color: &'static str,
+  pub bold: bool,
+}
+
+impl TokenStyle {
+  /// The style as css declarations
+  pub(crate) fn css(&self) -> String {
+    match self.bold {
+      true => format!("color: {}; font-weight: bold", self.color),
+      false => format!("color: {}", self.color),
+    }
+  }
+}
+
+pub(crate) struct Theme {
+  pub name: &'static str,
+  pub background: &'static str,
+  pub foreground: &'static str,
+  styles: &'static [(TokenKind, TokenStyle)],
+}
+
+impl Theme {
+  pub(crate) fn style(&self, kind: TokenKind) -> Option<TokenStyle> {
+    self
+      .styles
+      .iter()
+      .find(|(styled, _)| *styled == kind)
+      .map(|(_, style)| *style)
+  }
+
+  /// The css rules for the classes of highlight.js
+  pub(crate) fn stylesheet(&self) -> String {
+    let mut css = format!(
+      "\n/* {} */\n.hljs {{ background: {}; color: {}; }}\n",
+      self.name, self.background, self.foreground
+    );
+    for (kind, style) in self.styles {
+      if let Some(class) = kind.class() {
+        css.push_str(&format!(
+          ".{} {{ {}; }}\n",
+          class.replace(' ', "."),
+          style.css()
+        ));
+      }
+    }
+    css
+  }
+}
+
+/// The theme with the name. Unknown themes fall back to github.
+pub(crate) fn theme(name: Option<&str>) -> &'static Theme {
+  match name {
+    None | Some("github") => &GITHUB,
+    Some("monokai") => &MONOKAI,
+    Some(name) => {
+      warn!("unknown highlight-theme {}, using github", name);
+      &GITHUB
+    }
+  }
+}
+
+const fn color(color: &'static str) -> TokenStyle {
+  TokenStyle { color, bold: false }
+}
+
+const fn bold(color: &'static str) -> TokenStyle {
+  TokenStyle { color, bold: true }
+}
+
+static GITHUB: Theme = Theme {
+  name: "github",
+  background: "#ffffff",
+  foreground: "#24292e",
+  styles: &[
+    (TokenKind::Keyword, color("#d73a49")),
+    (TokenKind::Type, color("#d73a49")),
+    (TokenKind::BuiltIn, color("#e36209")),
+    (TokenKind::Literal, color("#005cc5")),
+    (TokenKind::Number, color("#005cc5")),
+    (TokenKind::String, color("#032f62")),
+    (TokenKind::Comment, color("#6a737d")),
+    (TokenKind::Meta, color("#005cc5")),
+    (TokenKind::Function, color("#6f42c1")),
+    (TokenKind::Variable, color("#005cc5")),
+    (TokenKind::Attr, color("#005cc5")),
+    (TokenKind::Section, bold("#005cc5")),
+    (TokenKind::Symbol, color("#e36209")),
+  ],
+};
+
+static MONOKAI: Theme = Theme {
+  name: "monokai",
+  background: "#272822",
+  foreground: "#dddddd",
+  styles: &[
+    (TokenKind::Keyword, bold("#f92672")),
+    (TokenKind::Type, bold("#a6e22e")),
+    (TokenKind::BuiltIn, color("#a6e22e")),
+    (TokenKind::Literal, bold("#f92672")),
+    (TokenKind::Number, color("#ae81ff")),
+    (TokenKind::String, color("#a6e22e")),
+    (TokenKind::Comment, color("#75715e")),
+    (TokenKind::Meta, color("#75715e")),
+    (TokenKind::Function, bold("#a6e22e")),
+    (TokenKind::Variable, color("#a6e22e")),
+    (TokenKind::Attr, color("#bf79db")),
+    (TokenKind::Section, bold("#a6e22e")),
+    (TokenKind::Symbol, color("#bf79db")),
+  ],
+};
+
+/// Splits the tokens at the line breaks. This way each line can be
+/// written on its own.
+pub(crate) fn lines<'a>(tokens: &[Token<'a>]) -> Vec<Vec<Token<'a>>> {
+  let mut lines = vec![Vec::new()];
+  for (kind, text) in tokens {
+    for (index, part) in text.split('\n').enumerate() {
+      if index > 0 {
+        lines.push(Vec::new());
+      }
+      if !part.is_empty() {
+        lines.last_mut().unwrap().push((*kind, part));
+      }
+    }
+  }
+  lines
+}
+
+// Grammars
+//----------------------------------------------------
+
+/// The rules of a programming language. Everything not covered by
+/// them is plain text.
+struct Syntax {
+  names: &'static [&'static str],
+  keywords: &'static [&'static str],
+  types: &'static [&'static str],
+  built_ins: &'static [&'static str],
+  literals: &'static [&'static str],
+  line_comments: &'static [&'static str],
+  block_comment: Option<(&'static str, &'static str)>,
+  /// The delimiters of strings, the longer ones first
+  strings: &'static [&'static str],
+  /// The delimiters of strings without escapes
+  raw_strings: &'static [&'static str],
+  /// Prefixes like `b` or `f` right before a string
+  string_prefixes: &'static [&'static str],
+  /// Lines starting with `#` are for the preprocessor (C)
+  preprocessor: bool,
+  /// `#[...]` attributes, `'a'` characters, `'a` lifetimes, `name!`
+  /// macros and `r#"..."#` raw strings (Rust)
+  rust: bool,
+  /// `@name` decorators (Python)
+  decorators: bool,
+  /// `$name` variables (shell)
+  variables: bool,
+  /// Names starting with a capital letter are types
+  capitalized_types: bool,
+  /// `[table]` headers and bare keys (TOML)
+  tables: bool,
+  /// A string before this character is a key
+  key_separator: Option<char>,
+}
+
+const PLAIN: Syntax = Syntax {
+  names: &[],
+  keywords: &[],
+  types: &[],
+  built_ins: &[],
+  literals: &[],
+  line_comments: &[],
+  block_comment: None,
+  strings: &[],
+  raw_strings: &[],
+  string_prefixes: &[],
+  preprocessor: false,
+  rust: false,
+  decorators: false,
+  variables: false,
+  capitalized_types: false,
+  tables: false,
+  key_separator: None,
+};
+
+const SYNTAXES: &[Syntax] = &[
+  Syntax {
+    names: &["rust", "rs"],
+    keywords: &[
+      "as", "async", "await", "break", "const", "continue", "crate", "dyn", "else", "enum",
+      "extern", "fn", "for", "if", "impl", "in", "let", "loop", "match", "mod", "move", "mut",
+      "pub", "ref", "return", "self", "Self", "static", "struct", "super", "trait", "type",
+      "unsafe", "use", "where", "while",
+    ],
+    types: &[
+      "i8", "i16", "i32", "i64", "i128", "isize", "u8", "u16", "u32", "u64", "u128", "usize",
+      "f32", "f64", "bool", "char", "str",
+    ],
+    built_ins: &["Some", "None", "Ok", "Err"],
+    literals: &["true", "false"],
+    line_comments: &["//"],
+    block_comment: Some(("/*", "*/")),
+    strings: &["\""],
+    string_prefixes: &["b", "r", "br"],
+    rust: true,
+    capitalized_types: true,
+    ..PLAIN
+  },
+  Syntax {
+    names: &["python", "py"],
+    keywords: &[
+      "and", "as", "assert", "async", "await", "break", "class", "continue", "def", "del", "elif",
+      "else", "except", "finally", "for", "from", "global", "if", "import", "in", "is", "lambda",
+      "nonlocal", "not", "or", "pass", "raise", "return", "try", "while", "with", "yield",
+    ],
+    built_ins: &[
+      "abs",
+      "all",
+      "any",
+      "bool",
+      "bytes",
+      "dict",
+      "enumerate",
+      "filter",
+      "float",
+      "getattr",
+      "hasattr",
+      "input",
+      "int",
+      "isinstance",
+      "iter",
+      "len",
+      "list",
+      "map",
+      "max",
+      "min",
+      "next",
+      "object",
+      "open",
+      "print",
+      "range",
+      "repr",
+      "set",
+      "setattr",
+      "sorted",
+      "str",
+      "sum",
+      "super",
+      "tuple",
+      "type",
+      "zip",
+    ],
+    literals: &["True", "False", "None"],
+    line_comments: &["#"],
+    strings: &["\"\"\"", "'''", "\"", "'"],
+    string_prefixes: &["f", "r", "b", "u", "rb", "br", "fr", "rf"],
+    decorators: true,
+    ..PLAIN
+  },
+  Syntax {
+    names: &["sh", "bash", "shell", "zsh", "console"],
+    keywords: &[
+      "if", "then", "else", "elif", "fi", "for", "while", "until", "do", "done", "case", "esac",
+      "in", "function", "select", "return", "local", "export",
+    ],
+    built_ins: &[
+      "alias", "cd", "echo", "eval", "exec", "exit", "printf", "pwd", "read", "set", "shift",
+      "source", "test", "trap", "unset", "wait",
+    ],
+    literals: &["true", "false"],
+    line_comments: &["#"],
+    strings: &["\""],
+    raw_strings: &["'"],
+    variables: true,
+    ..PLAIN
+  },
+  Syntax {
+    names: &["c", "h", "cpp", "c++", "cc", "cxx", "hpp"],
+    keywords: &[
+      "break",
+      "case",
+      "catch",
+      "class",
+      "const",
+      "constexpr",
+      "continue",
+      "default",
+      "delete",
+      "do",
+      "else",
+      "enum",
+      "explicit",
+      "extern",
+      "for",
+      "friend",
+      "goto",
+      "if",
+      "inline",
+      "namespace",
+      "new",
+      "noexcept",
+      "operator",
+      "override",
+      "private",
+      "protected",
+      "public",
+      "register",
+      "return",
+      "sizeof",
+      "static",
+      "struct",
+      "switch",
+      "template",
+      "this",
+      "throw",
+      "try",
+      "typedef",
+      "typename",
+      "union",
+      "using",
+      "virtual",
+      "volatile",
+      "while",
+    ],
+    types: &[
+      "auto", "bool", "char", "double", "float", "int", "long", "short", "signed", "unsigned",
+      "void", "size_t", "wchar_t", "int8_t", "int16_t", "int32_t", "int64_t", "uint8_t",
+      "uint16_t", "uint32_t", "uint64_t",
+    ],
+    built_ins: &["std", "printf", "malloc", "free", "cout", "cerr", "endl"],
+    literals: &["true", "false", "NULL", "nullptr"],
+    line_comments: &["//"],
+    block_comment: Some(("/*", "*/")),
+    strings: &["\"", "'"],
+    preprocessor: true,
+    ..PLAIN
+  },
+  Syntax {
+    names: &["json"],
+    literals: &["true", "false", "null"],
+    strings: &["\""],
+    key_separator: Some(':'),
+    ..PLAIN
+  },
+  Syntax {
+    names: &["toml"],
+    literals: &["true", "false"],
+    line_comments: &["#"],
+    strings: &["\"\"\"", "\""],
+    raw_strings: &["'''", "'"],
+    tables: true,
+    key_separator: Some('='),
+    ..PLAIN
+  },
+];
+
+const ASCIIDOC: &[&str] = &["asciidoc", "adoc"];
+
+// Lexers
+//----------------------------------------------------
+
+/// Collects the tokens while going through the code
+struct Lexer<'a> {
+  code: &'a str,
+  position: usize,
+  tokens: Vec<Token<'a>>,
+}
+
+impl<'a> Lexer<'a> {
+  fn new(code: &'a str) -> Self {
+    Lexer {
+      code,
+      position: 0,
+      tokens: Vec::new(),
+    }
+  }
+
+  fn rest(&self) -> &'a str {
+    &self.code[self.position..]
+  }
+
+  /// Checks if only whitespace precedes on the current line
+  fn at_line_start(&self) -> bool {
+    self.code[..self.position]
+      .rsplit('\n')
+      .next()
+      .unwrap_or_default()
+      .trim()
+      .is_empty()
+  }
+
+  fn line_length(&self) -> usize {
+    self.rest().find('\n').unwrap_or_else(|| self.rest().len())
+  }
+
+  /// Takes the next bytes as a token. Tokens of the same kind are
+  /// merged.
+  fn push(&mut self, kind: TokenKind, length: usize) {
+    if length == 0 {
+      return;
+    }
+    let start = self.position;
+    self.position += length;
+    match self.tokens.last_mut() {
+      Some((last, text)) if *last == kind => {
+        *text = &self.code[start - text.len()..self.position];
+      }
+      _ => self.tokens.push((kind, &self.code[start..self.position])),
+    }
+  }
+}
+
+fn is_identifier(c: char) -> bool {
+  c.is_alphanumeric() || c == '_'
+}
+
+fn identifier_length(text: &str) -> usize {
+  text.find(|c| !is_identifier(c)).unwrap_or(text.len())
+}
+
+/// The length of a string from its opening delimiter to the closing
+/// one or the end of the code
+fn string_length(text: &str, open: usize, close: &str, escapes: bool) -> usize {
+  let mut index = open;
+  while let Some(c) = text[index..].chars().next() {
+    if escapes && c == '\\' {
+      index += 1;
+      index += text[index..].chars().next().map_or(0, char::len_utf8);
+    } else if text[index..].starts_with(close) {
+      return index + close.len();
+    } else {
+      index += c.len_utf8();
+    }
+  }
+  text.len()
+}
+
+/// The length of brackets with everything in them
+fn bracket_length(text: &str, open: char, close: char) -> usize {
+  let mut depth = 0;
+  for (index, c) in text.char_indices() {
+    if c == open {
+      depth += 1;
+    } else if c == close {
+      depth -= 1;
+      if depth == 0 {
+        return index + 1;
+      }
+    }
+  }
+  text.len()
+}
+
+fn tokenize<'a>(syntax: &Syntax, code: &'a str) -> Vec<Token<'a>> {
+  let mut lexer = Lexer::new(code);
+
+  while let Some(c) = lexer.rest().chars().next() {
+    let rest = lexer.rest();
+    let line_start = lexer.at_line_start();
+
+    let shebang =
+      lexer.position == 0 && rest.starts_with("#!") && syntax.line_comments.contains(&"#");
+    if shebang || (syntax.preprocessor && line_start && c == '#') {
+      lexer.push(TokenKind::Meta, lexer.line_length());
+    } else if syntax.rust && (rest.starts_with("#[") || rest.starts_with("#![")) {
+      let open = rest.find('[').unwrap();
+      lexer.push(
+        TokenKind::Meta,
+        open + bracket_length(&rest[open..], '[', ']'),
+      );
+    } else if syntax
+      .line_comments
+      .iter()
+      .any(|start| rest.starts_with(start))
+    {
+      lexer.push(TokenKind::Comment, lexer.line_length());
+    } else if let Some((open, close)) = syntax
+      .block_comment
+      .filter(|(open, _)| rest.starts_with(*open))
+    {
+      lexer.push(
+        TokenKind::Comment,
+        string_length(rest, open.len(), close, false),
+      );
+    } else if syntax.tables && line_start && c == '[' {
+      lexer.push(TokenKind::Section, bracket_length(rest, '[', ']'));
+    } else if let Some(length) = (syntax.tables && line_start)
+      .then(|| key_length(rest, syntax.key_separator))
+      .flatten()
+    {
+      lexer.push(TokenKind::Attr, length);
+    } else if syntax.decorators && line_start && c == '@' {
+      let length = 1
+        + rest[1..]
+          .find(|c| !(is_identifier(c) || c == '.'))
+          .unwrap_or(rest.len() - 1);
+      lexer.push(TokenKind::Meta, length);
+    } else if syntax.variables && c == '$' {
+      lexer.push(TokenKind::Variable, variable_length(rest));
+    } else if let Some(length) = string_at(syntax, rest, 0) {
+      let kind = match key_follows(&rest[length..], syntax.key_separator) {
+        true => TokenKind::Attr,
+        false => TokenKind::String,
+      };
+      lexer.push(kind, length);
+    } else if syntax.rust && c == '\'' {
+      let (kind, length) = quote_length(rest);
+      lexer.push(kind, length);
+    } else if c.is_ascii_digit() {
+      let length = rest
+        .char_indices()
+        .find(|(index, c)| {
+          !(is_identifier(*c)
+            || (*c == '.' && rest[index + 1..].starts_with(|c: char| c.is_ascii_digit())))
+        })
+        .map_or(rest.len(), |(index, _)| index);
+      lexer.push(TokenKind::Number, length);
+    } else if is_identifier(c) {
+      let length = identifier_length(rest);
+      let word = &rest[..length];
+      let after = &rest[length..];
+      if let Some(string) = string_at(syntax, after, length).filter(|_| {
+        syntax
+          .string_prefixes
+          .contains(&word.to_lowercase().as_str())
+      }) {
+        lexer.push(TokenKind::String, string);
+      } else if syntax.rust && after.starts_with('!') && !after.starts_with("!=") {
+        lexer.push(TokenKind::BuiltIn, length + 1);
+      } else {
+        lexer.push(word_kind(syntax, word, after), length);
+      }
+    } else {
+      lexer.push(TokenKind::Text, c.len_utf8());
+    }
+  }
+
+  lexer.tokens
+}
+
+/// The kind of a name with the text after it
+fn word_kind(syntax: &Syntax, word: &str, after: &str) -> TokenKind {
+  if syntax.keywords.contains(&word) {
+    TokenKind::Keyword
+  } else if syntax.literals.contains(&word) {
+    TokenKind::Literal
+  } else if syntax.types.contains(&word) {
+    TokenKind::Type
+  } else if syntax.built_ins.contains(&word) {
+    TokenKind::BuiltIn
+  } else if after.trim_start_matches(' ').starts_with('(') {
+    TokenKind::Function
+  } else if syntax.capitalized_types && word.starts_with(|c: char| c.is_uppercase()) {
+    TokenKind::Type
+  } else {
+    TokenKind::Text
+  }
+}
+
+/// The length of a string starting the text. Prefixed strings start
+/// `prefix` bytes before the text.
+fn string_at(syntax: &Syntax, text: &str, prefix: usize) -> Option<usize> {
+  if syntax.rust && prefix > 0 && text.starts_with(['#', '"']) {
+    // Raw strings close with as many hashes as they open
+    let hashes = text.find(|c| c != '#').unwrap_or(text.len());
+    if !text[hashes..].starts_with('"') {
+      return None;
+    }
+    let close = format!("\"{}", &text[..hashes]);
+    return Some(prefix + string_length(text, hashes + 1, &close, hashes == 0));
+  }
+  if let Some(open) = syntax
+    .raw_strings
+    .iter()
+    .find(|open| text.starts_with(*open))
+  {
+    return Some(prefix + string_length(text, open.len(), open, false));
+  }
+  syntax
+    .strings
+    .iter()
+    .find(|open| text.starts_with(*open))
+    .map(|open| prefix + string_length(text, open.len(), open, true))
+}
+
+/// Checks if a key separator follows on the same line
+fn key_follows(text: &str, separator: Option<char>) -> bool {
+  separator.is_some_and(|separator| text.trim_start_matches([' ', '\t']).starts_with(separator))
+}
+
+/// The length of a bare key before a key separator
+fn key_length(text: &str, separator: Option<char>) -> Option<usize> {
+  let length = text
+    .find(|c: char| !(is_identifier(c) || c == '-' || c == '.'))
+    .unwrap_or(text.len());
+  Some(length).filter(|length| *length > 0 && key_follows(&text[*length..], separator))
+}
+
+/// The length of a shell variable like `$name`, `${name}` or `$?`
+fn variable_length(text: &str) -> usize {
+  let after = &text[1..];
+  if after.starts_with('{') {
+    1 + bracket_length(after, '{', '}')
+  } else if after.starts_with(|c: char| "#?@*$!-".contains(c) || c.is_ascii_digit()) {
+    2
+  } else {
+    1 + identifier_length(after)
+  }
+}
+
+/// Rust uses single quotes for characters and lifetimes
+fn quote_length(text: &str) -> (TokenKind, usize) {
+  let after = &text[1..];
+  if after.starts_with('\\') {
+    return (TokenKind::String, string_length(text, 1, "'", true));
+  }
+  if let Some(c) = after.chars().next() {
+    if after[c.len_utf8()..].starts_with('\'') {
+      return (TokenKind::String, 2 + c.len_utf8());
+    }
+  }
+  match identifier_length(after) {
+    0 => (TokenKind::Text, 1),
+    length => (TokenKind::Symbol, 1 + length),
+  }
+}
+
+/// Asciidoc is highlighted line by line
+fn asciidoc(code: &str) -> Vec<Token<'_>> {
+  let mut lexer = Lexer::new(code);
+  let mut in_comment = false;
+
+  for line in code.split_inclusive('\n') {
+    let text = line.trim_end_matches('\n');
+    let newline = line.len() - text.len();
+    if text == "////" {
+      in_comment = !in_comment;
+      lexer.push(TokenKind::Comment, text.len());
+    } else if in_comment || (text.starts_with("//") && !text.starts_with("///")) {
+      lexer.push(TokenKind::Comment, text.len());
+    } else if is_heading(text) || is_block_title(text) {
+      lexer.push(TokenKind::Section, text.len());
+    } else if let Some(name) = attribute_entry(text) {
+      lexer.push(TokenKind::Attr, name);
+      lexer.push(TokenKind::String, text.len() - name);
+    } else if is_delimiter(text) || (text.starts_with('[') && text.ends_with(']')) {
+      lexer.push(TokenKind::Meta, text.len());
+    } else if let Some(name) = block_macro(text) {
+      lexer.push(TokenKind::Keyword, name);
+      lexer.push(TokenKind::Text, text.len() - name);
+    } else {
+      lexer.push(TokenKind::Text, text.len());
+    }
+    lexer.push(TokenKind::Text, newline);
+  }
+
+  lexer.tokens
+}
+
+fn is_heading(line: &str) -> bool {
+  let level = line.find(|c| c != '=').unwrap_or(line.len());
+  (1..=6).contains(&level) && line[level..].starts_with(' ')
+}
+
+fn is_block_title(line: &str) -> bool {
+  line.starts_with('.') && line[1..].starts_with(|c: char| !(c.is_whitespace() || c == '.'))
+}
+
+fn is_delimiter(line: &str) -> bool {
+  line == "--"
+    || line == "|==="
+    || (line.len() >= 4
+      && ["-", ".", "=", "*", "_", "+"]
+        .iter()
+        .any(|c| line.chars().all(|l| l.to_string() == *c)))
+}
+
+/// The length of the name of an attribute entry like `:name:`
+fn attribute_entry(line: &str) -> Option<usize> {
+  let end = line.strip_prefix(':')?.find(':')? + 2;
+  let name = line[1..end - 1].trim_end_matches('!');
+  Some(end).filter(|_| {
+    !name.is_empty()
+      && name
+        .chars()
+        .all(|c| is_identifier(c) || c == '-' || c == '!')
+  })
+}
+
+/// The length of the name of a block macro like `image::`
+fn block_macro(line: &str) -> Option<usize> {
+  let name = line.find("::")?;
+  Some(name + 2).filter(|_| {
+    name > 0 && line[..name].chars().all(|c| c.is_ascii_alphanumeric()) && line.ends_with(']')
+  })
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use TokenKind::*;
+
+  fn kinds<'a>(language: &str, code: &'a str) -> Vec<(TokenKind, &'a str)> {
+    highlight(language, code)
+      .unwrap()
+      .into_iter()
+      .filter(|(kind, _)| *kind != Text)
+      .collect()
+  }
+
+  #[test]
+  fn rust_tokens() {
+    let code = "#[derive(Debug)]\nfn main() { let c: &'a str = 'x'; println!(\"{}\", r#\"\"raw\"\"#, 4.5); } // done";
+    assert_eq!(
+      kinds("rust", code),
+      vec![
+        (Meta, "#[derive(Debug)]"),
+        (Keyword, "fn"),
+        (Function, "main"),
+        (Keyword, "let"),
+        (Symbol, "'a"),
+        (Type, "str"),
+        (String, "'x'"),
+        (BuiltIn, "println!"),
+        (String, "\"{}\""),
+        (String, "r#\"\"raw\"\"#"),
+        (Number, "4.5"),
+        (Comment, "// done"),
+      ]
+    );
+  }
+
+  #[test]
+  fn python_and_shell_tokens() {
+    assert_eq!(
+      kinds("py", "@cached\ndef size(x):\n  return len(f'{x}') # bytes"),
+      vec![
+        (Meta, "@cached"),
+        (Keyword, "def"),
+        (Function, "size"),
+        (Keyword, "return"),
+        (BuiltIn, "len"),
+        (String, "f'{x}'"),
+        (Comment, "# bytes"),
+      ]
+    );
+    assert_eq!(
+      kinds(
+        "bash",
+        "#!/bin/sh\nif [ -n \"$1\" ]; then echo '$HOME' ${#list}; fi"
+      ),
+      vec![
+        (Meta, "#!/bin/sh"),
+        (Keyword, "if"),
+        (String, "\"$1\""),
+        (Keyword, "then"),
+        (BuiltIn, "echo"),
+        (String, "'$HOME'"),
+        (Variable, "${#list}"),
+        (Keyword, "fi"),
+      ]
+    );
+  }
+
+  #[test]
+  fn c_json_and_toml_tokens() {
+    assert_eq!(
+      kinds(
+        "c++",
+        "#include <stdio.h>\n/* entry */\nint main() { return 0; }"
+      ),
+      vec![
+        (Meta, "#include <stdio.h>"),
+        (Comment, "/* entry */"),
+        (Type, "int"),
+        (Function, "main"),
+        (Keyword, "return"),
+        (Number, "0"),
+      ]
+    );
+    assert_eq!(
+      kinds("json", "{\"name\": \"value\", \"list\": [1, null]}"),
+      vec![
+        (Attr, "\"name\""),
+        (String, "\"value\""),
+        (Attr, "\"list\""),
+        (Number, "1"),
+        (Literal, "null"),
+      ]
+    );
+    assert_eq!(
+      kinds(
+        "toml",
+        "[package]\nname = 'demo' # the name\nedition = \"2018\""
+      ),
+      vec![
+        (Section, "[package]"),
+        (Attr, "name"),
+        (String, "'demo'"),
+        (Comment, "# the name"),
+        (Attr, "edition"),
+        (String, "\"2018\""),
+      ]
+    );
+  }
+
+  #[test]
+  fn asciidoc_tokens() {
+    assert_eq!(
+      kinds(
+        "adoc",
+        "= Title\n:toc: left\n// note\n.Example\n[source, rust]\n----\ncode\n----\nimage::a.png[]"
+      ),
+      vec![
+        (Section, "= Title"),
+        (Attr, ":toc:"),
+        (String, " left"),
+        (Comment, "// note"),
+        (Section, ".Example"),
+        (Meta, "[source, rust]"),
+        (Meta, "----"),
+        (Meta, "----"),
+        (Keyword, "image::"),
+      ]
+    );
+  }
+
+  #[test]
+  fn unknown_languages() {
+    assert!(highlight("cobol", "DISPLAY 'HI'.").is_none());
+  }
+
+  #[test]
+  fn tokens_by_line() {
+    let tokens = highlight("rust", "/* a\nb */\nlet").unwrap();
+    assert_eq!(
+      lines(&tokens),
+      vec![
+        vec![(Comment, "/* a")],
+        vec![(Comment, "b */")],
+        vec![(Keyword, "let")],
+      ]
+    );
+  }
+}
@@ -1,11 +1,28 @@
 pub use crate::ast::*;
 use crate::util::Environment;
 use crate::{options, Result, AsciidoctrineError};
-use std::cell::RefCell;
-use std::io;
+use crate::extract_toc::extract_table_of_contents;
+use crate::sections::{collect_ids, number_titles};
+use crate::writer::highlight::{self, Highlighter, Token, TokenKind};
+use base64::prelude::*;
+use serde::Serialize;
+use std::cell::{Cell, RefCell};
+use std::collections::{HashMap, HashSet};
+use std::io::{self, Write};
+use std::path::Path;
 
 thread_local! {
   static FOOTNOTES: RefCell<Vec<String>> = RefCell::new(Vec::new());
+  /// The texts of the references without an own text by their ids
+  static REFERENCES: RefCell<HashMap<String, String>> = RefCell::new(HashMap::new());
+  static CAPTIONS: RefCell<Captions> = RefCell::new(Captions::new());
+  static HIGHLIGHTER: RefCell<Option<Highlighter>> = const { RefCell::new(None) };
+  /// The embedded images by their paths. Images which couldn't be read
+  /// have no data uri.
+  static DATA_URIS: RefCell<HashMap<String, Option<String>>> = RefCell::new(HashMap::new());
+  static PART_FILES: RefCell<PartFiles> = RefCell::new(PartFiles::default());
+  /// Whether the html is written as xhtml
+  static XML_SYNTAX: Cell<bool> = const { Cell::new(false) };
 }
 use tera::{Context, Tera};
 
@@ -19,33 +36,84 @@ impl HtmlWriter {
       io: crate::util::Env::Io(crate::util::Io::new()),
     }
   }
+
+  /// Reads stylesheets and images through the environment, e.g. a
+  /// `Cache` in tests
+  pub fn with_env(io: crate::util::Env) -> Self {
+    HtmlWriter { io }
+  }
 }
 
 impl<T: io::Write> crate::Writer<T> for HtmlWriter {
   fn write<'a>(&mut self, ast: AST, args: &options::Opts, mut out: T) -> Result<()> {
+    let ast = number_titles(ast);
+    let toclevels = toclevels(&ast);
     let mut buf = io::BufWriter::new(Vec::new());
+    begin_document(&ast);
+    embed_images(&ast, args, &mut self.io);
+    let toc_entries = toc_entries(&extract_table_of_contents(&ast, toclevels + 1).elements);
+    let toc_title = ast
+      .get_attribute("toc-title")
+      .unwrap_or("Table of Contents");
+    let placement = ast.get_attribute("toc");
+    let toc_class = match placement {
+      Some("left") | Some("right") => "toc2",
+      _ => "toc",
+    };
+    let mut toc = Vec::new();
+    write_toc(&toc_entries, toc_title, toc_class, &mut toc)?;
 
     FOOTNOTES.with(|footnotes| footnotes.borrow_mut().clear());
-    for element in ast.elements.iter() {
+    let mut toc_pending = placement.is_some();
+    for (index, element) in ast.elements.iter().enumerate() {
+      let toc_here = match (placement, &element.element) {
+        (Some("macro"), Element::TableOfContents) => true,
+        (Some("preamble"), Element::Title { level })
+        | (Some("preamble"), Element::Section { level }) => *level > 1,
+        (Some("macro"), _) | (Some("preamble"), _) => false,
+        // Without a document title the table of contents comes first
+        _ => element.element != (Element::Title { level: 1 }),
+      };
+      if toc_pending && toc_here {
+        buf.write_all(&toc)?;
+        toc_pending = false;
+      }
+
       write_html(element, 0, &mut buf)?;
+      // The authors and the revision follow the document title
+      if index == 0 && element.element == (Element::Title { level: 1 }) {
+        write_header_details(&ast.header, &mut buf)?;
+        if toc_pending && !matches!(placement, Some("macro") | Some("preamble")) {
+          buf.write_all(&toc)?;
+          toc_pending = false;
+        }
+      }
     }
     write_footnotes(&mut buf)?;
     let bytes = buf.into_inner()?;
 
     let mut context = Context::new();
-    context.insert("lang", "en");
-    context.insert("doctitle", "");
-    match &args.stylesheet {
+    insert_metadata(&ast, &mut context);
+    let mut stylesheet = match &args.stylesheet {
       Some(path) => {
         let path = path.to_str().expect("path to stylesheet unreadable");
-        let stylesheet = self.io.read_to_string(path)?;
-        context.insert("stylesheet", &stylesheet);
-      }
-      None => {
-        context.insert("stylesheet", include_str!("assets/asciidoctor.css"));
+        self.io.read_to_string(path)?
       }
-    }
-    context.insert("body_class", "article toc2 toc-left");
+      None => include_str!("assets/asciidoctor.css").to_string(),
+    };
+    stylesheet.push_str(&highlight_stylesheet());
+    context.insert("stylesheet", &stylesheet);
+    context.insert("toc", std::str::from_utf8(&toc)?);
+    context.insert("toc_entries", &toc_entries);
+    context.insert("toc_title", toc_title);
+    context.insert(
+      "body_class",
+      match placement {
+        Some("left") => "article toc2 toc-left",
+        Some("right") => "article toc2 toc-right",
+        _ => "article",
+      },
+    );
     context.insert("body", std::str::from_utf8(&bytes)?);
 
     let mut tera = Tera::default();
@@ -75,87 +143,320 @@ impl<T: io::Write> crate::Writer<T> for HtmlWriter {
   }
 }
 
-fn write_html<T: io::Write>(input: &ElementSpan, indent: usize, out: &mut T) -> Result<()> {
-  match &input.element {
-    Element::Title { level } => {
-      let tag = format!("h{}", level);
-
-      if level > &1 {
-        let id = match input.get_attribute("anchor") {
-          Some(id) => id.to_string(),
-          None => {
-            let title = input
-              .get_attribute("name")
-              .ok_or(AsciidoctrineError::MalformedAst)?;
-            "_".to_string() + &title.replace(" ", "_").to_lowercase()
-          }
-        };
-        let attrs = format!("id=\"{}\"", id);
-        write_attribute_tag(&tag, &attrs, input, indent, out)?;
-      } else {
-        write_tag(&tag, input, indent, out)?;
+/// Prepares the captions and the references of a document. Writers
+/// which write the document in parts call it before the first part.
+/// Inserts the metadata of the document header into the template
+/// context. The templates don't escape their values.
+pub(crate) fn insert_metadata(ast: &AST, context: &mut Context) {
+  let authors: Vec<&str> = ast
+    .header
+    .authors
+    .iter()
+    .map(|author| author.name.as_str())
+    .collect();
+  context.insert(
+    "lang",
+    &escape_attribute(ast.get_attribute("lang").unwrap_or("en")),
+  );
+  context.insert(
+    "doctitle",
+    &escape_attribute(ast.header.title.as_deref().unwrap_or("")),
+  );
+  context.insert("authors", &escape_attribute(&authors.join(", ")));
+  context.insert(
+    "description",
+    &escape_attribute(ast.get_attribute("description").unwrap_or("")),
+  );
+  context.insert(
+    "keywords",
+    &escape_attribute(ast.get_attribute("keywords").unwrap_or("")),
+  );
+  context.insert("header", &ast.header);
+}
+
+pub(crate) fn begin_document(ast: &AST) {
+  PART_FILES.with(|part_files| *part_files.borrow_mut() = PartFiles::default());
+  let mut captions = Captions::new();
+  for attribute in ast.attributes.iter() {
+    captions.set(&attribute.key, attribute.value.as_str());
+  }
+  CAPTIONS.with(|current| *current.borrow_mut() = captions);
+
+  let mut references = HashMap::new();
+  collect_references(&ast.elements, &mut references);
+  REFERENCES.with(|current| *current.borrow_mut() = references);
+
+  HIGHLIGHTER.with(|current| *current.borrow_mut() = Highlighter::from_document(ast));
+  DATA_URIS.with(|current| current.borrow_mut().clear());
+  XML_SYNTAX.with(|xml| xml.set(ast.get_attribute("htmlsyntax") == Some("xml")));
+}
+
+/// Writes the following html as xhtml. Call it after `begin_document`.
+pub(crate) fn use_xml_syntax() {
+  XML_SYNTAX.with(|xml| xml.set(true));
+}
+
+/// The end of a void element like `<br>`. Xhtml closes them.
+fn void_end() -> &'static str {
+  if XML_SYNTAX.with(Cell::get) {
+    "/>"
+  } else {
+    ">"
+  }
+}
+
+/// A boolean attribute. Xhtml sets it to its name.
+fn boolean_attribute(name: &str) -> String {
+  if XML_SYNTAX.with(Cell::get) {
+    format!("{}=\"{}\"", name, name)
+  } else {
+    name.to_string()
+  }
+}
+
+/// Reads the local images of a document with `:data-uri:`, so that
+/// they are embedded into the html. Call it after `begin_document`.
+pub(crate) fn embed_images<E: Environment>(ast: &AST, args: &options::Opts, env: &mut E) {
+  if ast.get_attribute("data-uri").is_none() {
+    return;
+  }
+  let mut base = args
+    .input
+    .as_ref()
+    .and_then(|input| input.parent())
+    .map(Path::to_path_buf)
+    .unwrap_or_default();
+  if let Some(imagesdir) = ast.get_attribute("imagesdir") {
+    base.push(imagesdir);
+  }
+
+  let mut paths = Vec::new();
+  collect_images(&ast.elements, &mut paths);
+  let uris = paths
+    .into_iter()
+    // Remote images stay links
+    .filter(|path| !(path.contains("://") || path.starts_with("data:")))
+    .filter_map(|path| {
+      let media_type = match image_media_type(&path) {
+        Some(media_type) => media_type,
+        None => {
+          warn!("image {} has no supported format", path);
+          return None;
+        }
       };
-      out.write_all(b"\n")?;
+      let file = base.join(&path);
+      let uri = match env.read(&file.to_string_lossy()) {
+        Ok(data) => Some(format!(
+          "data:{};base64,{}",
+          media_type,
+          BASE64_STANDARD.encode(data)
+        )),
+        Err(error) => {
+          warn!(
+            "image {} to embed not found or not readable: {}",
+            file.display(),
+            error
+          );
+          None
+        }
+      };
+      Some((path, uri))
+    })
+    .collect();
+  DATA_URIS.with(|current| *current.borrow_mut() = uris);
+}
+
+/// The rules for the classes of the highlighted listings. Listings
+/// with inline styles need none.
+pub(crate) fn highlight_stylesheet() -> String {
+  HIGHLIGHTER.with(|highlighter| match &*highlighter.borrow() {
+    Some(highlighter) if !highlighter.inline_styles => highlighter.theme.stylesheet(),
+    _ => String::new(),
+  })
+}
+
+/// Writes a part of the document with its own footnotes. This way
+/// other writers can reuse the html of the elements. The details of
+/// the header follow the document title.
+pub(crate) fn write_part<T: io::Write>(
+  elements: &[&ElementSpan],
+  header: Option<&DocumentHeader>,
+  out: &mut T,
+) -> Result<()> {
+  write_part_with(elements, header, out, write_element)
+}
+
+/// Like `write_part`, but the elements are written by the given
+/// function. It can fall back to `write_element` for the elements
+/// it doesn't handle itself.
+pub(crate) fn write_part_with<T, F>(
+  elements: &[&ElementSpan],
+  header: Option<&DocumentHeader>,
+  out: &mut T,
+  mut write: F,
+) -> Result<()>
+where
+  T: io::Write,
+  F: FnMut(&ElementSpan, &mut T) -> Result<()>,
+{
+  FOOTNOTES.with(|footnotes| footnotes.borrow_mut().clear());
+  for element in elements {
+    write(element, out)?;
+    if let (Some(header), Element::Title { level: 1 }) = (header, &element.element) {
+      write_header_details(header, out)?;
     }
-    Element::Paragraph => {
-      if let Some(id) = input.get_attribute("anchor") {
-        write_attribute_tag("p", &format!("id=\"{}\"", id), input, indent, out)?;
-      } else {
-        write_tag("p", input, indent, out)?;
-      }
-      out.write_all(b"\n")?;
+  }
+  write_footnotes(out)?;
+
+  Ok(())
+}
+
+/// Writes a single element of a part
+pub(crate) fn write_element<T: io::Write>(element: &ElementSpan, out: &mut T) -> Result<()> {
+  write_html(element, 0, out)
+}
+
+/// Writes the heading of a title with the given level but without
+/// its id
+pub(crate) fn write_title<T: io::Write>(
+  level: u32,
+  title: &ElementSpan,
+  out: &mut T,
+) -> Result<()> {
+  write_heading(level, None, title, 0, out)
+}
+
+/// The deepest level of the sections in the table of contents
+pub(crate) fn toclevels(ast: &AST) -> u32 {
+  match ast.get_attribute("toclevels").map(str::parse) {
+    Some(Ok(levels)) => levels,
+    Some(Err(_)) => {
+      warn!("invalid toclevels");
+      2
     }
-    Element::List(list_type) => {
-      let list_element = match list_type {
-        ListType::Bullet => "ul",
-        ListType::Number => "ol",
-      };
+    None => 2,
+  }
+}
 
-      let mut current_level = 0;
-      for element in input.children.iter() {
-        if let Element::ListItem(item_level) = element.element {
-          let attrs = match list_type {
-            ListType::Bullet => "",
-            ListType::Number => {
-              if item_level % 2 == 0 {
-                "class=\"loweralpha\" type=\"a\""
-              } else {
-                "class=\"arabic\""
-              }
-            }
-          };
-
-          let item_level = item_level as usize;
-          let offset = if current_level > 0 { item_level - 1 } else { 0 };
-          if current_level < item_level {
-            write_open_attribute_tag_ln(list_element, attrs, indent + current_level + offset, out)?;
-            write_open_tag_ln("li", indent + item_level + offset, out)?;
-          } else {
-            if current_level > item_level {
-              let diff = current_level - item_level;
-              let offset = (current_level * 2) - 1;
-              for i in 0..diff {
-                let indent = indent + offset - (2 * i);
-                write_close_tag_ln("li", indent, out)?;
-                write_close_tag_ln(list_element, indent - 1, out)?;
-              }
-            }
-            let item_level = if item_level > 1 {
-              item_level + 1
-            } else {
-              item_level
-            };
-            write_close_tag_ln("li", indent + item_level, out)?;
-            write_open_tag_ln("li", indent + item_level, out)?;
-          }
-          write_html(element, indent + item_level + offset, out)?;
+/// Writes the table of contents of the whole document
+pub(crate) fn write_document_toc<T: io::Write>(ast: &AST, out: &mut T) -> Result<()> {
+  let entries = toc_entries(&extract_table_of_contents(ast, toclevels(ast) + 1).elements);
+  let title = ast
+    .get_attribute("toc-title")
+    .unwrap_or("Table of Contents");
+  write_toc(&entries, title, "toc", out)
+}
 
-          current_level = item_level;
+/// Splits the document at the headings up to the level. The content
+/// before the first heading becomes a part of its own. Sections of a
+/// higher level start a part with their title and the rest of them is
+/// split further.
+pub(crate) fn split_parts<'b, 'a>(
+  elements: &'b [ElementSpan<'a>],
+  level: u32,
+  parts: &mut Vec<Vec<&'b ElementSpan<'a>>>,
+) {
+  for element in elements {
+    match &element.element {
+      // With the sections extension a section holds its subsections
+      Element::Section { level: section } if *section < level => {
+        match element.children.split_first() {
+          Some((title, content)) => {
+            parts.push(vec![title]);
+            split_parts(content, level, parts);
+          }
+          None => parts.push(vec![element]),
         }
       }
-      write_close_tag_ln("li", indent + 1, out)?;
-      write_close_tag_ln(list_element, indent, out)?;
+      Element::Title { level: heading } | Element::Section { level: heading }
+        if *heading <= level =>
+      {
+        parts.push(vec![element])
+      }
+      Element::IncludeElement(include) => split_parts(&include.inner.elements, level, parts),
+      _ => match parts.last_mut() {
+        Some(part) => part.push(element),
+        None => parts.push(vec![element]),
+      },
+    }
+  }
+}
+
+/// The title element of a part which starts with a heading
+pub(crate) fn part_heading<'b, 'a>(part: &[&'b ElementSpan<'a>]) -> Option<&'b ElementSpan<'a>> {
+  let element = part.first()?;
+  match element.element {
+    Element::Title { .. } => Some(element),
+    Element::Section { .. } => element.children.first(),
+    _ => None,
+  }
+}
+
+pub(crate) fn part_title<'b>(part: &[&'b ElementSpan]) -> Option<&'b str> {
+  part_heading(part)?.get_attribute("name")
+}
+
+/// The files of the ids and the file being written when the document
+/// is split into several files
+#[derive(Default)]
+struct PartFiles {
+  current: String,
+  targets: HashMap<String, String>,
+}
+
+/// Lets the references between the parts point to the file of their
+/// target. The parts are written to the files of the same index.
+pub(crate) fn set_part_files(parts: &[Vec<&ElementSpan>], files: &[String]) {
+  let mut targets = HashMap::new();
+  for (part, file) in parts.iter().zip(files.iter()) {
+    let mut ids = HashSet::new();
+    for element in part {
+      collect_ids(std::slice::from_ref(*element), &mut ids);
+    }
+    for id in ids {
+      targets.entry(id).or_insert_with(|| file.clone());
+    }
+  }
+  PART_FILES.with(|part_files| part_files.borrow_mut().targets = targets);
+}
+
+/// Sets the file of the part which is written next
+pub(crate) fn begin_part_file(file: &str) {
+  PART_FILES.with(|part_files| part_files.borrow_mut().current = file.to_string());
+}
+
+/// The link to an id, which is in another file if the document is
+/// split into parts
+fn id_href(id: &str) -> String {
+  PART_FILES.with(|part_files| {
+    let part_files = part_files.borrow();
+    match part_files
+      .targets
+      .get(id)
+      .filter(|file| **file != part_files.current)
+    {
+      Some(file) => format!("{}#{}", file, id),
+      None => format!("#{}", id),
+    }
+  })
+}
+
+fn write_html<T: io::Write>(input: &ElementSpan, indent: usize, out: &mut T) -> Result<()> {
+  match &input.element {
+    Element::Title { level } => {
+      let id = match level {
+        1 => None,
+        _ => input.get_attribute("id"),
+      };
+      write_heading(*level, id, input, indent, out)?;
+    }
+    Element::Section { level } => write_section(*level, input, indent, out)?,
+    Element::Paragraph => {
+      write_block_title(input, indent, out)?;
+      write_attribute_tag("p", &block_attributes("", input), input, indent, out)?;
+      out.write_all(b"\n")?;
     }
+    Element::List(list_type) => write_list(list_type, input, indent, out)?,
     Element::ListItem(_) => {
       for element in input.children.iter() {
         write_html(element, indent + 1, out)?;
@@ -180,13 +481,24 @@ fn write_html<T: io::Write>(input: &ElementSpan, indent: usize, out: &mut T) ->
           .find(|&attr| attr.as_str().find("%open").is_some())
           .is_some()
         {
-          write_open_tag("details open", indent, out)?;
+          let attrs = format!(
+            "{} {}",
+            block_attributes("", input),
+            boolean_attribute("open")
+          );
+          write_open_attribute_tag("details", attrs.trim_start(), indent, out)?;
         } else {
-          write_open_tag("details", indent, out)?;
+          write_open_attribute_tag("details", &block_attributes("", input), indent, out)?;
         }
 
         let title = input.get_attribute("title").unwrap_or("Details");
-        out.write_all(&format!("\n  <summary class=\"title\">{}</summary>\n", title).as_bytes())?;
+        out.write_all(
+          &format!(
+            "\n  <summary class=\"title\">{}</summary>\n",
+            escape_text(title)
+          )
+          .as_bytes(),
+        )?;
 
         write_open_tag_ln("div class=\"content\"", indent + 1, out)?;
         write_open_tag_ln("div class=\"paragraph\"", indent + 2, out)?;
@@ -199,67 +511,84 @@ fn write_html<T: io::Write>(input: &ElementSpan, indent: usize, out: &mut T) ->
 
         return Ok(());
       }
-
-      out.write_all(b"<div")?;
-
-      if let Some(id) = input.get_attribute("anchor") {
-        out.write_all(&format!(" id=\"{}\" ", id).as_bytes())?;
-      };
+      match kind {
+        BlockType::Admonition(admonition) => {
+          return write_admonition(admonition, input, indent, out)
+        }
+        BlockType::Example | BlockType::Sidebar | BlockType::Open => {
+          return write_compound_block(kind, input, indent, out)
+        }
+        BlockType::Quote | BlockType::Verse => return write_quote(kind, input, indent, out),
+        BlockType::Passtrough => {
+          // Passthrough content goes to the output unprocessed
+          let content = input.get_attribute("content").unwrap_or(&input.content);
+          out.write_all(content.as_bytes())?;
+          out.write_all(b"\n")?;
+          return Ok(());
+        }
+        _ => (),
+      }
 
       let class = match kind {
         BlockType::Listing => "listingblock",
         _ => "unknown-block",
       };
-      out.write_all(&format!(" class=\"{}\">\n", class).as_bytes())?;
+      write_open_attribute_tag_ln("div", &block_attributes(class, input), indent, out)?;
+      write_numbered_title("listing", input, indent + 1, out)?;
 
-      if let Some(title) = input.get_attribute("title") {
-        out.write_all(&format!("\n  <div class=\"title\">{}</div>\n", title).as_bytes())?;
+      let language = match kind {
+        BlockType::Listing => highlighted_language(input),
+        _ => None,
       };
-
       if kind == &BlockType::Listing {
-        out.write_all(b"  <pre>")?;
+        out.write_all(&b"  ".repeat(indent + 1))?;
+        match language {
+          Some(language) => write_highlight_open_tags(language, out)?,
+          None => out.write_all(b"<pre>")?,
+        }
       }
 
-      let content = input.get_attribute("content").unwrap_or(input.content);
-      out.write_all(escape_text(&content).as_bytes())?;
+      let content = input.get_attribute("content").unwrap_or(&input.content);
+      if kind == &BlockType::Listing {
+        write_listing_content(content, input, language, out)?;
+      } else {
+        out.write_all(escape_special_characters(content).as_bytes())?;
+      }
 
       if kind == &BlockType::Listing {
+        if language.is_some() {
+          out.write_all(b"</code>")?;
+        }
         out.write_all(b"</pre>\n")?;
       }
       write_close_tag_ln("div", indent, out)?;
     }
-    Element::Image => {
-      if let Some(path) = input.get_attribute("path") {
-        match input.get_attribute("opts") {
-          Some(options) => {
-            let content = input.get_attribute("content").unwrap_or("");
-            if options == "inline" {
-              if path.ends_with(".svg") {
-                out.write_all(content.as_bytes())?;
-              }
-              // TODO
-            } else if options == "interactive" {
-              // TODO
-            } else {
-              // TODO
-            }
-          }
-          None => {
-            out.write_all(&format!("<img src=\"{}\"></div>\n", path).as_bytes())?;
-          }
-        }
-      }
+    Element::Attribute(attribute) => {
+      // References to attributes are already resolved by the reader,
+      // but the captions can change in the document
+      CAPTIONS.with(|captions| {
+        captions
+          .borrow_mut()
+          .set(&attribute.key, attribute.value.as_str())
+      });
     }
+    Element::Image => write_image(input, indent, out)?,
     Element::Table => {
       write_open_attribute_tag_ln(
         "table",
-        "class=\"tableblock frame-all grid-all stretch\"",
+        &block_attributes("tableblock frame-all grid-all stretch", input),
         indent,
         out,
       )?;
+      if let Some(title) = numbered_title("table", input) {
+        out.write_all(&b"  ".repeat(indent + 1))?;
+        out.write_all(format!("<caption class=\"title\">{}</caption>\n", title).as_bytes())?;
+      }
       write_open_tag_ln("colgroup", indent + 1, out)?;
-      write_open_attribute_tag_ln("col", "style=\"width: 50%;\"", indent + 2, out)?;
-      write_open_attribute_tag_ln("col", "style=\"width: 50%;\"", indent + 2, out)?;
+      for _ in 0..2 {
+        out.write_all(&b"  ".repeat(indent + 2))?;
+        out.write_all(format!("<col style=\"width: 50%;\"{}\n", void_end()).as_bytes())?;
+      }
       write_close_tag_ln("colgroup", indent + 1, out)?;
       write_open_tag_ln("tbody", indent + 1, out)?;
       for table_row in input.children.iter() {
@@ -296,6 +625,14 @@ fn write_html<T: io::Write>(input: &ElementSpan, indent: usize, out: &mut T) ->
         write_html(element, indent, out)?;
       }
     }
+    // The table of contents is placed by the writer
+    Element::TableOfContents => (),
+    Element::Anchor => {
+      out.write_all(&b"  ".repeat(indent))?;
+      inline(input, out)?;
+      out.write_all(b"\n")?;
+    }
+    Element::Comment => (),
     _ => {
       out.write_all(
         &format!(
@@ -313,42 +650,195 @@ fn write_html<T: io::Write>(input: &ElementSpan, indent: usize, out: &mut T) ->
 fn inline<T: io::Write>(input: &ElementSpan, out: &mut T) -> Result<()> {
   match &input.element {
     Element::Text => {
-      out.write_all(input.content.as_bytes())?;
+      let content = input.get_attribute("content").unwrap_or(&input.content);
+      out.write_all(typographic_entities(&escape_text(content)).as_bytes())?;
+    }
+    Element::LineBreak => {
+      out.write_all(format!("<br{}", void_end()).as_bytes())?;
     }
     Element::Styled => {
-      let style = match input.get_attribute("style").unwrap_or("") {
-        "monospaced" => "code",
-        style => style,
-      };
-      write_tag(style, input, 0, out)?;
+      let content = input.get_attribute("content").unwrap_or("");
+      match input.get_attribute("style").unwrap_or("") {
+        "double_quoted" => {
+          out.write_all(format!("&#8220;{}&#8221;", escape_text(content)).as_bytes())?;
+        }
+        "single_quoted" => {
+          out.write_all(format!("&#8216;{}&#8217;", escape_text(content)).as_bytes())?;
+        }
+        "monospaced" => write_tag("code", input, 0, out)?,
+        style => write_tag(style, input, 0, out)?,
+      }
     }
     Element::Link => {
       let url = input.get_attribute("url").unwrap_or("");
-      let content = match input.positional_attributes.get(0) {
+      let mut content = match input.positional_attributes.get(0) {
         Some(value) => match value {
           AttributeValue::Ref(value) => value.to_string(),
           AttributeValue::String(value) => value.clone(),
         },
         None => "".to_string(),
       };
+      // A caret at the end of the text opens the link in a new window
+      let window = match content.strip_suffix('^') {
+        Some(text) => {
+          content = text.to_string();
+          Some("_blank")
+        }
+        None => input.get_attribute("window"),
+      };
+
+      let mut url = url.to_string();
+      if input.get_attribute("protocol") == Some("mailto") {
+        // The further attributes are the subject and the body of the mail
+        let params: Vec<String> = ["subject", "body"]
+          .iter()
+          .zip(input.positional_attributes.iter().skip(1))
+          .map(|(key, value)| format!("{}={}", key, encode_uri_component(value.as_str().trim())))
+          .collect();
+        if content.is_empty() {
+          content = url.trim_start_matches("mailto:").to_string();
+        }
+        if !params.is_empty() {
+          url = format!("{}?{}", url, params.join("&"));
+        }
+      }
 
+      let mut attributes = String::new();
+      // Without a text the link shows its target
       if content.is_empty() {
-        out.write_all(&format!("<a href=\"{}\"/>", url).as_bytes())?;
-      } else {
-        out.write_all(&format!("<a href=\"{}\">{}</a>", url, content).as_bytes())?;
+        content = url.clone();
+        attributes.push_str(" class=\"bare\"");
+      }
+      if let Some(window) = window {
+        attributes.push_str(&format!(" target=\"{}\"", escape_attribute(window)));
+        if window == "_blank" {
+          attributes.push_str(" rel=\"noopener\"");
+        }
       }
+      out.write_all(
+        format!(
+          "<a href=\"{}\"{}>{}</a>",
+          escape_attribute(&url),
+          attributes,
+          escape_text(&content)
+        )
+        .as_bytes(),
+      )?;
     }
     Element::XRef => {
-      let id = input.get_attribute("id").unwrap_or("");
-      let content = input.get_attribute("content").unwrap_or(id.clone());
+      let id = input.get_attribute("id");
+      let path = input.get_attribute("path");
+      let href = match (path, id) {
+        (Some(path), Some(id)) => format!("{}#{}", html_path(path), id),
+        (Some(path), None) => html_path(path),
+        (None, id) => id_href(id.unwrap_or("")),
+      };
+      // Without a text the reference shows the title of its target
+      let reference = match (path, id) {
+        (None, Some(id)) => REFERENCES.with(|references| references.borrow().get(id).cloned()),
+        _ => None,
+      };
+      let content = match input.get_attribute("content") {
+        Some(content) => escape_text(content),
+        None => reference
+          .or_else(|| id.or(path).map(escape_text))
+          .unwrap_or_default(),
+      };
 
-      out.write_all(&format!("<a href=\"#{}\">{}</a>", id, content).as_bytes())?;
+      out.write_all(format!("<a href=\"{}\">{}</a>", href, content).as_bytes())?;
+    }
+    Element::Anchor => {
+      if let Some(id) = input.get_attribute("anchor") {
+        out.write_all(format!("<a id=\"{}\"></a>", id).as_bytes())?;
+      }
+    }
+    // Comments are not printed in html
+    Element::Comment => (),
+    Element::InlineImage => {
+      let path = input.get_attribute("path").unwrap_or("");
+      let alt = image_alt(input, path);
+      let image = match image_source(path) {
+        Some(src) => format!(
+          "<img src=\"{}\" alt=\"{}\"{}{}",
+          escape_attribute(&src),
+          alt,
+          image_size(input),
+          void_end()
+        ),
+        None => missing_image(path, &alt),
+      };
+      out.write_all(format!("<span class=\"image\">{}</span>", image).as_bytes())?;
+    }
+    Element::Keyboard => {
+      let keys: Vec<String> = input
+        .positional_attributes
+        .iter()
+        .map(|key| format!("<kbd>{}</kbd>", escape_text(key.as_str())))
+        .collect();
+
+      if keys.len() == 1 {
+        out.write_all(keys[0].as_bytes())?;
+      } else {
+        out.write_all(format!("<span class=\"keyseq\">{}</span>", keys.join("+")).as_bytes())?;
+      }
+    }
+    Element::Button => {
+      let content = input.get_attribute("content").unwrap_or("");
+      out.write_all(format!("<b class=\"button\">{}</b>", escape_text(content)).as_bytes())?;
+    }
+    Element::Menu => {
+      let menu = escape_text(input.get_attribute("menu").unwrap_or(""));
+
+      match input.positional_attributes.split_last() {
+        Some((item, submenus)) => {
+          let mut parts = vec![format!("<b class=\"menu\">{}</b>", menu)];
+          for submenu in submenus {
+            parts.push(format!(
+              "<b class=\"submenu\">{}</b>",
+              escape_text(submenu.as_str())
+            ));
+          }
+          parts.push(format!(
+            "<b class=\"menuitem\">{}</b>",
+            escape_text(item.as_str())
+          ));
+          out.write_all(
+            format!(
+              "<span class=\"menuseq\">{}</span>",
+              parts.join("&#160;&#9656;&#32;")
+            )
+            .as_bytes(),
+          )?;
+        }
+        None => out.write_all(format!("<b class=\"menuref\">{}</b>", menu).as_bytes())?,
+      }
+    }
+    Element::InlinePassthrough => {
+      let content = input.get_attribute("content").unwrap_or("");
+      out.write_all(content.as_bytes())?;
+    }
+    Element::Stem => {
+      let content = escape_special_characters(input.get_attribute("content").unwrap_or(""));
+      match input.get_attribute("notation") {
+        Some("latexmath") => out.write_all(format!("\\({}\\)", content).as_bytes())?,
+        _ => out.write_all(format!("\\${}\\$", content).as_bytes())?,
+      }
+    }
+    Element::IndexTerm => {
+      // Concealed terms only appear in the index
+      if input.get_attribute("visible") == Some("true") {
+        if let Some(term) = input.positional_attributes.first() {
+          out.write_all(escape_text(term.as_str()).as_bytes())?;
+        }
+      }
     }
     Element::Footnote => {
       let content = input.get_attribute("content").unwrap_or("");
       let number = FOOTNOTES.with(|footnotes| {
         let mut footnotes = footnotes.borrow_mut();
-        footnotes.push(content.split_whitespace().collect::<Vec<_>>().join(" "));
+        footnotes.push(escape_text(
+          &content.split_whitespace().collect::<Vec<_>>().join(" "),
+        ));
         footnotes.len()
       });
       out.write_all(
@@ -391,9 +881,938 @@ fn table_paragraph<T: io::Write>(input: &ElementSpan, indent: usize, out: &mut T
   Ok(())
 }
 
+/// Html classes and types of nested numbered lists
+const NUMBERING_STYLES: &[(&str, &str)] = &[
+  ("arabic", ""),
+  ("loweralpha", "a"),
+  ("lowerroman", "i"),
+  ("upperalpha", "A"),
+  ("upperroman", "I"),
+];
+
+fn write_list<T: io::Write>(
+  list_type: &ListType,
+  input: &ElementSpan,
+  indent: usize,
+  out: &mut T,
+) -> Result<()> {
+  if list_type == &ListType::Callout {
+    return write_callout_list(input, indent, out);
+  }
+  write_block_title(input, indent, out)?;
+
+  let mut class = "";
+  let mut attrs = Vec::new();
+  let tag = match list_type {
+    ListType::Bullet => "ul",
+    ListType::Checklist => {
+      class = "checklist";
+      "ul"
+    }
+    ListType::Number => {
+      let level = match input.children.first().map(|item| &item.element) {
+        Some(Element::ListItem(level)) => (*level).max(1) as usize,
+        _ => 1,
+      };
+      let (numbering, kind) = NUMBERING_STYLES[(level - 1) % NUMBERING_STYLES.len()];
+      class = numbering;
+      if !kind.is_empty() {
+        attrs.push(format!("type=\"{}\"", kind));
+      }
+      if let Some(start) = input.get_attribute("start") {
+        attrs.push(format!("start=\"{}\"", start));
+      }
+      if input
+        .positional_attributes
+        .iter()
+        .any(|attr| attr.as_str().contains("%reversed"))
+      {
+        attrs.push(boolean_attribute("reversed"));
+      }
+      "ol"
+    }
+    ListType::Description | ListType::Callout => "dl",
+  };
+  let block = block_attributes(class, input);
+  if !block.is_empty() {
+    attrs.insert(0, block);
+  }
+
+  write_open_attribute_tag_ln(tag, &attrs.join(" "), indent, out)?;
+  for item in input.children.iter() {
+    match list_type {
+      ListType::Description => {
+        let term = item.get_attribute("term").unwrap_or("");
+        out.write_all(&b"  ".repeat(indent + 1))?;
+        out.write_all(format!("<dt class=\"hdlist1\">{}</dt>\n", term).as_bytes())?;
+        if !item.children.is_empty() {
+          write_open_tag_ln("dd", indent + 1, out)?;
+          write_html(item, indent + 1, out)?;
+          write_close_tag_ln("dd", indent + 1, out)?;
+        }
+      }
+      _ => {
+        write_open_tag_ln("li", indent + 1, out)?;
+        match item.get_attribute("checked") {
+          Some(checked) => write_checklist_item(checked == "true", item, indent + 1, out)?,
+          None => write_html(item, indent + 1, out)?,
+        }
+        write_close_tag_ln("li", indent + 1, out)?;
+      }
+    }
+  }
+  write_close_tag_ln(tag, indent, out)?;
+
+  Ok(())
+}
+
+/// The language of a source listing if the document is highlighted
+fn highlighted_language<'a>(input: &'a ElementSpan) -> Option<&'a str> {
+  let highlighted = HIGHLIGHTER.with(|highlighter| highlighter.borrow().is_some());
+  match (
+    positional_attribute(input, 0),
+    positional_attribute(input, 1),
+  ) {
+    (Some("source"), Some(language)) if highlighted => Some(language),
+    _ => None,
+  }
+}
+
+/// Writes the `pre` and `code` tags of a highlighted listing like
+/// asciidoctor does for highlight.js
+fn write_highlight_open_tags<T: io::Write>(language: &str, out: &mut T) -> Result<()> {
+  HIGHLIGHTER.with(|highlighter| {
+    let highlighter = highlighter.borrow();
+    let highlighter = highlighter.as_ref().expect("listing without highlighter");
+    let tags = match highlighter.inline_styles {
+      true => format!(
+        "<pre class=\"highlight\" style=\"background: {}; color: {}\"><code data-lang=\"{}\">",
+        highlighter.theme.background, highlighter.theme.foreground, language
+      ),
+      false => format!(
+        "<pre class=\"highlightjs highlight\"><code class=\"language-{} hljs\" data-lang=\"{}\">",
+        language, language
+      ),
+    };
+    out.write_all(tags.as_bytes())
+  })?;
+
+  Ok(())
+}
+
+/// Writes a token of a highlighted listing
+fn write_token<T: io::Write>((kind, text): Token, out: &mut T) -> Result<()> {
+  let text = escape_special_characters(text);
+  let span = HIGHLIGHTER.with(|highlighter| match &*highlighter.borrow() {
+    Some(highlighter) if highlighter.inline_styles => highlighter
+      .theme
+      .style(kind)
+      .map(|style| format!("<span style=\"{}\">{}</span>", style.css(), text)),
+    Some(_) => kind
+      .class()
+      .map(|class| format!("<span class=\"{}\">{}</span>", class, text)),
+    None => None,
+  });
+  out.write_all(span.unwrap_or(text).as_bytes())?;
+
+  Ok(())
+}
+
+/// Writes the lines of a listing and replaces their callouts with
+/// markers. Listings with a language are highlighted.
+fn write_listing_content<T: io::Write>(
+  content: &str,
+  input: &ElementSpan,
+  language: Option<&str>,
+  out: &mut T,
+) -> Result<()> {
+  // With further substitutions the text is parsed into inline elements
+  if input
+    .children
+    .iter()
+    .any(|child| !matches!(child.element, Element::Callout(_)))
+  {
+    for child in input.children.iter() {
+      inline(child, out)?;
+    }
+    return Ok(());
+  }
+
+  let callouts: Vec<&ElementSpan> = input
+    .children
+    .iter()
+    .filter(|child| matches!(child.element, Element::Callout(_)))
+    .collect();
+  let tokens = match language {
+    Some(language) => highlight::highlight(language, content).unwrap_or_else(|| {
+      warn!("no syntax highlighting for {}", language);
+      vec![(TokenKind::Text, content)]
+    }),
+    None => vec![(TokenKind::Text, content)],
+  };
+
+  for (index, line) in highlight::lines(&tokens).into_iter().enumerate() {
+    if index > 0 {
+      out.write_all(b"\n")?;
+    }
+    let index = index.to_string();
+    let markers: Vec<&&ElementSpan> = callouts
+      .iter()
+      .filter(|callout| callout.get_attribute("line") == Some(index.as_str()))
+      .collect();
+    // The callouts cut off the rest of the line
+    let mut rest = markers
+      .first()
+      .and_then(|callout| callout.get_attribute("column"))
+      .and_then(|column| column.parse().ok())
+      .unwrap_or(usize::MAX);
+    // Indented callouts need no space before them
+    let mut separate = false;
+    for (kind, text) in line {
+      if rest == 0 {
+        break;
+      }
+      let text = text.get(..rest).unwrap_or(text);
+      rest = rest.saturating_sub(text.len());
+      if !text.is_empty() {
+        separate = !text.ends_with(char::is_whitespace);
+      }
+      write_token((kind, text), out)?;
+    }
+
+    for callout in markers {
+      if let Element::Callout(number) = callout.element {
+        if separate {
+          out.write_all(b" ")?;
+        }
+        separate = true;
+        match callout.get_attribute("anchor") {
+          Some(id) => out
+            .write_all(format!("<b class=\"conum\" id=\"{}\">({})</b>", id, number).as_bytes())?,
+          None => out.write_all(format!("<b class=\"conum\">({})</b>", number).as_bytes())?,
+        }
+      }
+    }
+  }
+
+  Ok(())
+}
+
+/// Writes the explanations of callouts as a table which links to the
+/// callouts in the listing
+fn write_callout_list<T: io::Write>(input: &ElementSpan, indent: usize, out: &mut T) -> Result<()> {
+  write_block_title(input, indent, out)?;
+  write_open_attribute_tag_ln(
+    "div",
+    &block_attributes("colist arabic", input),
+    indent,
+    out,
+  )?;
+  write_open_tag_ln("table", indent + 1, out)?;
+
+  for item in input.children.iter() {
+    let number = item.get_attribute("callout").unwrap_or("");
+    write_open_tag_ln("tr", indent + 2, out)?;
+    out.write_all(&b"  ".repeat(indent + 3))?;
+    match item.get_attribute("id") {
+      Some(id) => out.write_all(
+        format!(
+          "<td><a href=\"#{}\"><b class=\"conum\">({})</b></a></td>\n",
+          id, number
+        )
+        .as_bytes(),
+      )?,
+      None => {
+        out.write_all(format!("<td><b class=\"conum\">({})</b></td>\n", number).as_bytes())?
+      }
+    }
+
+    write_open_tag("td", indent + 3, out)?;
+    let mut children = item.children.iter();
+    if let Some(paragraph) = item
+      .children
+      .first()
+      .filter(|child| child.element == Element::Paragraph)
+    {
+      children.next();
+      for child in paragraph.children.iter() {
+        inline(child, out)?;
+      }
+    }
+    if children.len() > 0 {
+      out.write_all(b"\n")?;
+      for child in children {
+        write_html(child, indent + 4, out)?;
+      }
+      write_close_tag_ln("td", indent + 3, out)?;
+    } else {
+      write_close_tag_ln("td", 0, out)?;
+    }
+    write_close_tag_ln("tr", indent + 2, out)?;
+  }
+
+  write_close_tag_ln("table", indent + 1, out)?;
+  write_close_tag_ln("div", indent, out)?;
+
+  Ok(())
+}
+
+fn write_checklist_item<T: io::Write>(
+  checked: bool,
+  input: &ElementSpan,
+  indent: usize,
+  out: &mut T,
+) -> Result<()> {
+  let marker = if checked { "&#10003;" } else { "&#10063;" };
+
+  for (index, element) in input.children.iter().enumerate() {
+    if index == 0 && element.element == Element::Paragraph {
+      write_open_tag("p", indent + 1, out)?;
+      out.write_all(format!("{} ", marker).as_bytes())?;
+      for child in element.children.iter() {
+        inline(child, out)?;
+      }
+      write_close_tag_ln("p", 0, out)?;
+    } else {
+      write_html(element, indent + 1, out)?;
+    }
+  }
+
+  Ok(())
+}
+
+fn write_admonition<T: io::Write>(
+  kind: &AdmonitionType,
+  input: &ElementSpan,
+  indent: usize,
+  out: &mut T,
+) -> Result<()> {
+  let class = format!("admonitionblock {}", kind.keyword().to_lowercase());
+  write_open_attribute_tag_ln("div", &block_attributes(&class, input), indent, out)?;
+  write_open_tag_ln("table", indent + 1, out)?;
+  write_open_tag_ln("tr", indent + 2, out)?;
+  write_open_attribute_tag_ln("td", "class=\"icon\"", indent + 3, out)?;
+  // An explicit caption replaces the label of the kind
+  let caption = match input.get_attribute("caption") {
+    Some(caption) => escape_text(caption),
+    None => kind.caption().to_string(),
+  };
+  out.write_all(&b"  ".repeat(indent + 4))?;
+  out.write_all(format!("<div class=\"title\">{}</div>\n", caption).as_bytes())?;
+  write_close_tag_ln("td", indent + 3, out)?;
+  write_open_attribute_tag_ln("td", "class=\"content\"", indent + 3, out)?;
+  write_block_title(input, indent + 4, out)?;
+
+  if input.get_attribute("content").is_some() {
+    // An admonition block can hold all kinds of content
+    for element in input.children.iter() {
+      write_html(element, indent + 4, out)?;
+    }
+  } else {
+    // An admonition paragraph just holds its text
+    for paragraph in input.children.iter() {
+      out.write_all(&b"  ".repeat(indent + 4))?;
+      for element in paragraph.children.iter() {
+        inline(element, out)?;
+      }
+      out.write_all(b"\n")?;
+    }
+  }
+
+  write_close_tag_ln("td", indent + 3, out)?;
+  write_close_tag_ln("tr", indent + 2, out)?;
+  write_close_tag_ln("table", indent + 1, out)?;
+  write_close_tag_ln("div", indent, out)?;
+
+  Ok(())
+}
+
+/// The labels and the counters of numbered block titles like
+/// `Figure 1. ` The labels are the values of the attributes
+/// `figure-caption`, `table-caption`, `example-caption` and
+/// `listing-caption`.
+struct Captions {
+  labels: HashMap<String, String>,
+  numbers: HashMap<String, usize>,
+}
+
+impl Captions {
+  /// Uses the labels of asciidoctor. Like there listings are only
+  /// numbered with a `listing-caption`.
+  fn new() -> Self {
+    let labels = [
+      ("figure", "Figure"),
+      ("table", "Table"),
+      ("example", "Example"),
+    ]
+    .iter()
+    .map(|(kind, label)| (kind.to_string(), label.to_string()))
+    .collect();
+    Captions {
+      labels,
+      numbers: HashMap::new(),
+    }
+  }
+
+  /// Takes the label of an attribute entry. Unsetting the attribute
+  /// turns off the numbering.
+  fn set(&mut self, key: &str, value: &str) {
+    let kind = match key.trim_matches('!').strip_suffix("-caption") {
+      Some(kind) => kind.to_string(),
+      None => return,
+    };
+    if key.starts_with('!') || key.ends_with('!') {
+      self.labels.remove(&kind);
+    } else {
+      self.labels.insert(kind, value.to_string());
+    }
+  }
+
+  /// The caption of the next titled block of the kind
+  fn next(&mut self, kind: &str) -> Option<String> {
+    let label = self.labels.get(kind)?;
+    let number = self.numbers.entry(kind.to_string()).or_insert(0);
+    *number += 1;
+    Some(format!("{} {}. ", label, number))
+  }
+}
+
+/// An entry of the table of contents as seen by the templates
+#[derive(Serialize)]
+struct TocEntry {
+  id: Option<String>,
+  sectnum: Option<String>,
+  title: String,
+  level: u32,
+  children: Vec<TocEntry>,
+}
+
+fn toc_entries(sections: &[ElementSpan]) -> Vec<TocEntry> {
+  sections
+    .iter()
+    .flat_map(|section| match section.element {
+      // The parts of a book only group the sections
+      Element::Section { level: 1 } => toc_entries(section.children.get(1..).unwrap_or(&[])),
+      Element::Section { level } => {
+        let title = section.children.first();
+        vec![TocEntry {
+          id: section.get_attribute("id").map(str::to_string),
+          sectnum: section.get_attribute("sectnum").map(str::to_string),
+          title: title.map(title_html).unwrap_or_default(),
+          level,
+          children: toc_entries(section.children.get(1..).unwrap_or(&[])),
+        }]
+      }
+      _ => Vec::new(),
+    })
+    .collect()
+}
+
+fn write_toc<T: io::Write>(
+  entries: &[TocEntry],
+  title: &str,
+  class: &str,
+  out: &mut T,
+) -> Result<()> {
+  if entries.is_empty() {
+    return Ok(());
+  }
+
+  out.write_all(format!("<div id=\"toc\" class=\"{}\">\n", class).as_bytes())?;
+  out.write_all(format!("<div id=\"toctitle\">{}</div>\n", escape_text(title)).as_bytes())?;
+  write_toc_entries(entries, out)?;
+  out.write_all(b"</div>\n")?;
+
+  Ok(())
+}
+
+fn write_toc_entries<T: io::Write>(entries: &[TocEntry], out: &mut T) -> Result<()> {
+  let level = entries.first().map(|entry| entry.level).unwrap_or(2);
+  out.write_all(format!("<ul class=\"sectlevel{}\">\n", level - 1).as_bytes())?;
+  for entry in entries {
+    let sectnum = match &entry.sectnum {
+      Some(sectnum) => format!("{} ", sectnum),
+      None => String::new(),
+    };
+    match &entry.id {
+      Some(id) => out.write_all(
+        format!(
+          "<li><a href=\"{}\">{}{}</a>",
+          id_href(id),
+          sectnum,
+          entry.title
+        )
+        .as_bytes(),
+      )?,
+      None => out.write_all(format!("<li>{}{}", sectnum, entry.title).as_bytes())?,
+    }
+    if !entry.children.is_empty() {
+      out.write_all(b"\n")?;
+      write_toc_entries(&entry.children, out)?;
+    }
+    out.write_all(b"</li>\n")?;
+  }
+  out.write_all(b"</ul>\n")?;
+
+  Ok(())
+}
+
+/// Writes the inline content of a title. The titles of other readers
+/// only have their name.
+fn write_title_content<T: io::Write>(title: &ElementSpan, out: &mut T) -> Result<()> {
+  if title.children.is_empty() {
+    let name = title
+      .get_attribute("name")
+      .ok_or(AsciidoctrineError::MalformedAst)?;
+    out.write_all(escape_text(name).as_bytes())?;
+  }
+  for child in title.children.iter() {
+    inline(child, out)?;
+  }
+  Ok(())
+}
+
+/// The title as html for the table of contents and references.
+/// Footnotes, anchors and links only belong to the heading itself.
+fn title_html(title: &ElementSpan) -> String {
+  let mut title = title.clone();
+  title
+    .children
+    .retain(|child| !matches!(child.element, Element::Footnote | Element::Anchor));
+  let mut out = Vec::new();
+  match write_title_content(&title, &mut out) {
+    Ok(()) => drop_links(&String::from_utf8_lossy(&out)),
+    Err(_) => String::new(),
+  }
+}
+
+/// Removes the `a` tags but keeps their text
+fn drop_links(html: &str) -> String {
+  let mut out = String::with_capacity(html.len());
+  let mut rest = html;
+  while let Some(start) = rest.find('<') {
+    out.push_str(&rest[..start]);
+    let tag = &rest[start..];
+    let end = tag.find('>').map_or(tag.len(), |end| end + 1);
+    let name = tag[1..end]
+      .trim_start_matches('/')
+      .split(|c: char| c.is_whitespace() || c == '>' || c == '/')
+      .next();
+    if name != Some("a") {
+      out.push_str(&tag[..end]);
+    }
+    rest = &tag[end..];
+  }
+  out.push_str(rest);
+  out
+}
+
+/// Writes the title of a section with its number
+fn write_heading<T: io::Write>(
+  level: u32,
+  id: Option<&str>,
+  title: &ElementSpan,
+  indent: usize,
+  out: &mut T,
+) -> Result<()> {
+  let tag = format!("h{}", level);
+  let attrs = match id {
+    Some(id) => format!("id=\"{}\"", id),
+    None => String::new(),
+  };
+
+  write_open_attribute_tag(&tag, &attrs, indent, out)?;
+  if let Some(sectnum) = title.get_attribute("sectnum") {
+    out.write_all(format!("{} ", sectnum).as_bytes())?;
+  }
+  write_title_content(title, out)?;
+  write_close_tag_ln(&tag, 0, out)?;
+
+  Ok(())
+}
+
+fn write_section<T: io::Write>(
+  level: u32,
+  input: &ElementSpan,
+  indent: usize,
+  out: &mut T,
+) -> Result<()> {
+  let mut children = input.children.iter();
+  let title = children.next().ok_or(AsciidoctrineError::MalformedAst)?;
+
+  let class = match title
+    .get_attribute("role")
+    .or_else(|| input.get_attribute("role"))
+  {
+    Some(role) => format!("sect{} {}", level - 1, role),
+    None => format!("sect{}", level - 1),
+  };
+  write_open_attribute_tag_ln("div", &format!("class=\"{}\"", class), indent, out)?;
+  write_heading(level, input.get_attribute("id"), title, indent + 1, out)?;
+
+  // The content of top level sections is wrapped once more
+  let body_indent = if level == 2 {
+    write_open_attribute_tag_ln("div", "class=\"sectionbody\"", indent + 1, out)?;
+    indent + 2
+  } else {
+    indent + 1
+  };
+  for element in children {
+    write_html(element, body_indent, out)?;
+  }
+  if level == 2 {
+    write_close_tag_ln("div", indent + 1, out)?;
+  }
+  write_close_tag_ln("div", indent, out)?;
+
+  Ok(())
+}
+
+fn write_compound_block<T: io::Write>(
+  kind: &BlockType,
+  input: &ElementSpan,
+  indent: usize,
+  out: &mut T,
+) -> Result<()> {
+  let class = match kind {
+    BlockType::Sidebar => "sidebarblock",
+    BlockType::Open => "openblock",
+    _ => "exampleblock",
+  };
+  write_open_attribute_tag_ln("div", &block_attributes(class, input), indent, out)?;
+  // The title of a sidebar is part of its content
+  match kind {
+    BlockType::Example => write_numbered_title("example", input, indent + 1, out)?,
+    BlockType::Sidebar => (),
+    _ => write_block_title(input, indent + 1, out)?,
+  }
+  write_open_attribute_tag_ln("div", "class=\"content\"", indent + 1, out)?;
+  if kind == &BlockType::Sidebar {
+    write_block_title(input, indent + 2, out)?;
+  }
+  for element in input.children.iter() {
+    write_html(element, indent + 2, out)?;
+  }
+  write_close_tag_ln("div", indent + 1, out)?;
+  write_close_tag_ln("div", indent, out)?;
+
+  Ok(())
+}
+
+/// Writes a block image with its numbered title below
+fn write_image<T: io::Write>(input: &ElementSpan, indent: usize, out: &mut T) -> Result<()> {
+  let path = match input.get_attribute("path") {
+    Some(path) => path,
+    None => return Ok(()),
+  };
+  let mut class = "imageblock".to_string();
+  if let Some(float) = input.get_attribute("float") {
+    class.push_str(&format!(" {}", float));
+  }
+  if let Some(align) = input.get_attribute("align") {
+    class.push_str(&format!(" text-{}", align));
+  }
+  write_open_attribute_tag_ln("div", &block_attributes(&class, input), indent, out)?;
+  write_open_attribute_tag_ln("div", "class=\"content\"", indent + 1, out)?;
+
+  let alt = image_alt(input, path);
+  let image = match image_source(path) {
+    // The reader already read the svg
+    _ if has_option(input, "inline") && path.ends_with(".svg") => input
+      .get_attribute("content")
+      .unwrap_or("")
+      .trim_end()
+      .to_string(),
+    None => missing_image(path, &alt),
+    Some(src) if has_option(input, "interactive") && path.ends_with(".svg") => format!(
+      "<object type=\"image/svg+xml\" data=\"{}\"{}><span class=\"alt\">{}</span></object>",
+      src,
+      image_size(input),
+      alt
+    ),
+    Some(src) => format!(
+      "<img src=\"{}\" alt=\"{}\"{}{}",
+      escape_attribute(&src),
+      alt,
+      image_size(input),
+      void_end()
+    ),
+  };
+  out.write_all(&b"  ".repeat(indent + 2))?;
+  match input.get_attribute("link") {
+    Some(link) => {
+      out.write_all(format!("<a class=\"image\" href=\"{}\">{}</a>\n", link, image).as_bytes())?
+    }
+    None => out.write_all(format!("{}\n", image).as_bytes())?,
+  }
+  write_close_tag_ln("div", indent + 1, out)?;
+  write_numbered_title("figure", input, indent + 1, out)?;
+  write_close_tag_ln("div", indent, out)?;
+
+  Ok(())
+}
+
+fn write_quote<T: io::Write>(
+  kind: &BlockType,
+  input: &ElementSpan,
+  indent: usize,
+  out: &mut T,
+) -> Result<()> {
+  let class = match kind {
+    BlockType::Verse => "verseblock",
+    _ => "quoteblock",
+  };
+  write_open_attribute_tag_ln("div", &block_attributes(class, input), indent, out)?;
+  write_block_title(input, indent + 1, out)?;
+
+  if kind == &BlockType::Verse {
+    // A verse keeps its line breaks
+    let content = input.get_attribute("content").unwrap_or(&input.content);
+    write_open_attribute_tag("pre", "class=\"content\"", indent + 1, out)?;
+    out.write_all(escape_text(content).as_bytes())?;
+    out.write_all(b"</pre>\n")?;
+  } else {
+    write_open_tag_ln("blockquote", indent + 1, out)?;
+    for element in input.children.iter() {
+      write_html(element, indent + 2, out)?;
+    }
+    write_close_tag_ln("blockquote", indent + 1, out)?;
+  }
+
+  // The attribution is given as `[quote, author, source]`
+  let author = input
+    .get_attribute("attribution")
+    .or_else(|| input.positional_attributes.get(1).map(|attr| attr.as_str()));
+  let citetitle = input
+    .get_attribute("citetitle")
+    .or_else(|| input.positional_attributes.get(2).map(|attr| attr.as_str()));
+  if author.is_some() || citetitle.is_some() {
+    write_open_attribute_tag_ln("div", "class=\"attribution\"", indent + 1, out)?;
+    if let Some(author) = author {
+      out.write_all(&b"  ".repeat(indent + 2))?;
+      out.write_all(format!("&#8212; {}", escape_text(author)).as_bytes())?;
+      if citetitle.is_some() {
+        out.write_all(format!("<br{}", void_end()).as_bytes())?;
+      }
+      out.write_all(b"\n")?;
+    }
+    if let Some(citetitle) = citetitle {
+      out.write_all(&b"  ".repeat(indent + 2))?;
+      out.write_all(format!("<cite>{}</cite>\n", escape_text(citetitle)).as_bytes())?;
+    }
+    write_close_tag_ln("div", indent + 1, out)?;
+  }
+  write_close_tag_ln("div", indent, out)?;
+
+  Ok(())
+}
+
 // Helper Functions
 //----------------------------------------------------
 
+/// The attributes of a block (an optional id and its class together
+/// with the role)
+fn block_attributes(class: &str, input: &ElementSpan) -> String {
+  let class = match input.get_attribute("role") {
+    Some(role) if class.is_empty() => role.to_string(),
+    Some(role) => format!("{} {}", class, role),
+    None => class.to_string(),
+  };
+  let mut attrs = Vec::new();
+  if let Some(id) = input.get_attribute("anchor") {
+    attrs.push(format!("id=\"{}\"", id));
+  }
+  if !class.is_empty() {
+    attrs.push(format!("class=\"{}\"", escape_attribute(&class)));
+  }
+  attrs.join(" ")
+}
+
+/// Writes the authors and the revision of the document header
+fn write_header_details<T: io::Write>(header: &DocumentHeader, out: &mut T) -> Result<()> {
+  if header.authors.is_empty() && header.revision.is_none() {
+    return Ok(());
+  }
+
+  out.write_all(b"<div class=\"details\">\n")?;
+  for (index, author) in header.authors.iter().enumerate() {
+    let suffix = match index {
+      0 => String::new(),
+      index => (index + 1).to_string(),
+    };
+    out.write_all(
+      format!(
+        "<span id=\"author{}\" class=\"author\">{}</span><br{}\n",
+        suffix,
+        escape_text(&author.name),
+        void_end()
+      )
+      .as_bytes(),
+    )?;
+    if let Some(email) = &author.email {
+      let href = if email.contains('@') && !email.contains(':') {
+        format!("mailto:{}", email)
+      } else {
+        email.clone()
+      };
+      out.write_all(
+        format!(
+          "<span id=\"email{}\" class=\"email\"><a href=\"{}\">{}</a></span><br{}\n",
+          suffix,
+          escape_attribute(&href),
+          escape_text(email),
+          void_end()
+        )
+        .as_bytes(),
+      )?;
+    }
+  }
+  if let Some(revision) = &header.revision {
+    if let Some(number) = &revision.number {
+      let separator = if revision.date.is_some() { "," } else { "" };
+      out.write_all(
+        format!(
+          "<span id=\"revnumber\">version {}{}</span>\n",
+          escape_text(number),
+          separator
+        )
+        .as_bytes(),
+      )?;
+    }
+    if let Some(date) = &revision.date {
+      out.write_all(format!("<span id=\"revdate\">{}</span>\n", escape_text(date)).as_bytes())?;
+    }
+    if let Some(remark) = &revision.remark {
+      out.write_all(
+        format!(
+          "<br{}<span id=\"revremark\">{}</span>\n",
+          void_end(),
+          escape_text(remark)
+        )
+        .as_bytes(),
+      )?;
+    }
+  }
+  out.write_all(b"</div>\n")?;
+
+  Ok(())
+}
+
+fn write_block_title<T: io::Write>(input: &ElementSpan, indent: usize, out: &mut T) -> Result<()> {
+  if let Some(title) = input.get_attribute("title") {
+    out.write_all(&b"  ".repeat(indent))?;
+    out.write_all(format!("<div class=\"title\">{}</div>\n", escape_text(title)).as_bytes())?;
+  }
+  Ok(())
+}
+
+/// The title of a block with its numbered caption. An explicit
+/// `caption` replaces the numbered one.
+fn numbered_title(kind: &str, input: &ElementSpan) -> Option<String> {
+  let title = input.get_attribute("title")?;
+  let caption = match input.get_attribute("caption") {
+    Some(caption) => caption.to_string(),
+    None => CAPTIONS
+      .with(|captions| captions.borrow_mut().next(kind))
+      .unwrap_or_default(),
+  };
+  Some(escape_text(&format!("{}{}", caption, title)))
+}
+
+fn write_numbered_title<T: io::Write>(
+  kind: &str,
+  input: &ElementSpan,
+  indent: usize,
+  out: &mut T,
+) -> Result<()> {
+  if let Some(title) = numbered_title(kind, input) {
+    out.write_all(&b"  ".repeat(indent))?;
+    out.write_all(format!("<div class=\"title\">{}</div>\n", title).as_bytes())?;
+  }
+  Ok(())
+}
+
+/// Collects the texts for references to sections, titled blocks and
+/// anchors
+fn collect_references(elements: &[ElementSpan], references: &mut HashMap<String, String>) {
+  for element in elements {
+    let reference = match &element.element {
+      Element::Title { .. } => element
+        .get_attribute("id")
+        .map(|id| (id, title_html(element))),
+      Element::Section { .. } => element
+        .get_attribute("id")
+        .zip(element.children.first().map(title_html)),
+      _ => element.get_attribute("anchor").zip(
+        element
+          .get_attribute("reftext")
+          .or_else(|| element.get_attribute("title"))
+          .map(escape_text),
+      ),
+    };
+    if let Some((id, text)) = reference {
+      references.entry(id.to_string()).or_insert(text);
+    }
+    if let Element::IncludeElement(include) = &element.element {
+      collect_references(&include.inner.elements, references);
+    }
+    collect_references(&element.children, references);
+  }
+}
+
+pub(crate) fn collect_images(elements: &[ElementSpan], paths: &mut Vec<String>) {
+  for element in elements {
+    match &element.element {
+      Element::Image | Element::InlineImage => {
+        if let Some(path) = element.get_attribute("path") {
+          if !paths.iter().any(|known| known == path) {
+            paths.push(path.to_string());
+          }
+        }
+      }
+      Element::IncludeElement(include) => collect_images(&include.inner.elements, paths),
+      _ => (),
+    }
+    collect_images(&element.children, paths);
+  }
+}
+
+/// The media type of an image by the extension of its path
+pub(crate) fn image_media_type(path: &str) -> Option<&'static str> {
+  match path
+    .rsplit('.')
+    .next()
+    .unwrap_or("")
+    .to_lowercase()
+    .as_str()
+  {
+    "png" => Some("image/png"),
+    "jpg" | "jpeg" => Some("image/jpeg"),
+    "gif" => Some("image/gif"),
+    "svg" => Some("image/svg+xml"),
+    "webp" => Some("image/webp"),
+    _ => None,
+  }
+}
+
+/// The source of an image. Embedded images have a data uri, those
+/// which couldn't be read have none.
+fn image_source(path: &str) -> Option<String> {
+  DATA_URIS.with(|uris| match uris.borrow().get(path) {
+    Some(uri) => uri.clone(),
+    None => Some(path.to_string()),
+  })
+}
+
+/// Shows that an image is missing instead of a broken link
+fn missing_image(path: &str, alt: &str) -> String {
+  format!(
+    "<span class=\"alt\">[{}] (image {} not found)</span>",
+    alt,
+    escape_text(path)
+  )
+}
+
 fn write_footnotes<T: io::Write>(out: &mut T) -> Result<()> {
   FOOTNOTES.with(|footnotes| -> Result<()> {
     let footnotes = footnotes.borrow();
@@ -401,7 +1820,7 @@ fn write_footnotes<T: io::Write>(out: &mut T) -> Result<()> {
       return Ok(());
     }
 
-    out.write_all(b"<div id=\"footnotes\">\n  <hr>\n")?;
+    out.write_all(format!("<div id=\"footnotes\">\n  <hr{}\n", void_end()).as_bytes())?;
     for (index, footnote) in footnotes.iter().enumerate() {
       let number = index + 1;
       out.write_all(
@@ -417,8 +1836,150 @@ fn write_footnotes<T: io::Write>(out: &mut T) -> Result<()> {
   })
 }
 
+/// A positional attribute without surrounding whitespace if it isn't
+/// empty
+fn positional_attribute<'a>(input: &'a ElementSpan, index: usize) -> Option<&'a str> {
+  input
+    .positional_attributes
+    .get(index)
+    .map(|value| value.as_str().trim())
+    .filter(|value| !value.is_empty())
+}
+
+/// The alternative text of an image. Like asciidoctor we fall back to
+/// the file name.
+fn image_alt(input: &ElementSpan, path: &str) -> String {
+  let alt = match positional_attribute(input, 0) {
+    Some(alt) => alt.to_string(),
+    None => Path::new(path)
+      .file_stem()
+      .map(|name| name.to_string_lossy().replace(['-', '_'], " "))
+      .unwrap_or_default(),
+  };
+  escape_attribute(&alt)
+}
+
+/// The `width` and `height` of an image given by name or as the
+/// second and third positional attribute
+fn image_size(input: &ElementSpan) -> String {
+  let mut attrs = String::new();
+  for (index, key) in ["width", "height"].iter().enumerate() {
+    if let Some(value) = input
+      .get_attribute(key)
+      .or_else(|| positional_attribute(input, index + 1))
+    {
+      attrs.push_str(&format!(" {}=\"{}\"", key, escape_attribute(value)));
+    }
+  }
+  attrs
+}
+
+/// Checks if the option is set with `%name` or in the `options`
+/// attribute of an element
+fn has_option(input: &ElementSpan, name: &str) -> bool {
+  input.positional_attributes.iter().any(|attribute| {
+    attribute
+      .as_str()
+      .split('%')
+      .skip(1)
+      .any(|option| option == name)
+  }) || input
+    .get_attribute("options")
+    .or_else(|| input.get_attribute("opts"))
+    .is_some_and(|options| options.split(',').any(|option| option.trim() == name))
+}
+
+/// The path of the html document generated from an asciidoc document
+fn html_path(path: &str) -> String {
+  match path.strip_suffix(".adoc") {
+    Some(path) => format!("{}.html", path),
+    None => path.to_string(),
+  }
+}
+
+fn encode_uri_component(input: &str) -> String {
+  input
+    .bytes()
+    .map(|byte| match byte {
+      b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+        (byte as char).to_string()
+      }
+      byte => format!("%{:02X}", byte),
+    })
+    .collect()
+}
+
+/// Escapes the special characters of text but keeps character
+/// references like `&copy;`
 fn escape_text(input: &str) -> String {
-  input.replace("<", "&lt;").replace(">", "&gt;")
+  let mut out = String::with_capacity(input.len());
+  for (index, c) in input.char_indices() {
+    match c {
+      '&' if is_reference(&input[index..]) => out.push(c),
+      c => push_escaped(c, &mut out),
+    }
+  }
+  out
+}
+
+/// Escapes all special characters of verbatim content
+fn escape_special_characters(input: &str) -> String {
+  let mut out = String::with_capacity(input.len());
+  for c in input.chars() {
+    push_escaped(c, &mut out);
+  }
+  out
+}
+
+fn push_escaped(c: char, out: &mut String) {
+  match c {
+    '&' => out.push_str("&amp;"),
+    '<' => out.push_str("&lt;"),
+    '>' => out.push_str("&gt;"),
+    c => out.push(c),
+  }
+}
+
+/// Whether the text starts with a character reference like `&amp;`,
+/// `&#169;` or `&#xa9;`
+fn is_reference(input: &str) -> bool {
+  let name = match input[1..].find(';') {
+    Some(end) => &input[1..=end],
+    None => return false,
+  };
+  match name.strip_prefix('#') {
+    Some(number) => match number.strip_prefix(['x', 'X']) {
+      Some(hex) => !hex.is_empty() && hex.chars().all(|c| c.is_ascii_hexdigit()),
+      None => !number.is_empty() && number.chars().all(|c| c.is_ascii_digit()),
+    },
+    // Xml only knows its own named entities
+    None if XML_SYNTAX.with(Cell::get) => ["amp", "lt", "gt", "quot", "apos"].contains(&name),
+    None => !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric()),
+  }
+}
+
+/// Escapes a value for the content of an element or an attribute
+pub(crate) fn escape_attribute(input: &str) -> String {
+  input
+    .replace('&', "&amp;")
+    .replace('<', "&lt;")
+    .replace('>', "&gt;")
+    .replace('"', "&quot;")
+}
+
+/// Writes the typographic characters of the replacements as numeric
+/// character references like asciidoctor
+fn typographic_entities(input: &str) -> String {
+  input
+    .chars()
+    .map(|c| match c {
+      '\u{a9}' | '\u{ae}' | '\u{2122}' | '\u{2009}' | '\u{2014}' | '\u{200b}' | '\u{2026}'
+      | '\u{2019}' | '\u{2192}' | '\u{21d2}' | '\u{2190}' | '\u{21d0}' => {
+        format!("&#{};", c as u32)
+      }
+      c => c.to_string(),
+    })
+    .collect()
 }
 
 fn write_tag<T: io::Write>(
@@ -440,12 +2001,7 @@ fn write_attribute_tag<T: io::Write>(
   write_open_attribute_tag(tag, attrs, indent, out)?;
 
   match &inner.element {
-    Element::Title { .. } => {
-      let title = inner
-        .get_attribute("name")
-        .ok_or(AsciidoctrineError::MalformedAst)?;
-      out.write_all(title.as_bytes())?;
-    }
+    Element::Title { .. } => write_title_content(inner, out)?,
     Element::Paragraph => {
       for element in inner.children.iter() {
         inline(element, out)?;
@@ -453,7 +2009,7 @@ fn write_attribute_tag<T: io::Write>(
     }
     Element::Styled => {
       let content = inner.get_attribute("content").unwrap_or("");
-      out.write_all(content.as_bytes())?;
+      out.write_all(escape_text(content).as_bytes())?;
     }
     Element::TableCell => {
       let indent = if inner.children.len() == 1 {
@@ -0,0 +1,1029 @@
+pub use crate::ast::*;
+use crate::reader::asciidoc::{parse_columns_format, ColKind, ColumnFormat, HAlign};
+use crate::sections::number_titles;
+use crate::util::Environment;
+use crate::writer::text::decode_references;
+use crate::{options, AsciidoctrineError, Result};
+use std::collections::HashSet;
+use std::io;
+use tera::{Context, Tera};
+
+/// Writes a complete LaTeX document. The preamble comes from a
+/// template which can be replaced with `--template`.
+///
+/// Listings use the `listings` package unless the document sets
+/// `:source-highlighter: minted` (which needs `-shell-escape`).
+pub struct LatexWriter {
+  io: crate::util::Env,
+}
+
+impl LatexWriter {
+  pub fn new() -> Self {
+    LatexWriter {
+      io: crate::util::Env::Io(crate::util::Io::new()),
+    }
+  }
+}
+
+impl Default for LatexWriter {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+impl<T: io::Write> crate::Writer<T> for LatexWriter {
+  fn write<'a>(&mut self, ast: AST, args: &options::Opts, mut out: T) -> Result<()> {
+    // The titles need their ids for the labels
+    let ast = number_titles(ast);
+    let book = ast.get_attribute("doctype") == Some("book");
+    let mut latex = Latex {
+      book,
+      minted: ast.get_attribute("source-highlighter") == Some("minted"),
+      sectnums: ast.get_attribute("sectnums").is_some(),
+      imagesdir: ast.get_attribute("imagesdir").map(str::to_string),
+      lists: Vec::new(),
+      footnotes: HashSet::new(),
+    };
+
+    // The document title is part of the preamble
+    let skip = match ast.elements.first() {
+      Some(title) if ast.header.title.is_some() && title.element == Element::Title { level: 1 } => {
+        1
+      }
+      _ => 0,
+    };
+    let mut body = Vec::new();
+    latex.write_blocks(&ast.elements[skip..], &mut body)?;
+
+    // The section levels are counted from `\section` (or `\chapter`)
+    let offset = if book { 1 } else { 0 };
+    let sectnumlevels = level_attribute(&ast, "sectnumlevels", 3);
+    let secnumdepth = match latex.sectnums {
+      true => sectnumlevels - offset,
+      false => -offset,
+    };
+    let tocdepth = level_attribute(&ast, "toclevels", 2) - offset;
+    let authors: Vec<String> = ast
+      .header
+      .authors
+      .iter()
+      .map(|author| escape_text(&author.name))
+      .collect();
+    let date = ast
+      .header
+      .revision
+      .as_ref()
+      .and_then(|revision| revision.date.as_deref())
+      .unwrap_or("");
+
+    let mut context = Context::new();
+    context.insert("documentclass", if book { "book" } else { "article" });
+    context.insert("babel", babel_language(ast.get_attribute("lang")));
+    context.insert("minted", &latex.minted);
+    context.insert("secnumdepth", &secnumdepth);
+    context.insert("tocdepth", &tocdepth);
+    context.insert(
+      "doctitle",
+      &escape_text(ast.header.title.as_deref().unwrap_or("")),
+    );
+    context.insert("authors", &authors.join(" \\and "));
+    context.insert("date", &escape_text(date));
+    context.insert("header", &ast.header);
+    // The `toc::[]` macro places the table of contents itself
+    context.insert(
+      "toc",
+      &matches!(ast.get_attribute("toc"), Some(placement) if placement != "macro"),
+    );
+    context.insert("body", std::str::from_utf8(&body)?);
+
+    let mut tera = Tera::default();
+    tera.autoescape_on(vec![]);
+    match &args.template {
+      Some(path) => {
+        let path = path.to_str().expect("path to template unreadable");
+        let template = if path == "-" {
+          "{{body}}".to_string()
+        } else {
+          self.io.read_to_string(path)?
+        };
+        tera.add_raw_template("default.tex", &template)?;
+      }
+      None => {
+        tera.add_raw_template("default.tex", include_str!("assets/template.tex"))?;
+      }
+    }
+    out.write_all(tera.render("default.tex", &context)?.as_bytes())?;
+    out.flush()?;
+
+    Ok(())
+  }
+}
+
+/// The state of the document while writing the body
+struct Latex {
+  book: bool,
+  minted: bool,
+  sectnums: bool,
+  imagesdir: Option<String>,
+  /// The kinds of the enclosing lists
+  lists: Vec<ListType>,
+  /// The ids of the footnotes which can be referenced
+  footnotes: HashSet<String>,
+}
+
+impl Latex {
+  fn write_blocks<T: io::Write>(&mut self, elements: &[ElementSpan], out: &mut T) -> Result<()> {
+    for (index, element) in elements.iter().enumerate() {
+      // A blank line ends the paragraph
+      if index > 0 {
+        out.write_all(b"\n")?;
+      }
+      self.write_block(element, out)?;
+    }
+
+    Ok(())
+  }
+
+  fn write_block<T: io::Write>(&mut self, input: &ElementSpan, out: &mut T) -> Result<()> {
+    match &input.element {
+      Element::Section { .. } => self.write_blocks(&input.children, out)?,
+      Element::Title { level } => {
+        let name = input.get_attribute("name").unwrap_or("");
+        writeln!(
+          out,
+          "\\{}{{{}}}",
+          self.sectioning(*level),
+          escape_text(name)
+        )?;
+        if let Some(id) = input
+          .get_attribute("id")
+          .or_else(|| input.get_attribute("anchor"))
+        {
+          writeln!(out, "\\label{{{}}}", label(id))?;
+        }
+      }
+      Element::Paragraph => {
+        write_anchor(input, out)?;
+        write_block_title(input.get_attribute("title"), out)?;
+        self.write_inlines(&input.children, out)?;
+        out.write_all(b"\n")?;
+      }
+      Element::List(list_type) => self.write_list(list_type, input, out)?,
+      Element::ListItem(_) => self.write_blocks(&input.children, out)?,
+      Element::TypedBlock { kind } => self.write_typed_block(kind, input, out)?,
+      Element::Table => self.write_table(input, out)?,
+      Element::Image => {
+        let path = input.get_attribute("path").unwrap_or("");
+        let title = input.get_attribute("title");
+        if path.contains("://") {
+          warn!("remote images can't be included in LaTeX: {}", path);
+          write_anchor(input, out)?;
+          write_block_title(title, out)?;
+          let alt = positional_attribute(input, 0).unwrap_or(path);
+          writeln!(
+            out,
+            "\\href{{{}}}{{{}}}",
+            escape_url(path),
+            escape_text(alt)
+          )?;
+          return Ok(());
+        }
+
+        let graphic = format!(
+          "\\includegraphics[{}]{{{}}}",
+          image_options(input),
+          self.image_path(path)
+        );
+        match title {
+          Some(title) => {
+            writeln!(out, "\\begin{{figure}}[htbp]\n\\centering\n{}", graphic)?;
+            writeln!(out, "\\caption{{{}}}", escape_text(title))?;
+            if let Some(id) = input.get_attribute("anchor") {
+              writeln!(out, "\\label{{{}}}", label(id))?;
+            }
+            writeln!(out, "\\end{{figure}}")?;
+          }
+          None => {
+            write_anchor(input, out)?;
+            writeln!(out, "\\begin{{center}}\n{}\n\\end{{center}}", graphic)?;
+          }
+        }
+      }
+      Element::Anchor => write_anchor(input, out)?,
+      Element::IncludeElement(include) => self.write_blocks(&include.inner.elements, out)?,
+      Element::TableOfContents => out.write_all(b"\\tableofcontents\n")?,
+      // Attribute entries are already resolved by the reader
+      Element::Attribute(_) => (),
+      Element::Comment => (),
+      Element::ExternalContent => {
+        warn!("external content is not supported in LaTeX");
+      }
+      Element::Error(msg) => {
+        warn!("{}", msg);
+        writeln!(out, "% ERROR: {}", msg.replace('\n', " "))?;
+      }
+      Element::Styled
+      | Element::Text
+      | Element::XRef
+      | Element::Link
+      | Element::Footnote
+      | Element::InlineImage
+      | Element::Keyboard
+      | Element::Button
+      | Element::Menu
+      | Element::InlinePassthrough
+      | Element::Stem
+      | Element::IndexTerm
+      | Element::LineBreak
+      | Element::Callout(_)
+      | Element::TableRow
+      | Element::TableCell => return Err(AsciidoctrineError::MalformedAst),
+    }
+
+    Ok(())
+  }
+
+  /// The sectioning command of a title. The document title has level 1.
+  fn sectioning(&self, level: u32) -> &'static str {
+    let commands: &[&str] = if self.book {
+      &[
+        "part",
+        "chapter",
+        "section",
+        "subsection",
+        "subsubsection",
+        "paragraph",
+      ]
+    } else {
+      &[
+        "part",
+        "section",
+        "subsection",
+        "subsubsection",
+        "paragraph",
+        "subparagraph",
+      ]
+    };
+    commands[(level.max(1) as usize - 1).min(commands.len() - 1)]
+  }
+
+  fn write_typed_block<T: io::Write>(
+    &mut self,
+    kind: &BlockType,
+    input: &ElementSpan,
+    out: &mut T,
+  ) -> Result<()> {
+    let title = input.get_attribute("title");
+    let content = input.get_attribute("content").unwrap_or(&input.content);
+
+    match kind {
+      BlockType::Comment => (),
+      BlockType::Passtrough => writeln!(out, "{}", content)?,
+      BlockType::Listing => {
+        let language = match (
+          positional_attribute(input, 0),
+          positional_attribute(input, 1),
+        ) {
+          (Some("source"), language) => language,
+          _ => None,
+        };
+        let content = content.trim_end_matches('\n');
+        if self.minted {
+          write_anchor(input, out)?;
+          write_block_title(title, out)?;
+          let language = language.map(minted_language);
+          writeln!(
+            out,
+            "\\begin{{minted}}{{{}}}\n{}\n\\end{{minted}}",
+            language.as_deref().unwrap_or("text"),
+            content
+          )?;
+        } else {
+          let mut options = Vec::new();
+          match language.and_then(listings_language) {
+            Some(language) => options.push(format!("language={}", language)),
+            None => {
+              if let Some(language) = language {
+                warn!(
+                  "the language {} is not known to the listings package",
+                  language
+                );
+              }
+            }
+          }
+          if let Some(title) = title {
+            options.push(format!("title={{{}}}", escape_text(title)));
+          }
+          if let Some(id) = input.get_attribute("anchor") {
+            options.push(format!("label={{{}}}", label(id)));
+          }
+          let options = match options.is_empty() {
+            true => String::new(),
+            false => format!("[{}]", options.join(",")),
+          };
+          writeln!(
+            out,
+            "\\begin{{lstlisting}}{}\n{}\n\\end{{lstlisting}}",
+            options, content
+          )?;
+        }
+      }
+      BlockType::Literal => {
+        write_anchor(input, out)?;
+        write_block_title(title, out)?;
+        writeln!(
+          out,
+          "\\begin{{verbatim}}\n{}\n\\end{{verbatim}}",
+          content.trim_end_matches('\n')
+        )?;
+      }
+      BlockType::Sidebar => {
+        write_anchor(input, out)?;
+        self.write_box("adocsidebar", title.map(escape_text), &input.children, out)?;
+      }
+      BlockType::Example => {
+        write_anchor(input, out)?;
+        self.write_box("adocexample", title.map(escape_text), &input.children, out)?;
+      }
+      BlockType::Admonition(admonition) => {
+        write_anchor(input, out)?;
+        let caption = match title {
+          Some(title) => format!("{}: {}", admonition.caption(), escape_text(title)),
+          None => admonition.caption().to_string(),
+        };
+        self.write_box("adocadmonition", Some(caption), &input.children, out)?;
+      }
+      BlockType::Open => {
+        write_anchor(input, out)?;
+        write_block_title(title, out)?;
+        self.write_blocks(&input.children, out)?;
+      }
+      BlockType::Quote | BlockType::Verse => {
+        write_anchor(input, out)?;
+        write_block_title(title, out)?;
+        let environment = match kind {
+          BlockType::Verse => "verse",
+          _ => "quote",
+        };
+        writeln!(out, "\\begin{{{}}}", environment)?;
+        if kind == &BlockType::Verse {
+          // A verse keeps its line breaks
+          let lines: Vec<String> = content
+            .trim()
+            .split('\n')
+            .map(|line| protect_bracket(&escape_text(line)))
+            .collect();
+          writeln!(out, "{}", lines.join("\\\\\n"))?;
+        } else {
+          self.write_blocks(&input.children, out)?;
+        }
+        // The attribution is given as `[quote, author, source]`
+        let author = input
+          .get_attribute("attribution")
+          .or_else(|| positional_attribute(input, 1));
+        let citetitle = input
+          .get_attribute("citetitle")
+          .or_else(|| positional_attribute(input, 2));
+        let attribution = match (author, citetitle) {
+          (Some(author), Some(citetitle)) => format!(
+            "{}, \\emph{{{}}}",
+            escape_text(author),
+            escape_text(citetitle)
+          ),
+          (Some(author), None) => escape_text(author),
+          (None, Some(citetitle)) => format!("\\emph{{{}}}", escape_text(citetitle)),
+          (None, None) => String::new(),
+        };
+        if !attribution.is_empty() {
+          writeln!(out, "\n\\hfill--- {}", attribution)?;
+        }
+        writeln!(out, "\\end{{{}}}", environment)?;
+      }
+    }
+
+    Ok(())
+  }
+
+  /// Writes a block in one of the boxes of the template
+  fn write_box<T: io::Write>(
+    &mut self,
+    environment: &str,
+    title: Option<String>,
+    children: &[ElementSpan],
+    out: &mut T,
+  ) -> Result<()> {
+    match title {
+      Some(title) => writeln!(out, "\\begin{{{}}}[title={{{}}}]", environment, title)?,
+      None => writeln!(out, "\\begin{{{}}}", environment)?,
+    }
+    self.write_blocks(children, out)?;
+    writeln!(out, "\\end{{{}}}", environment)?;
+
+    Ok(())
+  }
+
+  fn write_list<T: io::Write>(
+    &mut self,
+    list_type: &ListType,
+    input: &ElementSpan,
+    out: &mut T,
+  ) -> Result<()> {
+    // An empty list is an error in LaTeX
+    if input.children.is_empty() {
+      return Ok(());
+    }
+    write_anchor(input, out)?;
+    write_block_title(input.get_attribute("title"), out)?;
+
+    let environment = match list_type {
+      ListType::Bullet | ListType::Checklist => "itemize",
+      ListType::Number | ListType::Callout => "enumerate",
+      ListType::Description => "description",
+    };
+    writeln!(out, "\\begin{{{}}}", environment)?;
+    self.lists.push(list_type.clone());
+    if let Some(start) = input
+      .get_attribute("start")
+      .and_then(|start| start.trim().parse::<i64>().ok())
+    {
+      // Every level of the enumerations has its own counter
+      let depth = self
+        .lists
+        .iter()
+        .filter(|list| matches!(list, ListType::Number | ListType::Callout))
+        .count();
+      let counter = ["enumi", "enumii", "enumiii", "enumiv"][(depth - 1).min(3)];
+      writeln!(out, "\\setcounter{{{}}}{{{}}}", counter, start - 1)?;
+    }
+    for item in input.children.iter() {
+      let marker = match (item.get_attribute("checked"), list_type) {
+        (Some("true"), _) => "[$\\boxtimes$]".to_string(),
+        (Some(_), _) => "[$\\square$]".to_string(),
+        (None, ListType::Description) => {
+          format!(
+            "[{{{}}}]",
+            escape_text(item.get_attribute("term").unwrap_or(""))
+          )
+        }
+        (None, _) => String::new(),
+      };
+      self.write_list_item(&marker, item, out)?;
+    }
+    self.lists.pop();
+    writeln!(out, "\\end{{{}}}", environment)?;
+
+    Ok(())
+  }
+
+  fn write_list_item<T: io::Write>(
+    &mut self,
+    marker: &str,
+    item: &ElementSpan,
+    out: &mut T,
+  ) -> Result<()> {
+    write!(out, "\\item{}", marker)?;
+
+    let mut children = item.children.iter().peekable();
+    // The first paragraph follows the marker
+    if let Some(paragraph) = children.next_if(|child| {
+      child.element == Element::Paragraph
+        && child.get_attribute("title").is_none()
+        && child.get_attribute("anchor").is_none()
+    }) {
+      let mut text = Vec::new();
+      self.write_inlines(&paragraph.children, &mut text)?;
+      write!(out, " {}", protect_bracket(std::str::from_utf8(&text)?))?;
+    }
+    out.write_all(b"\n")?;
+    for child in children {
+      // Further paragraphs need a blank line
+      if !matches!(child.element, Element::List(_)) {
+        out.write_all(b"\n")?;
+      }
+      self.write_block(child, out)?;
+    }
+
+    Ok(())
+  }
+
+  fn write_table<T: io::Write>(&mut self, input: &ElementSpan, out: &mut T) -> Result<()> {
+    let columns = input
+      .children
+      .iter()
+      .map(|row| row.children.len())
+      .max()
+      .unwrap_or(0);
+    if columns == 0 {
+      return Ok(());
+    }
+    let mut formats = input
+      .get_attribute("cols")
+      .map(parse_columns_format)
+      .unwrap_or_default();
+    formats.resize(columns.max(formats.len()), ColumnFormat::default());
+    // Columns with a width or with blocks need paragraph columns
+    let paragraphs = formats
+      .iter()
+      .any(|format| format.width.is_some() || format.kind == ColKind::Asciidoc);
+    let header = has_option(input, "header");
+
+    let title = input.get_attribute("title");
+    match title {
+      Some(title) => {
+        writeln!(out, "\\begin{{table}}[htbp]\n\\centering")?;
+        writeln!(out, "\\caption{{{}}}", escape_text(title))?;
+        if let Some(id) = input.get_attribute("anchor") {
+          writeln!(out, "\\label{{{}}}", label(id))?;
+        }
+      }
+      None => {
+        write_anchor(input, out)?;
+        writeln!(out, "\\begin{{center}}")?;
+      }
+    }
+    writeln!(
+      out,
+      "\\begin{{tabular}}{{|{}|}}\n\\hline",
+      column_spec(&formats, paragraphs)
+    )?;
+    for (index, row) in input.children.iter().enumerate() {
+      if row.element != Element::TableRow {
+        return Err(AsciidoctrineError::MalformedAst);
+      }
+      let mut cells = Vec::new();
+      for cell in row.children.iter() {
+        if cell.element != Element::TableCell {
+          return Err(AsciidoctrineError::MalformedAst);
+        }
+        let mut content = Vec::new();
+        self.write_blocks(&cell.children, &mut content)?;
+        let content = String::from_utf8_lossy(&content).trim().to_string();
+        // Only paragraph columns can hold several paragraphs
+        let content = match paragraphs {
+          true => content,
+          false => content.split_whitespace().collect::<Vec<_>>().join(" "),
+        };
+        cells.push(match header && index == 0 {
+          true => format!("\\textbf{{{}}}", content),
+          false => content,
+        });
+      }
+      cells.resize(columns, String::new());
+      writeln!(out, "{} \\\\\n\\hline", cells.join(" & "))?;
+      if header && index == 0 {
+        writeln!(out, "\\hline")?;
+      }
+    }
+    writeln!(out, "\\end{{tabular}}")?;
+    match title {
+      Some(_) => writeln!(out, "\\end{{table}}")?,
+      None => writeln!(out, "\\end{{center}}")?,
+    }
+
+    Ok(())
+  }
+
+  fn write_inlines<T: io::Write>(&mut self, elements: &[ElementSpan], out: &mut T) -> Result<()> {
+    for (index, element) in elements.iter().enumerate() {
+      // There is no line to end after the last element
+      if element.element == Element::LineBreak && index + 1 == elements.len() {
+        continue;
+      }
+      self.write_inline(element, out)?;
+    }
+
+    Ok(())
+  }
+
+  fn write_inline<T: io::Write>(&mut self, input: &ElementSpan, out: &mut T) -> Result<()> {
+    match &input.element {
+      Element::Text => {
+        let content = input.get_attribute("content").unwrap_or(&input.content);
+        out.write_all(escape_text(content).as_bytes())?;
+      }
+      // Unlike `\\` it doesn't read a following `[` as an argument
+      Element::LineBreak => out.write_all(b"\\newline")?,
+      Element::Styled => {
+        let content = input.get_attribute("content").unwrap_or("");
+        let command = match input.get_attribute("style").unwrap_or("") {
+          "strong" => "textbf",
+          "em" => "emph",
+          "monospaced" => "texttt",
+          "mark" => "colorbox{yellow}",
+          "sup" => "textsuperscript",
+          "sub" => "textsubscript",
+          "double_quoted" => {
+            write!(out, "``{}''", escape_text(content))?;
+            return Ok(());
+          }
+          "single_quoted" => {
+            write!(out, "`{}'", escape_text(content))?;
+            return Ok(());
+          }
+          _ => {
+            out.write_all(escape_text(content).as_bytes())?;
+            return Ok(());
+          }
+        };
+        write!(out, "\\{}{{{}}}", command, escape_text(content))?;
+      }
+      Element::Link => {
+        let url = input.get_attribute("url").unwrap_or("");
+        match positional_attribute(input, 0) {
+          Some(content) => write!(
+            out,
+            "\\href{{{}}}{{{}}}",
+            escape_url(url),
+            escape_text(content)
+          )?,
+          None => match url.strip_prefix("mailto:") {
+            Some(address) => write!(
+              out,
+              "\\href{{{}}}{{{}}}",
+              escape_url(url),
+              escape_text(address)
+            )?,
+            None => write!(out, "\\url{{{}}}", escape_url(url))?,
+          },
+        }
+      }
+      Element::XRef => {
+        let id = input.get_attribute("id");
+        let content = input.get_attribute("content");
+        match (input.get_attribute("path"), id) {
+          (Some(path), id) => {
+            let href = match id {
+              Some(id) => format!("{}#{}", latex_path(path), id),
+              None => latex_path(path),
+            };
+            write!(
+              out,
+              "\\href{{{}}}{{{}}}",
+              escape_url(&href),
+              escape_text(content.or(id).unwrap_or(path))
+            )?;
+          }
+          (None, id) => {
+            let id = label(id.unwrap_or(""));
+            match content {
+              Some(content) => write!(out, "\\hyperref[{}]{{{}}}", id, escape_text(content))?,
+              // Like in asciidoctor the reference shows the title
+              None => write!(out, "\\nameref{{{}}}", id)?,
+            }
+          }
+        }
+      }
+      Element::Footnote => match input.get_attribute("kind") {
+        Some("ref") => {
+          let id = label(positional_attribute(input, 0).unwrap_or(""));
+          // A footnote which is referenced again gets a label
+          if self.footnotes.contains(&id) {
+            write!(out, "\\textsuperscript{{\\ref{{fn:{}}}}}", id)?;
+          } else {
+            let text = positional_attribute(input, 1).unwrap_or("");
+            write!(
+              out,
+              "\\footnote{{{}\\label{{fn:{}}}}}",
+              escape_text(text),
+              id
+            )?;
+            self.footnotes.insert(id);
+          }
+        }
+        _ => {
+          let content = input.get_attribute("content").unwrap_or("");
+          write!(out, "\\footnote{{{}}}", escape_text(content))?;
+        }
+      },
+      Element::InlineImage => {
+        let path = input.get_attribute("path").unwrap_or("");
+        if path.contains("://") {
+          warn!("remote images can't be included in LaTeX: {}", path);
+          let alt = positional_attribute(input, 0).unwrap_or(path);
+          write!(
+            out,
+            "\\href{{{}}}{{{}}}",
+            escape_url(path),
+            escape_text(alt)
+          )?;
+        } else {
+          write!(
+            out,
+            "\\includegraphics[{}]{{{}}}",
+            image_options(input),
+            self.image_path(path)
+          )?;
+        }
+      }
+      Element::Keyboard => {
+        let keys: Vec<String> = input
+          .positional_attributes
+          .iter()
+          .map(|key| format!("\\fbox{{\\texttt{{{}}}}}", escape_text(key.as_str())))
+          .collect();
+        out.write_all(keys.join("+").as_bytes())?;
+      }
+      Element::Button => {
+        let content = input.get_attribute("content").unwrap_or("");
+        write!(out, "\\textbf{{[{}]}}", escape_text(content))?;
+      }
+      Element::Menu => {
+        let mut items = vec![input.get_attribute("menu").unwrap_or("")];
+        items.extend(input.positional_attributes.iter().map(|item| item.as_str()));
+        let items: Vec<String> = items
+          .iter()
+          .map(|item| format!("\\textbf{{{}}}", escape_text(item)))
+          .collect();
+        out.write_all(items.join(" $\\rightarrow$ ").as_bytes())?;
+      }
+      Element::InlinePassthrough => {
+        out.write_all(input.get_attribute("content").unwrap_or("").as_bytes())?;
+      }
+      Element::Stem => {
+        let content = input.get_attribute("content").unwrap_or("");
+        match input.get_attribute("notation") {
+          Some("latexmath") => write!(out, "${}$", content)?,
+          _ => {
+            warn!("only latexmath is supported as math in LaTeX");
+            write!(out, "\\texttt{{{}}}", escape_text(content))?;
+          }
+        }
+      }
+      Element::IndexTerm => {
+        let terms: Vec<String> = input
+          .positional_attributes
+          .iter()
+          .map(|term| escape_text(term.as_str().trim()))
+          .collect();
+        write!(out, "\\index{{{}}}", terms.join("!"))?;
+        // Concealed terms only appear in the index
+        if input.get_attribute("visible") == Some("true") {
+          if let Some(term) = terms.first() {
+            out.write_all(term.as_bytes())?;
+          }
+        }
+      }
+      Element::Anchor => {
+        if let Some(id) = input.get_attribute("anchor") {
+          write!(out, "\\phantomsection\\label{{{}}}", label(id))?;
+        }
+      }
+      // Callouts are written as part of the listing content
+      Element::Callout(_) | Element::Comment => (),
+      _ => return Err(AsciidoctrineError::MalformedAst),
+    }
+
+    Ok(())
+  }
+
+  /// The path of an image relative to the `imagesdir`
+  fn image_path(&self, path: &str) -> String {
+    match &self.imagesdir {
+      Some(dir) if !path.starts_with('/') => {
+        format!("{}/{}", dir.trim_end_matches('/'), path)
+      }
+      _ => path.to_string(),
+    }
+  }
+}
+
+// Helper Functions
+//----------------------------------------------------
+
+/// Writes a label for the anchor of a block
+fn write_anchor<T: io::Write>(input: &ElementSpan, out: &mut T) -> Result<()> {
+  if let Some(id) = input.get_attribute("anchor") {
+    writeln!(out, "\\phantomsection\\label{{{}}}", label(id))?;
+  }
+
+  Ok(())
+}
+
+fn write_block_title<T: io::Write>(title: Option<&str>, out: &mut T) -> Result<()> {
+  if let Some(title) = title {
+    writeln!(out, "\\noindent\\textbf{{{}}}\\par", escape_text(title))?;
+  }
+
+  Ok(())
+}
+
+/// The column specification of a `tabular`. Paragraph columns share
+/// the line width by their relative widths.
+fn column_spec(formats: &[ColumnFormat], paragraphs: bool) -> String {
+  let total: usize = formats
+    .iter()
+    .map(|format| format.width.unwrap_or(1).max(1))
+    .sum();
+  let columns: Vec<String> = formats
+    .iter()
+    .map(|format| match paragraphs {
+      true => {
+        let share = format.width.unwrap_or(1).max(1) as f64 / total as f64;
+        let align = match format.halign {
+          HAlign::Left => "\\raggedright",
+          HAlign::Center => "\\centering",
+          HAlign::Right => "\\raggedleft",
+        };
+        format!(
+          ">{{{}\\arraybackslash}}p{{\\dimexpr {:.3}\\linewidth-2\\tabcolsep\\relax}}",
+          align, share
+        )
+      }
+      false => match format.halign {
+        HAlign::Left => "l".to_string(),
+        HAlign::Center => "c".to_string(),
+        HAlign::Right => "r".to_string(),
+      },
+    })
+    .collect();
+
+  columns.join("|")
+}
+
+/// The size of an image. Images never get wider than the line.
+fn image_options(input: &ElementSpan) -> String {
+  let mut options = vec!["max width=\\linewidth".to_string()];
+  let percent = input
+    .get_attribute("pdfwidth")
+    .or_else(|| input.get_attribute("scaledwidth"))
+    .and_then(|width| width.trim().strip_suffix('%'))
+    .and_then(|width| width.trim().parse::<f64>().ok());
+  let pixels = input
+    .get_attribute("width")
+    .or_else(|| positional_attribute(input, 1))
+    .and_then(|width| width.trim().parse::<u32>().ok());
+  match (percent, pixels) {
+    (Some(percent), _) => options.push(format!("width={:.3}\\linewidth", percent / 100.0)),
+    // Like asciidoctor-pdf a pixel is 0.75pt (96 dpi). The unit `px`
+    // depends on the engine.
+    (None, Some(pixels)) => options.push(format!("width={}pt", pixels as f64 * 0.75)),
+    (None, None) => (),
+  }
+
+  options.join(",")
+}
+
+/// Checks if the option is set with `%name` or in the `options`
+/// attribute of an element
+fn has_option(input: &ElementSpan, name: &str) -> bool {
+  input.positional_attributes.iter().any(|attribute| {
+    attribute
+      .as_str()
+      .split('%')
+      .skip(1)
+      .any(|option| option == name)
+  }) || input
+    .get_attribute("options")
+    .or_else(|| input.get_attribute("opts"))
+    .is_some_and(|options| options.split(',').any(|option| option.trim() == name))
+}
+
+fn level_attribute(ast: &AST, name: &str, default: i32) -> i32 {
+  match ast.get_attribute(name).map(|value| value.trim().parse()) {
+    Some(Ok(level)) => level,
+    Some(Err(_)) => {
+      warn!("invalid {}", name);
+      default
+    }
+    None => default,
+  }
+}
+
+/// The babel name of the language of the document
+fn babel_language(lang: Option<&str>) -> &'static str {
+  match lang.map(|lang| lang.split(['-', '_']).next().unwrap_or(lang)) {
+    Some("de") => "ngerman",
+    Some("en") => "english",
+    Some("fr") => "french",
+    Some("es") => "spanish",
+    Some("it") => "italian",
+    Some("nl") => "dutch",
+    Some("pt") => "portuguese",
+    Some("pl") => "polish",
+    Some("cs") => "czech",
+    Some("da") => "danish",
+    Some("fi") => "finnish",
+    Some("sv") => "swedish",
+    Some("ru") => "russian",
+    _ => "",
+  }
+}
+
+/// The names of the languages known to the listings package (and
+/// the Rust definition of the template)
+fn listings_language(language: &str) -> Option<&'static str> {
+  let language = match language.to_lowercase().as_str() {
+    "rust" | "rs" => "Rust",
+    "c" => "C",
+    "cpp" | "c++" => "C++",
+    "java" => "Java",
+    "python" | "py" => "Python",
+    "bash" | "sh" | "shell" | "console" => "bash",
+    "html" => "HTML",
+    "xml" => "XML",
+    "sql" => "SQL",
+    "ruby" => "Ruby",
+    "perl" => "Perl",
+    "php" => "PHP",
+    "haskell" => "Haskell",
+    "lisp" | "elisp" => "Lisp",
+    "make" | "makefile" => "make",
+    "tex" | "latex" => "TeX",
+    "matlab" => "Matlab",
+    "fortran" => "Fortran",
+    "pascal" => "Pascal",
+    "r" => "R",
+    "lua" => "Lua",
+    "erlang" => "erlang",
+    _ => return None,
+  };
+
+  Some(language)
+}
+
+/// Minted passes the language to Pygments
+fn minted_language(language: &str) -> String {
+  language
+    .to_lowercase()
+    .chars()
+    .filter(|c| c.is_ascii_alphanumeric() || ['+', '#', '-', '_'].contains(c))
+    .collect()
+}
+
+fn positional_attribute<'a>(input: &'a ElementSpan, index: usize) -> Option<&'a str> {
+  input
+    .positional_attributes
+    .get(index)
+    .map(|value| value.as_str().trim())
+    .filter(|value| !value.is_empty())
+}
+
+/// The path of the PDF document generated from an asciidoc document
+fn latex_path(path: &str) -> String {
+  match path.strip_suffix(".adoc") {
+    Some(path) => format!("{}.pdf", path),
+    None => path.to_string(),
+  }
+}
+
+/// Labels can't contain the special characters of LaTeX
+fn label(id: &str) -> String {
+  id.chars()
+    .map(
+      |c| match c.is_alphanumeric() || ['-', '_', ':', '.'].contains(&c) {
+        true => c,
+        false => '-',
+      },
+    )
+    .collect()
+}
+
+/// A `[` after `\item` or `\\` would be read as an argument
+fn protect_bracket(text: &str) -> String {
+  match text.starts_with('[') {
+    true => format!("{{}}{}", text),
+    false => text.to_string(),
+  }
+}
+
+fn escape_url(url: &str) -> String {
+  let mut out = String::with_capacity(url.len());
+  for c in url.chars() {
+    if ['#', '%', '{', '}', '\\'].contains(&c) {
+      out.push('\\');
+    }
+    out.push(c);
+  }
+  out
+}
+
+/// Escapes the special characters of LaTeX. Character references in
+/// the text are replaced by their characters.
+fn escape_text(input: &str) -> String {
+  let input = decode_references(input);
+  let mut out = String::with_capacity(input.len());
+  for c in input.chars() {
+    match c {
+      '\\' => out.push_str("\\textbackslash{}"),
+      '^' => out.push_str("\\textasciicircum{}"),
+      '~' => out.push_str("\\textasciitilde{}"),
+      // `"` is a shorthand of babel for some languages
+      '"' => out.push_str("\\textquotedbl{}"),
+      '\u{a0}' => out.push('~'),
+      // The replacements use characters inputenc doesn't know. The zero
+      // width space after a dash or an ellipsis allows a line break.
+      '\u{200b}' => out.push_str("\\hspace{0pt}"),
+      '\u{2009}' => out.push_str("\\,"),
+      '\u{2192}' => out.push_str("\\ensuremath{\\rightarrow}"),
+      '\u{2190}' => out.push_str("\\ensuremath{\\leftarrow}"),
+      '\u{21d2}' => out.push_str("\\ensuremath{\\Rightarrow}"),
+      '\u{21d0}' => out.push_str("\\ensuremath{\\Leftarrow}"),
+      '{' | '}' | '$' | '&' | '#' | '_' | '%' => {
+        out.push('\\');
+        out.push(c);
+      }
+      c => out.push(c),
+    }
+  }
+  out
+}
@@ -0,0 +1,690 @@
+pub use crate::ast::*;
+use crate::reader::asciidoc::{parse_columns_format, ColumnFormat, HAlign};
+use crate::sections::number_titles;
+use crate::writer::text::decode_references;
+use crate::{options, AsciidoctrineError, Result};
+use std::collections::HashMap;
+use std::io;
+
+/// Writes a Unix manual page with the macros of groff/man. The
+/// document follows the asciidoctor conventions for the doctype
+/// `manpage`: The title has the form `name(volume)` and the first
+/// section is called NAME.
+#[derive(Default)]
+pub struct ManpageWriter {}
+
+impl ManpageWriter {
+  pub fn new() -> Self {
+    ManpageWriter {}
+  }
+}
+
+impl<T: io::Write> crate::Writer<T> for ManpageWriter {
+  fn write<'a>(&mut self, ast: AST, _args: &options::Opts, mut out: T) -> Result<()> {
+    // The titles need their ids for the references
+    let ast = number_titles(ast);
+    let doctitle = ast.header.title.as_deref().unwrap_or("");
+    let (mantitle, volume) = match split_mantitle(doctitle) {
+      Some(parts) => parts,
+      None => {
+        warn!("the title of a manpage should have the form name(volume)");
+        (doctitle, "1")
+      }
+    };
+    let mantitle = ast.get_attribute("mantitle").unwrap_or(mantitle);
+    let volume = ast.get_attribute("manvolnum").unwrap_or(volume);
+    let manual = ast.get_attribute("manmanual").unwrap_or("");
+    let source = ast.get_attribute("mansource").unwrap_or("");
+    let date = ast
+      .header
+      .revision
+      .as_ref()
+      .and_then(|revision| revision.date.as_deref())
+      .or_else(|| ast.get_attribute("docdate"))
+      .or_else(|| ast.get_attribute("localdate"))
+      .unwrap_or("");
+
+    // tbl has to prepare the tables
+    writeln!(out, "'\\\" t")?;
+    writeln!(out, ".\\\"     Title: {}", mantitle)?;
+    writeln!(out, ".\\\" Generator: asciidoctrine")?;
+    writeln!(out, ".\\\"      Date: {}", date)?;
+    writeln!(out, ".\\\"    Manual: {}", manual)?;
+    writeln!(out, ".\\\"    Source: {}", source)?;
+    writeln!(out, ".\\\"")?;
+    writeln!(
+      out,
+      ".TH {} {} {} {} {}",
+      quote(&mantitle.to_uppercase()),
+      quote(volume),
+      quote(date),
+      quote(source),
+      quote(manual)
+    )?;
+    // No hyphenation and a ragged right margin
+    writeln!(out, ".nh\n.ad l")?;
+
+    let mut roff = Roff {
+      titles: HashMap::new(),
+      section: String::new(),
+      footnotes: Vec::new(),
+      footnote_ids: HashMap::new(),
+    };
+    collect_titles(&ast.elements, &mut roff.titles);
+
+    let mut sections = Vec::new();
+    collect_section_names(&ast.elements, &mut sections);
+    if !sections.iter().any(|name| name == "NAME") {
+      match (
+        ast.get_attribute("manname"),
+        ast.get_attribute("manpurpose"),
+      ) {
+        (Some(name), Some(purpose)) => writeln!(
+          out,
+          ".SH \"NAME\"\n{} \\- {}",
+          escape_text(name),
+          escape_text(purpose)
+        )?,
+        _ => warn!("a manpage needs a NAME section"),
+      }
+    }
+
+    // The document title is part of the header
+    let skip = match ast.elements.first() {
+      Some(title) if ast.header.title.is_some() && title.element == Element::Title { level: 1 } => {
+        1
+      }
+      _ => 0,
+    };
+    roff.write_blocks(&ast.elements[skip..], &mut out)?;
+
+    if !roff.footnotes.is_empty() {
+      writeln!(out, ".SH \"NOTES\"")?;
+      for (index, footnote) in roff.footnotes.iter().enumerate() {
+        writeln!(out, ".IP \"{:>2}.\" 4\n{}", index + 1, footnote)?;
+      }
+    }
+    let authors = &ast.header.authors;
+    if !authors.is_empty() && !sections.iter().any(|name| name.starts_with("AUTHOR")) {
+      let title = match authors.len() {
+        1 => "AUTHOR",
+        _ => "AUTHORS",
+      };
+      let names: Vec<String> = authors
+        .iter()
+        .map(|author| match &author.email {
+          Some(email) => format!("{} <{}>", escape_text(&author.name), escape_text(email)),
+          None => escape_text(&author.name),
+        })
+        .collect();
+      writeln!(out, ".SH \"{}\"\n.sp\n{}", title, names.join("\n.br\n"))?;
+    }
+    out.flush()?;
+
+    Ok(())
+  }
+}
+
+/// The state of the document while writing the body
+struct Roff {
+  /// The names of the titles by their ids
+  titles: HashMap<String, String>,
+  /// The name of the current section
+  section: String,
+  footnotes: Vec<String>,
+  /// The numbers of the footnotes which can be referenced
+  footnote_ids: HashMap<String, usize>,
+}
+
+impl Roff {
+  fn write_blocks<T: io::Write>(&mut self, elements: &[ElementSpan], out: &mut T) -> Result<()> {
+    for element in elements {
+      self.write_block(element, out)?;
+    }
+
+    Ok(())
+  }
+
+  /// Writes the content of a list item or another indented block. A
+  /// first paragraph follows directly without space.
+  fn write_content<T: io::Write>(&mut self, elements: &[ElementSpan], out: &mut T) -> Result<()> {
+    let mut children = elements.iter().peekable();
+    if let Some(paragraph) = children.next_if(|child| {
+      child.element == Element::Paragraph && child.get_attribute("title").is_none()
+    }) {
+      self.write_inlines(&paragraph.children, out)?;
+      out.write_all(b"\n")?;
+    }
+    for child in children {
+      self.write_block(child, out)?;
+    }
+
+    Ok(())
+  }
+
+  fn write_block<T: io::Write>(&mut self, input: &ElementSpan, out: &mut T) -> Result<()> {
+    match &input.element {
+      Element::Section { .. } => self.write_blocks(&input.children, out)?,
+      Element::Title { level } => {
+        let name = escape_text(input.get_attribute("name").unwrap_or(""));
+        if *level <= 2 {
+          self.section = name.to_uppercase();
+          writeln!(out, ".SH {}", quote(&self.section))?;
+        } else {
+          writeln!(out, ".SS {}", quote(&name))?;
+        }
+      }
+      Element::Paragraph => {
+        write_block_title(input.get_attribute("title"), out)?;
+        // whatis reads the NAME section as it is
+        if self.section != "NAME" {
+          out.write_all(b".sp\n")?;
+        }
+        self.write_inlines(&input.children, out)?;
+        out.write_all(b"\n")?;
+      }
+      Element::List(list_type) => self.write_list(list_type, input, out)?,
+      Element::ListItem(_) => self.write_content(&input.children, out)?,
+      Element::TypedBlock { kind } => self.write_typed_block(kind, input, out)?,
+      Element::Table => self.write_table(input, out)?,
+      Element::Image => {
+        let path = input.get_attribute("path").unwrap_or("");
+        let alt = positional_attribute(input, 0).unwrap_or(path);
+        write_block_title(input.get_attribute("title"), out)?;
+        writeln!(out, ".sp\n[{}]", escape_text(alt))?;
+      }
+      Element::IncludeElement(include) => self.write_blocks(&include.inner.elements, out)?,
+      // A manual page is too short for a table of contents
+      Element::TableOfContents => (),
+      // Attribute entries are already resolved by the reader
+      Element::Attribute(_) => (),
+      Element::Anchor | Element::Comment => (),
+      Element::ExternalContent => {
+        warn!("external content is not supported in manpages");
+      }
+      Element::Error(msg) => {
+        warn!("{}", msg);
+        writeln!(out, ".\\\" ERROR: {}", msg.replace('\n', " "))?;
+      }
+      Element::Styled
+      | Element::Text
+      | Element::XRef
+      | Element::Link
+      | Element::Footnote
+      | Element::InlineImage
+      | Element::Keyboard
+      | Element::Button
+      | Element::Menu
+      | Element::InlinePassthrough
+      | Element::Stem
+      | Element::IndexTerm
+      | Element::LineBreak
+      | Element::Callout(_)
+      | Element::TableRow
+      | Element::TableCell => return Err(AsciidoctrineError::MalformedAst),
+    }
+
+    Ok(())
+  }
+
+  fn write_typed_block<T: io::Write>(
+    &mut self,
+    kind: &BlockType,
+    input: &ElementSpan,
+    out: &mut T,
+  ) -> Result<()> {
+    let title = input.get_attribute("title");
+    let content = input.get_attribute("content").unwrap_or(&input.content);
+
+    match kind {
+      BlockType::Comment => (),
+      BlockType::Passtrough => writeln!(out, "{}", content)?,
+      BlockType::Listing | BlockType::Literal => {
+        write_block_title(title, out)?;
+        writeln!(
+          out,
+          ".sp\n.if n .RS 4\n.nf\n.fam C\n{}\n.fam\n.fi\n.if n .RE",
+          escape(content.trim_end_matches('\n'), true)
+        )?;
+      }
+      BlockType::Admonition(admonition) => {
+        let caption = match title {
+          Some(title) => format!("{}: {}", admonition.caption(), escape_text(title)),
+          None => admonition.caption().to_string(),
+        };
+        writeln!(out, ".sp\n.RS 4\n\\fB{}\\fP\n.br", caption)?;
+        self.write_content(&input.children, out)?;
+        writeln!(out, ".RE")?;
+      }
+      BlockType::Sidebar | BlockType::Example => {
+        out.write_all(b".sp\n.RS 4\n")?;
+        match title {
+          Some(title) => {
+            writeln!(out, "\\fB{}\\fP\n.br", escape_text(title))?;
+            self.write_content(&input.children, out)?;
+          }
+          None => self.write_content(&input.children, out)?,
+        }
+        writeln!(out, ".RE")?;
+      }
+      BlockType::Open => {
+        write_block_title(title, out)?;
+        self.write_blocks(&input.children, out)?;
+      }
+      BlockType::Quote | BlockType::Verse => {
+        write_block_title(title, out)?;
+        out.write_all(b".sp\n.RS 4\n")?;
+        if kind == &BlockType::Verse {
+          // A verse keeps its line breaks
+          writeln!(out, ".nf\n{}\n.fi", escape_text(content.trim()))?;
+        } else {
+          self.write_content(&input.children, out)?;
+        }
+        // The attribution is given as `[quote, author, source]`
+        let author = input
+          .get_attribute("attribution")
+          .or_else(|| positional_attribute(input, 1));
+        let citetitle = input
+          .get_attribute("citetitle")
+          .or_else(|| positional_attribute(input, 2));
+        let attribution = match (author, citetitle) {
+          (Some(author), Some(citetitle)) => format!(
+            "{}, \\fI{}\\fP",
+            escape_text(author),
+            escape_text(citetitle)
+          ),
+          (Some(author), None) => escape_text(author),
+          (None, Some(citetitle)) => format!("\\fI{}\\fP", escape_text(citetitle)),
+          (None, None) => String::new(),
+        };
+        if !attribution.is_empty() {
+          writeln!(out, ".sp\n\\(em {}", attribution)?;
+        }
+        writeln!(out, ".RE")?;
+      }
+    }
+
+    Ok(())
+  }
+
+  fn write_list<T: io::Write>(
+    &mut self,
+    list_type: &ListType,
+    input: &ElementSpan,
+    out: &mut T,
+  ) -> Result<()> {
+    write_block_title(input.get_attribute("title"), out)?;
+
+    let start = input
+      .get_attribute("start")
+      .and_then(|start| start.trim().parse::<i64>().ok())
+      .unwrap_or(1);
+    for (index, item) in input.children.iter().enumerate() {
+      if list_type == &ListType::Description {
+        let term = item.get_attribute("term").unwrap_or("");
+        writeln!(out, ".sp\n\\fB{}\\fP\n.RS 4", escape_text(term))?;
+        self.write_content(&item.children, out)?;
+        writeln!(out, ".RE")?;
+        continue;
+      }
+
+      // The marker hangs in the indentation of the item
+      let marker = match (list_type, item.get_attribute("checked")) {
+        (_, Some("true")) => "[x]\\h'+01'".to_string(),
+        (_, Some(_)) => "[ ]\\h'+01'".to_string(),
+        (ListType::Number, None) => format!("{:>2}.\\h'+01'", start + index as i64),
+        (ListType::Callout, None) => {
+          let number = item.get_attribute("callout").unwrap_or("");
+          format!("{:>3}\\h'+01'", format!("({})", number))
+        }
+        _ => "\\(bu\\h'+03'".to_string(),
+      };
+      writeln!(out, ".sp\n.RS 4\n\\h'-04'{}\\c", marker)?;
+      self.write_content(&item.children, out)?;
+      writeln!(out, ".RE")?;
+    }
+
+    Ok(())
+  }
+
+  /// Writes a table for the preprocessor tbl. The cells are text
+  /// blocks, so that they can hold several lines.
+  fn write_table<T: io::Write>(&mut self, input: &ElementSpan, out: &mut T) -> Result<()> {
+    let columns = input
+      .children
+      .iter()
+      .map(|row| row.children.len())
+      .max()
+      .unwrap_or(0);
+    if columns == 0 {
+      return Ok(());
+    }
+    let mut formats = input
+      .get_attribute("cols")
+      .map(parse_columns_format)
+      .unwrap_or_default();
+    formats.resize(columns.max(formats.len()), ColumnFormat::default());
+    let header = has_option(input, "header");
+
+    write_block_title(input.get_attribute("title"), out)?;
+    writeln!(out, ".sp\n.TS\nallbox tab(:);")?;
+    if header {
+      writeln!(out, "{}", column_spec(&formats, "B"))?;
+    }
+    writeln!(out, "{}.", column_spec(&formats, ""))?;
+    for row in input.children.iter() {
+      if row.element != Element::TableRow {
+        return Err(AsciidoctrineError::MalformedAst);
+      }
+      let mut cells = Vec::new();
+      for cell in row.children.iter() {
+        if cell.element != Element::TableCell {
+          return Err(AsciidoctrineError::MalformedAst);
+        }
+        let mut content = Vec::new();
+        self.write_content(&cell.children, &mut content)?;
+        cells.push(format!("T{{\n{}T}}", String::from_utf8_lossy(&content)));
+      }
+      cells.resize(columns, String::new());
+      writeln!(out, "{}", cells.join(":"))?;
+    }
+    writeln!(out, ".TE\n.sp 1")?;
+
+    Ok(())
+  }
+
+  fn write_inlines<T: io::Write>(&mut self, elements: &[ElementSpan], out: &mut T) -> Result<()> {
+    for (index, element) in elements.iter().enumerate() {
+      match element.element {
+        // There is no line to end after the last element
+        Element::LineBreak if index + 1 == elements.len() => (),
+        Element::LineBreak => out.write_all(b"\n.br\n")?,
+        Element::Text if index > 0 => {
+          let content = element.get_attribute("content").unwrap_or(&element.content);
+          // The request already ends the line
+          let line_start = elements[index - 1].element == Element::LineBreak;
+          let content = match line_start {
+            true => content.strip_prefix('\n').unwrap_or(content),
+            false => content,
+          };
+          out.write_all(escape_line(content, line_start).as_bytes())?;
+        }
+        _ => self.write_inline(element, out)?,
+      }
+    }
+
+    Ok(())
+  }
+
+  fn write_inline<T: io::Write>(&mut self, input: &ElementSpan, out: &mut T) -> Result<()> {
+    match &input.element {
+      Element::Text => {
+        let content = input.get_attribute("content").unwrap_or(&input.content);
+        out.write_all(escape_text(content).as_bytes())?;
+      }
+      Element::Styled => {
+        let content = escape_text(input.get_attribute("content").unwrap_or(""));
+        match input.get_attribute("style").unwrap_or("") {
+          "strong" => write!(out, "\\fB{}\\fP", content)?,
+          "em" => write!(out, "\\fI{}\\fP", content)?,
+          "monospaced" => write!(out, "\\f(CR{}\\fP", content)?,
+          "sup" => write!(out, "\\u{}\\d", content)?,
+          "sub" => write!(out, "\\d{}\\u", content)?,
+          "double_quoted" => write!(out, "\\(lq{}\\(rq", content)?,
+          "single_quoted" => write!(out, "\\(oq{}\\(cq", content)?,
+          _ => out.write_all(content.as_bytes())?,
+        }
+      }
+      Element::Link => {
+        let url = input.get_attribute("url").unwrap_or("");
+        let target = url.strip_prefix("mailto:").unwrap_or(url);
+        match positional_attribute(input, 0) {
+          Some(content) => write!(out, "{} <{}>", escape_text(content), escape_text(target))?,
+          None => out.write_all(escape_text(target).as_bytes())?,
+        }
+      }
+      Element::XRef => {
+        let id = input.get_attribute("id");
+        let content = input
+          .get_attribute("content")
+          .or_else(|| id.and_then(|id| self.titles.get(id)).map(String::as_str))
+          .or(id)
+          .or_else(|| input.get_attribute("path"))
+          .unwrap_or("");
+        out.write_all(escape_text(content).as_bytes())?;
+      }
+      Element::Footnote => {
+        let number = match input.get_attribute("kind") {
+          Some("ref") => {
+            let id = positional_attribute(input, 0).unwrap_or("");
+            match self.footnote_ids.get(id) {
+              Some(number) => *number,
+              None => {
+                let text = positional_attribute(input, 1).unwrap_or("");
+                self.footnotes.push(escape_text(text));
+                self
+                  .footnote_ids
+                  .insert(id.to_string(), self.footnotes.len());
+                self.footnotes.len()
+              }
+            }
+          }
+          _ => {
+            let content = input.get_attribute("content").unwrap_or("");
+            let content = content.split_whitespace().collect::<Vec<_>>().join(" ");
+            self.footnotes.push(escape_text(&content));
+            self.footnotes.len()
+          }
+        };
+        write!(out, "[{}]", number)?;
+      }
+      Element::InlineImage => {
+        let path = input.get_attribute("path").unwrap_or("");
+        let alt = positional_attribute(input, 0).unwrap_or(path);
+        write!(out, "[{}]", escape_text(alt))?;
+      }
+      Element::Keyboard => {
+        let keys: Vec<String> = input
+          .positional_attributes
+          .iter()
+          .map(|key| format!("\\fB{}\\fP", escape_text(key.as_str())))
+          .collect();
+        out.write_all(keys.join("+").as_bytes())?;
+      }
+      Element::Button => {
+        let content = input.get_attribute("content").unwrap_or("");
+        write!(out, "\\fB[{}]\\fP", escape_text(content))?;
+      }
+      Element::Menu => {
+        let mut items = vec![input.get_attribute("menu").unwrap_or("")];
+        items.extend(input.positional_attributes.iter().map(|item| item.as_str()));
+        let items: Vec<String> = items
+          .iter()
+          .map(|item| format!("\\fB{}\\fP", escape_text(item)))
+          .collect();
+        out.write_all(items.join(" \\(-> ").as_bytes())?;
+      }
+      Element::InlinePassthrough => {
+        out.write_all(input.get_attribute("content").unwrap_or("").as_bytes())?;
+      }
+      Element::Stem => {
+        let content = input.get_attribute("content").unwrap_or("");
+        out.write_all(escape_text(content).as_bytes())?;
+      }
+      Element::IndexTerm => {
+        // Concealed terms only appear in the index
+        if input.get_attribute("visible") == Some("true") {
+          if let Some(term) = positional_attribute(input, 0) {
+            out.write_all(escape_text(term).as_bytes())?;
+          }
+        }
+      }
+      // Callouts are written as part of the listing content
+      Element::Callout(_) | Element::Anchor | Element::Comment => (),
+      _ => return Err(AsciidoctrineError::MalformedAst),
+    }
+
+    Ok(())
+  }
+}
+
+// Helper Functions
+//----------------------------------------------------
+
+/// Splits a title like `git-commit(1)` into the name and the volume
+fn split_mantitle(title: &str) -> Option<(&str, &str)> {
+  let (name, volume) = title.trim().strip_suffix(')')?.rsplit_once('(')?;
+  match name.trim().is_empty() || volume.trim().is_empty() {
+    true => None,
+    false => Some((name.trim(), volume.trim())),
+  }
+}
+
+fn collect_titles(elements: &[ElementSpan], titles: &mut HashMap<String, String>) {
+  for element in elements {
+    match &element.element {
+      Element::Title { .. } => {
+        if let (Some(id), Some(name)) = (element.get_attribute("id"), element.get_attribute("name"))
+        {
+          titles.insert(id.to_string(), name.to_string());
+        }
+      }
+      Element::Section { .. } => {
+        if let (Some(id), Some(name)) = (
+          element.get_attribute("id"),
+          element
+            .children
+            .first()
+            .and_then(|title| title.get_attribute("name")),
+        ) {
+          titles.insert(id.to_string(), name.to_string());
+        }
+      }
+      Element::IncludeElement(include) => collect_titles(&include.inner.elements, titles),
+      _ => (),
+    }
+    collect_titles(&element.children, titles);
+  }
+}
+
+/// The names of the sections of the first level in upper case
+fn collect_section_names(elements: &[ElementSpan], names: &mut Vec<String>) {
+  for element in elements {
+    match &element.element {
+      Element::Title { level: 2 } => {
+        names.push(element.get_attribute("name").unwrap_or("").to_uppercase());
+      }
+      Element::Section { .. } => collect_section_names(&element.children, names),
+      Element::IncludeElement(include) => collect_section_names(&include.inner.elements, names),
+      _ => (),
+    }
+  }
+}
+
+fn write_block_title<T: io::Write>(title: Option<&str>, out: &mut T) -> Result<()> {
+  if let Some(title) = title {
+    writeln!(out, ".sp\n\\fB{}\\fP", escape_text(title))?;
+  }
+
+  Ok(())
+}
+
+/// The format line of tbl for the columns. The modifier applies to
+/// all columns.
+fn column_spec(formats: &[ColumnFormat], modifier: &str) -> String {
+  let columns: Vec<String> = formats
+    .iter()
+    .map(|format| {
+      let align = match format.halign {
+        HAlign::Left => "l",
+        HAlign::Center => "c",
+        HAlign::Right => "r",
+      };
+      format!("{}t{}", align, modifier)
+    })
+    .collect();
+
+  columns.join(" ")
+}
+
+/// Checks if the option is set with `%name` or in the `options`
+/// attribute of an element
+fn has_option(input: &ElementSpan, name: &str) -> bool {
+  input.positional_attributes.iter().any(|attribute| {
+    attribute
+      .as_str()
+      .split('%')
+      .skip(1)
+      .any(|option| option == name)
+  }) || input
+    .get_attribute("options")
+    .or_else(|| input.get_attribute("opts"))
+    .is_some_and(|options| options.split(',').any(|option| option.trim() == name))
+}
+
+fn positional_attribute<'a>(input: &'a ElementSpan, index: usize) -> Option<&'a str> {
+  input
+    .positional_attributes
+    .get(index)
+    .map(|value| value.as_str().trim())
+    .filter(|value| !value.is_empty())
+}
+
+/// Quotes the argument of a macro
+fn quote(text: &str) -> String {
+  format!("\"{}\"", text.replace('"', "\\(dq"))
+}
+
+/// Escapes the special characters of roff. Character references in
+/// the text are replaced by their characters.
+fn escape_text(input: &str) -> String {
+  escape_line(input, true)
+}
+
+/// Escapes text which starts a line or continues one
+fn escape_line(input: &str, line_start: bool) -> String {
+  let input = decode_references(input);
+  let mut out = String::with_capacity(input.len());
+  for c in escape(&input, line_start).chars() {
+    match c {
+      '\u{a0}' => out.push_str("\\~"),
+      '\u{2009}' => out.push(' '),
+      '\u{a9}' => out.push_str("\\(co"),
+      '\u{ae}' => out.push_str("\\(rg"),
+      '\u{2122}' => out.push_str("\\(tm"),
+      '\u{2014}' => out.push_str("\\(em"),
+      '\u{2013}' => out.push_str("\\(en"),
+      '\u{2018}' => out.push_str("\\(oq"),
+      '\u{2019}' => out.push_str("\\(cq"),
+      '\u{201c}' => out.push_str("\\(lq"),
+      '\u{201d}' => out.push_str("\\(rq"),
+      '\u{2026}' => out.push_str(".\\|.\\|."),
+      '\u{2192}' => out.push_str("\\(->"),
+      '\u{2190}' => out.push_str("\\(<-"),
+      '\u{21d2}' => out.push_str("\\(rA"),
+      '\u{21d0}' => out.push_str("\\(lA"),
+      '\u{200b}' => out.push_str("\\:"),
+      c => out.push(c),
+    }
+  }
+  out
+}
+
+/// Escapes the backslash and the hyphen. A dot or an apostrophe at
+/// the start of a line would be read as a request.
+fn escape(input: &str, mut line_start: bool) -> String {
+  let mut out = String::with_capacity(input.len());
+  for c in input.chars() {
+    match c {
+      '\\' => out.push_str("\\e"),
+      '-' => out.push_str("\\-"),
+      '.' | '\'' if line_start => {
+        out.push_str("\\&");
+        out.push(c);
+      }
+      c => out.push(c),
+    }
+    line_start = c == '\n';
+  }
+  out
+}
@@ -0,0 +1,697 @@
+pub use crate::ast::*;
+use crate::sections::number_titles;
+use crate::{options, AsciidoctrineError, Result};
+use std::collections::HashMap;
+use std::io;
+
+/// Writes GitHub flavored Markdown (GFM)
+///
+/// Elements without an equivalent in Markdown are approximated and
+/// reported with a warning.
+#[derive(Default)]
+pub struct MarkdownWriter {}
+
+impl MarkdownWriter {
+  pub fn new() -> Self {
+    MarkdownWriter {}
+  }
+}
+
+impl<T: io::Write> crate::Writer<T> for MarkdownWriter {
+  fn write<'a>(&mut self, ast: AST, _args: &options::Opts, mut out: T) -> Result<()> {
+    // Cross references need the ids of the titles
+    let ast = number_titles(ast);
+    let mut context = Context::new(&ast.elements);
+
+    let mut blocks = vec![write_blocks(&ast.elements, &mut context)?];
+    let footnotes: Vec<String> = context
+      .footnotes
+      .iter()
+      .map(|(label, text)| format!("[^{}]: {}", label, text))
+      .collect();
+    if !footnotes.is_empty() {
+      blocks.push(footnotes.join("\n"));
+    }
+    blocks.retain(|block| !block.is_empty());
+
+    if !blocks.is_empty() {
+      out.write_all(blocks.join("\n\n").as_bytes())?;
+      out.write_all(b"\n")?;
+    }
+    out.flush()?;
+
+    Ok(())
+  }
+}
+
+/// The state collected while writing a document
+struct Context {
+  /// The heading slugs and the names of the titles by their ids
+  titles: HashMap<String, (String, String)>,
+  /// The labels and texts of the footnotes in order of appearance
+  footnotes: Vec<(String, String)>,
+}
+
+impl Context {
+  fn new(elements: &[ElementSpan]) -> Self {
+    let mut context = Context {
+      titles: HashMap::new(),
+      footnotes: Vec::new(),
+    };
+    context.collect_titles(elements, &mut HashMap::new());
+    context
+  }
+
+  /// Computes the slugs of the headings like GitHub does. Repeated
+  /// slugs get a counter.
+  fn collect_titles(&mut self, elements: &[ElementSpan], counts: &mut HashMap<String, usize>) {
+    for element in elements {
+      if let Element::Title { .. } = element.element {
+        let name = element.get_attribute("name").unwrap_or("");
+        let mut slug = heading_slug(name);
+        let count = counts.entry(slug.clone()).or_insert(0);
+        if *count > 0 {
+          slug = format!("{}-{}", slug, count);
+        }
+        *count += 1;
+
+        if let Some(id) = element
+          .get_attribute("id")
+          .or_else(|| element.get_attribute("anchor"))
+        {
+          self.titles.insert(id.to_string(), (slug, name.to_string()));
+        }
+      }
+      self.collect_titles(&element.children, counts);
+    }
+  }
+
+  /// Registers a footnote and returns its label. A `footnoteref`
+  /// with a known id reuses the label of the first footnote.
+  fn footnote(&mut self, input: &ElementSpan) -> String {
+    let (label, text) = match input.get_attribute("kind") {
+      Some("ref") => {
+        let id = positional_attribute(input, 0).unwrap_or("");
+        if self.footnotes.iter().any(|(label, _)| label == id) {
+          return id.to_string();
+        }
+        (id.to_string(), positional_attribute(input, 1).unwrap_or(""))
+      }
+      _ => (
+        (self.footnotes.len() + 1).to_string(),
+        input.get_attribute("content").unwrap_or(""),
+      ),
+    };
+    let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    self.footnotes.push((label.clone(), escape_text(&text)));
+
+    label
+  }
+}
+
+fn write_blocks(elements: &[ElementSpan], context: &mut Context) -> Result<String> {
+  let mut blocks = Vec::new();
+  let mut previous = None;
+
+  for element in elements {
+    let block = write_block(element, context)?;
+    if block.is_empty() {
+      continue;
+    }
+    // Adjacent lists of the same kind would be merged into one list
+    let ordered = match &element.element {
+      Element::List(list_type) => Some(matches!(list_type, ListType::Number | ListType::Callout)),
+      _ => None,
+    };
+    if ordered.is_some() && ordered == previous {
+      blocks.push("<!-- -->".to_string());
+    }
+    previous = ordered;
+    blocks.push(block);
+  }
+
+  Ok(blocks.join("\n\n"))
+}
+
+fn write_block(input: &ElementSpan, context: &mut Context) -> Result<String> {
+  let block = match &input.element {
+    Element::Section { .. } => write_blocks(&input.children, context)?,
+    Element::Title { level } => {
+      let text = if input.children.is_empty() {
+        escape_text(input.get_attribute("name").unwrap_or(""))
+      } else {
+        write_inlines(&input.children, context)?
+      };
+      format!("{} {}", "#".repeat((*level).clamp(1, 6) as usize), text)
+    }
+    Element::Paragraph => {
+      let mut text = escape_line_starts(&write_inlines(&input.children, context)?);
+      // The anchor is kept inside of the paragraph
+      if let Some(id) = input.get_attribute("anchor") {
+        text = format!("{}{}", anchor(id), text);
+      }
+      with_title(input.get_attribute("title"), text)
+    }
+    Element::List(list_type) => with_anchor(
+      input,
+      with_title(
+        input.get_attribute("title"),
+        write_list(list_type, input, context)?,
+      ),
+    ),
+    Element::ListItem(_) => write_blocks(&input.children, context)?,
+    Element::TypedBlock { kind } => with_anchor(input, write_typed_block(kind, input, context)?),
+    Element::Table => with_anchor(
+      input,
+      with_title(input.get_attribute("title"), write_table(input, context)?),
+    ),
+    Element::Image => {
+      let path = input.get_attribute("path").unwrap_or("");
+      let alt = positional_attribute(input, 0).unwrap_or(path);
+      with_anchor(
+        input,
+        with_title(
+          input.get_attribute("title"),
+          format!("![{}]({})", escape_text(alt), destination(path)),
+        ),
+      )
+    }
+    Element::Anchor => match input.get_attribute("anchor") {
+      Some(id) => anchor(id),
+      None => String::new(),
+    },
+    Element::IncludeElement(include) => write_blocks(&include.inner.elements, context)?,
+    // Attribute entries are already resolved by the reader
+    Element::Attribute(_) => String::new(),
+    // The Markdown renderers generate their own table of contents
+    Element::TableOfContents => String::new(),
+    Element::Comment => String::new(),
+    Element::ExternalContent => {
+      warn!("external content is not supported in Markdown");
+      String::new()
+    }
+    Element::Error(msg) => {
+      warn!("{}", msg);
+      format!("<!-- ERROR: {} -->", msg.replace("--", "- -"))
+    }
+    Element::Styled
+    | Element::Text
+    | Element::XRef
+    | Element::Link
+    | Element::Footnote
+    | Element::InlineImage
+    | Element::Keyboard
+    | Element::Button
+    | Element::Menu
+    | Element::InlinePassthrough
+    | Element::Stem
+    | Element::IndexTerm
+    | Element::LineBreak
+    | Element::Callout(_)
+    | Element::TableRow
+    | Element::TableCell => return Err(AsciidoctrineError::MalformedAst),
+  };
+
+  Ok(block)
+}
+
+fn write_typed_block(
+  kind: &BlockType,
+  input: &ElementSpan,
+  context: &mut Context,
+) -> Result<String> {
+  let title = input.get_attribute("title");
+  let content = input.get_attribute("content").unwrap_or(&input.content);
+
+  let block = match kind {
+    BlockType::Comment => String::new(),
+    BlockType::Passtrough => content.to_string(),
+    BlockType::Listing => {
+      let language = match (
+        positional_attribute(input, 0),
+        positional_attribute(input, 1),
+      ) {
+        (Some("source"), language) => language,
+        _ => None,
+      };
+      with_title(title, fenced(content, language))
+    }
+    BlockType::Literal => with_title(title, fenced(content, None)),
+    BlockType::Sidebar => {
+      warn!("sidebars have no equivalent in Markdown and are written as quotes");
+      quote(&with_title(title, write_blocks(&input.children, context)?))
+    }
+    BlockType::Example => {
+      warn!("examples have no equivalent in Markdown and are written as plain blocks");
+      with_title(title, write_blocks(&input.children, context)?)
+    }
+    BlockType::Open => with_title(title, write_blocks(&input.children, context)?),
+    BlockType::Quote | BlockType::Verse => {
+      let text = if kind == &BlockType::Verse {
+        // A verse keeps its line breaks
+        escape_line_starts(&escape_text(content.trim()).replace('\n', "\\\n"))
+      } else {
+        write_blocks(&input.children, context)?
+      };
+      // The attribution is given as `[quote, author, source]`
+      let author = input
+        .get_attribute("attribution")
+        .or_else(|| positional_attribute(input, 1));
+      let citetitle = input
+        .get_attribute("citetitle")
+        .or_else(|| positional_attribute(input, 2));
+      let attribution = match (author, citetitle) {
+        (Some(author), Some(citetitle)) => {
+          format!(
+            "&#8212; {}, *{}*",
+            escape_text(author),
+            escape_text(citetitle)
+          )
+        }
+        (Some(author), None) => format!("&#8212; {}", escape_text(author)),
+        (None, Some(citetitle)) => format!("&#8212; *{}*", escape_text(citetitle)),
+        (None, None) => String::new(),
+      };
+      quote(&join_blocks(vec![with_title(title, text), attribution]))
+    }
+    BlockType::Admonition(admonition) => {
+      warn!(
+        "admonitions have no equivalent in Markdown and are written as quotes ({})",
+        admonition.keyword()
+      );
+      let text = write_blocks(&input.children, context)?;
+      let text = match title {
+        Some(title) => join_blocks(vec![
+          format!("**{}: {}**", admonition.caption(), escape_text(title)),
+          text,
+        ]),
+        // The caption leads the first paragraph
+        None => match input.children.first() {
+          Some(first) if first.element == Element::Paragraph => {
+            format!("**{}:** {}", admonition.caption(), text)
+          }
+          _ => join_blocks(vec![format!("**{}:**", admonition.caption()), text]),
+        },
+      };
+      quote(&text)
+    }
+  };
+
+  Ok(block)
+}
+
+fn write_list(list_type: &ListType, input: &ElementSpan, context: &mut Context) -> Result<String> {
+  if list_type == &ListType::Description {
+    warn!("description lists have no equivalent in Markdown and are written as bullet lists");
+  }
+  let start = input
+    .get_attribute("start")
+    .and_then(|start| start.trim().parse().ok())
+    .unwrap_or(1);
+
+  let mut items = Vec::new();
+  for (index, item) in input.children.iter().enumerate() {
+    let marker = match list_type {
+      ListType::Bullet | ListType::Checklist | ListType::Description => "-".to_string(),
+      ListType::Number => format!("{}.", start + index),
+      ListType::Callout => format!("{}.", item.get_attribute("callout").unwrap_or("1")),
+    };
+    let lead = match (item.get_attribute("checked"), item.get_attribute("term")) {
+      (Some("true"), _) => "[x] ".to_string(),
+      (Some(_), _) => "[ ] ".to_string(),
+      (None, Some(term)) if list_type == &ListType::Description => {
+        format!("**{}**: ", escape_text(term))
+      }
+      (None, _) => String::new(),
+    };
+    items.push(write_list_item(&marker, &lead, item, context)?);
+  }
+
+  Ok(items.join("\n"))
+}
+
+fn write_list_item(
+  marker: &str,
+  lead: &str,
+  item: &ElementSpan,
+  context: &mut Context,
+) -> Result<String> {
+  // The content is aligned with the text after the marker
+  let indent = " ".repeat(marker.len() + 1);
+  let mut children = item.children.iter().peekable();
+
+  let mut text = lead.to_string();
+  if let Some(paragraph) = children.next_if(|child| {
+    child.element == Element::Paragraph
+      && child.get_attribute("title").is_none()
+      && child.get_attribute("anchor").is_none()
+  }) {
+    text.push_str(&escape_line_starts(&write_inlines(
+      &paragraph.children,
+      context,
+    )?));
+  }
+  // The first line follows the marker
+  let mut out = format!(
+    "{} {}",
+    marker,
+    indent_lines(text.trim_end(), &indent).trim_start()
+  )
+  .trim_end()
+  .to_string();
+
+  for child in children {
+    let block = write_block(child, context)?;
+    if block.is_empty() {
+      continue;
+    }
+    // Nested lists are kept tight, other blocks need a blank line
+    match child.element {
+      Element::List(_) => out.push('\n'),
+      _ => out.push_str("\n\n"),
+    }
+    out.push_str(&indent_lines(&block, &indent));
+  }
+
+  Ok(out)
+}
+
+fn write_table(input: &ElementSpan, context: &mut Context) -> Result<String> {
+  let mut rows = Vec::new();
+  for row in input.children.iter() {
+    if row.element != Element::TableRow {
+      return Err(AsciidoctrineError::MalformedAst);
+    }
+    let mut cells = Vec::new();
+    for cell in row.children.iter() {
+      if cell.element != Element::TableCell {
+        return Err(AsciidoctrineError::MalformedAst);
+      }
+      cells.push(write_table_cell(cell, context)?);
+    }
+    rows.push(cells);
+  }
+  let columns = rows.iter().map(Vec::len).max().unwrap_or(0);
+  if columns == 0 {
+    return Ok(String::new());
+  }
+
+  // GFM tables always start with a header row
+  let mut lines = Vec::new();
+  for (index, row) in rows.iter_mut().enumerate() {
+    row.resize(columns, String::new());
+    lines.push(format!("| {} |", row.join(" | ")));
+    if index == 0 {
+      lines.push(format!("|{}", " --- |".repeat(columns)));
+    }
+  }
+
+  Ok(lines.join("\n"))
+}
+
+/// The content of a cell has to fit on one line
+fn write_table_cell(cell: &ElementSpan, context: &mut Context) -> Result<String> {
+  let mut blocks = Vec::new();
+  for element in cell.children.iter() {
+    let block = write_block(element, context)?;
+    if !block.is_empty() {
+      blocks.push(block.replace('\n', " "));
+    }
+  }
+
+  Ok(blocks.join("<br><br>").replace('|', "\\|"))
+}
+
+fn write_inlines(elements: &[ElementSpan], context: &mut Context) -> Result<String> {
+  let mut out = String::new();
+  for element in elements {
+    out.push_str(&write_inline(element, context)?);
+  }
+
+  Ok(out)
+}
+
+fn write_inline(input: &ElementSpan, context: &mut Context) -> Result<String> {
+  let inline = match &input.element {
+    Element::Text => escape_text(input.get_attribute("content").unwrap_or(&input.content)),
+    // The following text starts with the line break
+    Element::LineBreak => "\\".to_string(),
+    Element::Styled => {
+      let content = input.get_attribute("content").unwrap_or("");
+      match input.get_attribute("style").unwrap_or("") {
+        "strong" => format!("**{}**", escape_text(content)),
+        "em" => format!("*{}*", escape_text(content)),
+        "monospaced" => code_span(content),
+        "mark" => format!("<mark>{}</mark>", escape_text(content)),
+        "sup" => format!("<sup>{}</sup>", escape_text(content)),
+        "sub" => format!("<sub>{}</sub>", escape_text(content)),
+        "double_quoted" => format!("\u{201c}{}\u{201d}", escape_text(content)),
+        "single_quoted" => format!("\u{2018}{}\u{2019}", escape_text(content)),
+        _ => escape_text(content),
+      }
+    }
+    Element::Link => {
+      let url = input.get_attribute("url").unwrap_or("");
+      match positional_attribute(input, 0) {
+        Some(content) => format!("[{}]({})", escape_text(content), destination(url)),
+        // Urls and mail addresses without a text are autolinks
+        None if !url.contains(char::is_whitespace) && !url.contains(['<', '>']) => {
+          format!("<{}>", url.trim_start_matches("mailto:"))
+        }
+        None => format!("[{}]({})", escape_text(url), destination(url)),
+      }
+    }
+    Element::XRef => {
+      let id = input.get_attribute("id");
+      let content = input.get_attribute("content");
+      match (input.get_attribute("path"), id) {
+        (Some(path), id) => {
+          let href = match id {
+            Some(id) => format!("{}#{}", markdown_path(path), id),
+            None => markdown_path(path),
+          };
+          format!(
+            "[{}]({})",
+            escape_text(content.or(id).unwrap_or(path)),
+            destination(&href)
+          )
+        }
+        (None, id) => {
+          let id = id.unwrap_or("");
+          // Titles are linked by the slugs of their headings
+          let (slug, name) = match context.titles.get(id) {
+            Some((slug, name)) => (slug.as_str(), name.as_str()),
+            None => (id, id),
+          };
+          format!(
+            "[{}](#{})",
+            escape_text(content.unwrap_or(name)),
+            destination(slug)
+          )
+        }
+      }
+    }
+    Element::Footnote => format!("[^{}]", context.footnote(input)),
+    Element::InlineImage => {
+      let path = input.get_attribute("path").unwrap_or("");
+      let alt = positional_attribute(input, 0).unwrap_or(path);
+      format!("![{}]({})", escape_text(alt), destination(path))
+    }
+    Element::Keyboard => input
+      .positional_attributes
+      .iter()
+      .map(|key| format!("<kbd>{}</kbd>", escape_text(key.as_str())))
+      .collect::<Vec<_>>()
+      .join("+"),
+    Element::Button => format!(
+      "**[{}]**",
+      escape_text(input.get_attribute("content").unwrap_or(""))
+    ),
+    Element::Menu => {
+      let mut items = vec![input.get_attribute("menu").unwrap_or("")];
+      items.extend(input.positional_attributes.iter().map(|item| item.as_str()));
+      format!("**{}**", escape_text(&items.join(" \u{203a} ")))
+    }
+    Element::InlinePassthrough => input.get_attribute("content").unwrap_or("").to_string(),
+    Element::Stem => {
+      let content = input.get_attribute("content").unwrap_or("");
+      match input.get_attribute("notation") {
+        Some("latexmath") => format!("${}$", content),
+        _ => {
+          warn!("only latexmath is supported as math in Markdown");
+          code_span(content)
+        }
+      }
+    }
+    Element::IndexTerm => {
+      // Concealed terms only appear in the index
+      match (
+        input.get_attribute("visible"),
+        input.positional_attributes.first(),
+      ) {
+        (Some("true"), Some(term)) => escape_text(term.as_str().trim()),
+        _ => String::new(),
+      }
+    }
+    Element::Anchor => input
+      .get_attribute("anchor")
+      .map(anchor)
+      .unwrap_or_default(),
+    // Callouts are written as part of the listing content
+    Element::Callout(_) | Element::Comment => String::new(),
+    _ => return Err(AsciidoctrineError::MalformedAst),
+  };
+
+  Ok(inline)
+}
+
+// Helper Functions
+//----------------------------------------------------
+
+fn join_blocks(blocks: Vec<String>) -> String {
+  blocks
+    .into_iter()
+    .filter(|block| !block.is_empty())
+    .collect::<Vec<_>>()
+    .join("\n\n")
+}
+
+/// Puts the title of a block in front of it
+fn with_title(title: Option<&str>, block: String) -> String {
+  match title {
+    Some(title) => join_blocks(vec![format!("**{}**", escape_text(title)), block]),
+    None => block,
+  }
+}
+
+/// Puts the anchor of a block in front of it
+fn with_anchor(input: &ElementSpan, block: String) -> String {
+  match input.get_attribute("anchor") {
+    Some(id) => join_blocks(vec![anchor(id), block]),
+    None => block,
+  }
+}
+
+fn anchor(id: &str) -> String {
+  format!("<a id=\"{}\"></a>", id.replace('"', "&quot;"))
+}
+
+/// Writes the content as a fenced code block. The fence is longer
+/// than every run of backticks in the content.
+fn fenced(content: &str, language: Option<&str>) -> String {
+  let fence = "`".repeat(longest_backtick_run(content).max(2) + 1);
+  format!(
+    "{}{}\n{}\n{}",
+    fence,
+    language.unwrap_or(""),
+    content.trim_end_matches('\n'),
+    fence
+  )
+}
+
+fn code_span(content: &str) -> String {
+  let fence = "`".repeat(longest_backtick_run(content) + 1);
+  // A backtick at the border would be part of the fence
+  if content.starts_with('`') || content.ends_with('`') {
+    format!("{0} {1} {0}", fence, content)
+  } else {
+    format!("{0}{1}{0}", fence, content)
+  }
+}
+
+fn longest_backtick_run(content: &str) -> usize {
+  content.split(|c| c != '`').map(str::len).max().unwrap_or(0)
+}
+
+fn quote(text: &str) -> String {
+  text
+    .split('\n')
+    .map(|line| match line {
+      "" => ">".to_string(),
+      line => format!("> {}", line),
+    })
+    .collect::<Vec<_>>()
+    .join("\n")
+}
+
+fn indent_lines(text: &str, indent: &str) -> String {
+  text
+    .split('\n')
+    .map(|line| match line {
+      "" => String::new(),
+      line => format!("{}{}", indent, line),
+    })
+    .collect::<Vec<_>>()
+    .join("\n")
+}
+
+/// The destination of a link. Destinations with spaces or
+/// parentheses have to be enclosed in pointy brackets.
+fn destination(url: &str) -> String {
+  if url.contains([' ', '(', ')']) {
+    format!("<{}>", url.replace('<', "%3C").replace('>', "%3E"))
+  } else {
+    url.to_string()
+  }
+}
+
+/// GitHub derives the ids of the headings from their text
+fn heading_slug(name: &str) -> String {
+  name
+    .trim()
+    .to_lowercase()
+    .chars()
+    .filter(|c| c.is_alphanumeric() || [' ', '-', '_'].contains(c))
+    .map(|c| if c == ' ' { '-' } else { c })
+    .collect()
+}
+
+fn positional_attribute<'a>(input: &'a ElementSpan, index: usize) -> Option<&'a str> {
+  input
+    .positional_attributes
+    .get(index)
+    .map(|value| value.as_str().trim())
+    .filter(|value| !value.is_empty())
+}
+
+/// The path of the Markdown document generated from an asciidoc document
+fn markdown_path(path: &str) -> String {
+  match path.strip_suffix(".adoc") {
+    Some(path) => format!("{}.md", path),
+    None => path.to_string(),
+  }
+}
+
+/// Escapes the characters with a meaning in inline Markdown.
+/// Character references in the text are kept.
+fn escape_text(input: &str) -> String {
+  let mut out = String::with_capacity(input.len());
+  for c in input.chars() {
+    if ['\\', '`', '*', '_', '[', ']', '<', '>'].contains(&c) {
+      out.push('\\');
+    }
+    out.push(c);
+  }
+  out
+}
+
+/// Escapes the characters at the start of a line which would start
+/// another block (e.g. a heading or a list)
+fn escape_line_starts(text: &str) -> String {
+  text
+    .split('\n')
+    .map(|line| {
+      let trimmed = line.trim_start();
+      let indent = &line[..line.len() - trimmed.len()];
+      let digits = trimmed.chars().take_while(char::is_ascii_digit).count();
+      if trimmed.starts_with(['#', '-', '+', '=', '|']) {
+        format!("{}\\{}", indent, trimmed)
+      } else if digits > 0 && trimmed[digits..].starts_with(['.', ')']) {
+        format!("{}{}\\{}", indent, &trimmed[..digits], &trimmed[digits..])
+      } else {
+        line.to_string()
+      }
+    })
+    .collect::<Vec<_>>()
+    .join("\n")
+}
@@ -1,4 +1,14 @@
 pub mod html;
+pub mod chunked;
+mod highlight;
+mod text;
+pub mod docbook;
+pub mod pdf;
 pub mod docx;
+pub mod markdown;
+pub mod latex;
+pub mod epub;
+pub mod manpage;
+pub mod revealjs;
 pub mod json;
 pub mod asciidoc;
@@ -0,0 +1,2192 @@
+pub use crate::ast::*;
+use crate::sections::number_titles;
+use crate::writer::text::decode_references;
+use crate::{options, AsciidoctrineError, Result};
+use anyhow::Context;
+use flate2::{write::ZlibEncoder, Compression};
+use std::cell::RefCell;
+use std::collections::{BTreeSet, HashMap};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+// The pages are A4 with margins of 2cm (in points)
+const PAGE_WIDTH: f32 = 595.28;
+const PAGE_HEIGHT: f32 = 841.89;
+const MARGIN: f32 = 56.69;
+const CONTENT_WIDTH: f32 = PAGE_WIDTH - 2.0 * MARGIN;
+
+const FONT_SIZE: f32 = 10.5;
+const CODE_SIZE: f32 = 9.0;
+const LEADING: f32 = 1.4;
+const INDENT: f32 = 18.0;
+const PADDING: f32 = 4.0;
+const BLOCK_SPACING: f32 = 6.0;
+
+const TEXT_COLOR: [f32; 3] = [0.13, 0.13, 0.13];
+const LINK_COLOR: [f32; 3] = [0.16, 0.36, 0.63];
+const ERROR_COLOR: [f32; 3] = [0.8, 0.1, 0.1];
+
+thread_local! {
+  /// The characters of the document which the fonts can't show
+  static MISSING_GLYPHS: RefCell<BTreeSet<char>> = const { RefCell::new(BTreeSet::new()) };
+}
+
+/// Writes a PDF document with the fonts built into every PDF reader
+///
+/// The offsets of the objects are taken from the output, that's why
+/// it has to be seekable.
+#[derive(Default)]
+pub struct PdfWriter {}
+
+impl PdfWriter {
+  pub fn new() -> Self {
+    PdfWriter {}
+  }
+}
+
+impl<T: io::Write + io::Seek> crate::Writer<T> for PdfWriter {
+  fn write<'a>(&mut self, ast: AST, args: &options::Opts, mut out: T) -> Result<()> {
+    let ast = number_titles(ast);
+
+    let mut base = args
+      .input
+      .as_ref()
+      .and_then(|input| input.parent())
+      .map(Path::to_path_buf)
+      .unwrap_or_default();
+    if let Some(imagesdir) = ast.get_attribute("imagesdir") {
+      base.push(imagesdir);
+    }
+
+    MISSING_GLYPHS.with(|missing| missing.borrow_mut().clear());
+    let mut layout = Layout::new(base);
+    collect_titles(&ast.elements, &mut layout.titles);
+    layout.document(&ast)?;
+    if let Some(message) = MISSING_GLYPHS.with(|missing| missing_glyphs_message(&missing.borrow()))
+    {
+      warn!("{}", message);
+    }
+    let pages = paginate(&layout.items);
+    write_document(&ast, &pages, &layout.images, &mut out)?;
+    out.flush()?;
+
+    Ok(())
+  }
+}
+
+// Fonts
+//----------------------------------------------------
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Font {
+  Regular,
+  Bold,
+  Italic,
+  BoldItalic,
+  Mono,
+  MonoBold,
+}
+
+impl Font {
+  const ALL: [Font; 6] = [
+    Font::Regular,
+    Font::Bold,
+    Font::Italic,
+    Font::BoldItalic,
+    Font::Mono,
+    Font::MonoBold,
+  ];
+
+  fn base_font(self) -> &'static str {
+    match self {
+      Font::Regular => "Helvetica",
+      Font::Bold => "Helvetica-Bold",
+      Font::Italic => "Helvetica-Oblique",
+      Font::BoldItalic => "Helvetica-BoldOblique",
+      Font::Mono => "Courier",
+      Font::MonoBold => "Courier-Bold",
+    }
+  }
+
+  /// The name of the font in the page resources
+  fn resource(self) -> usize {
+    Font::ALL.iter().position(|font| *font == self).unwrap_or(0) + 1
+  }
+
+  fn bold(self) -> Font {
+    match self {
+      Font::Regular => Font::Bold,
+      Font::Italic => Font::BoldItalic,
+      Font::Mono => Font::MonoBold,
+      font => font,
+    }
+  }
+
+  fn italic(self) -> Font {
+    match self {
+      Font::Regular => Font::Italic,
+      Font::Bold => Font::BoldItalic,
+      font => font,
+    }
+  }
+
+  /// The width of a WinAnsi encoded character in thousandths of
+  /// the font size
+  fn width(self, c: u8) -> f32 {
+    if c < 32 {
+      return 0.0;
+    }
+    let index = (c - 32) as usize;
+    match self {
+      Font::Regular | Font::Italic => HELVETICA_WIDTHS[index] as f32,
+      Font::Bold | Font::BoldItalic => HELVETICA_BOLD_WIDTHS[index] as f32,
+      Font::Mono | Font::MonoBold => 600.0,
+    }
+  }
+}
+
+/// Maps a character to the WinAnsiEncoding of the built in fonts
+fn encode(c: char) -> Option<u8> {
+  Some(match c {
+    ' '..='~' | '\u{a0}'..='\u{ff}' => c as u32 as u8,
+    '\t' => b' ',
+    '\u{20ac}' => 0x80,
+    '\u{201a}' => 0x82,
+    '\u{0192}' => 0x83,
+    '\u{201e}' => 0x84,
+    '\u{2026}' => 0x85,
+    '\u{2020}' => 0x86,
+    '\u{2021}' => 0x87,
+    '\u{02c6}' => 0x88,
+    '\u{2030}' => 0x89,
+    '\u{0160}' => 0x8a,
+    '\u{2039}' => 0x8b,
+    '\u{0152}' => 0x8c,
+    '\u{017d}' => 0x8e,
+    '\u{2018}' => 0x91,
+    '\u{2019}' => 0x92,
+    '\u{201c}' => 0x93,
+    '\u{201d}' => 0x94,
+    '\u{2022}' => 0x95,
+    '\u{2013}' => 0x96,
+    '\u{2014}' => 0x97,
+    '\u{02dc}' => 0x98,
+    '\u{2122}' => 0x99,
+    '\u{0161}' => 0x9a,
+    '\u{203a}' => 0x9b,
+    '\u{0153}' => 0x9c,
+    '\u{017e}' => 0x9e,
+    '\u{0178}' => 0x9f,
+    _ => return None,
+  })
+}
+
+/// Encodes a character and remembers it if the fonts can't show it
+fn glyph(c: char) -> u8 {
+  encode(c).unwrap_or_else(|| {
+    MISSING_GLYPHS.with(|missing| missing.borrow_mut().insert(c));
+    b'?'
+  })
+}
+
+/// Names the characters without a glyph in the fonts
+fn missing_glyphs_message(missing: &BTreeSet<char>) -> Option<String> {
+  if missing.is_empty() {
+    return None;
+  }
+  let characters: Vec<String> = missing
+    .iter()
+    .map(|c| format!("'{}' (U+{:04X})", c, *c as u32))
+    .collect();
+  Some(format!(
+    "the PDF fonts can't show the characters {}, they are replaced by '?'",
+    characters.join(", ")
+  ))
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Style {
+  font: Font,
+  size: f32,
+  color: [f32; 3],
+  /// The offset of the baseline for super- and subscripts
+  rise: f32,
+}
+
+impl Style {
+  fn new(font: Font, size: f32) -> Self {
+    Style {
+      font,
+      size,
+      color: TEXT_COLOR,
+      rise: 0.0,
+    }
+  }
+
+  fn width(&self, text: &[u8]) -> f32 {
+    text.iter().map(|c| self.font.width(*c)).sum::<f32>() * self.size / 1000.0
+  }
+}
+
+// Layout
+//----------------------------------------------------
+
+#[derive(Clone, Debug, PartialEq)]
+enum Target {
+  Uri(String),
+  Anchor(String),
+}
+
+/// A piece of inline text with the same formatting
+#[derive(Clone, Debug)]
+struct Span {
+  text: String,
+  style: Style,
+  link: Option<Target>,
+  /// An id which should point to the text
+  anchor: Option<String>,
+}
+
+impl Span {
+  fn new(text: &str, style: Style) -> Self {
+    Span {
+      text: text.to_string(),
+      style,
+      link: None,
+      anchor: None,
+    }
+  }
+}
+
+#[derive(Debug)]
+struct Fragment {
+  /// The position relative to the start of the line
+  x: f32,
+  text: Vec<u8>,
+  style: Style,
+  width: f32,
+  link: Option<Target>,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Fill {
+  x: f32,
+  width: f32,
+  gray: f32,
+}
+
+#[derive(Debug, Default)]
+struct Line {
+  x: f32,
+  height: f32,
+  /// The distance of the baseline from the top of the line
+  baseline: f32,
+  fragments: Vec<Fragment>,
+  anchors: Vec<String>,
+  fill: Option<Fill>,
+  /// Headings and titles shouldn't end up alone at the end of a page
+  keep_with_next: bool,
+}
+
+#[derive(Debug)]
+struct Cell {
+  x: f32,
+  width: f32,
+  items: Vec<Item>,
+}
+
+#[derive(Debug)]
+struct Row {
+  height: f32,
+  cells: Vec<Cell>,
+}
+
+#[derive(Debug)]
+struct ImageBox {
+  x: f32,
+  width: f32,
+  height: f32,
+  image: usize,
+}
+
+#[derive(Debug)]
+enum Marker {
+  Anchor(String),
+  Outline { level: u32, title: String },
+}
+
+/// The laid out content which is distributed onto the pages
+#[derive(Debug)]
+enum Item {
+  Line(Line),
+  Row(Row),
+  Image(ImageBox),
+  Space(f32),
+  /// Points to the position of the next item
+  Marker(Marker),
+}
+
+impl Item {
+  fn height(&self) -> f32 {
+    match self {
+      Item::Line(line) => line.height,
+      Item::Row(row) => row.height,
+      Item::Image(image) => image.height,
+      Item::Space(height) => *height,
+      Item::Marker(_) => 0.0,
+    }
+  }
+}
+
+/// An image as it is embedded into the PDF
+struct Image {
+  width: u32,
+  height: u32,
+  color_space: &'static str,
+  filter: &'static str,
+  data: Vec<u8>,
+  /// The compressed alpha channel
+  alpha: Option<Vec<u8>>,
+}
+
+struct Layout {
+  items: Vec<Item>,
+  images: Vec<Image>,
+  image_paths: HashMap<PathBuf, usize>,
+  footnotes: Vec<Vec<Span>>,
+  /// The titles of the sections for cross references
+  titles: HashMap<String, String>,
+  /// The directory of the images
+  base: PathBuf,
+}
+
+impl Layout {
+  fn new(base: PathBuf) -> Self {
+    Layout {
+      items: Vec::new(),
+      images: Vec::new(),
+      image_paths: HashMap::new(),
+      footnotes: Vec::new(),
+      titles: HashMap::new(),
+      base,
+    }
+  }
+
+  fn document(&mut self, ast: &AST) -> Result<()> {
+    let mut elements = ast.elements.iter().peekable();
+    if ast.header.title.is_some() {
+      if let Some(title) = elements.next_if(|title| title.element == Element::Title { level: 1 }) {
+        self.document_title(title, &ast.header);
+      }
+    }
+    for element in elements {
+      self.block(element, MARGIN, CONTENT_WIDTH)?;
+    }
+    self.footnotes();
+
+    Ok(())
+  }
+
+  fn document_title(&mut self, title: &ElementSpan, header: &DocumentHeader) {
+    let name = title.get_attribute("name").unwrap_or("");
+    let style = Style::new(Font::Bold, 22.0);
+    self.lines(&[Span::new(name, style)], MARGIN, CONTENT_WIDTH, false);
+
+    let details = Style::new(Font::Italic, 9.5);
+    for author in header.authors.iter() {
+      let text = match &author.email {
+        Some(email) => format!("{} <{}>", author.name, email),
+        None => author.name.clone(),
+      };
+      self.lines(&[Span::new(&text, details)], MARGIN, CONTENT_WIDTH, false);
+    }
+    if let Some(revision) = &header.revision {
+      let mut text = String::new();
+      if let Some(number) = &revision.number {
+        text.push_str(&format!("Version {}", number));
+      }
+      if let Some(date) = &revision.date {
+        if !text.is_empty() {
+          text.push_str(", ");
+        }
+        text.push_str(date);
+      }
+      if let Some(remark) = &revision.remark {
+        text.push_str(&format!(": {}", remark));
+      }
+      self.lines(&[Span::new(&text, details)], MARGIN, CONTENT_WIDTH, false);
+    }
+    self.items.push(Item::Space(3.0 * BLOCK_SPACING));
+  }
+
+  fn block(&mut self, input: &ElementSpan, x: f32, width: f32) -> Result<()> {
+    if let Some(id) = input.get_attribute("anchor") {
+      self.marker(Marker::Anchor(id.to_string()));
+    }
+
+    match &input.element {
+      Element::Title { level } => {
+        let name = input.get_attribute("name").unwrap_or("");
+        let title = match input.get_attribute("sectnum") {
+          Some(sectnum) => format!("{} {}", sectnum, name),
+          None => name.to_string(),
+        };
+        let size = match level {
+          1 => 20.0,
+          2 => 17.0,
+          3 => 14.0,
+          4 => 12.0,
+          _ => 11.0,
+        };
+        if let Some(id) = input.get_attribute("id") {
+          self.marker(Marker::Anchor(id.to_string()));
+        }
+        self.marker(Marker::Outline {
+          level: *level,
+          title: title.clone(),
+        });
+        self.items.push(Item::Space(size * 0.6));
+        self.heading(&[Span::new(&title, Style::new(Font::Bold, size))], x, width);
+        self.items.push(Item::Space(BLOCK_SPACING));
+      }
+      Element::Section { .. } | Element::ListItem(_) => {
+        for element in input.children.iter() {
+          self.block(element, x, width)?;
+        }
+      }
+      Element::Paragraph => {
+        self.block_title(input, x, width);
+        let spans = self.inlines(&input.children, Style::new(Font::Regular, FONT_SIZE), false)?;
+        self.lines(&spans, x, width, false);
+        self.items.push(Item::Space(BLOCK_SPACING));
+      }
+      Element::List(list_type) => self.list(list_type, input, x, width)?,
+      Element::TypedBlock { kind } => self.typed_block(kind, input, x, width)?,
+      Element::Table => self.table(input, x, width)?,
+      Element::Image => {
+        self.image(input, x, width);
+        if let Some(title) = input.get_attribute("title") {
+          let style = Style::new(Font::Italic, FONT_SIZE);
+          self.lines(&[Span::new(title, style)], x, width, false);
+        }
+        self.items.push(Item::Space(BLOCK_SPACING));
+      }
+      // The anchor is already set above
+      Element::Anchor => (),
+      Element::IncludeElement(include) => {
+        for element in include.inner.elements.iter() {
+          self.block(element, x, width)?;
+        }
+      }
+      // The outline of the PDF replaces the table of contents
+      Element::TableOfContents => (),
+      Element::Attribute(_) | Element::Comment => (),
+      Element::ExternalContent => {
+        warn!("external content is not supported in PDF");
+      }
+      Element::Error(msg) => {
+        warn!("{}", msg);
+        let mut style = Style::new(Font::Italic, FONT_SIZE);
+        style.color = ERROR_COLOR;
+        self.lines(&[Span::new(msg, style)], x, width, false);
+        self.items.push(Item::Space(BLOCK_SPACING));
+      }
+      Element::Styled
+      | Element::Text
+      | Element::XRef
+      | Element::Link
+      | Element::Footnote
+      | Element::InlineImage
+      | Element::Keyboard
+      | Element::Button
+      | Element::Menu
+      | Element::InlinePassthrough
+      | Element::Stem
+      | Element::IndexTerm
+      | Element::LineBreak
+      | Element::Callout(_)
+      | Element::TableRow
+      | Element::TableCell => return Err(AsciidoctrineError::MalformedAst),
+    }
+
+    Ok(())
+  }
+
+  fn typed_block(
+    &mut self,
+    kind: &BlockType,
+    input: &ElementSpan,
+    x: f32,
+    width: f32,
+  ) -> Result<()> {
+    let content = input.get_attribute("content").unwrap_or(&input.content);
+
+    match kind {
+      BlockType::Comment => (),
+      BlockType::Listing | BlockType::Literal | BlockType::Passtrough => {
+        self.block_title(input, x, width);
+        let style = Style::new(Font::Mono, CODE_SIZE);
+        let spans = match kind {
+          BlockType::Listing => self.listing_spans(content, input, style)?,
+          _ => vec![Span::new(content, style)],
+        };
+        let start = self.items.len();
+        self.items.push(Item::Space(PADDING));
+        self.lines(&spans, x + PADDING, width - 2.0 * PADDING, true);
+        self.items.push(Item::Space(PADDING));
+        self.fill(start, x, width, 0.95);
+        self.items.push(Item::Space(BLOCK_SPACING));
+      }
+      BlockType::Sidebar | BlockType::Example => {
+        let start = self.items.len();
+        self.items.push(Item::Space(PADDING));
+        self.block_title(input, x + PADDING, width - 2.0 * PADDING);
+        for element in input.children.iter() {
+          self.block(element, x + PADDING, width - 2.0 * PADDING)?;
+        }
+        self.trim_space();
+        self.items.push(Item::Space(PADDING));
+        let gray = if kind == &BlockType::Sidebar {
+          0.93
+        } else {
+          0.97
+        };
+        self.fill(start, x, width, gray);
+        self.items.push(Item::Space(BLOCK_SPACING));
+      }
+      BlockType::Open => {
+        self.block_title(input, x, width);
+        for element in input.children.iter() {
+          self.block(element, x, width)?;
+        }
+      }
+      BlockType::Quote | BlockType::Verse => {
+        self.block_title(input, x, width);
+        if kind == &BlockType::Verse {
+          let spans = [Span::new(content, Style::new(Font::Regular, FONT_SIZE))];
+          self.lines(&spans, x + INDENT, width - INDENT, true);
+          self.items.push(Item::Space(BLOCK_SPACING));
+        } else {
+          for element in input.children.iter() {
+            self.block(element, x + INDENT, width - INDENT)?;
+          }
+        }
+
+        // The attribution is given as `[quote, author, source]`
+        let attribution: Vec<&str> = [
+          input
+            .get_attribute("attribution")
+            .or_else(|| positional_attribute(input, 1)),
+          input
+            .get_attribute("citetitle")
+            .or_else(|| positional_attribute(input, 2)),
+        ]
+        .iter()
+        .flatten()
+        .copied()
+        .collect();
+        if !attribution.is_empty() {
+          let text = format!("\u{2014} {}", attribution.join(", "));
+          let style = Style::new(Font::Italic, FONT_SIZE * 0.9);
+          self.lines(
+            &[Span::new(&text, style)],
+            x + INDENT,
+            width - INDENT,
+            false,
+          );
+          self.items.push(Item::Space(BLOCK_SPACING));
+        }
+      }
+      BlockType::Admonition(admonition) => {
+        let label = 60.0;
+        let start = self.items.len();
+        self.items.push(Item::Space(PADDING));
+        self.block_title(input, x + label, width - label - PADDING);
+        for element in input.children.iter() {
+          self.block(element, x + label, width - label - PADDING)?;
+        }
+        self.trim_space();
+        self.items.push(Item::Space(PADDING));
+        self.label(
+          start,
+          admonition.keyword(),
+          Style::new(Font::Bold, FONT_SIZE),
+          x + PADDING,
+        );
+        self.fill(start, x, width, 0.96);
+        self.items.push(Item::Space(BLOCK_SPACING));
+      }
+    }
+
+    Ok(())
+  }
+
+  /// Converts the lines of a listing with the callout markers
+  fn listing_spans(
+    &mut self,
+    content: &str,
+    input: &ElementSpan,
+    style: Style,
+  ) -> Result<Vec<Span>> {
+    // With further substitutions the text is parsed into inline elements
+    if input
+      .children
+      .iter()
+      .any(|child| !matches!(child.element, Element::Callout(_)))
+    {
+      return self.inlines(&input.children, style, true);
+    }
+
+    let mut spans = Vec::new();
+    for (index, line) in content.split('\n').enumerate() {
+      if index > 0 {
+        spans.push(Span::new("\n", style));
+      }
+      let index = index.to_string();
+      let markers: Vec<&ElementSpan> = input
+        .children
+        .iter()
+        .filter(|callout| callout.get_attribute("line") == Some(index.as_str()))
+        .collect();
+      let code = markers
+        .first()
+        .and_then(|callout| callout.get_attribute("column"))
+        .and_then(|column| column.parse().ok())
+        .and_then(|column| line.get(..column))
+        .unwrap_or(line);
+      spans.push(Span::new(code.trim_end(), style));
+
+      for callout in markers {
+        if let Element::Callout(number) = callout.element {
+          spans.push(Span::new(
+            &format!(" ({})", number),
+            Style::new(Font::MonoBold, style.size),
+          ));
+        }
+      }
+    }
+
+    Ok(spans)
+  }
+
+  fn list(&mut self, list_type: &ListType, input: &ElementSpan, x: f32, width: f32) -> Result<()> {
+    self.block_title(input, x, width);
+
+    let level = match input.children.first().map(|item| &item.element) {
+      Some(Element::ListItem(level)) => (*level).max(1) as usize,
+      _ => 1,
+    };
+    let start: u32 = input
+      .get_attribute("start")
+      .and_then(|start| start.parse().ok())
+      .unwrap_or(1);
+    let label_style = Style::new(Font::Regular, FONT_SIZE);
+
+    let labels: Vec<String> = input
+      .children
+      .iter()
+      .enumerate()
+      .map(|(index, item)| match list_type {
+        ListType::Bullet => BULLETS[(level - 1) % BULLETS.len()].to_string(),
+        ListType::Checklist => match item.get_attribute("checked") {
+          Some("true") => "[x]".to_string(),
+          Some(_) => "[ ]".to_string(),
+          None => BULLETS[(level - 1) % BULLETS.len()].to_string(),
+        },
+        ListType::Number => {
+          let numeration = NUMERATIONS[(level - 1) % NUMERATIONS.len()];
+          format!("{}.", numeral(start + index as u32, numeration))
+        }
+        ListType::Callout => format!("({})", item.get_attribute("callout").unwrap_or("")),
+        ListType::Description => String::new(),
+      })
+      .collect();
+    let hang = labels
+      .iter()
+      .map(|label| label_style.width(&encode_str(label)) + 6.0)
+      .fold(INDENT, f32::max);
+
+    for (item, label) in input.children.iter().zip(labels.iter()) {
+      if list_type == &ListType::Description {
+        let term = item.get_attribute("term").unwrap_or("");
+        self.heading(
+          &[Span::new(term, Style::new(Font::Bold, FONT_SIZE))],
+          x,
+          width,
+        );
+        self.list_item(item, x + INDENT, width - INDENT)?;
+      } else {
+        let start = self.items.len();
+        self.list_item(item, x + hang, width - hang)?;
+        self.label(start, label, label_style, x);
+      }
+    }
+    self.items.push(Item::Space(BLOCK_SPACING));
+
+    Ok(())
+  }
+
+  fn list_item(&mut self, item: &ElementSpan, x: f32, width: f32) -> Result<()> {
+    if let Some(id) = item.get_attribute("anchor") {
+      self.marker(Marker::Anchor(id.to_string()));
+    }
+    for element in item.children.iter() {
+      self.block(element, x, width)?;
+    }
+    // The items of a list are closer together than paragraphs
+    self.trim_space();
+    self.items.push(Item::Space(BLOCK_SPACING / 2.0));
+
+    Ok(())
+  }
+
+  fn table(&mut self, input: &ElementSpan, x: f32, width: f32) -> Result<()> {
+    if let Some(title) = input.get_attribute("title") {
+      let style = Style::new(Font::Italic, FONT_SIZE);
+      self.heading(&[Span::new(title, style)], x, width);
+    }
+
+    let columns = input
+      .children
+      .iter()
+      .map(|row| row.children.len())
+      .max()
+      .unwrap_or(0)
+      .max(1);
+    let column_width = width / columns as f32;
+
+    for row in input.children.iter() {
+      if row.element != Element::TableRow {
+        return Err(AsciidoctrineError::MalformedAst);
+      }
+      let mut cells = Vec::new();
+      for (index, cell) in row.children.iter().enumerate() {
+        if cell.element != Element::TableCell {
+          return Err(AsciidoctrineError::MalformedAst);
+        }
+        let cell_x = x + index as f32 * column_width;
+        let saved = std::mem::take(&mut self.items);
+        for element in cell.children.iter() {
+          self.block(element, cell_x + PADDING, column_width - 2.0 * PADDING)?;
+        }
+        self.trim_space();
+        let items = std::mem::replace(&mut self.items, saved);
+        cells.push(Cell {
+          x: cell_x,
+          width: column_width,
+          items,
+        });
+      }
+      let height = cells
+        .iter()
+        .map(|cell| cell.items.iter().map(Item::height).sum::<f32>())
+        .fold(0.0, f32::max)
+        + 2.0 * PADDING;
+      self.items.push(Item::Row(Row { height, cells }));
+    }
+    self.items.push(Item::Space(BLOCK_SPACING));
+
+    Ok(())
+  }
+
+  fn image(&mut self, input: &ElementSpan, x: f32, width: f32) {
+    let path = input.get_attribute("path").unwrap_or("");
+    let alt = positional_attribute(input, 0).unwrap_or(path);
+
+    let image = if path.contains("://") {
+      warn!("remote image {} can't be embedded in PDF", path);
+      None
+    } else {
+      let path = self.base.join(path);
+      match self.image_paths.get(&path) {
+        Some(image) => Some(*image),
+        None => match load_image(&path) {
+          Ok(image) => {
+            self.images.push(image);
+            self.image_paths.insert(path, self.images.len() - 1);
+            Some(self.images.len() - 1)
+          }
+          Err(e) => {
+            warn!("couldn't embed image {} ({:#})", path.display(), e);
+            None
+          }
+        },
+      }
+    };
+
+    match image {
+      Some(index) => {
+        let image = &self.images[index];
+        let ratio = image.height as f32 / image.width.max(1) as f32;
+        // The size is given in pixels
+        let mut image_width = input
+          .get_attribute("width")
+          .or_else(|| positional_attribute(input, 1))
+          .and_then(|width| width.parse::<f32>().ok())
+          .unwrap_or(image.width as f32)
+          * 0.75;
+        image_width = image_width.min(width);
+        let max_height = (PAGE_HEIGHT - 2.0 * MARGIN) * 0.8;
+        if image_width * ratio > max_height {
+          image_width = max_height / ratio;
+        }
+        self.items.push(Item::Image(ImageBox {
+          x,
+          width: image_width,
+          height: image_width * ratio,
+          image: index,
+        }));
+      }
+      None => {
+        let style = Style::new(Font::Italic, FONT_SIZE);
+        self.lines(&[Span::new(&format!("[{}]", alt), style)], x, width, false);
+      }
+    }
+  }
+
+  fn footnotes(&mut self) {
+    if self.footnotes.is_empty() {
+      return;
+    }
+
+    self.items.push(Item::Space(2.0 * BLOCK_SPACING));
+    self.items.push(Item::Line(Line {
+      x: MARGIN,
+      height: 0.5,
+      fill: Some(Fill {
+        x: MARGIN,
+        width: CONTENT_WIDTH / 4.0,
+        gray: 0.6,
+      }),
+      ..Line::default()
+    }));
+    self.items.push(Item::Space(BLOCK_SPACING));
+
+    let style = Style::new(Font::Regular, FONT_SIZE * 0.85);
+    for (index, footnote) in std::mem::take(&mut self.footnotes).into_iter().enumerate() {
+      let number = index + 1;
+      let mut spans = vec![Span {
+        link: Some(Target::Anchor(format!("_footnoteref_{}", number))),
+        anchor: Some(format!("_footnotedef_{}", number)),
+        ..Span::new(&format!("[{}] ", number), style)
+      }];
+      spans.extend(footnote);
+      self.lines(&spans, MARGIN, CONTENT_WIDTH, false);
+    }
+  }
+
+  /// Converts the inline elements into formatted text
+  fn inlines(
+    &mut self,
+    elements: &[ElementSpan],
+    style: Style,
+    preserve: bool,
+  ) -> Result<Vec<Span>> {
+    let mut spans = Vec::new();
+
+    for element in elements {
+      match &element.element {
+        Element::Text => {
+          let content = element.get_attribute("content").unwrap_or(&element.content);
+          let content = decode_references(content);
+          let content = if preserve {
+            content
+          } else {
+            content.replace('\n', " ")
+          };
+          spans.push(Span::new(&content, style));
+        }
+        Element::LineBreak => spans.push(Span::new("\n", style)),
+        Element::Styled => {
+          let content = &decode_references(element.get_attribute("content").unwrap_or(""));
+          let mut styled = style;
+          let content = match element.get_attribute("style").unwrap_or("") {
+            "strong" => {
+              styled.font = style.font.bold();
+              content.to_string()
+            }
+            "em" => {
+              styled.font = style.font.italic();
+              content.to_string()
+            }
+            "monospaced" => {
+              styled.font = Font::Mono;
+              content.to_string()
+            }
+            "sup" => {
+              styled.size = style.size * 0.7;
+              styled.rise = style.size * 0.35;
+              content.to_string()
+            }
+            "sub" => {
+              styled.size = style.size * 0.7;
+              styled.rise = -style.size * 0.15;
+              content.to_string()
+            }
+            "double_quoted" => format!("\u{201c}{}\u{201d}", content),
+            "single_quoted" => format!("\u{2018}{}\u{2019}", content),
+            _ => content.to_string(),
+          };
+          spans.push(Span::new(&content, styled));
+        }
+        Element::Link => {
+          let url = element.get_attribute("url").unwrap_or("");
+          let content =
+            positional_attribute(element, 0).unwrap_or_else(|| url.trim_start_matches("mailto:"));
+          spans.push(link(content, style, Target::Uri(url.to_string())));
+        }
+        Element::XRef => {
+          let id = element.get_attribute("id");
+          let content = element.get_attribute("content");
+          match element.get_attribute("path") {
+            Some(path) => {
+              let path = match path.strip_suffix(".adoc") {
+                Some(path) => format!("{}.pdf", path),
+                None => path.to_string(),
+              };
+              let (uri, text) = match id {
+                Some(id) => (format!("{}#{}", path, id), content.unwrap_or(id)),
+                None => (path.clone(), content.unwrap_or(&path)),
+              };
+              spans.push(link(text, style, Target::Uri(uri)));
+            }
+            None => {
+              let id = id.unwrap_or("");
+              let text = match content.or_else(|| self.titles.get(id).map(String::as_str)) {
+                Some(text) => text.to_string(),
+                None => format!("[{}]", id),
+              };
+              spans.push(link(&text, style, Target::Anchor(id.to_string())));
+            }
+          }
+        }
+        Element::Footnote => {
+          let content = element.get_attribute("content").unwrap_or("");
+          let footnote_style = Style::new(Font::Regular, FONT_SIZE * 0.85);
+          self
+            .footnotes
+            .push(vec![Span::new(content, footnote_style)]);
+          let number = self.footnotes.len();
+          let mut marker = style;
+          marker.size = style.size * 0.7;
+          marker.rise = style.size * 0.35;
+          spans.push(Span {
+            anchor: Some(format!("_footnoteref_{}", number)),
+            ..link(
+              &format!("[{}]", number),
+              marker,
+              Target::Anchor(format!("_footnotedef_{}", number)),
+            )
+          });
+        }
+        Element::InlineImage => {
+          // Inline images are replaced by their alternative text
+          let alt = positional_attribute(element, 0)
+            .unwrap_or_else(|| element.get_attribute("path").unwrap_or(""));
+          spans.push(Span::new(&format!("[{}]", alt), style));
+        }
+        Element::Keyboard => {
+          let keys: Vec<&str> = element
+            .positional_attributes
+            .iter()
+            .map(|key| key.as_str())
+            .collect();
+          spans.push(Span::new(
+            &keys.join("+"),
+            Style {
+              font: Font::Mono,
+              ..style
+            },
+          ));
+        }
+        Element::Button => {
+          let content = element.get_attribute("content").unwrap_or("");
+          spans.push(Span::new(
+            &format!("[{}]", content),
+            Style {
+              font: style.font.bold(),
+              ..style
+            },
+          ));
+        }
+        Element::Menu => {
+          let mut items = vec![element.get_attribute("menu").unwrap_or("")];
+          items.extend(
+            element
+              .positional_attributes
+              .iter()
+              .map(|item| item.as_str()),
+          );
+          spans.push(Span::new(
+            &items.join(" \u{203a} "),
+            Style {
+              font: style.font.bold(),
+              ..style
+            },
+          ));
+        }
+        Element::InlinePassthrough => {
+          let content = element.get_attribute("content").unwrap_or("");
+          spans.push(Span::new(content, style));
+        }
+        Element::Stem => {
+          let content = element.get_attribute("content").unwrap_or("");
+          spans.push(Span::new(
+            content,
+            Style {
+              font: Font::Mono,
+              ..style
+            },
+          ));
+        }
+        Element::IndexTerm => {
+          // Concealed terms only appear in the index
+          if element.get_attribute("visible") == Some("true") {
+            if let Some(term) = positional_attribute(element, 0) {
+              spans.push(Span::new(term, style));
+            }
+          }
+        }
+        Element::Callout(number) => {
+          spans.push(Span::new(
+            &format!("({})", number),
+            Style {
+              font: style.font.bold(),
+              ..style
+            },
+          ));
+        }
+        Element::Anchor | Element::Comment => (),
+        _ => return Err(AsciidoctrineError::MalformedAst),
+      }
+    }
+
+    Ok(spans)
+  }
+
+  fn block_title(&mut self, input: &ElementSpan, x: f32, width: f32) {
+    if let Some(title) = input.get_attribute("title") {
+      let style = Style::new(Font::Bold, FONT_SIZE);
+      self.heading(&[Span::new(title, style)], x, width);
+    }
+  }
+
+  /// Lays out lines which are kept together with the following content
+  fn heading(&mut self, spans: &[Span], x: f32, width: f32) {
+    let start = self.items.len();
+    self.lines(spans, x, width, false);
+    for item in self.items[start..].iter_mut() {
+      if let Item::Line(line) = item {
+        line.keep_with_next = true;
+      }
+    }
+  }
+
+  fn lines(&mut self, spans: &[Span], x: f32, width: f32, preserve: bool) {
+    let lines = break_lines(spans, x, width, preserve);
+    self.items.extend(lines.into_iter().map(Item::Line));
+  }
+
+  fn marker(&mut self, marker: Marker) {
+    self.items.push(Item::Marker(marker));
+  }
+
+  fn trim_space(&mut self) {
+    while let Some(Item::Space(_)) = self.items.last() {
+      self.items.pop();
+    }
+  }
+
+  /// Puts a label (e.g. of a list item) in front of the first line
+  /// of the content starting at the item index
+  fn label(&mut self, start: usize, text: &str, style: Style, x: f32) {
+    let text = encode_str(text);
+    let first = self.items[start..]
+      .iter()
+      .position(|item| !matches!(item, Item::Marker(_) | Item::Space(_)))
+      .map(|index| start + index);
+
+    if let Some(Item::Line(line)) = first.and_then(|index| self.items.get_mut(index)) {
+      if !line.fragments.is_empty() {
+        line.fragments.push(Fragment {
+          x: x - line.x,
+          width: style.width(&text),
+          text,
+          style,
+          link: None,
+        });
+        return;
+      }
+    }
+
+    // The content doesn't start with text
+    let mut lines = break_lines(&[Span::new("", style)], x, 0.0, false);
+    if let Some(mut line) = lines.pop() {
+      line.fragments.push(Fragment {
+        x: 0.0,
+        width: style.width(&text),
+        text,
+        style,
+        link: None,
+      });
+      self.items.insert(first.unwrap_or(start), Item::Line(line));
+    }
+  }
+
+  /// Puts a background behind the content starting at the item index
+  fn fill(&mut self, start: usize, x: f32, width: f32, gray: f32) {
+    let fill = Fill { x, width, gray };
+    for item in self.items[start..].iter_mut() {
+      match item {
+        Item::Line(line) => line.fill = Some(fill),
+        Item::Space(height) => {
+          *item = Item::Line(Line {
+            x,
+            height: *height,
+            fill: Some(fill),
+            ..Line::default()
+          })
+        }
+        _ => (),
+      }
+    }
+  }
+}
+
+/// The markers of nested bullet lists
+const BULLETS: &[&str] = &["\u{2022}", "\u{2013}", "\u{b7}"];
+
+/// The numeration of nested ordered lists
+const NUMERATIONS: &[&str] = &[
+  "arabic",
+  "loweralpha",
+  "lowerroman",
+  "upperalpha",
+  "upperroman",
+];
+
+fn numeral(number: u32, numeration: &str) -> String {
+  match numeration {
+    "loweralpha" => alpha(number).to_lowercase(),
+    "upperalpha" => alpha(number),
+    "lowerroman" => roman(number).to_lowercase(),
+    "upperroman" => roman(number),
+    _ => number.to_string(),
+  }
+}
+
+fn alpha(number: u32) -> String {
+  let mut number = number.max(1);
+  let mut out = Vec::new();
+  while number > 0 {
+    number -= 1;
+    out.push((b'A' + (number % 26) as u8) as char);
+    number /= 26;
+  }
+  out.iter().rev().collect()
+}
+
+fn roman(number: u32) -> String {
+  const NUMERALS: &[(u32, &str)] = &[
+    (1000, "M"),
+    (900, "CM"),
+    (500, "D"),
+    (400, "CD"),
+    (100, "C"),
+    (90, "XC"),
+    (50, "L"),
+    (40, "XL"),
+    (10, "X"),
+    (9, "IX"),
+    (5, "V"),
+    (4, "IV"),
+    (1, "I"),
+  ];
+  let mut number = number;
+  let mut out = String::new();
+  for (value, numeral) in NUMERALS {
+    while number >= *value {
+      out.push_str(numeral);
+      number -= value;
+    }
+  }
+  out
+}
+
+fn link(text: &str, style: Style, target: Target) -> Span {
+  Span {
+    link: Some(target),
+    ..Span::new(
+      text,
+      Style {
+        color: LINK_COLOR,
+        ..style
+      },
+    )
+  }
+}
+
+fn encode_str(text: &str) -> Vec<u8> {
+  text.chars().map(glyph).collect()
+}
+
+/// A word, a run of spaces or a line break
+struct Token<'a> {
+  text: Vec<u8>,
+  width: f32,
+  span: &'a Span,
+  space: bool,
+  newline: bool,
+}
+
+/// Breaks the formatted text into lines which fit into the width
+///
+/// With `preserve` all spaces are kept like in listings.
+fn break_lines(spans: &[Span], x: f32, width: f32, preserve: bool) -> Vec<Line> {
+  let base = spans
+    .first()
+    .map(|span| span.style)
+    .unwrap_or(Style::new(Font::Regular, FONT_SIZE));
+  let mut lines = Vec::new();
+  let mut current: Vec<Token> = Vec::new();
+  let mut anchors: Vec<String> = Vec::new();
+  let mut line_width = 0.0;
+
+  for span in spans {
+    if let Some(anchor) = &span.anchor {
+      anchors.push(anchor.clone());
+    }
+    for token in tokenize(span) {
+      if token.newline {
+        lines.push(build_line(
+          std::mem::take(&mut current),
+          &mut anchors,
+          x,
+          base,
+        ));
+        line_width = 0.0;
+        continue;
+      }
+      if token.space {
+        let after_space = current.last().map(|last| last.space).unwrap_or(true);
+        if !preserve && after_space {
+          continue;
+        }
+        line_width += token.width;
+        current.push(token);
+        continue;
+      }
+
+      let mut token = token;
+      if line_width + token.width > width && current.iter().any(|token| !token.space) {
+        lines.push(build_line(
+          std::mem::take(&mut current),
+          &mut anchors,
+          x,
+          base,
+        ));
+        line_width = 0.0;
+      }
+      // Words which are longer than a line are split
+      while token.width > width - line_width && token.text.len() > 1 {
+        let mut split = 0;
+        let mut split_width = 0.0;
+        for c in token.text.iter() {
+          let c_width = token.span.style.width(&[*c]);
+          if line_width + split_width + c_width > width && split > 0 {
+            break;
+          }
+          split += 1;
+          split_width += c_width;
+        }
+        if split >= token.text.len() {
+          break;
+        }
+        let rest = token.text.split_off(split);
+        current.push(Token {
+          width: token.span.style.width(&token.text),
+          text: std::mem::replace(&mut token.text, rest),
+          ..token
+        });
+        token.width = token.span.style.width(&token.text);
+        lines.push(build_line(
+          std::mem::take(&mut current),
+          &mut anchors,
+          x,
+          base,
+        ));
+        line_width = 0.0;
+      }
+      line_width += token.width;
+      current.push(token);
+    }
+  }
+  if !current.is_empty() || !anchors.is_empty() {
+    lines.push(build_line(current, &mut anchors, x, base));
+  }
+
+  lines
+}
+
+fn tokenize(span: &Span) -> Vec<Token<'_>> {
+  let mut tokens: Vec<Token> = Vec::new();
+
+  for c in span.text.chars() {
+    // Zero width characters aren't part of the fonts
+    if matches!(c, '\r' | '\u{200b}' | '\u{2060}') {
+      continue;
+    }
+    let newline = c == '\n';
+    let space = c == ' ' || c == '\t';
+    match tokens.last_mut() {
+      Some(last) if !newline && !last.newline && last.space == space => last.text.push(glyph(c)),
+      _ => tokens.push(Token {
+        text: if newline { Vec::new() } else { vec![glyph(c)] },
+        width: 0.0,
+        span,
+        space,
+        newline,
+      }),
+    }
+  }
+  for token in tokens.iter_mut() {
+    token.width = span.style.width(&token.text);
+  }
+
+  tokens
+}
+
+fn build_line(tokens: Vec<Token>, anchors: &mut Vec<String>, x: f32, base: Style) -> Line {
+  let mut tokens = tokens;
+  // Spaces at the end of a line aren't visible
+  while tokens.last().map(|token| token.space).unwrap_or(false)
+    && tokens.iter().any(|token| !token.space)
+  {
+    tokens.pop();
+  }
+
+  let size = tokens
+    .iter()
+    .map(|token| token.span.style.size)
+    .fold(base.size, f32::max);
+  let mut line = Line {
+    x,
+    height: size * LEADING,
+    baseline: size * (LEADING - 1.0) / 2.0 + size * 0.8,
+    anchors: std::mem::take(anchors),
+    ..Line::default()
+  };
+
+  let mut position = 0.0;
+  for token in tokens {
+    match line.fragments.last_mut() {
+      Some(last) if last.style == token.span.style && last.link == token.span.link => {
+        last.text.extend(token.text);
+        last.width += token.width;
+      }
+      _ => line.fragments.push(Fragment {
+        x: position,
+        text: token.text,
+        style: token.span.style,
+        width: token.width,
+        link: token.span.link.clone(),
+      }),
+    }
+    position += token.width;
+  }
+
+  line
+}
+
+/// Collects the titles with ids as the text of cross references
+fn collect_titles(elements: &[ElementSpan], titles: &mut HashMap<String, String>) {
+  for element in elements {
+    if let Element::Title { .. } = element.element {
+      if let (Some(id), Some(name)) = (element.get_attribute("id"), element.get_attribute("name")) {
+        titles.insert(id.to_string(), name.to_string());
+      }
+    }
+    if let Element::IncludeElement(include) = &element.element {
+      collect_titles(&include.inner.elements, titles);
+    }
+    collect_titles(&element.children, titles);
+  }
+}
+
+/// A positional attribute without surrounding whitespace if it isn't
+/// empty
+fn positional_attribute<'a>(input: &'a ElementSpan, index: usize) -> Option<&'a str> {
+  input
+    .positional_attributes
+    .get(index)
+    .map(|value| value.as_str().trim())
+    .filter(|value| !value.is_empty())
+}
+
+// Images
+//----------------------------------------------------
+
+fn load_image(path: &Path) -> anyhow::Result<Image> {
+  let data = std::fs::read(path)?;
+
+  if data.starts_with(&[0xff, 0xd8]) {
+    // JPEG images are embedded as they are
+    let (width, height, components) = jpeg_info(&data).context("invalid JPEG image")?;
+    let color_space = match components {
+      1 => "DeviceGray",
+      3 => "DeviceRGB",
+      _ => anyhow::bail!("unsupported JPEG color space"),
+    };
+    return Ok(Image {
+      width,
+      height,
+      color_space,
+      filter: "DCTDecode",
+      data,
+      alpha: None,
+    });
+  }
+
+  if data.starts_with(b"\x89PNG") {
+    let mut decoder = png::Decoder::new(io::Cursor::new(data));
+    decoder.set_transformations(png::Transformations::EXPAND | png::Transformations::STRIP_16);
+    let mut reader = decoder.read_info()?;
+    let mut buf = vec![0; reader.output_buffer_size().context("PNG image too large")?];
+    let info = reader.next_frame(&mut buf)?;
+    buf.truncate(info.buffer_size());
+
+    let (channels, color_space) = match info.color_type {
+      png::ColorType::Grayscale => (1, "DeviceGray"),
+      png::ColorType::GrayscaleAlpha => (2, "DeviceGray"),
+      png::ColorType::Rgb => (3, "DeviceRGB"),
+      png::ColorType::Rgba => (4, "DeviceRGB"),
+      png::ColorType::Indexed => anyhow::bail!("indexed PNG images are not expanded"),
+    };
+    let (data, alpha) = match channels {
+      2 | 4 => {
+        let color: Vec<u8> = buf
+          .chunks(channels)
+          .flat_map(|pixel| pixel[..channels - 1].iter().copied())
+          .collect();
+        let alpha: Vec<u8> = buf
+          .chunks(channels)
+          .map(|pixel| pixel[channels - 1])
+          .collect();
+        (color, Some(compress(&alpha)?))
+      }
+      _ => (buf, None),
+    };
+    return Ok(Image {
+      width: info.width,
+      height: info.height,
+      color_space,
+      filter: "FlateDecode",
+      data: compress(&data)?,
+      alpha,
+    });
+  }
+
+  anyhow::bail!("only PNG and JPEG images are supported")
+}
+
+/// Reads the size and the number of color components from the start
+/// of frame segment
+fn jpeg_info(data: &[u8]) -> Option<(u32, u32, u8)> {
+  let mut index = 2;
+  while index + 9 < data.len() {
+    if data[index] != 0xff {
+      return None;
+    }
+    let marker = data[index + 1];
+    if marker == 0xff {
+      index += 1;
+      continue;
+    }
+    if (0xc0..=0xcf).contains(&marker) && !matches!(marker, 0xc4 | 0xc8 | 0xcc) {
+      let height = u16::from_be_bytes([data[index + 5], data[index + 6]]);
+      let width = u16::from_be_bytes([data[index + 7], data[index + 8]]);
+      return Some((width as u32, height as u32, data[index + 9]));
+    }
+    let length = u16::from_be_bytes([data[index + 2], data[index + 3]]) as usize;
+    index += 2 + length;
+  }
+  None
+}
+
+fn compress(data: &[u8]) -> io::Result<Vec<u8>> {
+  let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+  encoder.write_all(data)?;
+  encoder.finish()
+}
+
+// Pagination
+//----------------------------------------------------
+
+#[derive(Default)]
+struct Page {
+  content: Vec<u8>,
+  links: Vec<([f32; 4], Target)>,
+  images: Vec<usize>,
+}
+
+struct OutlineEntry {
+  level: u32,
+  title: String,
+  page: usize,
+  y: f32,
+}
+
+struct Pages {
+  pages: Vec<Page>,
+  anchors: HashMap<String, (usize, f32)>,
+  outline: Vec<OutlineEntry>,
+}
+
+/// Distributes the items onto pages and draws them
+fn paginate(items: &[Item]) -> Pages {
+  let top = PAGE_HEIGHT - MARGIN;
+  let mut pages = Pages {
+    pages: vec![Page::default()],
+    anchors: HashMap::new(),
+    outline: Vec::new(),
+  };
+  let mut y = top;
+  let mut pending: Vec<&Marker> = Vec::new();
+
+  for (index, item) in items.iter().enumerate() {
+    let mut needed = item.height();
+    if let Item::Line(Line {
+      keep_with_next: true,
+      ..
+    }) = item
+    {
+      needed += items[index + 1..]
+        .iter()
+        .filter(|item| !matches!(item, Item::Space(_) | Item::Marker(_)))
+        .take_while(|item| {
+          matches!(
+            item,
+            Item::Line(Line {
+              keep_with_next: true,
+              ..
+            })
+          )
+        })
+        .map(Item::height)
+        .sum::<f32>();
+      needed += items[index + 1..]
+        .iter()
+        .find(|item| {
+          !matches!(
+            item,
+            Item::Space(_)
+              | Item::Marker(_)
+              | Item::Line(Line {
+                keep_with_next: true,
+                ..
+              })
+          )
+        })
+        .map(Item::height)
+        .unwrap_or(0.0);
+    }
+    if y - needed < MARGIN && y < top {
+      pages.pages.push(Page::default());
+      y = top;
+    }
+
+    match item {
+      // Spaces at the top of a page are dropped
+      Item::Space(height) => {
+        if y < top {
+          y -= height;
+        }
+      }
+      Item::Marker(marker) => pending.push(marker),
+      item => {
+        for marker in pending.drain(..) {
+          pages.mark(marker, y);
+        }
+        pages.draw(item, y);
+        y -= item.height();
+      }
+    }
+  }
+  for marker in pending.drain(..) {
+    pages.mark(marker, y);
+  }
+
+  // Links to unknown ids are dropped
+  let anchors = &pages.anchors;
+  for page in pages.pages.iter_mut() {
+    page.links.retain(|(_, target)| match target {
+      Target::Anchor(id) if !anchors.contains_key(id) => {
+        warn!("unknown reference {}", id);
+        false
+      }
+      _ => true,
+    });
+  }
+
+  pages
+}
+
+impl Pages {
+  fn current(&mut self) -> &mut Page {
+    self.pages.last_mut().expect("there is always a page")
+  }
+
+  fn mark(&mut self, marker: &Marker, y: f32) {
+    let page = self.pages.len() - 1;
+    match marker {
+      Marker::Anchor(id) => {
+        self.anchors.insert(id.clone(), (page, y));
+      }
+      Marker::Outline { level, title } => self.outline.push(OutlineEntry {
+        level: *level,
+        title: title.clone(),
+        page,
+        y,
+      }),
+    }
+  }
+
+  fn draw(&mut self, item: &Item, top: f32) {
+    match item {
+      Item::Line(line) => self.draw_line(line, top),
+      Item::Row(row) => {
+        for cell in row.cells.iter() {
+          let mut y = top - PADDING;
+          for item in cell.items.iter() {
+            match item {
+              Item::Marker(marker) => self.mark(marker, y),
+              item => self.draw(item, y),
+            }
+            y -= item.height();
+          }
+          let out = &mut self.current().content;
+          out.extend(
+            format!(
+              "0.6 G 0.5 w {} {} {} {} re S\n",
+              num(cell.x),
+              num(top - row.height),
+              num(cell.width),
+              num(row.height)
+            )
+            .bytes(),
+          );
+        }
+      }
+      Item::Image(image) => {
+        let page = self.current();
+        if !page.images.contains(&image.image) {
+          page.images.push(image.image);
+        }
+        page.content.extend(
+          format!(
+            "q {} 0 0 {} {} {} cm /Im{} Do Q\n",
+            num(image.width),
+            num(image.height),
+            num(image.x),
+            num(top - image.height),
+            image.image + 1
+          )
+          .bytes(),
+        );
+      }
+      Item::Space(_) => (),
+      Item::Marker(marker) => self.mark(marker, top),
+    }
+  }
+
+  fn draw_line(&mut self, line: &Line, top: f32) {
+    for anchor in line.anchors.iter() {
+      self.mark(&Marker::Anchor(anchor.clone()), top);
+    }
+
+    let page = self.current();
+    if let Some(fill) = line.fill {
+      page.content.extend(
+        format!(
+          "{} g {} {} {} {} re f\n",
+          num(fill.gray),
+          num(fill.x),
+          num(top - line.height),
+          num(fill.width),
+          num(line.height)
+        )
+        .bytes(),
+      );
+    }
+
+    let baseline = top - line.baseline;
+    for fragment in line.fragments.iter() {
+      let x = line.x + fragment.x;
+      let style = fragment.style;
+      if !fragment.text.is_empty() {
+        let [r, g, b] = style.color;
+        page.content.extend(
+          format!(
+            "BT /F{} {} Tf {} {} {} rg {} {} Td <{}> Tj ET\n",
+            style.font.resource(),
+            num(style.size),
+            num(r),
+            num(g),
+            num(b),
+            num(x),
+            num(baseline + style.rise),
+            hex(&fragment.text)
+          )
+          .bytes(),
+        );
+      }
+      if let Some(target) = &fragment.link {
+        let y = baseline + style.rise;
+        page.links.push((
+          [
+            x,
+            y - style.size * 0.25,
+            x + fragment.width,
+            y + style.size * 0.85,
+          ],
+          target.clone(),
+        ));
+      }
+    }
+  }
+}
+
+// PDF Objects
+//----------------------------------------------------
+
+struct PdfFile<'a, T: io::Write + io::Seek> {
+  out: &'a mut T,
+  start: u64,
+  offsets: Vec<u64>,
+}
+
+impl<'a, T: io::Write + io::Seek> PdfFile<'a, T> {
+  fn object(&mut self, id: usize, body: &str) -> Result<()> {
+    self.begin(id)?;
+    self.out.write_all(body.as_bytes())?;
+    self.out.write_all(b"\nendobj\n")?;
+    Ok(())
+  }
+
+  fn stream(&mut self, id: usize, dictionary: &str, data: &[u8]) -> Result<()> {
+    self.begin(id)?;
+    self
+      .out
+      .write_all(format!("<< {} /Length {} >>\nstream\n", dictionary, data.len()).as_bytes())?;
+    self.out.write_all(data)?;
+    self.out.write_all(b"\nendstream\nendobj\n")?;
+    Ok(())
+  }
+
+  fn begin(&mut self, id: usize) -> Result<()> {
+    if self.offsets.len() <= id {
+      self.offsets.resize(id + 1, 0);
+    }
+    self.offsets[id] = self.out.stream_position()? - self.start;
+    self.out.write_all(format!("{} 0 obj\n", id).as_bytes())?;
+    Ok(())
+  }
+}
+
+fn write_document<T: io::Write + io::Seek>(
+  ast: &AST,
+  pages: &Pages,
+  images: &[Image],
+  out: &mut T,
+) -> Result<()> {
+  // The ids of the objects
+  const CATALOG: usize = 1;
+  const PAGES: usize = 2;
+  const INFO: usize = 3;
+  const FONTS: usize = 4;
+  let mut next = FONTS + Font::ALL.len();
+  // The bookmarks are only written if there are headings
+  let outline_root = next;
+  if !pages.outline.is_empty() {
+    next += 1;
+  }
+  let mut image_ids = Vec::new();
+  for image in images {
+    image_ids.push((next, image.alpha.as_ref().map(|_| next + 1)));
+    next += if image.alpha.is_some() { 2 } else { 1 };
+  }
+  let mut page_ids = Vec::new();
+  for page in pages.pages.iter() {
+    page_ids.push((next, next + 1, next + 2));
+    next += 2 + page.links.len();
+  }
+  let outline_start = next;
+
+  let start = out.stream_position()?;
+  let mut pdf = PdfFile {
+    out,
+    start,
+    offsets: Vec::new(),
+  };
+  pdf.out.write_all(b"%PDF-1.4\n%\xe2\xe3\xcf\xd3\n")?;
+
+  let page_mode = if pages.outline.is_empty() {
+    String::new()
+  } else {
+    format!(" /Outlines {} 0 R /PageMode /UseOutlines", outline_root)
+  };
+  pdf.object(
+    CATALOG,
+    &format!("<< /Type /Catalog /Pages {} 0 R{} >>", PAGES, page_mode),
+  )?;
+  let kids: Vec<String> = page_ids
+    .iter()
+    .map(|(page, _, _)| format!("{} 0 R", page))
+    .collect();
+  pdf.object(
+    PAGES,
+    &format!(
+      "<< /Type /Pages /Kids [{}] /Count {} >>",
+      kids.join(" "),
+      kids.len()
+    ),
+  )?;
+
+  let mut info = String::from("<< /Creator (asciidoctrine) /Producer (asciidoctrine)");
+  if let Some(title) = &ast.header.title {
+    info.push_str(&format!(" /Title {}", text_string(title)));
+  }
+  if !ast.header.authors.is_empty() {
+    let authors: Vec<&str> = ast
+      .header
+      .authors
+      .iter()
+      .map(|author| author.name.as_str())
+      .collect();
+    info.push_str(&format!(" /Author {}", text_string(&authors.join(", "))));
+  }
+  info.push_str(" >>");
+  pdf.object(INFO, &info)?;
+
+  for (index, font) in Font::ALL.iter().enumerate() {
+    pdf.object(
+      FONTS + index,
+      &format!(
+        "<< /Type /Font /Subtype /Type1 /BaseFont /{} /Encoding /WinAnsiEncoding >>",
+        font.base_font()
+      ),
+    )?;
+  }
+
+  for (image, (id, alpha_id)) in images.iter().zip(image_ids.iter()) {
+    let smask = match alpha_id {
+      Some(alpha_id) => format!(" /SMask {} 0 R", alpha_id),
+      None => String::new(),
+    };
+    pdf.stream(
+      *id,
+      &format!(
+        "/Type /XObject /Subtype /Image /Width {} /Height {} /ColorSpace /{} /BitsPerComponent 8 /Filter /{}{}",
+        image.width, image.height, image.color_space, image.filter, smask
+      ),
+      &image.data,
+    )?;
+    if let (Some(alpha_id), Some(alpha)) = (alpha_id, &image.alpha) {
+      pdf.stream(
+        *alpha_id,
+        &format!(
+          "/Type /XObject /Subtype /Image /Width {} /Height {} /ColorSpace /DeviceGray /BitsPerComponent 8 /Filter /FlateDecode",
+          image.width, image.height
+        ),
+        alpha,
+      )?;
+    }
+  }
+
+  let fonts: Vec<String> = (0..Font::ALL.len())
+    .map(|index| format!("/F{} {} 0 R", index + 1, FONTS + index))
+    .collect();
+  let destination =
+    |page: usize, y: f32| format!("[{} 0 R /XYZ 0 {} null]", page_ids[page].0, num(y));
+  let page_count = pages.pages.len();
+  for (number, (page, (page_id, content_id, annots_start))) in
+    pages.pages.iter().zip(page_ids.iter()).enumerate()
+  {
+    let mut annots = Vec::new();
+    for (index, (rect, target)) in page.links.iter().enumerate() {
+      let action = match target {
+        Target::Uri(uri) => format!("/A << /S /URI /URI {} >>", literal_string(uri.as_bytes())),
+        Target::Anchor(id) => {
+          let (page, y) = pages.anchors[id];
+          format!("/Dest {}", destination(page, y))
+        }
+      };
+      let id = annots_start + index;
+      pdf.object(
+        id,
+        &format!(
+          "<< /Type /Annot /Subtype /Link /Rect [{} {} {} {}] /Border [0 0 0] {} >>",
+          num(rect[0]),
+          num(rect[1]),
+          num(rect[2]),
+          num(rect[3]),
+          action
+        ),
+      )?;
+      annots.push(format!("{} 0 R", id));
+    }
+
+    let xobjects: Vec<String> = page
+      .images
+      .iter()
+      .map(|image| format!("/Im{} {} 0 R", image + 1, image_ids[*image].0))
+      .collect();
+    let annots = if annots.is_empty() {
+      String::new()
+    } else {
+      format!(" /Annots [{}]", annots.join(" "))
+    };
+    pdf.object(
+      *page_id,
+      &format!(
+        "<< /Type /Page /Parent {} 0 R /MediaBox [0 0 {} {}] /Resources << /Font << {} >> /XObject << {} >> >> /Contents {} 0 R{} >>",
+        PAGES,
+        num(PAGE_WIDTH),
+        num(PAGE_HEIGHT),
+        fonts.join(" "),
+        xobjects.join(" "),
+        content_id,
+        annots
+      ),
+    )?;
+
+    let mut content = page.content.clone();
+    if page_count > 1 {
+      let label = (number + 1).to_string();
+      let style = Style::new(Font::Regular, 9.0);
+      content.extend(
+        format!(
+          "BT /F{} 9 Tf 0.4 0.4 0.4 rg {} {} Td ({}) Tj ET\n",
+          Font::Regular.resource(),
+          num((PAGE_WIDTH - style.width(label.as_bytes())) / 2.0),
+          num(MARGIN / 2.0),
+          label
+        )
+        .bytes(),
+      );
+    }
+    pdf.stream(*content_id, "/Filter /FlateDecode", &compress(&content)?)?;
+  }
+
+  if !pages.outline.is_empty() {
+    write_outline(
+      &mut pdf,
+      &pages.outline,
+      outline_root,
+      outline_start,
+      &destination,
+    )?;
+  }
+
+  let xref = pdf.out.stream_position()? - start;
+  let mut table = format!("xref\n0 {}\n0000000000 65535 f \n", pdf.offsets.len());
+  for offset in pdf.offsets.iter().skip(1) {
+    table.push_str(&format!("{:010} 00000 n \n", offset));
+  }
+  table.push_str(&format!(
+    "trailer\n<< /Size {} /Root {} 0 R /Info {} 0 R >>\nstartxref\n{}\n%%EOF\n",
+    pdf.offsets.len(),
+    CATALOG,
+    INFO,
+    xref
+  ));
+  pdf.out.write_all(table.as_bytes())?;
+
+  Ok(())
+}
+
+/// Writes the bookmarks of the headings as a tree
+fn write_outline<T: io::Write + io::Seek>(
+  pdf: &mut PdfFile<T>,
+  entries: &[OutlineEntry],
+  root: usize,
+  start: usize,
+  destination: &dyn Fn(usize, f32) -> String,
+) -> Result<()> {
+  // The parent of every entry (`None` is the root)
+  let mut parents: Vec<Option<usize>> = Vec::new();
+  let mut stack: Vec<usize> = Vec::new();
+  for (index, entry) in entries.iter().enumerate() {
+    while let Some(last) = stack.last() {
+      if entries[*last].level < entry.level {
+        break;
+      }
+      stack.pop();
+    }
+    parents.push(stack.last().copied());
+    stack.push(index);
+  }
+  let children = |parent: Option<usize>| -> Vec<usize> {
+    (0..entries.len())
+      .filter(|index| parents[*index] == parent)
+      .collect()
+  };
+  let descendants = |parent: usize| -> usize {
+    (0..entries.len())
+      .filter(|index| {
+        let mut ancestor = parents[*index];
+        while let Some(current) = ancestor {
+          if current == parent {
+            return true;
+          }
+          ancestor = parents[current];
+        }
+        false
+      })
+      .count()
+  };
+
+  let top = children(None);
+  pdf.object(
+    root,
+    &format!(
+      "<< /Type /Outlines /First {} 0 R /Last {} 0 R /Count {} >>",
+      start + top[0],
+      start + top[top.len() - 1],
+      entries.len()
+    ),
+  )?;
+  for (index, entry) in entries.iter().enumerate() {
+    let siblings = children(parents[index]);
+    let position = siblings
+      .iter()
+      .position(|sibling| *sibling == index)
+      .unwrap_or(0);
+    let mut object = format!(
+      "<< /Title {} /Parent {} 0 R /Dest {}",
+      text_string(&entry.title),
+      parents[index].map(|parent| start + parent).unwrap_or(root),
+      destination(entry.page, entry.y)
+    );
+    if position > 0 {
+      object.push_str(&format!(" /Prev {} 0 R", start + siblings[position - 1]));
+    }
+    if let Some(next) = siblings.get(position + 1) {
+      object.push_str(&format!(" /Next {} 0 R", start + next));
+    }
+    let own = children(Some(index));
+    if let (Some(first), Some(last)) = (own.first(), own.last()) {
+      object.push_str(&format!(
+        " /First {} 0 R /Last {} 0 R /Count {}",
+        start + first,
+        start + last,
+        descendants(index)
+      ));
+    }
+    object.push_str(" >>");
+    pdf.object(start + index, &object)?;
+  }
+
+  Ok(())
+}
+
+// Helper Functions
+//----------------------------------------------------
+
+/// Formats a number with at most two decimals
+fn num(value: f32) -> String {
+  let value = format!("{:.2}", value);
+  let value = value.trim_end_matches('0').trim_end_matches('.');
+  match value {
+    "-0" | "" => "0".to_string(),
+    value => value.to_string(),
+  }
+}
+
+fn hex(data: &[u8]) -> String {
+  data.iter().map(|byte| format!("{:02X}", byte)).collect()
+}
+
+/// A string for the document information and the bookmarks in
+/// UTF-16 so that every character can be displayed
+fn text_string(text: &str) -> String {
+  let utf16: Vec<u8> = text
+    .encode_utf16()
+    .flat_map(|unit| unit.to_be_bytes())
+    .collect();
+  format!("<FEFF{}>", hex(&utf16))
+}
+
+fn literal_string(data: &[u8]) -> String {
+  let mut out = String::from("(");
+  for byte in data {
+    match byte {
+      b'(' | b')' | b'\\' => {
+        out.push('\\');
+        out.push(*byte as char);
+      }
+      32..=126 => out.push(*byte as char),
+      byte => out.push_str(&format!("\\{:03o}", byte)),
+    }
+  }
+  out.push(')');
+  out
+}
+
+// Font Metrics
+//----------------------------------------------------
+
+// The widths of the WinAnsi characters 32 to 255 from the Adobe font
+// metrics of the standard fonts
+
+const HELVETICA_WIDTHS: [u16; 224] = [
+  278, 278, 355, 556, 556, 889, 667, 191, 333, 333, 389, 584, 278, 333, 278, 278, 556, 556, 556,
+  556, 556, 556, 556, 556, 556, 556, 278, 278, 584, 584, 584, 556, 1015, 667, 667, 722, 722, 667,
+  611, 778, 722, 278, 500, 667, 556, 833, 722, 778, 667, 778, 722, 667, 611, 722, 667, 944, 667,
+  667, 611, 278, 278, 278, 469, 556, 333, 556, 556, 500, 556, 556, 278, 556, 556, 222, 222, 500,
+  222, 833, 556, 556, 556, 556, 333, 500, 278, 556, 500, 722, 500, 500, 500, 334, 260, 334, 584,
+  350, 556, 350, 222, 556, 333, 1000, 556, 556, 333, 1000, 667, 333, 1000, 350, 611, 350, 350, 222,
+  222, 333, 333, 350, 556, 1000, 333, 1000, 500, 333, 944, 350, 500, 667, 278, 333, 556, 556, 556,
+  556, 260, 556, 333, 737, 370, 556, 584, 333, 737, 333, 400, 584, 333, 333, 333, 556, 537, 278,
+  333, 333, 365, 556, 834, 834, 834, 611, 667, 667, 667, 667, 667, 667, 1000, 722, 667, 667, 667,
+  667, 278, 278, 278, 278, 722, 722, 778, 778, 778, 778, 778, 584, 778, 722, 722, 722, 722, 667,
+  667, 611, 556, 556, 556, 556, 556, 556, 889, 500, 556, 556, 556, 556, 278, 278, 278, 278, 556,
+  556, 556, 556, 556, 556, 556, 584, 611, 556, 556, 556, 556, 500, 556, 500,
+];
+
+const HELVETICA_BOLD_WIDTHS: [u16; 224] = [
+  278, 333, 474, 556, 556, 889, 722, 238, 333, 333, 389, 584, 278, 333, 278, 278, 556, 556, 556,
+  556, 556, 556, 556, 556, 556, 556, 333, 333, 584, 584, 584, 611, 975, 722, 722, 722, 722, 667,
+  611, 778, 722, 278, 556, 722, 611, 833, 722, 778, 667, 778, 722, 667, 611, 722, 667, 944, 667,
+  667, 611, 333, 278, 333, 584, 556, 333, 556, 611, 556, 611, 556, 333, 611, 611, 278, 278, 556,
+  278, 889, 611, 611, 611, 611, 389, 556, 333, 611, 556, 778, 556, 556, 500, 389, 280, 389, 584,
+  350, 556, 350, 278, 556, 500, 1000, 556, 556, 333, 1000, 667, 333, 1000, 350, 611, 350, 350, 278,
+  278, 500, 500, 350, 556, 1000, 333, 1000, 556, 333, 944, 350, 500, 667, 278, 333, 556, 556, 556,
+  556, 280, 556, 333, 737, 370, 556, 584, 333, 737, 333, 400, 584, 333, 333, 333, 611, 556, 278,
+  333, 333, 365, 556, 834, 834, 834, 611, 722, 722, 722, 722, 722, 722, 1000, 722, 667, 667, 667,
+  667, 278, 278, 278, 278, 722, 722, 778, 778, 778, 778, 778, 584, 778, 722, 722, 722, 722, 667,
+  667, 611, 556, 556, 556, 556, 556, 556, 889, 556, 556, 556, 556, 556, 278, 278, 278, 278, 611,
+  611, 611, 611, 611, 611, 611, 584, 611, 611, 611, 611, 611, 556, 611, 556,
+];
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn missing_glyphs_are_reported() {
+    MISSING_GLYPHS.with(|missing| missing.borrow_mut().clear());
+    let span = Span::new("Жx€日Ж", Style::new(Font::Regular, FONT_SIZE));
+
+    let tokens = tokenize(&span);
+    assert_eq!(tokens.len(), 1);
+    assert_eq!(tokens[0].text, b"?x\x80??".to_vec());
+
+    let message = MISSING_GLYPHS.with(|missing| missing_glyphs_message(&missing.borrow()));
+    assert_eq!(
+      message.as_deref(),
+      Some("the PDF fonts can't show the characters 'Ж' (U+0416), '日' (U+65E5), they are replaced by '?'")
+    );
+    assert_eq!(missing_glyphs_message(&BTreeSet::new()), None);
+  }
+}
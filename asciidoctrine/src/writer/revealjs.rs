@@ -0,0 +1,275 @@
+pub use crate::ast::*;
+use crate::sections::number_titles;
+use crate::util::Environment;
+use crate::writer::html;
+use crate::{options, Result};
+use std::io;
+use tera::{Context, Tera};
+
+/// Writes a slide deck for reveal.js as a single html file. The
+/// sections of the first level become slides and their subsections
+/// vertical slides.
+///
+/// reveal.js is loaded from `revealjsdir`, which points to a CDN by
+/// default. A local copy makes the deck work offline.
+pub struct RevealjsWriter {
+  io: crate::util::Env,
+}
+
+impl RevealjsWriter {
+  pub fn new() -> Self {
+    RevealjsWriter {
+      io: crate::util::Env::Io(crate::util::Io::new()),
+    }
+  }
+}
+
+impl Default for RevealjsWriter {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+impl<T: io::Write> crate::Writer<T> for RevealjsWriter {
+  fn write<'a>(&mut self, ast: AST, args: &options::Opts, mut out: T) -> Result<()> {
+    let ast = number_titles(ast);
+    html::begin_document(&ast);
+    html::embed_images(&ast, args, &mut self.io);
+    let mut deck = Deck {
+      title: None,
+      preamble: Vec::new(),
+      slides: Vec::new(),
+    };
+    split_slides(&ast.elements, &mut deck);
+
+    let mut slides = Vec::new();
+    if deck.title.is_some() || !deck.preamble.is_empty() {
+      let elements: Vec<&ElementSpan> = deck.title.into_iter().chain(deck.preamble).collect();
+      slides.extend_from_slice(b"<section class=\"title\" data-state=\"title\">\n");
+      html::write_part_with(
+        &elements,
+        Some(&ast.header),
+        &mut slides,
+        write_slide_element,
+      )?;
+      slides.extend_from_slice(b"</section>\n");
+    }
+    for slide in deck.slides.iter() {
+      match slide.vertical.is_empty() {
+        true => write_slide(slide, &mut slides)?,
+        false => {
+          slides.extend_from_slice(b"<section>\n");
+          write_slide(slide, &mut slides)?;
+          for vertical in slide.vertical.iter() {
+            write_slide(vertical, &mut slides)?;
+          }
+          slides.extend_from_slice(b"</section>\n");
+        }
+      }
+    }
+
+    let mut context = Context::new();
+    html::insert_metadata(&ast, &mut context);
+    let revealjsdir = ast
+      .get_attribute("revealjsdir")
+      .unwrap_or("https://cdn.jsdelivr.net/npm/reveal.js@5.1.0");
+    context.insert(
+      "revealjsdir",
+      &html::escape_attribute(revealjsdir.trim_end_matches('/')),
+    );
+    let theme = ast.get_attribute("revealjs_theme").unwrap_or("white");
+    context.insert("revealjs_theme", &html::escape_attribute(theme));
+    let mut stylesheet = match &args.stylesheet {
+      Some(path) => {
+        let path = path.to_str().expect("path to stylesheet unreadable");
+        self.io.read_to_string(path)?
+      }
+      None => include_str!("assets/slides-theme.css").to_string(),
+    };
+    stylesheet.push_str(&html::highlight_stylesheet());
+    context.insert("stylesheet", &stylesheet);
+    context.insert("slides", std::str::from_utf8(&slides)?);
+
+    let mut tera = Tera::default();
+    tera.autoescape_on(vec![]);
+    match &args.template {
+      Some(path) => {
+        let path = path.to_str().expect("path to template unreadable");
+        let template = if path == "-" {
+          "{{slides}}".to_string()
+        } else {
+          self.io.read_to_string(path)?
+        };
+        tera.add_raw_template("slides.html", &template)?;
+      }
+      None => {
+        tera.add_raw_template("slides.html", include_str!("assets/slides.html"))?;
+      }
+    }
+    out.write_all(tera.render("slides.html", &context)?.as_bytes())?;
+    out.flush()?;
+
+    Ok(())
+  }
+}
+
+/// The document split into slides
+struct Deck<'b, 'a> {
+  title: Option<&'b ElementSpan<'a>>,
+  /// The content before the first slide is part of the title slide
+  preamble: Vec<&'b ElementSpan<'a>>,
+  slides: Vec<Slide<'b, 'a>>,
+}
+
+struct Slide<'b, 'a> {
+  title: &'b ElementSpan<'a>,
+  /// The slides below others have a heading of the next level
+  level: u32,
+  id: Option<&'b str>,
+  content: Vec<&'b ElementSpan<'a>>,
+  /// The slides below this one
+  vertical: Vec<Slide<'b, 'a>>,
+}
+
+impl<'b, 'a> Deck<'b, 'a> {
+  fn push_slide(&mut self, level: u32, title: &'b ElementSpan<'a>, id: Option<&'b str>) {
+    let slide = Slide {
+      title,
+      level,
+      id,
+      content: Vec::new(),
+      vertical: Vec::new(),
+    };
+    match self.slides.last_mut() {
+      Some(parent) if level > 2 => parent.vertical.push(slide),
+      _ => self.slides.push(slide),
+    }
+  }
+
+  fn push_content(&mut self, element: &'b ElementSpan<'a>) {
+    match self.slides.last_mut() {
+      Some(slide) => match slide.vertical.last_mut() {
+        Some(vertical) => vertical.content.push(element),
+        None => slide.content.push(element),
+      },
+      None => self.preamble.push(element),
+    }
+  }
+}
+
+/// Splits the document at the sections up to the second level. The
+/// deeper sections stay on the slide of their parent.
+fn split_slides<'b, 'a>(elements: &'b [ElementSpan<'a>], deck: &mut Deck<'b, 'a>) {
+  for element in elements {
+    match &element.element {
+      Element::Title { level: 1 } if deck.title.is_none() && deck.slides.is_empty() => {
+        deck.title = Some(element)
+      }
+      Element::Title { level } if *level <= 3 => {
+        deck.push_slide(*level, element, element.get_attribute("id"))
+      }
+      // With the sections extension a section holds its subsections
+      Element::Section { level } if *level <= 3 => {
+        if let Some((title, content)) = element.children.split_first() {
+          deck.push_slide(*level, title, element.get_attribute("id"));
+          split_slides(content, deck);
+        }
+      }
+      Element::IncludeElement(include) => split_slides(&include.inner.elements, deck),
+      _ => deck.push_content(element),
+    }
+  }
+}
+
+fn write_slide<T: io::Write>(slide: &Slide, out: &mut T) -> Result<()> {
+  match slide.id {
+    Some(id) => writeln!(out, "<section id=\"{}\">", id)?,
+    None => writeln!(out, "<section>")?,
+  }
+  html::write_title(slide.level.clamp(2, 3), slide.title, out)?;
+  html::write_part_with(&slide.content, None, out, write_slide_element)?;
+  writeln!(out, "</section>")?;
+
+  Ok(())
+}
+
+/// Writes an element of a slide. Blocks with the role `notes` become
+/// speaker notes and the items of lists with the option `step` appear
+/// one after the other.
+fn write_slide_element<T: io::Write>(element: &ElementSpan, out: &mut T) -> Result<()> {
+  if has_role(element, "notes") {
+    out.write_all(b"<aside class=\"notes\">\n")?;
+    match element.element {
+      // Only the content of a delimited block is part of the notes
+      Element::TypedBlock {
+        kind: BlockType::Open,
+      }
+      | Element::TypedBlock {
+        kind: BlockType::Sidebar,
+      }
+      | Element::TypedBlock {
+        kind: BlockType::Example,
+      } => {
+        for child in element.children.iter() {
+          html::write_element(child, out)?;
+        }
+      }
+      // The role only marks the notes
+      _ => {
+        let mut element = element.clone();
+        element
+          .attributes
+          .retain(|attribute| attribute.key != "role");
+        html::write_element(&element, out)?
+      }
+    }
+    out.write_all(b"</aside>\n")?;
+  } else if matches!(element.element, Element::List(_)) && has_option(element, "step") {
+    let mut list = Vec::new();
+    html::write_element(element, &mut list)?;
+    let list = std::str::from_utf8(&list)?.replace("<li>", "<li class=\"fragment\">");
+    out.write_all(list.as_bytes())?;
+  } else {
+    html::write_element(element, out)?;
+  }
+
+  Ok(())
+}
+
+// Helper Functions
+//----------------------------------------------------
+
+/// Checks if the role is set with `.name` or in the `role` attribute
+/// of an element
+fn has_role(input: &ElementSpan, name: &str) -> bool {
+  let shorthand = input
+    .positional_attributes
+    .first()
+    .map(|attribute| attribute.as_str())
+    .unwrap_or("");
+  shorthand
+    .split('%')
+    .next()
+    .unwrap_or("")
+    .split('.')
+    .skip(1)
+    .any(|role| role.split('#').next() == Some(name))
+    || input
+      .get_attribute("role")
+      .is_some_and(|roles| roles.split_whitespace().any(|role| role == name))
+}
+
+/// Checks if the option is set with `%name` or in the `options`
+/// attribute of an element
+fn has_option(input: &ElementSpan, name: &str) -> bool {
+  input.positional_attributes.iter().any(|attribute| {
+    attribute
+      .as_str()
+      .split('%')
+      .skip(1)
+      .any(|option| option == name)
+  }) || input
+    .get_attribute("options")
+    .or_else(|| input.get_attribute("opts"))
+    .is_some_and(|options| options.split(',').any(|option| option.trim() == name))
+}
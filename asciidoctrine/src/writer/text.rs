@@ -0,0 +1,53 @@
+//! Helpers for the writers of formats without html character
+//! references.
+
+/// Replaces the character references (e.g. from the replacements) by
+/// the characters
+pub(crate) fn decode_references(text: &str) -> String {
+  let mut out = String::with_capacity(text.len());
+  let mut rest = text;
+
+  while let Some(start) = rest.find('&') {
+    out.push_str(&rest[..start]);
+    rest = &rest[start..];
+    let decoded = rest[1..]
+      .find(';')
+      .filter(|end| *end <= 10)
+      .and_then(|end| {
+        let name = &rest[1..end + 1];
+        let c = match name.strip_prefix('#') {
+          Some(number) => match number
+            .strip_prefix('x')
+            .or_else(|| number.strip_prefix('X'))
+          {
+            Some(hex) => u32::from_str_radix(hex, 16).ok(),
+            None => number.parse().ok(),
+          }
+          .and_then(char::from_u32),
+          None => match name {
+            "amp" => Some('&'),
+            "lt" => Some('<'),
+            "gt" => Some('>'),
+            "quot" => Some('"'),
+            "apos" => Some('\''),
+            "nbsp" => Some('\u{a0}'),
+            _ => None,
+          },
+        }?;
+        Some((c, end + 2))
+      });
+    match decoded {
+      Some((c, length)) => {
+        out.push(c);
+        rest = &rest[length..];
+      }
+      None => {
+        out.push('&');
+        rest = &rest[1..];
+      }
+    }
+  }
+  out.push_str(rest);
+
+  out
+}
@@ -8,9 +8,10 @@ use pretty_assertions::assert_eq;
 #[test]
 fn parse_empty_document() -> Result<()> {
   let ast = AST {
-    content: "",
+    content: "".into(),
     elements: Vec::new(),
     attributes: Vec::new(),
+    header: DocumentHeader::default(),
   };
 
   let reader = AsciidocReader::new();
@@ -23,9 +24,10 @@ fn parse_empty_document() -> Result<()> {
 #[test]
 fn parse_whitespace_only() -> Result<()> {
   let ast = AST {
-    content: "  ",
+    content: "  ".into(),
     elements: Vec::new(),
     attributes: Vec::new(),
+    header: DocumentHeader::default(),
   };
 
   let reader = AsciidocReader::new();
@@ -42,10 +44,10 @@ fn parse_whitespace_only() -> Result<()> {
 #[test]
 fn parse_basic_header() -> Result<()> {
   let ast = AST {
-    content: "= test\n",
+    content: "= test\n".into(),
     elements: vec![ElementSpan {
       source: None,
-      content: "= test",
+      content: "= test".into(),
       element: Element::Title { level: 1 },
       start: 0,
       end: 6,
@@ -53,7 +55,20 @@ fn parse_basic_header() -> Result<()> {
       start_col: 1,
       end_line: 1,
       end_col: 7,
-      children: Vec::new(),
+      children: vec![ElementSpan {
+        source: None,
+        content: "test".into(),
+        element: Element::Text,
+        start: 2,
+        end: 6,
+        start_line: 1,
+        start_col: 3,
+        end_line: 1,
+        end_col: 7,
+        children: Vec::new(),
+        positional_attributes: Vec::new(),
+        attributes: Vec::new(),
+      }],
       positional_attributes: Vec::new(),
       attributes: vec![Attribute {
         key: "name".to_string(),
@@ -61,6 +76,10 @@ fn parse_basic_header() -> Result<()> {
       }],
     }],
     attributes: Vec::new(),
+    header: DocumentHeader {
+      title: Some("test".to_string()),
+      ..DocumentHeader::default()
+    },
   };
 
   let reader = AsciidocReader::new();
@@ -69,17 +88,70 @@ fn parse_basic_header() -> Result<()> {
   let mut env = util::Env::Cache(util::Cache::new());
   assert_eq!(ast, reader.parse("= test\n", &opts, &mut env)?);
 
-  // TODO author_info, attributes, etc
+  Ok(())
+}
+
+#[test]
+fn parse_header_metadata() -> Result<()> {
+  let content = r#"= Document Title
+Kismet R. Lee <kismet@asciidoctor.org>; B. Steppenwolf
+v1.0, 2024-01-01: First release
+:description: An example
+
+Written by {author} ({revnumber}).
+"#;
+  let header = DocumentHeader {
+    title: Some("Document Title".to_string()),
+    authors: vec![
+      Author {
+        name: "Kismet R. Lee".to_string(),
+        firstname: "Kismet".to_string(),
+        middlename: Some("R.".to_string()),
+        lastname: Some("Lee".to_string()),
+        initials: "KRL".to_string(),
+        email: Some("kismet@asciidoctor.org".to_string()),
+      },
+      Author {
+        name: "B. Steppenwolf".to_string(),
+        firstname: "B.".to_string(),
+        middlename: None,
+        lastname: Some("Steppenwolf".to_string()),
+        initials: "BS".to_string(),
+        email: None,
+      },
+    ],
+    revision: Some(Revision {
+      number: Some("1.0".to_string()),
+      date: Some("2024-01-01".to_string()),
+      remark: Some("First release".to_string()),
+    }),
+  };
+
+  let reader = AsciidocReader::new();
+  let opts = options::Opts::parse_from(vec![""]);
+  let mut env = util::Env::Cache(util::Cache::new());
+  let ast = reader.parse(content, &opts, &mut env)?;
+
+  assert_eq!(header, ast.header);
+  assert_eq!(Some("An example"), ast.get_attribute("description"));
+  let paragraph = &ast.elements[1];
+  let text: Vec<_> = paragraph
+    .children
+    .iter()
+    .map(|child| child.get_attribute("content").unwrap_or(&child.content))
+    .collect();
+  assert_eq!(vec!["Written by Kismet R. Lee (1.0)."], text);
+
   Ok(())
 }
 
 #[test]
 fn parse_title_with_anchor() -> Result<()> {
   let ast = AST {
-    content: "[[test-anchor]]\n== test\n",
+    content: "[[test-anchor]]\n== test\n".into(),
     elements: vec![ElementSpan {
       source: None,
-      content: "[[test-anchor]]\n== test",
+      content: "[[test-anchor]]\n== test".into(),
       element: Element::Title { level: 2 },
       start: 0,
       end: 23,
@@ -87,7 +159,20 @@ fn parse_title_with_anchor() -> Result<()> {
       start_col: 1,
       end_line: 2,
       end_col: 8,
-      children: Vec::new(),
+      children: vec![ElementSpan {
+        source: None,
+        content: "test".into(),
+        element: Element::Text,
+        start: 19,
+        end: 23,
+        start_line: 2,
+        start_col: 4,
+        end_line: 2,
+        end_col: 8,
+        children: Vec::new(),
+        positional_attributes: Vec::new(),
+        attributes: Vec::new(),
+      }],
       positional_attributes: Vec::new(),
       attributes: vec![
         Attribute {
@@ -101,6 +186,7 @@ fn parse_title_with_anchor() -> Result<()> {
       ],
     }],
     attributes: Vec::new(),
+    header: DocumentHeader::default(),
   };
 
   let reader = AsciidocReader::new();
@@ -116,10 +202,10 @@ fn parse_title_with_anchor() -> Result<()> {
 #[test]
 fn parse_atx_header() -> Result<()> {
   let ast = AST {
-    content: "== test\n",
+    content: "== test\n".into(),
     elements: vec![ElementSpan {
       source: None,
-      content: "== test",
+      content: "== test".into(),
       element: Element::Title { level: 2 },
       start: 0,
       end: 7,
@@ -127,7 +213,20 @@ fn parse_atx_header() -> Result<()> {
       start_col: 1,
       end_line: 1,
       end_col: 8,
-      children: Vec::new(),
+      children: vec![ElementSpan {
+        source: None,
+        content: "test".into(),
+        element: Element::Text,
+        start: 3,
+        end: 7,
+        start_line: 1,
+        start_col: 4,
+        end_line: 1,
+        end_col: 8,
+        children: Vec::new(),
+        positional_attributes: Vec::new(),
+        attributes: Vec::new(),
+      }],
       positional_attributes: Vec::new(),
       attributes: vec![Attribute {
         key: "name".to_string(),
@@ -135,6 +234,7 @@ fn parse_atx_header() -> Result<()> {
       }],
     }],
     attributes: Vec::new(),
+    header: DocumentHeader::default(),
   };
 
   let reader = AsciidocReader::new();
@@ -147,10 +247,10 @@ fn parse_atx_header() -> Result<()> {
 #[test]
 fn parse_setext_header() -> Result<()> {
   let ast = AST {
-    content: "test\n====\n",
+    content: "test\n====\n".into(),
     elements: vec![ElementSpan {
       source: None,
-      content: "test\n====",
+      content: "test\n====".into(),
       element: Element::Title { level: 1 },
       start: 0,
       end: 9,
@@ -158,7 +258,20 @@ fn parse_setext_header() -> Result<()> {
       start_col: 1,
       end_line: 2,
       end_col: 5,
-      children: Vec::new(),
+      children: vec![ElementSpan {
+        source: None,
+        content: "test".into(),
+        element: Element::Text,
+        start: 0,
+        end: 4,
+        start_line: 1,
+        start_col: 1,
+        end_line: 1,
+        end_col: 5,
+        children: Vec::new(),
+        positional_attributes: Vec::new(),
+        attributes: Vec::new(),
+      }],
       positional_attributes: Vec::new(),
       attributes: vec![Attribute {
         key: "name".to_string(),
@@ -166,6 +279,10 @@ fn parse_setext_header() -> Result<()> {
       }],
     }],
     attributes: Vec::new(),
+    header: DocumentHeader {
+      title: Some("test".to_string()),
+      ..DocumentHeader::default()
+    },
   };
 
   let reader = AsciidocReader::new();
@@ -196,10 +313,10 @@ require "mytestmodule"
 "#;
 
   let ast = AST {
-    content: input,
+    content: input.into(),
     elements: vec![ElementSpan {
       source: None,
-      content: input.trim(),
+      content: input.trim().into(),
       element: Element::TypedBlock {
         kind: BlockType::Listing,
       },
@@ -223,6 +340,7 @@ require "mytestmodule"
       ],
     }],
     attributes: Vec::new(),
+    header: DocumentHeader::default(),
   };
 
   let reader = AsciidocReader::new();
@@ -263,10 +381,10 @@ require "mytestmodule"
 asciidoctrine dont sees it."#;
 
   let ast = AST {
-    content: input,
+    content: input.into(),
     elements: vec![ElementSpan {
       source: None,
-      content: input.trim(),
+      content: input.trim().into(),
       element: Element::TypedBlock {
         kind: BlockType::Listing,
       },
@@ -293,6 +411,7 @@ asciidoctrine dont sees it."#;
       ],
     }],
     attributes: Vec::new(),
+    header: DocumentHeader::default(),
   };
 
   let reader = AsciidocReader::new();
@@ -306,6 +425,104 @@ asciidoctrine dont sees it."#;
 // Paragraphs
 // --------------------------------------------------------------------------
 
+#[test]
+fn parse_fenced_code() -> Result<()> {
+  let input = r#"
+```rust
+fn main() {}
+```
+"#;
+
+  let ast = AST {
+    content: input.into(),
+    elements: vec![ElementSpan {
+      source: None,
+      content: input.trim().into(),
+      element: Element::TypedBlock {
+        kind: BlockType::Listing,
+      },
+      start: 1,
+      end: 25,
+      start_line: 2,
+      start_col: 1,
+      end_line: 4,
+      end_col: 4,
+      children: Vec::new(),
+      positional_attributes: vec![AttributeValue::Ref("source"), AttributeValue::Ref("rust")],
+      attributes: vec![Attribute {
+        key: "content".to_string(),
+        value: AttributeValue::Ref("fn main() {}"),
+      }],
+    }],
+    attributes: Vec::new(),
+    header: DocumentHeader::default(),
+  };
+
+  let reader = AsciidocReader::new();
+  let opts = options::Opts::parse_from(vec![""]);
+  let mut env = util::Env::Cache(util::Cache::new());
+  assert_eq!(ast, reader.parse(input, &opts, &mut env)?);
+  Ok(())
+}
+
+#[test]
+fn parse_listing_callouts() -> Result<()> {
+  let input = r#"----
+let a = 1; // <.>
+let b = a < 2; # <.> <!--3-->
+println!("<4>");
+----
+<.> First
+<.> Second
+<3> Third"#;
+
+  let reader = AsciidocReader::new();
+  let opts = options::Opts::parse_from(vec![""]);
+  let mut env = util::Env::Cache(util::Cache::new());
+  let ast = reader.parse(input, &opts, &mut env)?;
+
+  let callouts: Vec<_> = ast.elements[0]
+    .children
+    .iter()
+    .map(|callout| {
+      (
+        callout.element.clone(),
+        callout.content.as_ref(),
+        callout.start_line,
+        callout.start_col,
+        callout.get_attribute("line"),
+        callout.get_attribute("column"),
+        callout.get_attribute("anchor"),
+      )
+    })
+    .collect();
+  assert_eq!(
+    callouts,
+    vec![
+      (Element::Callout(1), "// <.>", 2, 12, Some("0"), Some("10"), Some("CO1-1")),
+      (Element::Callout(2), "# <.> <!--3-->", 3, 16, Some("1"), Some("14"), Some("CO1-2")),
+      (Element::Callout(3), "# <.> <!--3-->", 3, 16, Some("1"), Some("14"), Some("CO1-3")),
+    ]
+  );
+
+  let list = &ast.elements[1];
+  assert_eq!(list.element, Element::List(ListType::Callout));
+  let items: Vec<_> = list
+    .children
+    .iter()
+    .map(|item| (item.get_attribute("callout"), item.get_attribute("id")))
+    .collect();
+  assert_eq!(
+    items,
+    vec![
+      (Some("1"), Some("CO1-1")),
+      (Some("2"), Some("CO1-2")),
+      (Some("3"), Some("CO1-3")),
+    ]
+  );
+  Ok(())
+}
+
 #[test]
 fn parse_basic_paragraph_with_links_and_references() -> Result<()> {
   let input = r#"
@@ -315,10 +532,10 @@ it has an internal <<reference>>. Both should be parsed.
 "#;
 
   let ast = AST {
-    content: input,
+    content: input.into(),
     elements: vec![ElementSpan {
       source: None,
-      content: input.trim(),
+      content: input.trim().into(),
       element: Element::Paragraph,
       start: 1,
       end: 148,
@@ -329,7 +546,7 @@ it has an internal <<reference>>. Both should be parsed.
       children: vec![
         ElementSpan {
           source: None,
-          content: "This is a basic paragraph. It has a link to ",
+          content: "This is a basic paragraph. It has a link to ".into(),
           element: Element::Text,
           start: 1,
           end: 45,
@@ -343,7 +560,7 @@ it has an internal <<reference>>. Both should be parsed.
         },
         ElementSpan {
           source: None,
-          content: "https://www.mytestsite.org[A test website]",
+          content: "https://www.mytestsite.org[A test website]".into(),
           element: Element::Link,
           start: 45,
           end: 87,
@@ -366,7 +583,7 @@ it has an internal <<reference>>. Both should be parsed.
         },
         ElementSpan {
           source: None,
-          content: " and\nit has an internal ",
+          content: " and\nit has an internal ".into(),
           element: Element::Text,
           start: 87,
           end: 111,
@@ -380,7 +597,7 @@ it has an internal <<reference>>. Both should be parsed.
         },
         ElementSpan {
           source: None,
-          content: "<<reference>>",
+          content: "<<reference>>".into(),
           element: Element::XRef,
           start: 111,
           end: 124,
@@ -397,7 +614,7 @@ it has an internal <<reference>>. Both should be parsed.
         },
         ElementSpan {
           source: None,
-          content: ". Both should be parsed.",
+          content: ". Both should be parsed.".into(),
           element: Element::Text,
           start: 124,
           end: 148,
@@ -414,6 +631,7 @@ it has an internal <<reference>>. Both should be parsed.
       attributes: Vec::new(),
     }],
     attributes: Vec::new(),
+    header: DocumentHeader::default(),
   };
 
   let reader = AsciidocReader::new();
@@ -423,6 +641,114 @@ it has an internal <<reference>>. Both should be parsed.
   Ok(())
 }
 
+#[test]
+fn parse_inline_macros() -> Result<()> {
+  let input = r#"kbd:[Ctrl++] menu:File[Save] stem:[x] latexmath:[a\]b] (((A, B))) xref:doc.adoc#id[Text]"#;
+
+  let reader = AsciidocReader::new();
+  let opts = options::Opts::parse_from(vec![""]);
+  let mut env = util::Env::Cache(util::Cache::new());
+  let ast = reader.parse(input, &opts, &mut env)?;
+
+  let macros: Vec<_> = ast.elements[0]
+    .children
+    .iter()
+    .filter(|element| element.element != Element::Text)
+    .map(|element| {
+      (
+        element.element.clone(),
+        element.positional_attributes.clone(),
+        element.attributes.clone(),
+      )
+    })
+    .collect();
+  let attribute = |key: &str, value| Attribute {
+    key: key.to_string(),
+    value,
+  };
+  assert_eq!(
+    macros,
+    vec![
+      (
+        Element::Keyboard,
+        vec![AttributeValue::Ref("Ctrl"), AttributeValue::Ref("+")],
+        Vec::new(),
+      ),
+      (
+        Element::Menu,
+        vec![AttributeValue::Ref("Save")],
+        vec![attribute("menu", AttributeValue::Ref("File"))],
+      ),
+      (
+        Element::Stem,
+        Vec::new(),
+        vec![attribute("content", AttributeValue::Ref("x"))],
+      ),
+      (
+        Element::Stem,
+        Vec::new(),
+        vec![
+          attribute("notation", AttributeValue::Ref("latexmath")),
+          attribute("content", AttributeValue::String("a]b".to_string())),
+        ],
+      ),
+      (
+        Element::IndexTerm,
+        vec![AttributeValue::Ref("A"), AttributeValue::Ref("B")],
+        vec![attribute("visible", AttributeValue::String("false".to_string()))],
+      ),
+      (
+        Element::XRef,
+        vec![AttributeValue::Ref("Text")],
+        vec![
+          attribute("path", AttributeValue::Ref("doc.adoc")),
+          attribute("id", AttributeValue::Ref("id")),
+          attribute("content", AttributeValue::String("Text".to_string())),
+        ],
+      ),
+    ]
+  );
+  Ok(())
+}
+
+#[test]
+fn parse_substitutions() -> Result<()> {
+  let input = "Roses (C) +\nare red.\n\n[subs=none]\nRaw *text*";
+
+  let reader = AsciidocReader::new();
+  let opts = options::Opts::parse_from(vec![""]);
+  let mut env = util::Env::Cache(util::Cache::new());
+  let ast = reader.parse(input, &opts, &mut env)?;
+
+  let parts: Vec<_> = ast
+    .elements
+    .iter()
+    .flat_map(|element| element.children.iter())
+    .map(|element| {
+      (
+        element.element.clone(),
+        element.content.as_ref(),
+        element.start,
+        element.start_line,
+        element.start_col,
+        element.get_attribute("content"),
+      )
+    })
+    .collect();
+  assert_eq!(
+    parts,
+    vec![
+      (Element::Text, "Roses (C)", 0, 1, 1, Some("Roses \u{a9}")),
+      (Element::LineBreak, " +", 9, 1, 10, None),
+      (Element::Text, "\nare red.", 11, 1, 12, None),
+      (Element::InlinePassthrough, "Raw ", 34, 5, 1, Some("Raw ")),
+      (Element::InlinePassthrough, "*text*", 38, 5, 5, Some("*text*")),
+    ]
+  );
+
+  Ok(())
+}
+
 #[test]
 fn parse_basic_paragraph_with_inline_anchor() -> Result<()> {
   let input = r#"
@@ -431,10 +757,10 @@ This is a basic paragraph. It has an inline [[myanchor]]`anchor`.
 "#;
 
   let ast = AST {
-    content: input,
+    content: input.into(),
     elements: vec![ElementSpan {
       source: None,
-      content: input.trim(),
+      content: input.trim().into(),
       element: Element::Paragraph,
       start: 1,
       end: 66,
@@ -445,7 +771,7 @@ This is a basic paragraph. It has an inline [[myanchor]]`anchor`.
       children: vec![
         ElementSpan {
           source: None,
-          content: "This is a basic paragraph. It has an inline ",
+          content: "This is a basic paragraph. It has an inline ".into(),
           element: Element::Text,
           start: 1,
           end: 45,
@@ -459,7 +785,7 @@ This is a basic paragraph. It has an inline [[myanchor]]`anchor`.
         },
         ElementSpan {
           source: None,
-          content: "[[myanchor]]`anchor`",
+          content: "[[myanchor]]`anchor`".into(),
           element: Element::Styled,
           start: 45,
           end: 65,
@@ -486,7 +812,7 @@ This is a basic paragraph. It has an inline [[myanchor]]`anchor`.
         },
         ElementSpan {
           source: None,
-          content: ".",
+          content: ".".into(),
           element: Element::Text,
           start: 65,
           end: 66,
@@ -503,6 +829,7 @@ This is a basic paragraph. It has an inline [[myanchor]]`anchor`.
       attributes: Vec::new(),
     }],
     attributes: Vec::new(),
+    header: DocumentHeader::default(),
   };
 
   let reader = AsciidocReader::new();
@@ -530,10 +857,10 @@ fn parse_bullet_list() -> Result<()> {
 "#;
 
   let ast = AST {
-    content: input,
+    content: input.into(),
     elements: vec![ElementSpan {
       source: None,
-      content: input.trim_start(),
+      content: input.trim_start().into(),
       element: Element::List(ListType::Bullet),
       start: 1,
       end: 28,
@@ -544,7 +871,7 @@ fn parse_bullet_list() -> Result<()> {
       children: vec![
         ElementSpan {
           source: None,
-          content: "* Item 1\n",
+          content: "* Item 1\n".into(),
           element: Element::ListItem(1),
           start: 1,
           end: 10,
@@ -554,7 +881,7 @@ fn parse_bullet_list() -> Result<()> {
           end_col: 1,
           children: vec![ElementSpan {
             source: None,
-            content: "Item 1",
+            content: "Item 1".into(),
             start: 3,
             end: 9,
             start_line: 2,
@@ -564,7 +891,7 @@ fn parse_bullet_list() -> Result<()> {
             element: Element::Paragraph,
             children: vec![ElementSpan {
               source: None,
-              content: "Item 1",
+              content: "Item 1".into(),
               start: 3,
               end: 9,
               start_line: 2,
@@ -584,7 +911,7 @@ fn parse_bullet_list() -> Result<()> {
         },
         ElementSpan {
           source: None,
-          content: "* Item 2\n",
+          content: "* Item 2\n".into(),
           element: Element::ListItem(1),
           start: 10,
           end: 19,
@@ -594,7 +921,7 @@ fn parse_bullet_list() -> Result<()> {
           end_col: 1,
           children: vec![ElementSpan {
             source: None,
-            content: "Item 2",
+            content: "Item 2".into(),
             start: 12,
             end: 18,
             start_line: 3,
@@ -604,7 +931,7 @@ fn parse_bullet_list() -> Result<()> {
             element: Element::Paragraph,
             children: vec![ElementSpan {
               source: None,
-              content: "Item 2",
+              content: "Item 2".into(),
               start: 12,
               end: 18,
               start_line: 3,
@@ -624,7 +951,7 @@ fn parse_bullet_list() -> Result<()> {
         },
         ElementSpan {
           source: None,
-          content: "* Item 3\n",
+          content: "* Item 3\n".into(),
           element: Element::ListItem(1),
           start: 19,
           end: 28,
@@ -634,7 +961,7 @@ fn parse_bullet_list() -> Result<()> {
           end_col: 1,
           children: vec![ElementSpan {
             source: None,
-            content: "Item 3",
+            content: "Item 3".into(),
             start: 21,
             end: 27,
             start_line: 4,
@@ -644,7 +971,7 @@ fn parse_bullet_list() -> Result<()> {
             element: Element::Paragraph,
             children: vec![ElementSpan {
               source: None,
-              content: "Item 3",
+              content: "Item 3".into(),
               start: 21,
               end: 27,
               start_line: 4,
@@ -667,6 +994,7 @@ fn parse_bullet_list() -> Result<()> {
       attributes: Vec::new(),
     }],
     attributes: Vec::new(),
+    header: DocumentHeader::default(),
   };
 
   let reader = AsciidocReader::new();
@@ -684,11 +1012,13 @@ fn parse_nested_bullet_list() -> Result<()> {
 ** Item 3
 "#;
 
+  // A new marker always starts a nested list, no matter how long it is
   let ast = AST {
-    content: input,
-    elements: vec![ElementSpan {
+    content: input.into(),
+    elements: vec![
+    ElementSpan {
       source: None,
-      content: input.trim_start(),
+      content: input.trim_start().into(),
       element: Element::List(ListType::Bullet),
       start: 1,
       end: 31,
@@ -697,131 +1027,168 @@ fn parse_nested_bullet_list() -> Result<()> {
       end_line: 5,
       end_col: 1,
       children: vec![
+      ElementSpan {
+        source: None,
+        content: input.trim_start().into(),
+        element: Element::ListItem(1),
+        start: 1,
+        end: 31,
+        start_line: 2,
+        start_col: 1,
+        end_line: 5,
+        end_col: 1,
+        children: vec![
         ElementSpan {
           source: None,
-          content: "* Item 1\n",
-          element: Element::ListItem(1),
-          start: 1,
-          end: 10,
+          content: "Item 1".into(),
+          start: 3,
+          end: 9,
           start_line: 2,
-          start_col: 1,
-          end_line: 3,
-          end_col: 1,
+          start_col: 3,
+          end_line: 2,
+          end_col: 9,
+          element: Element::Paragraph,
           children: vec![ElementSpan {
             source: None,
-            content: "Item 1",
+            content: "Item 1".into(),
             start: 3,
             end: 9,
             start_line: 2,
             start_col: 3,
             end_line: 2,
             end_col: 9,
-            element: Element::Paragraph,
-            children: vec![ElementSpan {
-              source: None,
-              content: "Item 1",
-              start: 3,
-              end: 9,
-              start_line: 2,
-              start_col: 3,
-              end_line: 2,
-              end_col: 9,
-              element: Element::Text,
-              children: vec![],
-              positional_attributes: vec![],
-              attributes: vec![],
-            }],
+            element: Element::Text,
+            children: vec![],
             positional_attributes: vec![],
             attributes: vec![],
           }],
-          positional_attributes: Vec::new(),
-          attributes: Vec::new(),
+          positional_attributes: vec![],
+          attributes: vec![],
         },
         ElementSpan {
           source: None,
-          content: "*** Item 2\n",
-          element: Element::ListItem(3),
+          content: "*** Item 2\n** Item 3\n".into(),
+          element: Element::List(ListType::Bullet),
           start: 10,
-          end: 21,
+          end: 31,
           start_line: 3,
           start_col: 1,
-          end_line: 4,
+          end_line: 5,
           end_col: 1,
-          children: vec![ElementSpan {
+          children: vec![
+          ElementSpan {
             source: None,
-            content: "Item 2",
-            start: 14,
-            end: 20,
+            content: "*** Item 2\n** Item 3\n".into(),
+            element: Element::ListItem(2),
+            start: 10,
+            end: 31,
             start_line: 3,
-            start_col: 5,
-            end_line: 3,
-            end_col: 11,
-            element: Element::Paragraph,
-            children: vec![ElementSpan {
+            start_col: 1,
+            end_line: 5,
+            end_col: 1,
+            children: vec![
+            ElementSpan {
               source: None,
-              content: "Item 2",
+              content: "Item 2".into(),
               start: 14,
               end: 20,
               start_line: 3,
               start_col: 5,
               end_line: 3,
               end_col: 11,
-              element: Element::Text,
-              children: vec![],
+              element: Element::Paragraph,
+              children: vec![ElementSpan {
+                source: None,
+                content: "Item 2".into(),
+                start: 14,
+                end: 20,
+                start_line: 3,
+                start_col: 5,
+                end_line: 3,
+                end_col: 11,
+                element: Element::Text,
+                children: vec![],
+                positional_attributes: vec![],
+                attributes: vec![],
+              }],
               positional_attributes: vec![],
               attributes: vec![],
-            }],
-            positional_attributes: vec![],
-            attributes: vec![],
-          }],
-          positional_attributes: Vec::new(),
-          attributes: Vec::new(),
-        },
-        ElementSpan {
-          source: None,
-          content: "** Item 3\n",
-          element: Element::ListItem(2),
-          start: 21,
-          end: 31,
-          start_line: 4,
-          start_col: 1,
-          end_line: 5,
-          end_col: 1,
-          children: vec![ElementSpan {
-            source: None,
-            content: "Item 3",
-            start: 24,
-            end: 30,
-            start_line: 4,
-            start_col: 4,
-            end_line: 4,
-            end_col: 10,
-            element: Element::Paragraph,
-            children: vec![ElementSpan {
+            },
+            ElementSpan {
               source: None,
-              content: "Item 3",
-              start: 24,
-              end: 30,
+              content: "** Item 3\n".into(),
+              element: Element::List(ListType::Bullet),
+              start: 21,
+              end: 31,
               start_line: 4,
-              start_col: 4,
-              end_line: 4,
-              end_col: 10,
-              element: Element::Text,
-              children: vec![],
-              positional_attributes: vec![],
-              attributes: vec![],
-            }],
-            positional_attributes: vec![],
-            attributes: vec![],
-          }],
+              start_col: 1,
+              end_line: 5,
+              end_col: 1,
+              children: vec![
+              ElementSpan {
+                source: None,
+                content: "** Item 3\n".into(),
+                element: Element::ListItem(3),
+                start: 21,
+                end: 31,
+                start_line: 4,
+                start_col: 1,
+                end_line: 5,
+                end_col: 1,
+                children: vec![
+                ElementSpan {
+                  source: None,
+                  content: "Item 3".into(),
+                  start: 24,
+                  end: 30,
+                  start_line: 4,
+                  start_col: 4,
+                  end_line: 4,
+                  end_col: 10,
+                  element: Element::Paragraph,
+                  children: vec![ElementSpan {
+                    source: None,
+                    content: "Item 3".into(),
+                    start: 24,
+                    end: 30,
+                    start_line: 4,
+                    start_col: 4,
+                    end_line: 4,
+                    end_col: 10,
+                    element: Element::Text,
+                    children: vec![],
+                    positional_attributes: vec![],
+                    attributes: vec![],
+                  }],
+                  positional_attributes: vec![],
+                  attributes: vec![],
+                },
+                ],
+                positional_attributes: Vec::new(),
+                attributes: Vec::new(),
+              },
+              ],
+              positional_attributes: Vec::new(),
+              attributes: Vec::new(),
+            },
+            ],
+            positional_attributes: Vec::new(),
+            attributes: Vec::new(),
+          },
+          ],
           positional_attributes: Vec::new(),
           attributes: Vec::new(),
         },
+        ],
+        positional_attributes: Vec::new(),
+        attributes: Vec::new(),
+      },
       ],
       positional_attributes: Vec::new(),
       attributes: Vec::new(),
     }],
     attributes: Vec::new(),
+    header: DocumentHeader::default(),
   };
 
   let reader = AsciidocReader::new();
@@ -853,11 +1220,11 @@ This is some outro text.
 "#;
 
   let ast = AST {
-    content: input,
+    content: input.into(),
     elements: vec![
       ElementSpan {
         source: None,
-        content: "This is some intro text.",
+        content: "This is some intro text.".into(),
         element: Element::Paragraph,
         start: 0,
         end: 24,
@@ -867,7 +1234,7 @@ This is some outro text.
         end_col: 25,
         children: vec![ElementSpan {
           source: None,
-          content: "This is some intro text.",
+          content: "This is some intro text.".into(),
           element: Element::Text,
           start: 0,
           end: 24,
@@ -884,14 +1251,14 @@ This is some outro text.
       },
       ElementSpan {
         source: None,
-        content: "include::included.adoc[]",
+        content: "include::included.adoc[]".into(),
         element: Element::IncludeElement(IncludeElement::from_data(
           included_content.to_string(),
           AST {
-            content: included_content,
+            content: included_content.into(),
             elements: vec![ElementSpan {
               source: None,
-              content: "This is included content.",
+              content: "This is included content.".into(),
               element: Element::Paragraph,
               start: 0,
               end: 25,
@@ -901,7 +1268,7 @@ This is some outro text.
               end_col: 26,
               children: vec![ElementSpan {
                 source: None,
-                content: "This is included content.",
+                content: "This is included content.".into(),
                 element: Element::Text,
                 start: 0,
                 end: 25,
@@ -920,6 +1287,7 @@ This is some outro text.
               key: "source".to_string(),
               value: AttributeValue::String("included.adoc".to_string()),
             }],
+            header: DocumentHeader::default(),
           },
         )),
         start: 26,
@@ -937,7 +1305,7 @@ This is some outro text.
       },
       ElementSpan {
         source: None,
-        content: "This is some outro text.",
+        content: "This is some outro text.".into(),
         element: Element::Paragraph,
         start: 52,
         end: 76,
@@ -947,7 +1315,7 @@ This is some outro text.
         end_col: 25,
         children: vec![ElementSpan {
           source: None,
-          content: "This is some outro text.",
+          content: "This is some outro text.".into(),
           element: Element::Text,
           start: 52,
           end: 76,
@@ -964,6 +1332,7 @@ This is some outro text.
       },
     ],
     attributes: Vec::new(),
+    header: DocumentHeader::default(),
   };
 
   let reader = AsciidocReader::new();
@@ -974,3 +1343,263 @@ This is some outro text.
   Ok(())
 }
 
+
+fn paragraph_text<'a>(ast: &'a AST) -> &'a str {
+  let text = &ast.elements[0].children[0];
+  text.get_attribute("content").unwrap_or(&text.content)
+}
+
+#[test]
+fn parse_attribute_defines() -> Result<()> {
+  let input = r#":version: 1.0
+:edition: first
+
+{version} {edition}"#;
+
+  let reader = AsciidocReader::new();
+  let opts = options::Opts::parse_from(vec![
+    "asciidoctrine",
+    "-a",
+    "version=2.0",
+    "-a",
+    "edition=draft@",
+  ]);
+  let mut env = util::Env::Cache(util::Cache::new());
+  let ast = reader.parse(input, &opts, &mut env)?;
+
+  // Hard defines win over the document, soft defines don't
+  assert_eq!(ast.get_attribute("version"), Some("2.0"));
+  assert_eq!(ast.get_attribute("edition"), Some("first"));
+  assert_eq!(paragraph_text(&ast), "2.0 first");
+  Ok(())
+}
+
+#[test]
+fn parse_attributes_in_links() -> Result<()> {
+  let input = r#":version: 1.0
+
+link:https://example.org/{version}/[Release {version}] or https://example.org/{version}/notes[]"#;
+
+  let reader = AsciidocReader::new();
+  let opts = options::Opts::parse_from(vec!["asciidoctrine"]);
+  let mut env = util::Env::Cache(util::Cache::new());
+  let ast = reader.parse(input, &opts, &mut env)?;
+
+  let urls: Vec<_> = ast.elements[0]
+    .children
+    .iter()
+    .filter(|element| element.element == Element::Link)
+    .map(|element| element.get_attribute("url"))
+    .collect();
+  assert_eq!(
+    urls,
+    vec![
+      Some("https://example.org/1.0/"),
+      Some("https://example.org/1.0/notes")
+    ]
+  );
+  Ok(())
+}
+
+#[test]
+fn parse_attribute_shorthands() -> Result<()> {
+  let input = r#"[quote#einstein.wise.short%unbreakable, Albert Einstein]
+____
+Imagination is more important than knowledge.
+____"#;
+
+  let reader = AsciidocReader::new();
+  let opts = options::Opts::parse_from(vec!["asciidoctrine"]);
+  let mut env = util::Env::Cache(util::Cache::new());
+  let ast = reader.parse(input, &opts, &mut env)?;
+
+  let quote = &ast.elements[0];
+  assert_eq!(quote.get_attribute("anchor"), Some("einstein"));
+  assert_eq!(quote.get_attribute("role"), Some("wise short"));
+  assert_eq!(
+    quote.positional_attributes,
+    vec![
+      AttributeValue::String("quote%unbreakable".to_string()),
+      AttributeValue::Ref("Albert Einstein")
+    ]
+  );
+  Ok(())
+}
+
+#[test]
+fn parse_attribute_unset_define() -> Result<()> {
+  let input = r#":draft: yes
+
+{draft}"#;
+
+  let reader = AsciidocReader::new();
+  let opts = options::Opts::parse_from(vec!["asciidoctrine", "-a", "draft!"]);
+  let mut env = util::Env::Cache(util::Cache::new());
+  let ast = reader.parse(input, &opts, &mut env)?;
+
+  assert_eq!(ast.get_attribute("draft"), None);
+  assert_eq!(paragraph_text(&ast), "{draft}");
+  Ok(())
+}
+
+#[test]
+fn parse_builtin_attributes() -> Result<()> {
+  let input = r#"{docdir}/images/{docname}.png {startsb}\{docdir}{endsb}"#;
+
+  let reader = AsciidocReader::new();
+  let opts = options::Opts::parse_from(vec!["asciidoctrine", "docs/manual.adoc"]);
+  let mut env = util::Env::Cache(util::Cache::new());
+  let ast = reader.parse(input, &opts, &mut env)?;
+
+  let docdir = std::env::current_dir()?.join("docs");
+  assert_eq!(
+    paragraph_text(&ast),
+    format!("{}/images/manual.png [{{docdir}}]", docdir.display())
+  );
+
+  let ast = reader.parse("{localdate}", &opts, &mut env)?;
+  let localdate = paragraph_text(&ast);
+  assert_eq!(localdate.len(), 10);
+  assert!(localdate.chars().all(|c| c.is_ascii_digit() || c == '-'));
+  Ok(())
+}
+
+#[test]
+fn parse_conditionals_drop_wrong_directives() -> Result<()> {
+  let input = r#"endif::[]
+ifdef::draft[]
+Draft notes.
+endif::final[]
+endif::draft[]
+ifeval::draft[1 == 1]
+ifeval::[1 ==]
+Hidden text.
+endif::[]
+ifdef::[]
+ifdef::a b[]
+
+Final text."#;
+
+  let reader = AsciidocReader::new();
+  let opts = options::Opts::parse_from(vec!["asciidoctrine", "-a", "draft"]);
+  let mut env = util::Env::Cache(util::Cache::new());
+  let ast = reader.parse(input, &opts, &mut env)?;
+
+  let paragraphs: Vec<_> = ast
+    .elements
+    .iter()
+    .map(|element| element.content.as_ref())
+    .collect();
+  assert_eq!(paragraphs, vec!["Draft notes.", "Final text."]);
+  Ok(())
+}
+
+#[test]
+fn parse_conditionals_keep_source_positions() -> Result<()> {
+  let input = r#"ifdef::draft[]
+Draft notes.
+endif::[]
+ifndef::draft[Final text.]
+
+Second paragraph."#;
+
+  let reader = AsciidocReader::new();
+  let opts = options::Opts::parse_from(vec!["asciidoctrine"]);
+  let mut env = util::Env::Cache(util::Cache::new());
+  let ast = reader.parse(input, &opts, &mut env)?;
+
+  let positions: Vec<_> = ast
+    .elements
+    .iter()
+    .map(|element| {
+      (
+        element.content.as_ref(),
+        element.start,
+        element.start_line,
+        element.start_col,
+        element.end_line,
+      )
+    })
+    .collect();
+  assert_eq!(
+    positions,
+    vec![("Final text.", 52, 4, 15, 4), ("Second paragraph.", 66, 6, 1, 6)]
+  );
+  assert_eq!(&input[52..63], "Final text.");
+  Ok(())
+}
+
+#[test]
+fn owned_ast_equals_the_borrowed_one() -> Result<()> {
+  let input = r#"= Document
+:draft:
+
+ifdef::draft[]
+A "quoted" text with a \ backslash.
+endif::[]
+
+* first
+* second"#;
+
+  let reader = AsciidocReader::new();
+  let opts = options::Opts::parse_from(vec!["asciidoctrine"]);
+  let mut env = util::Env::Cache(util::Cache::new());
+  let ast = reader.parse(input, &opts, &mut env)?;
+
+  let owned: AST<'static> = ast.clone().into_owned();
+  assert_eq!(owned, ast);
+
+  // The json reader keeps strings with escapes
+  let mut json = Vec::new();
+  JsonWriter::new().write(ast.clone(), &opts, &mut json)?;
+  let json = String::from_utf8(json)?;
+  let read = JsonReader::new().parse(&json, &opts, &mut env)?;
+  assert_eq!(read, ast);
+  Ok(())
+}
+
+struct Appendix {}
+
+impl Extension for Appendix {
+  fn transform<'a>(&mut self, input: AST<'a>) -> anyhow::Result<AST<'a>> {
+    let mut ast = input;
+    let text = format!("{} elements before.", ast.elements.len());
+    let paragraph = ElementSpan {
+      source: None,
+      content: text.clone().into(),
+      element: Element::Paragraph,
+      start: 0,
+      end: 0,
+      start_line: 0,
+      start_col: 0,
+      end_line: 0,
+      end_col: 0,
+      children: Vec::new(),
+      positional_attributes: Vec::new(),
+      attributes: Vec::new(),
+    };
+    let child = ElementSpan {
+      element: Element::Text,
+      ..paragraph.clone()
+    };
+    ast.elements.push(paragraph.add_child(child));
+    Ok(ast)
+  }
+}
+
+#[test]
+fn extensions_insert_generated_elements() -> Result<()> {
+  let reader = AsciidocReader::new();
+  let opts = options::Opts::parse_from(vec!["asciidoctrine", "--template", "-"]);
+  let mut env = util::Env::Cache(util::Cache::new());
+  let ast = reader.parse("First paragraph.", &opts, &mut env)?;
+  let ast = Appendix {}.transform(ast)?;
+
+  let mut html = Vec::new();
+  HtmlWriter::new().write(ast, &opts, &mut html)?;
+  let html = String::from_utf8(html)?;
+
+  assert!(html.contains("<p>First paragraph.</p>"));
+  assert!(html.contains("<p>1 elements before.</p>"));
+  Ok(())
+}
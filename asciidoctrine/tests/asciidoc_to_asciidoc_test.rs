@@ -237,6 +237,96 @@ fn numbered_list_nested() -> Result<()> {
   Ok(())
 }
 
+#[test]
+fn numbered_list_start() -> Result<()> {
+  let content = r#"[%reversed,start=3]
+. Three
+. Two"#;
+
+  let reader = AsciidocReader::new();
+  let opts = options::Opts::parse_from(vec!["asciidoctrine"]);
+  let mut env = util::Env::Cache(util::Cache::new());
+  let ast = reader.parse(content, &opts, &mut env)?;
+
+  let mut buf = BufWriter::new(Vec::new());
+  let mut writer = AsciidocWriter::new();
+  writer.write(ast, &opts, &mut buf)?;
+
+  let output = String::from_utf8(buf.into_inner()?)?;
+  assert_eq!(output, content);
+
+  Ok(())
+}
+
+#[test]
+fn checklist() -> Result<()> {
+  let content = r#"* [x] Done
+* [ ] Open"#;
+
+  let reader = AsciidocReader::new();
+  let opts = options::Opts::parse_from(vec!["asciidoctrine"]);
+  let mut env = util::Env::Cache(util::Cache::new());
+  let ast = reader.parse(content, &opts, &mut env)?;
+
+  let mut buf = BufWriter::new(Vec::new());
+  let mut writer = AsciidocWriter::new();
+  writer.write(ast, &opts, &mut buf)?;
+
+  let output = String::from_utf8(buf.into_inner()?)?;
+  assert_eq!(output, content);
+
+  Ok(())
+}
+
+#[test]
+fn description_list() -> Result<()> {
+  let content = r#"CPU:: The brain
+RAM:: The memory
+* Volatile"#;
+
+  let reader = AsciidocReader::new();
+  let opts = options::Opts::parse_from(vec!["asciidoctrine"]);
+  let mut env = util::Env::Cache(util::Cache::new());
+  let ast = reader.parse(content, &opts, &mut env)?;
+
+  let mut buf = BufWriter::new(Vec::new());
+  let mut writer = AsciidocWriter::new();
+  writer.write(ast, &opts, &mut buf)?;
+
+  let output = String::from_utf8(buf.into_inner()?)?;
+  assert_eq!(output, content);
+
+  Ok(())
+}
+
+#[test]
+fn list_continuation() -> Result<()> {
+  let content = r#"* Install it
++
+----
+cargo install asciidoctrine
+----
++
+Then run it.
+* Done
+
+After the list."#;
+
+  let reader = AsciidocReader::new();
+  let opts = options::Opts::parse_from(vec!["asciidoctrine"]);
+  let mut env = util::Env::Cache(util::Cache::new());
+  let ast = reader.parse(content, &opts, &mut env)?;
+
+  let mut buf = BufWriter::new(Vec::new());
+  let mut writer = AsciidocWriter::new();
+  writer.write(ast, &opts, &mut buf)?;
+
+  let output = String::from_utf8(buf.into_inner()?)?;
+  assert_eq!(output, content);
+
+  Ok(())
+}
+
 #[test]
 fn code_block_listing() -> Result<()> {
   let content = r#"[source,bash]
@@ -260,6 +350,35 @@ ls -la
   Ok(())
 }
 
+#[test]
+fn code_block_callouts() -> Result<()> {
+  let content = r#"[source,ruby]
+----
+require 'sinatra' # <1>
+
+get '/hi' do # <2> <3>
+  "Hello World!"
+end
+----
+<1> Library import
+<2> URL mapping
+<3> Response block"#;
+
+  let reader = AsciidocReader::new();
+  let opts = options::Opts::parse_from(vec!["asciidoctrine"]);
+  let mut env = util::Env::Cache(util::Cache::new());
+  let ast = reader.parse(content, &opts, &mut env)?;
+
+  let mut buf = BufWriter::new(Vec::new());
+  let mut writer = AsciidocWriter::new();
+  writer.write(ast, &opts, &mut buf)?;
+
+  let output = String::from_utf8(buf.into_inner()?)?;
+  assert_eq!(output, content);
+
+  Ok(())
+}
+
 #[test]
 fn simple_table() -> Result<()> {
   let content = r#"|===
@@ -309,6 +428,315 @@ fn link_inline() -> Result<()> {
   Ok(())
 }
 
+#[test]
+fn inline_macros() -> Result<()> {
+  let content = r#"Press kbd:[Ctrl+S] or btn:[Save] and select menu:File[Export > PDF].
+The image:play.png[Play,16,16] icon, +++<u>raw</u>+++ and pass:[<del>x</del>] text,
+stem:[sqrt(4)] and latexmath:[\alpha] with ((Linux))(((Kernel, Linux))) terms.
+See link:index.html[the index], mailto:join@example.org[the list,Subscribe me],
+<<install,the installation>> and xref:usage.adoc#options[the options]."#;
+
+  let reader = AsciidocReader::new();
+  let opts = options::Opts::parse_from(vec!["asciidoctrine"]);
+  let mut env = util::Env::Cache(util::Cache::new());
+  let ast = reader.parse(content, &opts, &mut env)?;
+
+  let mut buf = BufWriter::new(Vec::new());
+  let mut writer = AsciidocWriter::new();
+  writer.write(ast, &opts, &mut buf)?;
+
+  let output = String::from_utf8(buf.into_inner()?)?;
+  assert_eq!(output, content);
+
+  Ok(())
+}
+
+#[test]
+fn index_term_macros() -> Result<()> {
+  let content = r#"The indexterm:[Kernel, Linux] and indexterm2:[Linux] terms."#;
+
+  let reader = AsciidocReader::new();
+  let opts = options::Opts::parse_from(vec!["asciidoctrine"]);
+  let mut env = util::Env::Cache(util::Cache::new());
+  let ast = reader.parse(content, &opts, &mut env)?;
+
+  let mut buf = BufWriter::new(Vec::new());
+  let mut writer = AsciidocWriter::new();
+  writer.write(ast, &opts, &mut buf)?;
+
+  let output = String::from_utf8(buf.into_inner()?)?;
+  assert_eq!(output, "The (((Kernel, Linux))) and ((Linux)) terms.");
+
+  Ok(())
+}
+
+#[test]
+fn substitutions() -> Result<()> {
+  let content = r#"It's a "`quoted`" text -- with (C) +
+a break.
+
+[%hardbreaks]
+First line
+second line
+
+[subs="+quotes,-callouts"]
+----
+Some *bold* code <1>
+----"#;
+
+  let reader = AsciidocReader::new();
+  let opts = options::Opts::parse_from(vec!["asciidoctrine"]);
+  let mut env = util::Env::Cache(util::Cache::new());
+  let ast = reader.parse(content, &opts, &mut env)?;
+
+  let mut buf = BufWriter::new(Vec::new());
+  let mut writer = AsciidocWriter::new();
+  writer.write(ast, &opts, &mut buf)?;
+
+  let output = String::from_utf8(buf.into_inner()?)?;
+  assert_eq!(output, content);
+
+  Ok(())
+}
+
+#[test]
+fn admonition_paragraph() -> Result<()> {
+  let content = "NOTE: Remember to *save* your work.";
+
+  let reader = AsciidocReader::new();
+  let opts = options::Opts::parse_from(vec!["asciidoctrine"]);
+  let mut env = util::Env::Cache(util::Cache::new());
+  let ast = reader.parse(content, &opts, &mut env)?;
+
+  let mut buf = BufWriter::new(Vec::new());
+  let mut writer = AsciidocWriter::new();
+  writer.write(ast, &opts, &mut buf)?;
+
+  let output = String::from_utf8(buf.into_inner()?)?;
+  assert_eq!(output, content);
+
+  Ok(())
+}
+
+#[test]
+fn admonition_block() -> Result<()> {
+  let content = r#"[TIP]
+====
+A tip can span
+
+multiple paragraphs.
+===="#;
+
+  let reader = AsciidocReader::new();
+  let opts = options::Opts::parse_from(vec!["asciidoctrine"]);
+  let mut env = util::Env::Cache(util::Cache::new());
+  let ast = reader.parse(content, &opts, &mut env)?;
+
+  let mut buf = BufWriter::new(Vec::new());
+  let mut writer = AsciidocWriter::new();
+  writer.write(ast, &opts, &mut buf)?;
+
+  let output = String::from_utf8(buf.into_inner()?)?;
+  assert_eq!(output, content);
+
+  Ok(())
+}
+
+#[test]
+fn sidebar_block() -> Result<()> {
+  let content = r#".Background
+****
+Sidebars hold additional information.
+****"#;
+
+  let reader = AsciidocReader::new();
+  let opts = options::Opts::parse_from(vec!["asciidoctrine"]);
+  let mut env = util::Env::Cache(util::Cache::new());
+  let ast = reader.parse(content, &opts, &mut env)?;
+
+  let mut buf = BufWriter::new(Vec::new());
+  let mut writer = AsciidocWriter::new();
+  writer.write(ast, &opts, &mut buf)?;
+
+  let output = String::from_utf8(buf.into_inner()?)?;
+  assert_eq!(output, content);
+
+  Ok(())
+}
+
+#[test]
+fn id_and_role_shorthand() -> Result<()> {
+  let content = r#"[#c2.big]
+== Custom2
+
+[#lst.role]
+----
+x
+----
+
+[source#l2.a.b,python]
+----
+y
+----
+
+[.lead]
+Para."#;
+
+  let reader = AsciidocReader::new();
+  let opts = options::Opts::parse_from(vec!["asciidoctrine"]);
+  let mut env = util::Env::Cache(util::Cache::new());
+  let ast = reader.parse(content, &opts, &mut env)?;
+
+  let mut buf = BufWriter::new(Vec::new());
+  let mut writer = AsciidocWriter::new();
+  writer.write(ast, &opts, &mut buf)?;
+
+  let output = String::from_utf8(buf.into_inner()?)?;
+  assert_eq!(output, content);
+
+  Ok(())
+}
+
+#[test]
+fn quote_block() -> Result<()> {
+  let content = r#"[quote,Abraham Lincoln,Gettysburg Address]
+____
+Four score and seven years ago
+____"#;
+
+  let reader = AsciidocReader::new();
+  let opts = options::Opts::parse_from(vec!["asciidoctrine"]);
+  let mut env = util::Env::Cache(util::Cache::new());
+  let ast = reader.parse(content, &opts, &mut env)?;
+
+  let mut buf = BufWriter::new(Vec::new());
+  let mut writer = AsciidocWriter::new();
+  writer.write(ast, &opts, &mut buf)?;
+
+  let output = String::from_utf8(buf.into_inner()?)?;
+  assert_eq!(output, content);
+
+  Ok(())
+}
+
+#[test]
+fn verse_block() -> Result<()> {
+  let content = r#"[verse,Carl Sandburg,Fog]
+____
+The fog comes
+on little cat feet.
+____"#;
+
+  let reader = AsciidocReader::new();
+  let opts = options::Opts::parse_from(vec!["asciidoctrine"]);
+  let mut env = util::Env::Cache(util::Cache::new());
+  let ast = reader.parse(content, &opts, &mut env)?;
+
+  let mut buf = BufWriter::new(Vec::new());
+  let mut writer = AsciidocWriter::new();
+  writer.write(ast, &opts, &mut buf)?;
+
+  let output = String::from_utf8(buf.into_inner()?)?;
+  assert_eq!(output, content);
+
+  Ok(())
+}
+
+#[test]
+fn open_block() -> Result<()> {
+  let content = r#"--
+An open block
+
+groups paragraphs.
+--"#;
+
+  let reader = AsciidocReader::new();
+  let opts = options::Opts::parse_from(vec!["asciidoctrine"]);
+  let mut env = util::Env::Cache(util::Cache::new());
+  let ast = reader.parse(content, &opts, &mut env)?;
+
+  let mut buf = BufWriter::new(Vec::new());
+  let mut writer = AsciidocWriter::new();
+  writer.write(ast, &opts, &mut buf)?;
+
+  let output = String::from_utf8(buf.into_inner()?)?;
+  assert_eq!(output, content);
+
+  Ok(())
+}
+
+#[test]
+fn passthrough_block() -> Result<()> {
+  let content = r#"++++
+<span class="badge">raw html</span>
+++++"#;
+
+  let reader = AsciidocReader::new();
+  let opts = options::Opts::parse_from(vec!["asciidoctrine"]);
+  let mut env = util::Env::Cache(util::Cache::new());
+  let ast = reader.parse(content, &opts, &mut env)?;
+
+  let mut buf = BufWriter::new(Vec::new());
+  let mut writer = AsciidocWriter::new();
+  writer.write(ast, &opts, &mut buf)?;
+
+  let output = String::from_utf8(buf.into_inner()?)?;
+  assert_eq!(output, content);
+
+  Ok(())
+}
+
+#[test]
+fn header_attributes() -> Result<()> {
+  let content = r#"= Document Title
+:version: 1.0
+:draft!:
+
+Version {version}.
+
+:version: 1.1
+
+Version {version}."#;
+
+  let reader = AsciidocReader::new();
+  let opts = options::Opts::parse_from(vec!["asciidoctrine"]);
+  let mut env = util::Env::Cache(util::Cache::new());
+  let ast = reader.parse(content, &opts, &mut env)?;
+
+  let mut buf = BufWriter::new(Vec::new());
+  let mut writer = AsciidocWriter::new();
+  writer.write(ast, &opts, &mut buf)?;
+
+  let output = String::from_utf8(buf.into_inner()?)?;
+  assert_eq!(output, content);
+
+  Ok(())
+}
+
+#[test]
+fn header_author_and_revision() -> Result<()> {
+  let content = r#"= Document Title
+Kismet R. Lee <kismet@asciidoctor.org>; B. Steppenwolf
+v1.0, 2024-01-01: First release
+:description: An example
+
+Written by {author}."#;
+
+  let reader = AsciidocReader::new();
+  let opts = options::Opts::parse_from(vec!["asciidoctrine"]);
+  let mut env = util::Env::Cache(util::Cache::new());
+  let ast = reader.parse(content, &opts, &mut env)?;
+
+  let mut buf = BufWriter::new(Vec::new());
+  let mut writer = AsciidocWriter::new();
+  writer.write(ast, &opts, &mut buf)?;
+
+  let output = String::from_utf8(buf.into_inner()?)?;
+  assert_eq!(output, content);
+
+  Ok(())
+}
+
 #[test]
 fn round_trip_complex_document() -> Result<()> {
   let content = r#"= Document Title
@@ -0,0 +1,355 @@
+mod common;
+
+use anyhow::Result;
+use asciidoctrine::{self, *};
+use pretty_assertions::assert_eq;
+use quick_xml::events::Event;
+
+fn to_docbook(content: &str) -> Result<String> {
+  common::convert_to_string(&mut DocbookWriter::default(), "docbook", content, &[])
+}
+
+/// Parses the xml and returns the paths of all elements below the
+/// article. Fails if the xml isn't well-formed.
+fn element_paths(xml: &str) -> Result<Vec<String>> {
+  let mut reader = quick_xml::Reader::from_str(xml);
+  let mut stack: Vec<String> = Vec::new();
+  let mut paths = Vec::new();
+
+  loop {
+    match reader.read_event()? {
+      Event::Start(tag) => {
+        stack.push(String::from_utf8(tag.name().as_ref().to_vec())?);
+        paths.push(stack.join("/"));
+      }
+      Event::Empty(tag) => {
+        let name = String::from_utf8(tag.name().as_ref().to_vec())?;
+        paths.push(format!("{}/{}", stack.join("/"), name));
+      }
+      Event::End(_) => {
+        stack.pop();
+      }
+      Event::Eof => break,
+      _ => (),
+    }
+  }
+  assert!(stack.is_empty(), "unclosed elements {:?}", stack);
+
+  Ok(
+    paths
+      .into_iter()
+      .filter_map(|path| path.strip_prefix("article/").map(str::to_string))
+      .collect(),
+  )
+}
+
+#[test]
+fn article_with_header() -> Result<()> {
+  let content = r#"= Document Title
+Jane Doe <jane@example.org>
+v1.0, 2024-01-02: First draft
+:lang: de
+
+A paragraph."#;
+  let output = to_docbook(content)?;
+  let paths = element_paths(&output)?;
+
+  assert!(output.starts_with("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n"));
+  assert!(output.contains(
+    "<article xmlns=\"http://docbook.org/ns/docbook\" xmlns:xl=\"http://www.w3.org/1999/xlink\" version=\"5.0\" xml:lang=\"de\">"
+  ));
+  assert!(output.contains("<info>\n<title>Document Title</title>\n<date>2024-01-02</date>\n"));
+  assert!(output.contains("<firstname>Jane</firstname>\n<surname>Doe</surname>"));
+  assert!(output.contains("<email>jane@example.org</email>"));
+  assert!(output.contains("<revnumber>1.0</revnumber>"));
+  assert!(output.contains("<revremark>First draft</revremark>"));
+  // The title is only part of the info
+  assert_eq!(
+    paths,
+    vec![
+      "info",
+      "info/title",
+      "info/date",
+      "info/author",
+      "info/author/personname",
+      "info/author/personname/firstname",
+      "info/author/personname/surname",
+      "info/author/email",
+      "info/authorinitials",
+      "info/revhistory",
+      "info/revhistory/revision",
+      "info/revhistory/revision/revnumber",
+      "info/revhistory/revision/date",
+      "info/revhistory/revision/authorinitials",
+      "info/revhistory/revision/revremark",
+      "simpara",
+    ]
+  );
+
+  Ok(())
+}
+
+#[test]
+fn nested_sections() -> Result<()> {
+  let content = r#"= Document Title
+
+Preamble
+
+== First
+
+Text
+
+=== Nested
+
+More text
+
+[[second]]
+== Second"#;
+  let output = to_docbook(content)?;
+  let paths = element_paths(&output)?;
+
+  assert_eq!(
+    paths[2..],
+    [
+      "simpara",
+      "section",
+      "section/title",
+      "section/simpara",
+      "section/section",
+      "section/section/title",
+      "section/section/simpara",
+      "section",
+      "section/title",
+    ]
+  );
+  assert!(output.contains("<section xml:id=\"_first\">\n<title>First</title>"));
+  assert!(output.contains("<section xml:id=\"_nested\">\n<title>Nested</title>"));
+  assert!(output.contains("<section xml:id=\"second\">\n<title>Second</title>"));
+
+  Ok(())
+}
+
+#[test]
+fn inline_formatting() -> Result<()> {
+  let content =
+    "Some *strong*, _emphasized_ and `monospaced` text with <markup> & an &#169; entity.";
+  let output = to_docbook(content)?;
+  element_paths(&output)?;
+
+  assert!(output.contains(
+    "<simpara>Some <emphasis role=\"strong\">strong</emphasis>, <emphasis>emphasized</emphasis> and <literal>monospaced</literal> text with &lt;markup&gt; &amp; an &#169; entity.</simpara>"
+  ));
+
+  Ok(())
+}
+
+#[test]
+fn bullet_and_ordered_lists() -> Result<()> {
+  let content = r#"* One
+* Two
+** Nested
+
+[start=3]
+. Three
+. Four"#;
+  let output = to_docbook(content)?;
+  let paths = element_paths(&output)?;
+
+  assert!(paths.contains(&"itemizedlist/listitem/itemizedlist/listitem/simpara".to_string()));
+  assert!(output.contains("<orderedlist numeration=\"arabic\" startingnumber=\"3\">"));
+  assert!(output.contains("<listitem>\n<simpara>Four</simpara>\n</listitem>"));
+
+  Ok(())
+}
+
+#[test]
+fn checklist() -> Result<()> {
+  let content = r#"* [x] Done
+* [ ] Open"#;
+  let output = to_docbook(content)?;
+  element_paths(&output)?;
+
+  assert!(output.contains("<itemizedlist role=\"checklist\">"));
+  assert!(output.contains("<simpara>&#10003; Done</simpara>"));
+  assert!(output.contains("<simpara>&#10063; Open</simpara>"));
+
+  Ok(())
+}
+
+#[test]
+fn description_list() -> Result<()> {
+  let content = r#"CPU:: The brain
+RAM:: The memory"#;
+  let output = to_docbook(content)?;
+  let paths = element_paths(&output)?;
+
+  assert!(paths.contains(&"variablelist/varlistentry/term".to_string()));
+  assert!(output.contains(
+    "<varlistentry>\n<term>CPU</term>\n<listitem>\n<simpara>The brain</simpara>\n</listitem>\n</varlistentry>"
+  ));
+
+  Ok(())
+}
+
+#[test]
+fn table() -> Result<()> {
+  let content = r#".Numbers
+|===
+|One |Two
+|Three |Four
+|==="#;
+  let output = to_docbook(content)?;
+  let paths = element_paths(&output)?;
+
+  assert!(paths.contains(&"table/title".to_string()));
+  assert!(paths.contains(&"table/tgroup/tbody/row/entry/simpara".to_string()));
+  assert!(output.contains("<tgroup cols=\"2\">"));
+  assert_eq!(output.matches("<row>").count(), 2);
+  assert!(output.contains("<entry><simpara>Four</simpara>\n</entry>"));
+
+  Ok(())
+}
+
+#[test]
+fn source_listing_with_callouts() -> Result<()> {
+  let content = r#".Hello
+[source, rust]
+----
+fn main() { // <1>
+  println!("<hello>");
+}
+----
+<1> The entry point"#;
+  let output = to_docbook(content)?;
+  let paths = element_paths(&output)?;
+
+  assert!(paths.contains(&"formalpara/para/programlisting/co".to_string()));
+  assert!(output.contains(
+    "<programlisting language=\"rust\" linenumbering=\"unnumbered\">fn main() { <co xml:id=\"CO1-1\"/>\n  println!(\"&lt;hello&gt;\");\n}</programlisting>"
+  ));
+  assert!(output.contains(
+    "<calloutlist>\n<callout arearefs=\"CO1-1\">\n<simpara>The entry point</simpara>\n</callout>\n</calloutlist>"
+  ));
+
+  Ok(())
+}
+
+#[test]
+fn plain_listing_and_verse() -> Result<()> {
+  let content = r#"----
+a < b
+----
+
+[verse]
+____
+First line
+second line
+____"#;
+  let output = to_docbook(content)?;
+  element_paths(&output)?;
+
+  assert!(output.contains("<screen>a &lt; b</screen>"));
+  assert!(output.contains(
+    "<blockquote>\n<literallayout>First line\nsecond line</literallayout>\n</blockquote>"
+  ));
+
+  Ok(())
+}
+
+#[test]
+fn admonitions() -> Result<()> {
+  let content = r#"NOTE: Take note.
+
+[WARNING]
+.Be careful
+====
+This block is a warning.
+===="#;
+  let output = to_docbook(content)?;
+  element_paths(&output)?;
+
+  assert!(output.contains("<note>\n<simpara>Take note.</simpara>\n</note>"));
+  assert!(output.contains(
+    "<warning>\n<title>Be careful</title>\n<simpara>This block is a warning.</simpara>\n</warning>"
+  ));
+
+  Ok(())
+}
+
+#[test]
+fn quote_and_example_blocks() -> Result<()> {
+  let content = r#"[quote, Someone, A Book]
+____
+Quoted text.
+____
+
+.An example
+====
+Example text.
+===="#;
+  let output = to_docbook(content)?;
+  element_paths(&output)?;
+
+  assert!(output.contains(
+    "<blockquote>\n<attribution>Someone<citetitle>A Book</citetitle></attribution>\n<simpara>Quoted text.</simpara>\n</blockquote>"
+  ));
+  assert!(output.contains(
+    "<example>\n<title>An example</title>\n<simpara>Example text.</simpara>\n</example>"
+  ));
+
+  Ok(())
+}
+
+#[test]
+fn images() -> Result<()> {
+  let content = r#"image::diagram.png[A diagram, 300, 200]
+
+Inline image:icon.png[Icon] here."#;
+  let output = to_docbook(content)?;
+  let paths = element_paths(&output)?;
+
+  assert!(paths.contains(&"informalfigure/mediaobject/imageobject/imagedata".to_string()));
+  assert!(output
+    .contains("<imagedata fileref=\"diagram.png\" contentwidth=\"300\" contentdepth=\"200\"/>"));
+  assert!(output.contains("<textobject><phrase>A diagram</phrase></textobject>"));
+  assert!(paths.contains(&"simpara/inlinemediaobject/imageobject/imagedata".to_string()));
+
+  Ok(())
+}
+
+#[test]
+fn links_xrefs_and_footnotes() -> Result<()> {
+  let content = r#"See https://example.org[the site], <<target,the target>>, <<target>>, <<other.adoc#part,elsewhere>> and a note.footnote:[The footnote.]
+
+[[target]]
+Target paragraph."#;
+  let output = to_docbook(content)?;
+  element_paths(&output)?;
+
+  assert!(output.contains("<link xl:href=\"https://example.org\">the site</link>"));
+  assert!(output.contains("<link linkend=\"target\">the target</link>"));
+  assert!(output.contains("<xref linkend=\"target\"/>"));
+  assert!(output.contains("<link xl:href=\"other.xml#part\">elsewhere</link>"));
+  assert!(output.contains("<footnote><simpara>The footnote.</simpara></footnote>"));
+  assert!(output.contains("<simpara xml:id=\"target\">Target paragraph.</simpara>"));
+
+  Ok(())
+}
+
+#[test]
+fn ui_macros_stem_and_index_terms() -> Result<()> {
+  let content =
+    r#"Press kbd:[Ctrl+C] or btn:[OK] in menu:File[Save As] for stem:[a < b] and ((terms))."#;
+  let output = to_docbook(content)?;
+  element_paths(&output)?;
+
+  assert!(output.contains("<keycombo><keycap>Ctrl</keycap><keycap>C</keycap></keycombo>"));
+  assert!(output.contains("<guibutton>OK</guibutton>"));
+  assert!(output.contains(
+    "<menuchoice><guimenu>File</guimenu><guimenuitem>Save As</guimenuitem></menuchoice>"
+  ));
+  assert!(output.contains("<mathphrase><![CDATA[a < b]]></mathphrase>"));
+  assert!(output.contains("<indexterm><primary>terms</primary></indexterm>terms"));
+
+  Ok(())
+}
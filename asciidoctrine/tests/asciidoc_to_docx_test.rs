@@ -0,0 +1,350 @@
+mod common;
+
+use anyhow::Result;
+use asciidoctrine::{self, *};
+use pretty_assertions::assert_eq;
+use std::collections::HashMap;
+use std::io::{Cursor, Read};
+use std::path::PathBuf;
+
+/// The parts of a Word document
+struct Docx {
+  files: HashMap<String, Vec<u8>>,
+}
+
+impl Docx {
+  fn text(&self, name: &str) -> String {
+    let content = self
+      .files
+      .get(name)
+      .unwrap_or_else(|| panic!("missing {}", name));
+    String::from_utf8(content.clone()).unwrap()
+  }
+
+  fn document(&self) -> String {
+    self.text("word/document.xml")
+  }
+}
+
+fn to_docx_with_args(content: &str, args: &[&str]) -> Result<Docx> {
+  let output = common::convert(&mut DocxWriter::new(), "docx", content, args)?;
+
+  let mut archive = zip::ZipArchive::new(Cursor::new(output))?;
+  let mut docx = Docx {
+    files: HashMap::new(),
+  };
+  for index in 0..archive.len() {
+    let mut file = archive.by_index(index)?;
+    let mut content = Vec::new();
+    file.read_to_end(&mut content)?;
+    docx.files.insert(file.name().to_string(), content);
+  }
+
+  Ok(docx)
+}
+
+fn to_docx(content: &str) -> Result<Docx> {
+  to_docx_with_args(content, &[])
+}
+
+/// The paragraph with the text
+fn paragraph_with<'a>(document: &'a str, text: &str) -> &'a str {
+  let end = document
+    .find(text)
+    .unwrap_or_else(|| panic!("missing {}", text));
+  let start = document[..end].rfind("<w:p ").unwrap();
+  let end = end + document[end..].find("</w:p>").unwrap();
+  &document[start..end]
+}
+
+#[test]
+fn headings_with_bookmarks() -> Result<()> {
+  let content = r#"= Manual
+:sectnums:
+
+== Usage
+
+See <<_usage>> and <<_usage,the usage>>.
+
+=== Options"#;
+  let document = to_docx(content)?.document();
+
+  assert!(paragraph_with(&document, ">Manual<").contains("<w:pStyle w:val=\"Title\" />"));
+  let usage = paragraph_with(&document, ">1. Usage<");
+  assert!(usage.contains("<w:pStyle w:val=\"Heading1\" />"));
+  assert!(usage.contains("<w:bookmarkStart w:id=\"1\" w:name=\"_usage\" />"));
+  assert!(paragraph_with(&document, ">1.1. Options<").contains("<w:pStyle w:val=\"Heading2\" />"));
+  // References without a text show the title
+  assert!(document.contains(
+    "<w:hyperlink w:anchor=\"_usage\" w:history=\"1\"><w:r><w:rPr><w:rStyle w:val=\"Hyperlink\" /></w:rPr><w:t xml:space=\"preserve\">Usage</w:t></w:r></w:hyperlink>"
+  ));
+  assert!(document.contains("<w:t xml:space=\"preserve\">the usage</w:t></w:r></w:hyperlink>"));
+
+  Ok(())
+}
+
+#[test]
+fn styled_runs_and_links() -> Result<()> {
+  let content =
+    "*bold*, _italic_, `mono` at https://example.org[the site] or mailto:jane@example.org[].";
+  let docx = to_docx(content)?;
+  let document = docx.document();
+
+  assert!(
+    document.contains("<w:rPr><w:b /><w:bCs /></w:rPr><w:t xml:space=\"preserve\">bold</w:t>")
+  );
+  assert!(
+    document.contains("<w:rPr><w:i /><w:iCs /></w:rPr><w:t xml:space=\"preserve\">italic</w:t>")
+  );
+  assert!(document.contains(
+    "<w:rPr><w:rFonts w:ascii=\"Courier New\" w:hAnsi=\"Courier New\" /></w:rPr><w:t xml:space=\"preserve\">mono</w:t>"
+  ));
+  // The links keep their text
+  assert!(document.contains("<w:t xml:space=\"preserve\">the site</w:t></w:r></w:hyperlink>"));
+  assert!(
+    document.contains("<w:t xml:space=\"preserve\">jane@example.org</w:t></w:r></w:hyperlink>")
+  );
+  let rels = docx.text("word/_rels/document.xml.rels");
+  assert!(rels.contains("Target=\"https://example.org\" TargetMode=\"External\""));
+  assert!(rels.contains("Target=\"mailto:jane@example.org\" TargetMode=\"External\""));
+
+  Ok(())
+}
+
+#[test]
+fn numbered_and_bulleted_lists() -> Result<()> {
+  let content = r#"* One
+** Nested
+
+[start=4]
+. Four
+. Five"#;
+  let docx = to_docx(content)?;
+  let document = docx.document();
+
+  assert!(paragraph_with(&document, ">One<")
+    .contains("<w:numPr><w:numId w:val=\"2\" /><w:ilvl w:val=\"0\" /></w:numPr>"));
+  assert!(paragraph_with(&document, ">Nested<")
+    .contains("<w:numPr><w:numId w:val=\"3\" /><w:ilvl w:val=\"1\" /></w:numPr>"));
+  assert!(paragraph_with(&document, ">Four<")
+    .contains("<w:numPr><w:numId w:val=\"4\" /><w:ilvl w:val=\"0\" /></w:numPr>"));
+  let numbering = docx.text("word/numbering.xml");
+  assert!(numbering.contains("<w:num w:numId=\"4\"><w:abstractNumId w:val=\"3\" />"));
+  assert!(numbering.contains("<w:lvlOverride w:ilvl=\"0\"><w:startOverride w:val=\"4\" />"));
+
+  Ok(())
+}
+
+#[test]
+fn tables_with_header_rows() -> Result<()> {
+  let content = r#".Values
+[cols="1,>3", options="header"]
+|===
+|Name |Value
+|a |c
+|b
+|==="#;
+  let document = to_docx(content)?.document();
+
+  assert!(paragraph_with(&document, ">Values<").contains("<w:pStyle w:val=\"Caption\" />"));
+  let table = &document[document.find("<w:tbl>").unwrap()..document.find("</w:tbl>").unwrap()];
+  assert!(table.contains("<w:tblGrid><w:gridCol w:w=\"2126\" w:type=\"dxa\" /><w:gridCol w:w=\"6378\" w:type=\"dxa\" /></w:tblGrid>"));
+  assert!(table.contains("<w:shd w:val=\"clear\" w:color=\"auto\" w:fill=\"D9D9D9\" />"));
+  assert!(paragraph_with(table, ">Name<").contains("<w:pStyle w:val=\"TableHeading\" />"));
+  assert!(paragraph_with(table, ">Value<").contains("<w:jc w:val=\"right\" />"));
+  // Missing cells are filled up
+  assert_eq!(table.matches("<w:tc>").count(), 6);
+
+  Ok(())
+}
+
+#[test]
+fn listings_in_source_code_style() -> Result<()> {
+  let content = r#".Main
+[source, rust]
+----
+fn main() {
+  println!("<hello>");
+}
+----"#;
+  let docx = to_docx(content)?;
+  let document = docx.document();
+
+  assert!(paragraph_with(&document, ">Main<").contains("<w:pStyle w:val=\"Caption\" />"));
+  let listing = paragraph_with(&document, "fn main");
+  assert!(listing.contains("<w:pStyle w:val=\"SourceCode\" />"));
+  assert!(listing.contains(
+    "<w:t xml:space=\"preserve\">fn main() {</w:t><w:br w:type=\"textWrapping\" /><w:t xml:space=\"preserve\">  println!(&quot;&lt;hello&gt;&quot;);</w:t><w:br w:type=\"textWrapping\" /><w:t xml:space=\"preserve\">}</w:t>"
+  ));
+  let styles = docx.text("word/styles.xml");
+  assert!(styles.contains(
+    "<w:style w:type=\"paragraph\" w:styleId=\"SourceCode\"><w:name w:val=\"Source Code\" />"
+  ));
+  assert!(styles.contains("<w:rFonts w:ascii=\"Courier New\" w:hAnsi=\"Courier New\" />"));
+
+  Ok(())
+}
+
+#[test]
+fn footnotes_are_word_footnotes() -> Result<()> {
+  let content = r#"Text.footnote:[First note.] Shared.footnoteref:[shared,Shared note.] Again.footnoteref:[shared]
+
+|===
+|In a table.footnote:[Table note.]
+|==="#;
+  let docx = to_docx(content)?;
+  let document = docx.document();
+  let footnotes = docx.text("word/footnotes.xml");
+
+  assert_eq!(document.matches("<w:footnoteReference ").count(), 2);
+  assert!(footnotes.contains("<w:t xml:space=\"preserve\">First note.</w:t>"));
+  assert!(footnotes.contains("<w:t xml:space=\"preserve\">Shared note.</w:t>"));
+  // Word can only refer to a footnote once
+  assert!(document.contains(
+    "<w:r><w:rPr><w:rStyle w:val=\"FootnoteReference\" /></w:rPr><w:t xml:space=\"preserve\">2</w:t></w:r>"
+  ));
+  // Footnotes in tables would be lost
+  assert!(!footnotes.contains("Table note."));
+  assert!(document.contains("<w:t xml:space=\"preserve\">(Table note.)</w:t>"));
+
+  Ok(())
+}
+
+#[test]
+fn embedded_images() -> Result<()> {
+  let dir = std::env::temp_dir().join(format!("asciidoctrine-docx-{}", std::process::id()));
+  std::fs::create_dir_all(&dir)?;
+  let image = std::fs::File::create(dir.join("pixel.png"))?;
+  let mut encoder = png::Encoder::new(image, 2, 1);
+  encoder.set_color(png::ColorType::Rgba);
+  encoder.set_depth(png::BitDepth::Eight);
+  encoder
+    .write_header()?
+    .write_image_data(&[255, 0, 0, 255, 0, 0, 255, 128])?;
+
+  let input: PathBuf = dir.join("document.adoc");
+  let content = r#"image::pixel.png[A pixel, 100]
+
+image::missing.png[Missing image]"#;
+  let docx = to_docx_with_args(content, &[input.to_str().unwrap()])?;
+  std::fs::remove_dir_all(&dir)?;
+  let document = docx.document();
+
+  assert!(docx
+    .files
+    .keys()
+    .any(|name| name.starts_with("word/media/")));
+  assert!(docx
+    .text("word/_rels/document.xml.rels")
+    .contains("Target=\"media/"));
+  // The width of 100px in EMU
+  assert!(document.contains("<wp:extent cx=\"952500\" cy=\"476250\" />"));
+  assert!(document.contains("<w:t xml:space=\"preserve\">[Missing image]</w:t>"));
+
+  Ok(())
+}
+
+#[test]
+fn styles_from_reference_docx() -> Result<()> {
+  let path = std::env::temp_dir().join(format!(
+    "asciidoctrine-reference-{}.docx",
+    std::process::id()
+  ));
+  let reference = docx_rs::Docx::new()
+    .add_style(
+      docx_rs::Style::new("Heading1", docx_rs::StyleType::Paragraph)
+        .name("heading 1")
+        .color("C00000"),
+    )
+    .page_margin(docx_rs::PageMargin::new().left(1000).right(1000));
+  reference.build().pack(std::fs::File::create(&path)?)?;
+
+  let docx = to_docx_with_args(
+    "== Usage\n\nText.",
+    &["--reference-docx", path.to_str().unwrap()],
+  )?;
+  std::fs::remove_file(&path)?;
+  let styles = docx.text("word/styles.xml");
+
+  assert!(styles.contains("<w:color w:val=\"C00000\" />"));
+  assert_eq!(styles.matches("w:styleId=\"Heading1\"").count(), 1);
+  // The missing styles are still defined
+  assert!(styles.contains("w:styleId=\"SourceCode\""));
+  assert!(docx
+    .document()
+    .contains("<w:pgMar w:top=\"1985\" w:right=\"1000\" w:bottom=\"1701\" w:left=\"1000\""));
+
+  Ok(())
+}
+
+#[test]
+fn missing_reference_docx_names_the_file() -> Result<()> {
+  let error = to_docx_with_args("Text.", &["--reference-docx", "missing-reference.docx"])
+    .err()
+    .expect("the reference document is missing");
+
+  assert!(format!("{:#}", error).contains("could not read missing-reference.docx"));
+
+  Ok(())
+}
+
+#[test]
+fn core_properties_from_the_header() -> Result<()> {
+  let content = r#"= Manual & Guide
+Jane Doe
+:docdate: 2024-03-01
+:keywords: rust, asciidoc
+
+Text."#;
+  let core = to_docx(content)?.text("docProps/core.xml");
+
+  assert!(core.contains(
+    "<dcterms:created xsi:type=\"dcterms:W3CDTF\">2024-03-01T00:00:00Z</dcterms:created>"
+  ));
+  assert!(core.contains("<dc:title>Manual &amp; Guide</dc:title>"));
+  assert!(core.contains("<dc:creator>Jane Doe</dc:creator>"));
+  assert!(!core.contains("1970"));
+
+  Ok(())
+}
+
+#[test]
+fn created_at_the_revision_date() -> Result<()> {
+  let content = r#"= Manual
+Jane Doe
+v1.0, 2023-11-05
+:docdate: 2024-03-01
+
+Text."#;
+  let core = to_docx(content)?.text("docProps/core.xml");
+
+  assert!(core.contains(
+    "<dcterms:created xsi:type=\"dcterms:W3CDTF\">2023-11-05T00:00:00Z</dcterms:created>"
+  ));
+
+  Ok(())
+}
+
+#[test]
+fn highlighted_listings() -> Result<()> {
+  let content = r#"= Highlighted
+:source-highlighter: builtin
+
+[source, rust]
+----
+fn main() {}
+----"#;
+  let document = to_docx(content)?.document();
+
+  let listing = paragraph_with(&document, ">main<");
+  assert!(listing.contains(
+    "<w:r><w:rPr><w:color w:val=\"d73a49\" /></w:rPr><w:t xml:space=\"preserve\">fn</w:t></w:r>"
+  ));
+  assert!(listing.contains(
+    "<w:r><w:rPr><w:color w:val=\"6f42c1\" /></w:rPr><w:t xml:space=\"preserve\">main</w:t></w:r>"
+  ));
+  // Plain text keeps the colour of the paragraph
+  assert!(listing.contains("<w:r><w:rPr /><w:t xml:space=\"preserve\">() {}</w:t></w:r>"));
+
+  Ok(())
+}
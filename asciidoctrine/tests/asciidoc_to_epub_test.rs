@@ -0,0 +1,406 @@
+mod common;
+
+use anyhow::Result;
+use asciidoctrine::{self, *};
+use pretty_assertions::assert_eq;
+use quick_xml::events::Event;
+use std::collections::HashMap;
+use std::io::{Cursor, Read};
+
+/// The files of an e-book in the order of the container
+struct Epub {
+  names: Vec<String>,
+  files: HashMap<String, Vec<u8>>,
+}
+
+impl Epub {
+  fn text(&self, name: &str) -> String {
+    let content = self
+      .files
+      .get(name)
+      .unwrap_or_else(|| panic!("missing {}", name));
+    String::from_utf8(content.clone()).unwrap()
+  }
+}
+
+/// Converts the asciidoc content to an e-book and checks that all
+/// xml files are well-formed
+fn to_epub_with_args(content: &str, args: &[&str]) -> Result<Epub> {
+  to_epub_with_env(content, args, util::Env::Io(util::Io::new()))
+}
+
+/// Like `to_epub_with_args`, but the writer reads the stylesheet and
+/// the images from the environment
+fn to_epub_with_env(content: &str, args: &[&str], env: util::Env) -> Result<Epub> {
+  let output = common::convert(&mut EpubWriter::with_env(env), "epub", content, args)?;
+
+  let mut archive = zip::ZipArchive::new(Cursor::new(output))?;
+  let mut epub = Epub {
+    names: Vec::new(),
+    files: HashMap::new(),
+  };
+  for index in 0..archive.len() {
+    let mut file = archive.by_index(index)?;
+    if index == 0 {
+      assert_eq!(file.compression(), zip::CompressionMethod::Stored);
+    }
+    let mut content = Vec::new();
+    file.read_to_end(&mut content)?;
+    epub.names.push(file.name().to_string());
+    epub.files.insert(file.name().to_string(), content);
+  }
+  for name in epub.names.iter() {
+    if name.ends_with(".xhtml") || name.ends_with(".opf") || name.ends_with(".xml") {
+      check_xml(&epub.text(name))?;
+    }
+  }
+
+  Ok(epub)
+}
+
+fn to_epub(content: &str) -> Result<Epub> {
+  to_epub_with_args(content, &[])
+}
+
+/// Fails if the xml isn't well-formed or uses entities unknown to xml
+fn check_xml(xml: &str) -> Result<()> {
+  let mut reader = quick_xml::Reader::from_str(xml);
+  let mut depth = 0;
+
+  loop {
+    match reader.read_event()? {
+      Event::Start(_) => depth += 1,
+      Event::End(_) => depth -= 1,
+      Event::GeneralRef(reference) => {
+        let name = reference.decode()?;
+        assert!(
+          name.starts_with('#') || ["amp", "lt", "gt", "quot", "apos"].contains(&name.as_ref()),
+          "unknown entity {}",
+          name
+        );
+      }
+      Event::Eof => break,
+      _ => (),
+    }
+  }
+  assert_eq!(depth, 0, "unclosed elements");
+
+  Ok(())
+}
+
+#[test]
+fn container_layout() -> Result<()> {
+  let epub = to_epub("= Book\n\nA paragraph.")?;
+
+  assert_eq!(
+    epub.names,
+    vec![
+      "mimetype",
+      "META-INF/container.xml",
+      "OEBPS/content.opf",
+      "OEBPS/nav.xhtml",
+      "OEBPS/stylesheet.css",
+      "OEBPS/chapter-1.xhtml",
+    ]
+  );
+  assert_eq!(epub.text("mimetype"), "application/epub+zip");
+  assert!(epub.text("META-INF/container.xml").contains(
+    "<rootfile full-path=\"OEBPS/content.opf\" media-type=\"application/oebps-package+xml\"/>"
+  ));
+  assert!(epub
+    .text("OEBPS/chapter-1.xhtml")
+    .contains("<h1>Book</h1>\n<p>A paragraph.</p>\n"));
+
+  Ok(())
+}
+
+#[test]
+fn chapters_at_first_level_sections() -> Result<()> {
+  let content = r#"= Book
+Jane Doe
+
+Preamble
+
+== One
+
+Text.footnote:[First note.]
+
+=== Nested
+
+== Two
+
+See <<_one>>, <<_two>> and <<_nested>>.footnote:[Second note.]"#;
+  let epub = to_epub(content)?;
+
+  let preamble = epub.text("OEBPS/chapter-1.xhtml");
+  assert!(preamble.contains("<title>Book</title>"));
+  assert!(preamble.contains("<span id=\"author\" class=\"author\">Jane Doe</span><br/>"));
+  assert!(preamble.contains("<p>Preamble</p>"));
+
+  let one = epub.text("OEBPS/chapter-2.xhtml");
+  assert!(one.contains("<title>One</title>"));
+  assert!(one.contains("<h2 id=\"_one\">One</h2>"));
+  assert!(one.contains("<h3 id=\"_nested\">Nested</h3>"));
+  assert!(one.contains("<div class=\"footnote\" id=\"_footnotedef_1\">"));
+  assert!(!one.contains("Two"));
+
+  // The references point to the chapter of their target and every
+  // chapter numbers its own footnotes
+  let two = epub.text("OEBPS/chapter-3.xhtml");
+  assert!(two.contains(
+    "See <a href=\"chapter-2.xhtml#_one\">One</a>, <a href=\"#_two\">Two</a> and <a href=\"chapter-2.xhtml#_nested\">Nested</a>."
+  ));
+  assert!(two.contains("href=\"#_footnotedef_1\""));
+  assert!(two.contains("<a href=\"#_footnoteref_1\">1</a>. Second note."));
+
+  Ok(())
+}
+
+#[test]
+fn listings_keep_their_links() -> Result<()> {
+  let content = r##"= Book
+
+== One
+
+----
+<a href="#_two">Two</a>
+----
+
+== Two
+
+Text."##;
+  let one = to_epub(content)?.text("OEBPS/chapter-2.xhtml");
+
+  assert!(one.contains("&lt;a href=\"#_two\"&gt;Two&lt;/a&gt;"));
+
+  Ok(())
+}
+
+#[test]
+fn navigation_document() -> Result<()> {
+  let content = r#"= Book
+:sectnums:
+:toc-title: Contents
+
+== One
+
+=== Nested
+
+==== Too deep
+
+== Two"#;
+  let epub = to_epub(content)?;
+  let nav = epub.text("OEBPS/nav.xhtml");
+
+  assert!(nav.contains(
+    r#"<nav epub:type="toc" id="toc">
+<h1>Contents</h1>
+<ol>
+<li><a href="chapter-2.xhtml#_one">1. One</a>
+<ol>
+<li><a href="chapter-2.xhtml#_nested">1.1. Nested</a></li>
+</ol>
+</li>
+<li><a href="chapter-3.xhtml#_two">2. Two</a></li>
+</ol>
+</nav>
+"#
+  ));
+
+  Ok(())
+}
+
+#[test]
+fn parts_of_a_book() -> Result<()> {
+  let content = r#"= Book
+:doctype: book
+
+= Part One
+
+== Chapter A
+
+= Part Two
+
+== Chapter B"#;
+  let epub = to_epub(content)?;
+
+  assert!(epub.names.contains(&"OEBPS/chapter-5.xhtml".to_string()));
+  assert!(epub
+    .text("OEBPS/chapter-2.xhtml")
+    .contains("<body class=\"book\">\n<h1>Part One</h1>\n</body>"));
+  assert!(epub.text("OEBPS/nav.xhtml").contains(
+    r#"<ol>
+<li><a href="chapter-2.xhtml">Part One</a>
+<ol>
+<li><a href="chapter-3.xhtml#_chapter_a">Chapter A</a></li>
+</ol>
+</li>
+<li><a href="chapter-4.xhtml">Part Two</a>
+"#
+  ));
+
+  Ok(())
+}
+
+#[test]
+fn package_metadata() -> Result<()> {
+  let content = r#"= Literate & Co
+Jane Doe; John Roe
+v1.0, 2024-01-02
+:lang: de
+:description: A book about literate programming
+:keywords: rust, asciidoc
+
+== One
+
+== Two"#;
+  let epub = to_epub(content)?;
+  let opf = epub.text("OEBPS/content.opf");
+
+  assert!(opf.contains("unique-identifier=\"bookid\" xml:lang=\"de\""));
+  assert!(opf.contains("<dc:identifier id=\"bookid\">urn:asciidoctrine:"));
+  assert!(opf.contains("<dc:title>Literate &amp; Co</dc:title>"));
+  assert!(opf.contains("<dc:language>de</dc:language>"));
+  assert!(opf.contains("<dc:creator>Jane Doe</dc:creator>\n    <dc:creator>John Roe</dc:creator>"));
+  assert!(opf.contains("<dc:date>2024-01-02</dc:date>"));
+  assert!(opf.contains("<dc:description>A book about literate programming</dc:description>"));
+  assert!(opf.contains("<dc:subject>rust</dc:subject>\n    <dc:subject>asciidoc</dc:subject>"));
+  assert!(opf.contains("<meta property=\"dcterms:modified\">"));
+  assert!(opf.contains(
+    "<item id=\"nav\" href=\"nav.xhtml\" media-type=\"application/xhtml+xml\" properties=\"nav\"/>"
+  ));
+  assert!(opf.contains(
+    r#"<spine>
+    <itemref idref="chapter-1"/>
+    <itemref idref="chapter-2"/>
+    <itemref idref="chapter-3"/>
+  </spine>"#
+  ));
+  assert!(epub
+    .text("OEBPS/chapter-2.xhtml")
+    .contains("xml:lang=\"de\" lang=\"de\""));
+
+  // The identifier is stable
+  let again = to_epub(content)?.text("OEBPS/content.opf");
+  let identifier = |opf: &str| {
+    opf
+      .lines()
+      .find(|line| line.contains("dc:identifier"))
+      .map(str::to_string)
+  };
+  assert_eq!(identifier(&opf), identifier(&again));
+
+  Ok(())
+}
+
+#[test]
+fn embedded_images_and_stylesheet() -> Result<()> {
+  let mut files = util::Cache::new();
+  files.insert("book/images/diagram.png", b"\x89PNG\r\n".to_vec());
+  files.insert("book/images/icons/play.svg", b"<svg/>".to_vec());
+  files.insert("book/book.css", b"body { color: black; }".to_vec());
+
+  let content = r#"= Book
+:imagesdir: images
+
+image::diagram.png[A diagram]
+
+Press image:icons/play.svg[Play] or image:missing.png[] or image:https://example.org/remote.png[]."#;
+  let epub = to_epub_with_env(
+    content,
+    &["book/book.adoc", "--stylesheet", "book/book.css"],
+    util::Env::Cache(files),
+  )?;
+
+  assert_eq!(epub.files["OEBPS/diagram.png"], b"\x89PNG\r\n");
+  assert_eq!(epub.files["OEBPS/icons/play.svg"], b"<svg/>");
+  assert_eq!(epub.text("OEBPS/stylesheet.css"), "body { color: black; }");
+  assert!(!epub
+    .names
+    .iter()
+    .any(|name| name.contains("missing") || name.contains("remote")));
+
+  let opf = epub.text("OEBPS/content.opf");
+  assert!(opf.contains("<item id=\"image-1\" href=\"diagram.png\" media-type=\"image/png\"/>"));
+  assert!(
+    opf.contains("<item id=\"image-2\" href=\"icons/play.svg\" media-type=\"image/svg+xml\"/>")
+  );
+  assert!(opf.contains("href=\"chapter-1.xhtml\" media-type=\"application/xhtml+xml\" properties=\"remote-resources\"/>"));
+
+  let chapter = epub.text("OEBPS/chapter-1.xhtml");
+  assert!(chapter.contains("<img src=\"diagram.png\" alt=\"A diagram\"/>"));
+  assert!(chapter.contains("<img src=\"icons/play.svg\" alt=\"Play\"/>"));
+
+  Ok(())
+}
+
+#[test]
+fn html_becomes_xhtml() -> Result<()> {
+  let content = r#"Fish & chips, &#169; and &amp; +
+next line, mailto:jane@example.org[Mail, Hello, Hi there] and https://example.org[Q & A].
+
+[%reversed]
+. One
+. Two
+
+|===
+|a |b
+|==="#;
+  let epub = to_epub(content)?;
+  let chapter = epub.text("OEBPS/chapter-1.xhtml");
+
+  assert!(chapter.contains("<p>Fish &amp; chips, &#169; and &amp;<br/>\nnext line"));
+  assert!(chapter
+    .contains("<a href=\"mailto:jane@example.org?subject=Hello&amp;body=Hi%20there\">Mail</a>"));
+  assert!(chapter.contains("<a href=\"https://example.org\">Q &amp; A</a>"));
+  assert!(chapter.contains("<ol class=\"arabic\" reversed=\"reversed\">"));
+  assert!(chapter.contains("<col style=\"width: 50%;\"/>"));
+
+  Ok(())
+}
+
+#[test]
+fn chapters_are_well_formed() -> Result<()> {
+  let content = r#"= Kitchen <Sink> & Co
+Jane Doe <jane@example.org>
+v1.0, 2024-01-01: Second <draft>
+
+== The <Sink> & (C) &copy;
+
+Fish & chips with <b> +
+a break, a footnote:[About <it> & more] and kbd:[Ctrl+<] "`quoted <q>`".
+
+[%collapsible%open]
+.More <details>
+====
+Hidden & shown.
+====
+
+[quote, Jane <Doe>, The <Book>]
+____
+A quote & more.
+____
+
+== Two <Two>
+
+----
+if a < b && c > d {}
+----"#;
+  let epub = to_epub_with_args(content, &["-a", "experimental"])?;
+
+  for name in epub.names.iter().filter(|name| name.ends_with(".xhtml")) {
+    check_xml(&epub.text(name))?;
+  }
+  let first = epub.text("OEBPS/chapter-1.xhtml");
+  assert!(first.contains("<h1>Kitchen &lt;Sink&gt; &amp; Co</h1>"));
+  assert!(first.contains("<br/><span id=\"revremark\">Second &lt;draft&gt;</span>"));
+  let second = epub.text("OEBPS/chapter-2.xhtml");
+  assert!(second.contains("The &lt;Sink&gt; &amp; &#169; &amp;copy;</h2>"));
+  assert!(second.contains("<details open=\"open\">"));
+  assert!(second.contains("<hr/>"));
+  let third = epub.text("OEBPS/chapter-3.xhtml");
+  assert!(third.contains("Two &lt;Two&gt;</h2>"));
+  assert!(third.contains("if a &lt; b &amp;&amp; c &gt; d {}"));
+
+  Ok(())
+}
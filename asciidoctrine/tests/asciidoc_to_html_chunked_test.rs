@@ -0,0 +1,180 @@
+mod common;
+
+use anyhow::Result;
+use asciidoctrine::util::Environment;
+use asciidoctrine::{self, *};
+use pretty_assertions::assert_eq;
+use std::collections::HashMap;
+
+/// Converts the asciidoc content to chunked html. The index page is
+/// stored as `book/index.html` with the other pages.
+fn to_pages(
+  content: &str,
+  args: &[&str],
+  files: &[(&str, &str)],
+) -> Result<HashMap<String, String>> {
+  let mut all_args = vec!["-o", "book/index.html"];
+  all_args.extend(args);
+  let mut io = util::Env::Cache(util::Cache::new());
+  for (path, content) in files {
+    io.write(path, content)?;
+  }
+  let mut writer = ChunkedHtmlWriter::with_env(io);
+  let index = common::convert_to_string(&mut writer, "html5-chunked", content, &all_args)?;
+
+  let mut pages = writer.into_env().get_cache().unwrap();
+  pages.insert("book/index.html".to_string(), index);
+  Ok(pages)
+}
+
+const BOOK: &str = r#"= Manual
+:sectnums:
+
+Read this first.
+
+== Install
+
+See <<_configure>> and <<_install,above>>.
+
+=== Configure
+
+Set it up.
+
+== Usage
+
+Run it."#;
+
+#[test]
+fn chunks_with_navigation() -> Result<()> {
+  let pages = to_pages(BOOK, &["--template", "-"], &[])?;
+
+  let mut files: Vec<&String> = pages.keys().collect();
+  files.sort();
+  assert_eq!(
+    files,
+    vec![
+      "book/_install.html",
+      "book/_usage.html",
+      "book/index.html",
+      "book/stylesheet.css"
+    ]
+  );
+
+  let index = &pages["book/index.html"];
+  assert!(index.starts_with("<h1>Manual</h1>\n"));
+  assert!(index.contains("Read this first."));
+  // The table of contents points to the pages
+  assert!(index.contains("<li><a href=\"_install.html#_install\">1. Install</a>"));
+  assert!(index.contains("<li><a href=\"_install.html#_configure\">1.1. Configure</a></li>"));
+  assert!(!index.contains("Set it up."));
+
+  let install = &pages["book/_install.html"];
+  assert!(install.contains("Set it up."));
+  assert!(!install.contains("Run it."));
+  // References in the same page stay anchors
+  assert!(install.contains("<a href=\"#_configure\">Configure</a>"));
+
+  Ok(())
+}
+
+#[test]
+fn metadata_is_escaped_on_every_page() -> Result<()> {
+  let content = r#"= Tom & Jerry
+:description: He said "hi" & left
+
+== Cats & Mice
+
+Run."#;
+  let pages = to_pages(content, &[], &[])?;
+
+  for file in ["book/index.html", "book/_cats_mice.html"] {
+    let page = &pages[file];
+    assert!(
+      page.contains(r#"<meta name="description" content="He said &quot;hi&quot; &amp; left">"#)
+    );
+  }
+  assert!(pages["book/index.html"].contains("Cats &amp; Mice &rarr;"));
+  assert!(
+    pages["book/_cats_mice.html"].contains("<title>Cats &amp; Mice | Tom &amp; Jerry</title>")
+  );
+  Ok(())
+}
+
+#[test]
+fn references_between_chunks() -> Result<()> {
+  let content = r#"= Manual
+
+== One
+
+See <<_two>>.
+
+== Two
+
+Back to <<_one,the start>>."#;
+  let pages = to_pages(content, &["--template", "-"], &[])?;
+
+  assert!(pages["book/_one.html"].contains("<a href=\"_two.html#_two\">Two</a>"));
+  assert!(pages["book/_two.html"].contains("<a href=\"_one.html#_one\">the start</a>"));
+
+  Ok(())
+}
+
+#[test]
+fn listings_keep_their_links() -> Result<()> {
+  let content = r##"= Manual
+
+== One
+
+[source,html]
+----
+<a href="#_two">Two</a>
+----
+
+== Two
+
+Text."##;
+  let pages = to_pages(content, &["--template", "-"], &[])?;
+
+  assert!(pages["book/_one.html"].contains("&lt;a href=\"#_two\"&gt;Two&lt;/a&gt;"));
+  assert!(!pages["book/_one.html"].contains("_two.html"));
+
+  Ok(())
+}
+
+#[test]
+fn deeper_chunks_go_up_to_their_section() -> Result<()> {
+  let content = format!(":chunk-level: 2\n{}", BOOK.replace("= Manual\n", ""));
+  let pages = to_pages(&content, &["-e", "sections"], &[])?;
+
+  let configure = &pages["book/_configure.html"];
+  assert!(configure.contains("<a rel=\"prev\" href=\"_install.html\">&larr; 1. Install</a>"));
+  assert!(configure.contains("<a rel=\"up\" href=\"_install.html\">&uarr; 1. Install</a>"));
+  assert!(configure.contains("<a rel=\"next\" href=\"_usage.html\">2. Usage &rarr;</a>"));
+  assert!(configure.contains("<title>1.1. Configure</title>"));
+  assert!(configure.contains("<link rel=\"stylesheet\" href=\"stylesheet.css\">"));
+
+  let usage = &pages["book/_usage.html"];
+  assert!(usage.contains("<a rel=\"up\" href=\"index.html\">&uarr; Index</a>"));
+  assert!(!usage.contains("<a rel=\"next\""));
+
+  Ok(())
+}
+
+#[test]
+fn templates_replace_blocks() -> Result<()> {
+  let template = r#"{% extends "chunk.html" %}
+{% block footer %}<footer>{{title}} of {{doctitle}}</footer>{% endblock footer %}"#;
+  let pages = to_pages(
+    BOOK,
+    &["--template", "layout.html"],
+    &[("layout.html", template)],
+  )?;
+
+  let usage = &pages["book/_usage.html"];
+  assert!(usage.contains("<footer>2. Usage of Manual</footer>"));
+  // The other blocks are kept
+  assert_eq!(usage.matches("<nav class=\"chunk-nav\">").count(), 1);
+  assert!(usage.contains("<title>2. Usage | Manual</title>"));
+
+  Ok(())
+}
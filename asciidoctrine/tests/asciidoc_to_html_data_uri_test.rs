@@ -0,0 +1,84 @@
+mod common;
+
+use anyhow::Result;
+use asciidoctrine::util::Environment;
+use asciidoctrine::{self, *};
+
+/// Converts the asciidoc content to html. The writer reads the images
+/// from the files of the cache.
+fn to_html(content: &str, files: &[(&str, &str)]) -> Result<String> {
+  let mut images = util::Env::Cache(util::Cache::new());
+  for (path, content) in files {
+    images.write(path, content)?;
+  }
+  let mut writer = HtmlWriter::with_env(images);
+  common::convert_to_string(&mut writer, "html5", content, &["--template", "-"])
+}
+
+const LOGO: &str = r#"<svg xmlns="http://www.w3.org/2000/svg"/>"#;
+
+#[test]
+fn embedded_images() -> Result<()> {
+  let content = r#"= Report
+:data-uri:
+:imagesdir: images
+
+image::logo.svg[Logo]
+
+The logo image:logo.svg[] is on https://example.org/logo.png[] and image:https://example.org/logo.png[]."#;
+  let html = to_html(content, &[("images/logo.svg", LOGO)])?;
+
+  let uri = "data:image/svg+xml;base64,PHN2ZyB4bWxucz0iaHR0cDovL3d3dy53My5vcmcvMjAwMC9zdmciLz4=";
+  assert!(html.contains(&format!("<img src=\"{}\" alt=\"Logo\">", uri)));
+  assert!(html.contains(&format!(
+    "<span class=\"image\"><img src=\"{}\" alt=\"logo\">",
+    uri
+  )));
+  // Remote images stay links
+  assert!(html.contains("<img src=\"https://example.org/logo.png\""));
+
+  Ok(())
+}
+
+#[test]
+fn missing_images_are_shown() -> Result<()> {
+  let content = r#"= Report
+:data-uri:
+
+image::chart.png[Sales]"#;
+  let html = to_html(content, &[])?;
+
+  assert!(html.contains("<span class=\"alt\">[Sales] (image chart.png not found)</span>"));
+  assert!(!html.contains("<img"));
+
+  Ok(())
+}
+
+#[test]
+fn linked_images_without_data_uri() -> Result<()> {
+  let html = to_html("image::logo.svg[Logo]", &[("logo.svg", LOGO)])?;
+
+  assert!(html.contains("<img src=\"logo.svg\" alt=\"Logo\">"));
+
+  Ok(())
+}
+
+#[test]
+fn embedded_binary_images() -> Result<()> {
+  let mut cache = util::Cache::new();
+  cache.insert(
+    "chart.png",
+    vec![0x89, b'P', b'N', b'G', 0x0d, 0x0a, 0x1a, 0x0a, 0xff, 0x00],
+  );
+  let mut writer = HtmlWriter::with_env(util::Env::Cache(cache));
+  let html = common::convert_to_string(
+    &mut writer,
+    "html5",
+    ":data-uri:\n\nimage::chart.png[Sales]",
+    &["--template", "-"],
+  )?;
+
+  assert!(html.contains("<img src=\"data:image/png;base64,iVBORw0KGgr/AA==\" alt=\"Sales\">"));
+
+  Ok(())
+}
@@ -5,6 +5,235 @@ use clap::Parser;
 use pretty_assertions::assert_eq;
 use std::io::BufWriter;
 
+#[test]
+fn admonition_block() -> Result<()> {
+  let content = r##"
+[WARNING]
+.Be careful
+====
+This block is a warning.
+
+It has more than one paragraph.
+====
+"##;
+  let reader = AsciidocReader::new();
+  let opts = options::Opts::parse_from(vec!["asciidoctrine", "--template", "-"]);
+  let mut env = util::Env::Cache(util::Cache::new());
+
+  let ast = reader.parse(content, &opts, &mut env)?;
+
+  let mut buf = BufWriter::new(Vec::new());
+  let mut writer = HtmlWriter::new();
+  writer.write(ast, &opts, &mut buf)?;
+
+  let output = String::from_utf8(buf.into_inner()?)?;
+  assert_eq!(
+    output,
+    r##"<div class="admonitionblock warning">
+  <table>
+    <tr>
+      <td class="icon">
+        <div class="title">Warning</div>
+      </td>
+      <td class="content">
+        <div class="title">Be careful</div>
+        <p>This block is a warning.</p>
+        <p>It has more than one paragraph.</p>
+      </td>
+    </tr>
+  </table>
+</div>
+"##
+  );
+
+  Ok(())
+}
+
+#[test]
+fn admonition_paragraph() -> Result<()> {
+  let content = r##"
+NOTE: Admonitions draw the attention
+of the reader.
+"##;
+  let reader = AsciidocReader::new();
+  let opts = options::Opts::parse_from(vec!["asciidoctrine", "--template", "-"]);
+  let mut env = util::Env::Cache(util::Cache::new());
+
+  let ast = reader.parse(content, &opts, &mut env)?;
+
+  let mut buf = BufWriter::new(Vec::new());
+  let mut writer = HtmlWriter::new();
+  writer.write(ast, &opts, &mut buf)?;
+
+  let output = String::from_utf8(buf.into_inner()?)?;
+  assert_eq!(
+    output,
+    r##"<div class="admonitionblock note">
+  <table>
+    <tr>
+      <td class="icon">
+        <div class="title">Note</div>
+      </td>
+      <td class="content">
+        Admonitions draw the attention
+of the reader.
+      </td>
+    </tr>
+  </table>
+</div>
+"##
+  );
+
+  Ok(())
+}
+
+#[test]
+fn admonition_paragraph_attributes() -> Result<()> {
+  let content = r##"
+.Title
+NOTE: x
+
+.Title
+[NOTE]
+y
+
+[[id1]]
+.T2
+TIP: z
+
+[NOTE,caption=Hint]
+w
+"##;
+  let reader = AsciidocReader::new();
+  let opts = options::Opts::parse_from(vec!["asciidoctrine", "--template", "-"]);
+  let mut env = util::Env::Cache(util::Cache::new());
+
+  let ast = reader.parse(content, &opts, &mut env)?;
+
+  let mut buf = BufWriter::new(Vec::new());
+  let mut writer = HtmlWriter::new();
+  writer.write(ast, &opts, &mut buf)?;
+
+  let output = String::from_utf8(buf.into_inner()?)?;
+  assert_eq!(
+    output,
+    r##"<div class="admonitionblock note">
+  <table>
+    <tr>
+      <td class="icon">
+        <div class="title">Note</div>
+      </td>
+      <td class="content">
+        <div class="title">Title</div>
+        x
+      </td>
+    </tr>
+  </table>
+</div>
+<div class="admonitionblock note">
+  <table>
+    <tr>
+      <td class="icon">
+        <div class="title">Note</div>
+      </td>
+      <td class="content">
+        <div class="title">Title</div>
+        y
+      </td>
+    </tr>
+  </table>
+</div>
+<div id="id1" class="admonitionblock tip">
+  <table>
+    <tr>
+      <td class="icon">
+        <div class="title">Tip</div>
+      </td>
+      <td class="content">
+        <div class="title">T2</div>
+        z
+      </td>
+    </tr>
+  </table>
+</div>
+<div class="admonitionblock note">
+  <table>
+    <tr>
+      <td class="icon">
+        <div class="title">Hint</div>
+      </td>
+      <td class="content">
+        w
+      </td>
+    </tr>
+  </table>
+</div>
+"##
+  );
+
+  Ok(())
+}
+
+#[test]
+fn attribute_references() -> Result<()> {
+  let content = r##"
+:project: asciidoctrine
+:version: 0.2.0
+
+Welcome to *{project}* version {version}.
+"##;
+  let reader = AsciidocReader::new();
+  let opts = options::Opts::parse_from(vec!["asciidoctrine", "--template", "-"]);
+  let mut env = util::Env::Cache(util::Cache::new());
+
+  let ast = reader.parse(content, &opts, &mut env)?;
+
+  let mut buf = BufWriter::new(Vec::new());
+  let mut writer = HtmlWriter::new();
+  writer.write(ast, &opts, &mut buf)?;
+
+  let output = String::from_utf8(buf.into_inner()?)?;
+  assert_eq!(
+    output,
+    r##"<p>Welcome to <strong>asciidoctrine</strong> version 0.2.0.</p>
+"##
+  );
+
+  Ok(())
+}
+
+#[test]
+fn attribute_unset() -> Result<()> {
+  let content = r##"
+:stage: beta
+
+This is the {stage} release.
+
+:stage!:
+
+Unknown references like {stage} stay as they are.
+"##;
+  let reader = AsciidocReader::new();
+  let opts = options::Opts::parse_from(vec!["asciidoctrine", "--template", "-"]);
+  let mut env = util::Env::Cache(util::Cache::new());
+
+  let ast = reader.parse(content, &opts, &mut env)?;
+
+  let mut buf = BufWriter::new(Vec::new());
+  let mut writer = HtmlWriter::new();
+  writer.write(ast, &opts, &mut buf)?;
+
+  let output = String::from_utf8(buf.into_inner()?)?;
+  assert_eq!(
+    output,
+    r##"<p>This is the beta release.</p>
+<p>Unknown references like {stage} stay as they are.</p>
+"##
+  );
+
+  Ok(())
+}
+
 #[test]
 fn bullet_list_with_dashes() -> Result<()> {
   let content = r##"
@@ -131,6 +360,122 @@ fn bullet_list() -> Result<()> {
   Ok(())
 }
 
+#[test]
+fn callouts() -> Result<()> {
+  let content = r##"
+[source, rust]
+----
+fn main() {
+  let name = "World"; // <1>
+  println!("Hello {}", name); // <2>
+}
+----
+<1> Who we greet
+<2> The greeting
+"##;
+  let reader = AsciidocReader::new();
+  let opts = options::Opts::parse_from(vec!["asciidoctrine", "--template", "-"]);
+  let mut env = util::Env::Cache(util::Cache::new());
+
+  let ast = reader.parse(content, &opts, &mut env)?;
+
+  let mut buf = BufWriter::new(Vec::new());
+  let mut writer = HtmlWriter::new();
+  writer.write(ast, &opts, &mut buf)?;
+
+  let output = String::from_utf8(buf.into_inner()?)?;
+  assert_eq!(
+    output,
+    r##"<div class="listingblock">
+  <pre>fn main() {
+  let name = "World"; <b class="conum" id="CO1-1">(1)</b>
+  println!("Hello {}", name); <b class="conum" id="CO1-2">(2)</b>
+}</pre>
+</div>
+<div class="colist arabic">
+  <table>
+    <tr>
+      <td><a href="#CO1-1"><b class="conum">(1)</b></a></td>
+      <td>Who we greet</td>
+    </tr>
+    <tr>
+      <td><a href="#CO1-2"><b class="conum">(2)</b></a></td>
+      <td>The greeting</td>
+    </tr>
+  </table>
+</div>
+"##
+  );
+
+  Ok(())
+}
+
+#[test]
+fn indented_callouts() -> Result<()> {
+  let content = r##"
+[source, python]
+----
+def greet(name):
+    # <1>
+    print("Hello", name) # <2> <3>
+----
+<1> The indentation stays
+<2> Who we greet
+<3> The greeting
+"##;
+  let reader = AsciidocReader::new();
+  let opts = options::Opts::parse_from(vec!["asciidoctrine", "--template", "-"]);
+  let mut env = util::Env::Cache(util::Cache::new());
+
+  let ast = reader.parse(content, &opts, &mut env)?;
+
+  let mut buf = BufWriter::new(Vec::new());
+  let mut writer = HtmlWriter::new();
+  writer.write(ast, &opts, &mut buf)?;
+
+  let output = String::from_utf8(buf.into_inner()?)?;
+  assert!(output.contains(
+    r##"<pre>def greet(name):
+    <b class="conum" id="CO1-1">(1)</b>
+    print("Hello", name) <b class="conum" id="CO1-2">(2)</b> <b class="conum" id="CO1-3">(3)</b></pre>"##
+  ));
+
+  Ok(())
+}
+
+#[test]
+fn checklist() -> Result<()> {
+  let content = r##"
+* [x] Write the parser
+* [ ] Write the tests
+"##;
+  let reader = AsciidocReader::new();
+  let opts = options::Opts::parse_from(vec!["asciidoctrine", "--template", "-"]);
+  let mut env = util::Env::Cache(util::Cache::new());
+
+  let ast = reader.parse(content, &opts, &mut env)?;
+
+  let mut buf = BufWriter::new(Vec::new());
+  let mut writer = HtmlWriter::new();
+  writer.write(ast, &opts, &mut buf)?;
+
+  let output = String::from_utf8(buf.into_inner()?)?;
+  assert_eq!(
+    output,
+    r##"<ul class="checklist">
+  <li>
+    <p>&#10003; Write the parser</p>
+  </li>
+  <li>
+    <p>&#10063; Write the tests</p>
+  </li>
+</ul>
+"##
+  );
+
+  Ok(())
+}
+
 #[test]
 fn collapsible_blocks() -> Result<()> {
   let content = r##"
@@ -202,11 +547,14 @@ This Information is visible by default.
 }
 
 #[test]
-fn footnotes() -> Result<()> {
+fn description_list() -> Result<()> {
   let content = r##"
-This is the main text footnote:[Not all information goes into the man
-text. Some is usefull in footnotes] it explains the genral idea of this
-paragraph.
+CPU:: The brain of the computer.
+RAM::
+  The short term memory.
+Storage::
+* HDD
+* SSD
 "##;
   let reader = AsciidocReader::new();
   let opts = options::Opts::parse_from(vec!["asciidoctrine", "--template", "-"]);
@@ -221,12 +569,1544 @@ paragraph.
   let output = String::from_utf8(buf.into_inner()?)?;
   assert_eq!(
     output,
-    r##"<p>This is the main text <sup class="footnote">[<a id="_footnoteref_1" class="footnote" href="#_footnotedef_1" title="View footnote.">1</a>]</sup> it explains the genral idea of this
-paragraph.</p>
-<div id="footnotes">
-  <hr>
-  <div class="footnote" id="_footnotedef_1">
-    <a href="#_footnoteref_1">1</a>. Not all information goes into the man text. Some is usefull in footnotes
+    r##"<dl>
+  <dt class="hdlist1">CPU</dt>
+  <dd>
+    <p>The brain of the computer.</p>
+  </dd>
+  <dt class="hdlist1">RAM</dt>
+  <dd>
+    <p>The short term memory.</p>
+  </dd>
+  <dt class="hdlist1">Storage</dt>
+  <dd>
+    <ul>
+      <li>
+        <p>HDD</p>
+      </li>
+      <li>
+        <p>SSD</p>
+      </li>
+    </ul>
+  </dd>
+</dl>
+"##
+  );
+
+  Ok(())
+}
+
+#[test]
+fn document_header() -> Result<()> {
+  let content = r##"
+= Document Title
+Kismet R. Lee <kismet@asciidoctor.org>; B. Steppenwolf
+v1.0, 2024-01-01: First release
+:description: An example
+
+Written by {author} ({revnumber}).
+"##;
+  let reader = AsciidocReader::new();
+  let opts = options::Opts::parse_from(vec!["asciidoctrine", "--template", "-"]);
+  let mut env = util::Env::Cache(util::Cache::new());
+
+  let ast = reader.parse(content, &opts, &mut env)?;
+
+  let mut buf = BufWriter::new(Vec::new());
+  let mut writer = HtmlWriter::new();
+  writer.write(ast, &opts, &mut buf)?;
+
+  let output = String::from_utf8(buf.into_inner()?)?;
+  assert_eq!(
+    output,
+    r##"<h1>Document Title</h1>
+<div class="details">
+<span id="author" class="author">Kismet R. Lee</span><br>
+<span id="email" class="email"><a href="mailto:kismet@asciidoctor.org">kismet@asciidoctor.org</a></span><br>
+<span id="author2" class="author">B. Steppenwolf</span><br>
+<span id="revnumber">version 1.0,</span>
+<span id="revdate">2024-01-01</span>
+<br><span id="revremark">First release</span>
+</div>
+<p>Written by Kismet R. Lee (1.0).</p>
+"##
+  );
+
+  Ok(())
+}
+
+#[test]
+fn document_metadata_is_escaped() -> Result<()> {
+  let content = r##"= Tom & "Jerry"
+:description: He said "hi" & left
+:keywords: <cats>, mice
+
+Text.
+"##;
+  let reader = AsciidocReader::new();
+  let opts = options::Opts::parse_from(vec!["asciidoctrine"]);
+  let mut env = util::Env::Cache(util::Cache::new());
+
+  let ast = reader.parse(content, &opts, &mut env)?;
+
+  let mut buf = BufWriter::new(Vec::new());
+  let mut writer = HtmlWriter::new();
+  writer.write(ast, &opts, &mut buf)?;
+
+  let output = String::from_utf8(buf.into_inner()?)?;
+  assert!(output.contains(r#"<meta name="description" content="He said &quot;hi&quot; &amp; left">"#));
+  assert!(output.contains(r#"<meta name="keywords" content="&lt;cats&gt;, mice">"#));
+  assert!(output.contains("<title>Tom &amp; &quot;Jerry&quot;</title>"));
+
+  Ok(())
+}
+
+#[test]
+fn document_text_is_escaped() -> Result<()> {
+  let content = r##"= Kitchen <Sink> & Co
+Kismet Lee
+v1.0, 2024 <Jan>: Second <draft> & more
+
+== Part <Two> & &copy;
+
+.A <title> & more
+----
+a & b &copy; <c>
+----
+
+Text & more <i> &copy; it's.
+"##;
+  let reader = AsciidocReader::new();
+  let opts = options::Opts::parse_from(vec!["asciidoctrine", "--template", "-"]);
+  let mut env = util::Env::Cache(util::Cache::new());
+
+  let ast = reader.parse(content, &opts, &mut env)?;
+
+  let mut buf = BufWriter::new(Vec::new());
+  let mut writer = HtmlWriter::new();
+  writer.write(ast, &opts, &mut buf)?;
+
+  let output = String::from_utf8(buf.into_inner()?)?;
+  assert_eq!(
+    output,
+    r##"<h1>Kitchen &lt;Sink&gt; &amp; Co</h1>
+<div class="details">
+<span id="author" class="author">Kismet Lee</span><br>
+<span id="revnumber">version 1.0,</span>
+<span id="revdate">2024 &lt;Jan&gt;</span>
+<br><span id="revremark">Second &lt;draft&gt; &amp; more</span>
+</div>
+<h2 id="_part_two_copy">Part &lt;Two&gt; &amp; &copy;</h2>
+<div class="listingblock">
+  <div class="title">A &lt;title&gt; &amp; more</div>
+  <pre>a &amp; b &amp;copy; &lt;c&gt;</pre>
+</div>
+<p>Text &amp; more &lt;i&gt; &copy; it&#8217;s.</p>
+"##
+  );
+
+  Ok(())
+}
+
+#[test]
+fn example_block() -> Result<()> {
+  let content = r##"
+.First
+====
+An example.
+====
+
+[caption="Exhibit A: "]
+.Second
+====
+A special example.
+====
+
+:example-caption!:
+
+.Third
+====
+An example without a number.
+====
+"##;
+  let reader = AsciidocReader::new();
+  let opts = options::Opts::parse_from(vec!["asciidoctrine", "--template", "-"]);
+  let mut env = util::Env::Cache(util::Cache::new());
+
+  let ast = reader.parse(content, &opts, &mut env)?;
+
+  let mut buf = BufWriter::new(Vec::new());
+  let mut writer = HtmlWriter::new();
+  writer.write(ast, &opts, &mut buf)?;
+
+  let output = String::from_utf8(buf.into_inner()?)?;
+  assert_eq!(
+    output,
+    r##"<div class="exampleblock">
+  <div class="title">Example 1. First</div>
+  <div class="content">
+    <p>An example.</p>
+  </div>
+</div>
+<div class="exampleblock">
+  <div class="title">Exhibit A: Second</div>
+  <div class="content">
+    <p>A special example.</p>
+  </div>
+</div>
+<div class="exampleblock">
+  <div class="title">Third</div>
+  <div class="content">
+    <p>An example without a number.</p>
+  </div>
+</div>
+"##
+  );
+
+  Ok(())
+}
+
+#[test]
+fn fenced_code_blocks() -> Result<()> {
+  let content = r##"
+```bash
+echo "hello world!"
+```
+"##;
+  let reader = AsciidocReader::new();
+  let opts = options::Opts::parse_from(vec!["asciidoctrine", "--template", "-"]);
+  let mut env = util::Env::Cache(util::Cache::new());
+
+  let ast = reader.parse(content, &opts, &mut env)?;
+
+  let mut buf = BufWriter::new(Vec::new());
+  let mut writer = HtmlWriter::new();
+  writer.write(ast, &opts, &mut buf)?;
+
+  let output = String::from_utf8(buf.into_inner()?)?;
+  assert_eq!(
+    output,
+    r##"<div class="listingblock">
+  <pre>echo "hello world!"</pre>
+</div>
+"##
+  );
+
+  Ok(())
+}
+
+#[test]
+fn footnotes() -> Result<()> {
+  let content = r##"
+This is the main text footnote:[Not all information goes into the man
+text. Some is usefull in footnotes] it explains the genral idea of this
+paragraph.
+"##;
+  let reader = AsciidocReader::new();
+  let opts = options::Opts::parse_from(vec!["asciidoctrine", "--template", "-"]);
+  let mut env = util::Env::Cache(util::Cache::new());
+
+  let ast = reader.parse(content, &opts, &mut env)?;
+
+  let mut buf = BufWriter::new(Vec::new());
+  let mut writer = HtmlWriter::new();
+  writer.write(ast, &opts, &mut buf)?;
+
+  let output = String::from_utf8(buf.into_inner()?)?;
+  assert_eq!(
+    output,
+    r##"<p>This is the main text <sup class="footnote">[<a id="_footnoteref_1" class="footnote" href="#_footnotedef_1" title="View footnote.">1</a>]</sup> it explains the genral idea of this
+paragraph.</p>
+<div id="footnotes">
+  <hr>
+  <div class="footnote" id="_footnotedef_1">
+    <a href="#_footnoteref_1">1</a>. Not all information goes into the man text. Some is usefull in footnotes
+  </div>
+</div>
+"##
+  );
+
+  Ok(())
+}
+
+#[test]
+fn formated_table() -> Result<()> {
+  let content = r##"
+[cols="1,a"]
+|===
+|
+Here inline *markup* _text_ is rendered specially
+
+But paragraphs
+
+* or
+** Lists
+** are not handled as such
+|
+In this cell *markup* _text_ is handeled specially
+
+We can even have multiple paragraphs
+
+* List
+** with
+** multiple
+* entries
+|===
+"##;
+  let reader = AsciidocReader::new();
+  let opts = options::Opts::parse_from(vec!["asciidoctrine", "--template", "-"]);
+  let mut env = util::Env::Cache(util::Cache::new());
+
+  let ast = reader.parse(content, &opts, &mut env)?;
+
+  let mut buf = BufWriter::new(Vec::new());
+  let mut writer = HtmlWriter::new();
+  writer.write(ast, &opts, &mut buf)?;
+
+  let output = String::from_utf8(buf.into_inner()?)?;
+  assert_eq!(
+    output,
+    r##"<table class="tableblock frame-all grid-all stretch">
+  <colgroup>
+    <col style="width: 50%;">
+    <col style="width: 50%;">
+  </colgroup>
+  <tbody>
+    <tr>
+      <td><p>Here inline <strong>markup</strong> <em>text</em> is rendered specially
+
+But paragraphs
+
+* or
+** Lists
+** are not handled as such</p></td>
+      <td>
+        <p>In this cell <strong>markup</strong> <em>text</em> is handeled specially</p>
+        <p>We can even have multiple paragraphs</p>
+        <ul>
+          <li>
+            <p>List</p>
+            <ul>
+              <li>
+                <p>with</p>
+              </li>
+              <li>
+                <p>multiple</p>
+              </li>
+            </ul>
+          </li>
+          <li>
+            <p>entries</p>
+          </li>
+        </ul>
+      </td>
+    </tr>
+  </tbody>
+</table>
+"##
+  );
+
+  Ok(())
+}
+
+#[test]
+fn hard_line_breaks() -> Result<()> {
+  let content = r##"
+Roses are red, +
+violets are blue.
+
+[%hardbreaks]
+Sugar is sweet
+and so are you.
+"##;
+  let reader = AsciidocReader::new();
+  let opts = options::Opts::parse_from(vec!["asciidoctrine", "--template", "-"]);
+  let mut env = util::Env::Cache(util::Cache::new());
+
+  let ast = reader.parse(content, &opts, &mut env)?;
+
+  let mut buf = BufWriter::new(Vec::new());
+  let mut writer = HtmlWriter::new();
+  writer.write(ast, &opts, &mut buf)?;
+
+  let output = String::from_utf8(buf.into_inner()?)?;
+  assert_eq!(
+    output,
+    r##"<p>Roses are red,<br>
+violets are blue.</p>
+<p>Sugar is sweet<br>
+and so are you.</p>
+"##
+  );
+
+  Ok(())
+}
+
+#[test]
+fn atx_headers() -> Result<()> {
+  let content = r##"
+= This is a header
+
+== This is a subheader
+
+=== This is a subsubheader
+
+==== This is a subsubsubheader
+"##;
+  let reader = AsciidocReader::new();
+  let opts = options::Opts::parse_from(vec!["asciidoctrine", "--template", "-"]);
+  let mut env = util::Env::Cache(util::Cache::new());
+
+  let ast = reader.parse(content, &opts, &mut env)?;
+
+  let mut buf = BufWriter::new(Vec::new());
+  let mut writer = HtmlWriter::new();
+  writer.write(ast, &opts, &mut buf)?;
+
+  let output = String::from_utf8(buf.into_inner()?)?;
+  assert_eq!(
+    output,
+    r##"<h1>This is a header</h1>
+<h2 id="_this_is_a_subheader">This is a subheader</h2>
+<h3 id="_this_is_a_subsubheader">This is a subsubheader</h3>
+<h4 id="_this_is_a_subsubsubheader">This is a subsubsubheader</h4>
+"##
+  );
+
+  Ok(())
+}
+
+#[test]
+fn setext_headers() -> Result<()> {
+  let content = r##"
+This is a header
+================
+
+This is a subheader
+-------------------
+
+This is a subsubheader
+~~~~~~~~~~~~~~~~~~~~~~
+
+This is a subsubsubheader
+^^^^^^^^^^^^^^^^^^^^^^^^^
+"##;
+  let reader = AsciidocReader::new();
+  let opts = options::Opts::parse_from(vec!["asciidoctrine", "--template", "-"]);
+  let mut env = util::Env::Cache(util::Cache::new());
+
+  let ast = reader.parse(content, &opts, &mut env)?;
+
+  let mut buf = BufWriter::new(Vec::new());
+  let mut writer = HtmlWriter::new();
+  writer.write(ast, &opts, &mut buf)?;
+
+  let output = String::from_utf8(buf.into_inner()?)?;
+  assert_eq!(
+    output,
+    r##"<h1>This is a header</h1>
+<h2 id="_this_is_a_subheader">This is a subheader</h2>
+<h3 id="_this_is_a_subsubheader">This is a subsubheader</h3>
+<h4 id="_this_is_a_subsubsubheader">This is a subsubsubheader</h4>
+"##
+  );
+
+  Ok(())
+}
+
+#[test]
+fn highlighted_source_blocks() -> Result<()> {
+  let content = r##"
+:source-highlighter: builtin
+
+[source, toml]
+----
+[package]
+name = "demo"
+----
+"##;
+  let reader = AsciidocReader::new();
+  let opts = options::Opts::parse_from(vec!["asciidoctrine", "--template", "-"]);
+  let mut env = util::Env::Cache(util::Cache::new());
+
+  let ast = reader.parse(content, &opts, &mut env)?;
+
+  let mut buf = BufWriter::new(Vec::new());
+  let mut writer = HtmlWriter::new();
+  writer.write(ast, &opts, &mut buf)?;
+
+  let output = String::from_utf8(buf.into_inner()?)?;
+  assert_eq!(
+    output,
+    r##"<div class="listingblock">
+  <pre class="highlightjs highlight"><code class="language-toml hljs" data-lang="toml"><span class="hljs-section">[package]</span>
+<span class="hljs-attr">name</span> = <span class="hljs-string">"demo"</span></code></pre>
+</div>
+"##
+  );
+
+  Ok(())
+}
+
+#[test]
+fn highlighted_source_styles() -> Result<()> {
+  let content = r##"
+:source-highlighter: builtin
+:highlight-css: style
+:highlight-theme: monokai
+
+[source, python]
+----
+def greet(name):
+    return f"Hello {name}!"  # TODO
+----
+"##;
+  let reader = AsciidocReader::new();
+  let opts = options::Opts::parse_from(vec!["asciidoctrine", "--template", "-"]);
+  let mut env = util::Env::Cache(util::Cache::new());
+
+  let ast = reader.parse(content, &opts, &mut env)?;
+
+  let mut buf = BufWriter::new(Vec::new());
+  let mut writer = HtmlWriter::new();
+  writer.write(ast, &opts, &mut buf)?;
+
+  let output = String::from_utf8(buf.into_inner()?)?;
+  assert_eq!(
+    output,
+    r##"<div class="listingblock">
+  <pre class="highlight" style="background: #272822; color: #dddddd"><code data-lang="python"><span style="color: #f92672; font-weight: bold">def</span> <span style="color: #a6e22e; font-weight: bold">greet</span>(name):
+    <span style="color: #f92672; font-weight: bold">return</span> <span style="color: #a6e22e">f"Hello {name}!"</span>  <span style="color: #75715e"># TODO</span></code></pre>
+</div>
+"##
+  );
+
+  Ok(())
+}
+
+#[test]
+fn conditional_ifdef() -> Result<()> {
+  let content = r##"
+:platform: linux
+
+ifdef::platform[]
+This text is shown on {platform}.
+endif::[]
+ifndef::platform[]
+This text is shown without a platform.
+endif::[]
+"##;
+  let reader = AsciidocReader::new();
+  let opts = options::Opts::parse_from(vec!["asciidoctrine", "--template", "-"]);
+  let mut env = util::Env::Cache(util::Cache::new());
+
+  let ast = reader.parse(content, &opts, &mut env)?;
+
+  let mut buf = BufWriter::new(Vec::new());
+  let mut writer = HtmlWriter::new();
+  writer.write(ast, &opts, &mut buf)?;
+
+  let output = String::from_utf8(buf.into_inner()?)?;
+  assert_eq!(
+    output,
+    r##"<p>This text is shown on linux.</p>
+"##
+  );
+
+  Ok(())
+}
+
+#[test]
+fn conditional_ifeval() -> Result<()> {
+  let content = r##"
+:level: 3
+
+ifeval::[{level} >= 2]
+Only experts read this.
+endif::[]
+ifeval::["{level}" == "1"]
+Beginners read this.
+endif::[]
+"##;
+  let reader = AsciidocReader::new();
+  let opts = options::Opts::parse_from(vec!["asciidoctrine", "--template", "-"]);
+  let mut env = util::Env::Cache(util::Cache::new());
+
+  let ast = reader.parse(content, &opts, &mut env)?;
+
+  let mut buf = BufWriter::new(Vec::new());
+  let mut writer = HtmlWriter::new();
+  writer.write(ast, &opts, &mut buf)?;
+
+  let output = String::from_utf8(buf.into_inner()?)?;
+  assert_eq!(
+    output,
+    r##"<p>Only experts read this.</p>
+"##
+  );
+
+  Ok(())
+}
+
+#[test]
+fn conditional_backend() -> Result<()> {
+  let content = r##"
+ifeval::["{backend}" == "html5"]
+Written as {doctype} for the web.
+endif::[]
+ifeval::["{backend}" == "pdf"]
+Written for print.
+endif::[]
+"##;
+  let reader = AsciidocReader::new();
+  let opts = options::Opts::parse_from(vec!["asciidoctrine", "--template", "-"]);
+  let mut env = util::Env::Cache(util::Cache::new());
+
+  let ast = reader.parse(content, &opts, &mut env)?;
+
+  let mut buf = BufWriter::new(Vec::new());
+  let mut writer = HtmlWriter::new();
+  writer.write(ast, &opts, &mut buf)?;
+
+  let output = String::from_utf8(buf.into_inner()?)?;
+  assert_eq!(
+    output,
+    r##"<p>Written as article for the web.</p>
+"##
+  );
+
+  Ok(())
+}
+
+#[test]
+fn image_block() -> Result<()> {
+  let content = r##"
+.A mountain sunset
+[[sunset]]
+[link=https://example.org, align=center]
+image::sunset.jpg[Sunset,300,200]
+
+image::images/tiger-cub.png[]
+
+See <<sunset>>.
+"##;
+  let reader = AsciidocReader::new();
+  let opts = options::Opts::parse_from(vec!["asciidoctrine", "--template", "-"]);
+  let mut env = util::Env::Cache(util::Cache::new());
+
+  let ast = reader.parse(content, &opts, &mut env)?;
+
+  let mut buf = BufWriter::new(Vec::new());
+  let mut writer = HtmlWriter::new();
+  writer.write(ast, &opts, &mut buf)?;
+
+  let output = String::from_utf8(buf.into_inner()?)?;
+  assert_eq!(
+    output,
+    r##"<div id="sunset" class="imageblock text-center">
+  <div class="content">
+    <a class="image" href="https://example.org"><img src="sunset.jpg" alt="Sunset" width="300" height="200"></a>
+  </div>
+  <div class="title">Figure 1. A mountain sunset</div>
+</div>
+<div class="imageblock">
+  <div class="content">
+    <img src="images/tiger-cub.png" alt="tiger cub">
+  </div>
+</div>
+<p>See <a href="#sunset">A mountain sunset</a>.</p>
+"##
+  );
+
+  Ok(())
+}
+
+#[test]
+fn image_block_with_shorthand_attributes() -> Result<()> {
+  let content = r##"
+[#fig1.center.wide]
+.A figure
+image::diagram.png[Diagram]
+
+See <<fig1>>.
+"##;
+  let reader = AsciidocReader::new();
+  let opts = options::Opts::parse_from(vec!["asciidoctrine", "--template", "-"]);
+  let mut env = util::Env::Cache(util::Cache::new());
+
+  let ast = reader.parse(content, &opts, &mut env)?;
+
+  let mut buf = BufWriter::new(Vec::new());
+  let mut writer = HtmlWriter::new();
+  writer.write(ast, &opts, &mut buf)?;
+
+  let output = String::from_utf8(buf.into_inner()?)?;
+  assert_eq!(
+    output,
+    r##"<div id="fig1" class="imageblock center wide">
+  <div class="content">
+    <img src="diagram.png" alt="Diagram">
+  </div>
+  <div class="title">Figure 1. A figure</div>
+</div>
+<p>See <a href="#fig1">A figure</a>.</p>
+"##
+  );
+
+  Ok(())
+}
+
+#[test]
+fn image_attributes_are_escaped() -> Result<()> {
+  let content = r##"
+image::my.chart-v2.png[]
+
+image::chart.png[Fish & <chips>,100,height=50"x]
+"##;
+  let reader = AsciidocReader::new();
+  let opts = options::Opts::parse_from(vec!["asciidoctrine", "--template", "-"]);
+  let mut env = util::Env::Cache(util::Cache::new());
+
+  let ast = reader.parse(content, &opts, &mut env)?;
+
+  let mut buf = BufWriter::new(Vec::new());
+  let mut writer = HtmlWriter::new();
+  writer.write(ast, &opts, &mut buf)?;
+
+  let output = String::from_utf8(buf.into_inner()?)?;
+  assert!(output.contains(r#"<img src="my.chart-v2.png" alt="my.chart v2">"#));
+  assert!(output.contains(r#"alt="Fish &amp; &lt;chips&gt;" width="100" height="50&quot;x">"#));
+
+  Ok(())
+}
+
+#[test]
+fn block_ids_and_roles() -> Result<()> {
+  let content = r##"
+[#c2.big]
+== Custom2
+
+[#lst.role]
+----
+x
+----
+
+[.lead]
+Para.
+
+[#side.note]
+****
+Aside.
+****
+
+[.steps]
+* One
+"##;
+  let reader = AsciidocReader::new();
+  let opts = options::Opts::parse_from(vec!["asciidoctrine", "--template", "-"]);
+  let mut env = util::Env::Cache(util::Cache::new());
+
+  let ast = reader.parse(content, &opts, &mut env)?;
+
+  let mut buf = BufWriter::new(Vec::new());
+  let mut writer = HtmlWriter::new();
+  writer.write(ast, &opts, &mut buf)?;
+
+  let output = String::from_utf8(buf.into_inner()?)?;
+  assert_eq!(
+    output,
+    r##"<h2 id="c2">Custom2</h2>
+<div id="lst" class="listingblock role">
+  <pre>x</pre>
+</div>
+<p class="lead">Para.</p>
+<div id="side" class="sidebarblock note">
+  <div class="content">
+    <p>Aside.</p>
+  </div>
+</div>
+<ul class="steps">
+  <li>
+    <p>One</p>
+  </li>
+</ul>
+"##
+  );
+
+  Ok(())
+}
+
+#[test]
+fn include_leveloffset() -> Result<()> {
+  let content = r##"
+include::chapter.adoc[leveloffset=+1]
+"##;
+  let reader = AsciidocReader::new();
+  let opts = options::Opts::parse_from(vec!["asciidoctrine", "--template", "-"]);
+  let mut env = util::Env::Cache(util::Cache::new());
+  env.write("chapter.adoc", r#"== A Chapter
+
+Some text in the chapter."#)?;
+  let ast = reader.parse(content, &opts, &mut env)?;
+
+  let mut buf = BufWriter::new(Vec::new());
+  let mut writer = HtmlWriter::new();
+  writer.write(ast, &opts, &mut buf)?;
+
+  let output = String::from_utf8(buf.into_inner()?)?;
+  assert_eq!(
+    output,
+    r##"<h3 id="_a_chapter">A Chapter</h3>
+<p>Some text in the chapter.</p>
+"##
+  );
+
+  Ok(())
+}
+
+#[test]
+fn include_lines() -> Result<()> {
+  let content = r##"
+include::lines.adoc[lines=1;3..]
+"##;
+  let reader = AsciidocReader::new();
+  let opts = options::Opts::parse_from(vec!["asciidoctrine", "--template", "-"]);
+  let mut env = util::Env::Cache(util::Cache::new());
+  env.write("lines.adoc", r#"The first line.
+The second line.
+
+The last paragraph."#)?;
+  let ast = reader.parse(content, &opts, &mut env)?;
+
+  let mut buf = BufWriter::new(Vec::new());
+  let mut writer = HtmlWriter::new();
+  writer.write(ast, &opts, &mut buf)?;
+
+  let output = String::from_utf8(buf.into_inner()?)?;
+  assert_eq!(
+    output,
+    r##"<p>The first line.</p>
+<p>The last paragraph.</p>
+"##
+  );
+
+  Ok(())
+}
+
+#[test]
+fn include_listing() -> Result<()> {
+  let content = r##"
+[source, rust]
+----
+include::hello.rs[tag=print,indent=0]
+----
+"##;
+  let reader = AsciidocReader::new();
+  let opts = options::Opts::parse_from(vec!["asciidoctrine", "--template", "-"]);
+  let mut env = util::Env::Cache(util::Cache::new());
+  env.write("hello.rs", r#"fn main() {
+  // tag::print[]
+  let name = "World";
+  println!("Hello {}", name);
+  // end::print[]
+}"#)?;
+  let ast = reader.parse(content, &opts, &mut env)?;
+
+  let mut buf = BufWriter::new(Vec::new());
+  let mut writer = HtmlWriter::new();
+  writer.write(ast, &opts, &mut buf)?;
+
+  let output = String::from_utf8(buf.into_inner()?)?;
+  assert_eq!(
+    output,
+    r##"<div class="listingblock">
+  <pre>let name = "World";
+println!("Hello {}", name);</pre>
+</div>
+"##
+  );
+
+  Ok(())
+}
+
+#[test]
+fn include_macro() -> Result<()> {
+  let content = r##"
+include::included_document.adoc[]
+"##;
+  let reader = AsciidocReader::new();
+  let opts = options::Opts::parse_from(vec!["asciidoctrine", "--template", "-"]);
+  let mut env = util::Env::Cache(util::Cache::new());
+  env.write("included_document.adoc", r#"Some text in another file."#)?;
+  let ast = reader.parse(content, &opts, &mut env)?;
+
+  let mut buf = BufWriter::new(Vec::new());
+  let mut writer = HtmlWriter::new();
+  writer.write(ast, &opts, &mut buf)?;
+
+  let output = String::from_utf8(buf.into_inner()?)?;
+  assert_eq!(
+    output,
+    r##"<p>Some text in another file.</p>
+"##
+  );
+
+  Ok(())
+}
+
+#[test]
+fn include_missing_file() -> Result<()> {
+  let content = r##"
+include::missing.adoc[leveloffset=+1]
+
+----
+include::missing.rs[]
+----
+"##;
+  let reader = AsciidocReader::new();
+  let opts = options::Opts::parse_from(vec!["asciidoctrine", "--template", "-", "book.adoc"]);
+  let mut env = util::Env::Cache(util::Cache::new());
+  let ast = reader.parse(content, &opts, &mut env)?;
+
+  let mut buf = BufWriter::new(Vec::new());
+  let mut writer = HtmlWriter::new();
+  writer.write(ast, &opts, &mut buf)?;
+
+  let output = String::from_utf8(buf.into_inner()?)?;
+  assert_eq!(
+    output,
+    r##"<p>Unresolved directive in book.adoc - include::missing.adoc[leveloffset=+1]</p>
+<div class="listingblock">
+  <pre>Unresolved directive in book.adoc - include::missing.rs[]</pre>
+</div>
+"##
+  );
+
+  Ok(())
+}
+
+#[test]
+fn include_macro_nested() -> Result<()> {
+  let content = r##"
+include::nested/included_document.adoc[]
+"##;
+  let reader = AsciidocReader::new();
+  let opts = options::Opts::parse_from(vec!["asciidoctrine", "--template", "-"]);
+  let mut env = util::Env::Cache(util::Cache::new());
+  env.write("nested/included_document.adoc", r#"Some text in another file. Will include a nested file.
+
+include::nested/included_document.adoc[]"#)?;env.write("nested/nested/included_document.adoc", r#"This one is nested. It is relative to the included document."#)?;
+  let ast = reader.parse(content, &opts, &mut env)?;
+
+  let mut buf = BufWriter::new(Vec::new());
+  let mut writer = HtmlWriter::new();
+  writer.write(ast, &opts, &mut buf)?;
+
+  let output = String::from_utf8(buf.into_inner()?)?;
+  assert_eq!(
+    output,
+    r##"<p>Some text in another file. Will include a nested file.</p>
+<p>This one is nested. It is relative to the included document.</p>
+"##
+  );
+
+  Ok(())
+}
+
+#[test]
+fn include_tags() -> Result<()> {
+  let content = r##"
+include::tagged.adoc[tag=intro]
+"##;
+  let reader = AsciidocReader::new();
+  let opts = options::Opts::parse_from(vec!["asciidoctrine", "--template", "-"]);
+  let mut env = util::Env::Cache(util::Cache::new());
+  env.write("tagged.adoc", r#"// tag::intro[]
+This is the introduction.
+// end::intro[]
+
+This text is not included."#)?;
+  let ast = reader.parse(content, &opts, &mut env)?;
+
+  let mut buf = BufWriter::new(Vec::new());
+  let mut writer = HtmlWriter::new();
+  writer.write(ast, &opts, &mut buf)?;
+
+  let output = String::from_utf8(buf.into_inner()?)?;
+  assert_eq!(
+    output,
+    r##"<p>This is the introduction.</p>
+"##
+  );
+
+  Ok(())
+}
+
+#[test]
+fn index_terms() -> Result<()> {
+  let content = r##"
+The ((Linux)) kernel(((Operating Systems, Linux))) is
+written in indexterm2:[C].indexterm:[Languages,C]
+"##;
+  let reader = AsciidocReader::new();
+  let opts = options::Opts::parse_from(vec!["asciidoctrine", "--template", "-"]);
+  let mut env = util::Env::Cache(util::Cache::new());
+
+  let ast = reader.parse(content, &opts, &mut env)?;
+
+  let mut buf = BufWriter::new(Vec::new());
+  let mut writer = HtmlWriter::new();
+  writer.write(ast, &opts, &mut buf)?;
+
+  let output = String::from_utf8(buf.into_inner()?)?;
+  assert_eq!(
+    output,
+    r##"<p>The Linux kernel is
+written in C.</p>
+"##
+  );
+
+  Ok(())
+}
+
+#[test]
+fn inline_bold() -> Result<()> {
+  let content = r##"
+Some text is *bold*.
+"##;
+  let reader = AsciidocReader::new();
+  let opts = options::Opts::parse_from(vec!["asciidoctrine", "--template", "-"]);
+  let mut env = util::Env::Cache(util::Cache::new());
+
+  let ast = reader.parse(content, &opts, &mut env)?;
+
+  let mut buf = BufWriter::new(Vec::new());
+  let mut writer = HtmlWriter::new();
+  writer.write(ast, &opts, &mut buf)?;
+
+  let output = String::from_utf8(buf.into_inner()?)?;
+  assert_eq!(
+    output,
+    r##"<p>Some text is <strong>bold</strong>.</p>
+"##
+  );
+
+  Ok(())
+}
+
+#[test]
+fn inline_image() -> Result<()> {
+  let content = r##"
+Press the image:play.png[Play,16,16] button.
+"##;
+  let reader = AsciidocReader::new();
+  let opts = options::Opts::parse_from(vec!["asciidoctrine", "--template", "-"]);
+  let mut env = util::Env::Cache(util::Cache::new());
+
+  let ast = reader.parse(content, &opts, &mut env)?;
+
+  let mut buf = BufWriter::new(Vec::new());
+  let mut writer = HtmlWriter::new();
+  writer.write(ast, &opts, &mut buf)?;
+
+  let output = String::from_utf8(buf.into_inner()?)?;
+  assert_eq!(
+    output,
+    r##"<p>Press the <span class="image"><img src="play.png" alt="Play" width="16" height="16"></span> button.</p>
+"##
+  );
+
+  Ok(())
+}
+
+#[test]
+fn inline_italic() -> Result<()> {
+  let content = r##"
+Some text is _italic_.
+"##;
+  let reader = AsciidocReader::new();
+  let opts = options::Opts::parse_from(vec!["asciidoctrine", "--template", "-"]);
+  let mut env = util::Env::Cache(util::Cache::new());
+
+  let ast = reader.parse(content, &opts, &mut env)?;
+
+  let mut buf = BufWriter::new(Vec::new());
+  let mut writer = HtmlWriter::new();
+  writer.write(ast, &opts, &mut buf)?;
+
+  let output = String::from_utf8(buf.into_inner()?)?;
+  assert_eq!(
+    output,
+    r##"<p>Some text is <em>italic</em>.</p>
+"##
+  );
+
+  Ok(())
+}
+
+#[test]
+fn inline_monospaced() -> Result<()> {
+  let content = r##"
+Some text is `monospaced`.
+"##;
+  let reader = AsciidocReader::new();
+  let opts = options::Opts::parse_from(vec!["asciidoctrine", "--template", "-"]);
+  let mut env = util::Env::Cache(util::Cache::new());
+
+  let ast = reader.parse(content, &opts, &mut env)?;
+
+  let mut buf = BufWriter::new(Vec::new());
+  let mut writer = HtmlWriter::new();
+  writer.write(ast, &opts, &mut buf)?;
+
+  let output = String::from_utf8(buf.into_inner()?)?;
+  assert_eq!(
+    output,
+    r##"<p>Some text is <code>monospaced</code>.</p>
+"##
+  );
+
+  Ok(())
+}
+
+#[test]
+fn inline_passthrough() -> Result<()> {
+  let content = r##"
+This is +++<u>underlined</u>+++ and pass:[<del>deleted</del>] text.
+"##;
+  let reader = AsciidocReader::new();
+  let opts = options::Opts::parse_from(vec!["asciidoctrine", "--template", "-"]);
+  let mut env = util::Env::Cache(util::Cache::new());
+
+  let ast = reader.parse(content, &opts, &mut env)?;
+
+  let mut buf = BufWriter::new(Vec::new());
+  let mut writer = HtmlWriter::new();
+  writer.write(ast, &opts, &mut buf)?;
+
+  let output = String::from_utf8(buf.into_inner()?)?;
+  assert_eq!(
+    output,
+    r##"<p>This is <u>underlined</u> and <del>deleted</del> text.</p>
+"##
+  );
+
+  Ok(())
+}
+
+#[test]
+fn link_macros() -> Result<()> {
+  let content = r##"
+See link:index.html[the index], link:other.html[] or
+https://example.org[a new window^] or write to
+mailto:join@example.org[the list,Subscribe me,I want to join].
+"##;
+  let reader = AsciidocReader::new();
+  let opts = options::Opts::parse_from(vec!["asciidoctrine", "--template", "-"]);
+  let mut env = util::Env::Cache(util::Cache::new());
+
+  let ast = reader.parse(content, &opts, &mut env)?;
+
+  let mut buf = BufWriter::new(Vec::new());
+  let mut writer = HtmlWriter::new();
+  writer.write(ast, &opts, &mut buf)?;
+
+  let output = String::from_utf8(buf.into_inner()?)?;
+  assert_eq!(
+    output,
+    r##"<p>See <a href="index.html">the index</a>, <a href="other.html" class="bare">other.html</a> or
+<a href="https://example.org" target="_blank" rel="noopener">a new window</a> or write to
+<a href="mailto:join@example.org?subject=Subscribe%20me&amp;body=I%20want%20to%20join">the list</a>.</p>
+"##
+  );
+
+  Ok(())
+}
+
+#[test]
+fn links() -> Result<()> {
+  let content = r##"
+This paragraph has links. A raw link
+https://kober-systems.github.io/literate_programming_toolsuite/
+and a link to
+https://github.com/kober-systems/literate_programming_toolsuite/tree/master/asciidoctrine[Asciidocrine].
+"##;
+  let reader = AsciidocReader::new();
+  let opts = options::Opts::parse_from(vec!["asciidoctrine", "--template", "-"]);
+  let mut env = util::Env::Cache(util::Cache::new());
+
+  let ast = reader.parse(content, &opts, &mut env)?;
+
+  let mut buf = BufWriter::new(Vec::new());
+  let mut writer = HtmlWriter::new();
+  writer.write(ast, &opts, &mut buf)?;
+
+  let output = String::from_utf8(buf.into_inner()?)?;
+  assert_eq!(
+    output,
+    r##"<p>This paragraph has links. A raw link
+<a href="https://kober-systems.github.io/literate_programming_toolsuite/" class="bare">https://kober-systems.github.io/literate_programming_toolsuite/</a>
+and a link to
+<a href="https://github.com/kober-systems/literate_programming_toolsuite/tree/master/asciidoctrine">Asciidocrine</a>.</p>
+"##
+  );
+
+  Ok(())
+}
+
+#[test]
+fn list_continuation() -> Result<()> {
+  let content = r##"
+* Install the program:
++
+----
+cargo install asciidoctrine
+----
++
+Then run it.
+* Done
+"##;
+  let reader = AsciidocReader::new();
+  let opts = options::Opts::parse_from(vec!["asciidoctrine", "--template", "-"]);
+  let mut env = util::Env::Cache(util::Cache::new());
+
+  let ast = reader.parse(content, &opts, &mut env)?;
+
+  let mut buf = BufWriter::new(Vec::new());
+  let mut writer = HtmlWriter::new();
+  writer.write(ast, &opts, &mut buf)?;
+
+  let output = String::from_utf8(buf.into_inner()?)?;
+  assert_eq!(
+    output,
+    r##"<ul>
+  <li>
+    <p>Install the program:</p>
+    <div class="listingblock">
+      <pre>cargo install asciidoctrine</pre>
+    </div>
+    <p>Then run it.</p>
+  </li>
+  <li>
+    <p>Done</p>
+  </li>
+</ul>
+"##
+  );
+
+  Ok(())
+}
+
+#[test]
+fn mixed_list() -> Result<()> {
+  let content = r##"
+* Fruits
+. Apple
+. Banana
+* Vegetables
+"##;
+  let reader = AsciidocReader::new();
+  let opts = options::Opts::parse_from(vec!["asciidoctrine", "--template", "-"]);
+  let mut env = util::Env::Cache(util::Cache::new());
+
+  let ast = reader.parse(content, &opts, &mut env)?;
+
+  let mut buf = BufWriter::new(Vec::new());
+  let mut writer = HtmlWriter::new();
+  writer.write(ast, &opts, &mut buf)?;
+
+  let output = String::from_utf8(buf.into_inner()?)?;
+  assert_eq!(
+    output,
+    r##"<ul>
+  <li>
+    <p>Fruits</p>
+    <ol class="loweralpha" type="a">
+      <li>
+        <p>Apple</p>
+      </li>
+      <li>
+        <p>Banana</p>
+      </li>
+    </ol>
+  </li>
+  <li>
+    <p>Vegetables</p>
+  </li>
+</ul>
+"##
+  );
+
+  Ok(())
+}
+
+#[test]
+fn numbered_list() -> Result<()> {
+  let content = r##"
+. This
+. is
+. a
+.. nested
+. numbered list
+"##;
+  let reader = AsciidocReader::new();
+  let opts = options::Opts::parse_from(vec!["asciidoctrine", "--template", "-"]);
+  let mut env = util::Env::Cache(util::Cache::new());
+
+  let ast = reader.parse(content, &opts, &mut env)?;
+
+  let mut buf = BufWriter::new(Vec::new());
+  let mut writer = HtmlWriter::new();
+  writer.write(ast, &opts, &mut buf)?;
+
+  let output = String::from_utf8(buf.into_inner()?)?;
+  assert_eq!(
+    output,
+    r##"<ol class="arabic">
+  <li>
+    <p>This</p>
+  </li>
+  <li>
+    <p>is</p>
+  </li>
+  <li>
+    <p>a</p>
+    <ol class="loweralpha" type="a">
+      <li>
+        <p>nested</p>
+      </li>
+    </ol>
+  </li>
+  <li>
+    <p>numbered list</p>
+  </li>
+</ol>
+"##
+  );
+
+  Ok(())
+}
+
+#[test]
+fn numbered_list_start() -> Result<()> {
+  let content = r##"
+[%reversed,start=3]
+. Three
+. Two
+"##;
+  let reader = AsciidocReader::new();
+  let opts = options::Opts::parse_from(vec!["asciidoctrine", "--template", "-"]);
+  let mut env = util::Env::Cache(util::Cache::new());
+
+  let ast = reader.parse(content, &opts, &mut env)?;
+
+  let mut buf = BufWriter::new(Vec::new());
+  let mut writer = HtmlWriter::new();
+  writer.write(ast, &opts, &mut buf)?;
+
+  let output = String::from_utf8(buf.into_inner()?)?;
+  assert_eq!(
+    output,
+    r##"<ol class="arabic" start="3" reversed>
+  <li>
+    <p>Three</p>
+  </li>
+  <li>
+    <p>Two</p>
+  </li>
+</ol>
+"##
+  );
+
+  Ok(())
+}
+
+#[test]
+fn open_block() -> Result<()> {
+  let content = r##"
+--
+An open block groups
+
+several paragraphs.
+--
+"##;
+  let reader = AsciidocReader::new();
+  let opts = options::Opts::parse_from(vec!["asciidoctrine", "--template", "-"]);
+  let mut env = util::Env::Cache(util::Cache::new());
+
+  let ast = reader.parse(content, &opts, &mut env)?;
+
+  let mut buf = BufWriter::new(Vec::new());
+  let mut writer = HtmlWriter::new();
+  writer.write(ast, &opts, &mut buf)?;
+
+  let output = String::from_utf8(buf.into_inner()?)?;
+  assert_eq!(
+    output,
+    r##"<div class="openblock">
+  <div class="content">
+    <p>An open block groups</p>
+    <p>several paragraphs.</p>
+  </div>
+</div>
+"##
+  );
+
+  Ok(())
+}
+
+#[test]
+fn passthrough_block() -> Result<()> {
+  let content = r##"
+++++
+<span class="badge">raw html</span>
+++++
+"##;
+  let reader = AsciidocReader::new();
+  let opts = options::Opts::parse_from(vec!["asciidoctrine", "--template", "-"]);
+  let mut env = util::Env::Cache(util::Cache::new());
+
+  let ast = reader.parse(content, &opts, &mut env)?;
+
+  let mut buf = BufWriter::new(Vec::new());
+  let mut writer = HtmlWriter::new();
+  writer.write(ast, &opts, &mut buf)?;
+
+  let output = String::from_utf8(buf.into_inner()?)?;
+  assert_eq!(
+    output,
+    r##"<span class="badge">raw html</span>
+"##
+  );
+
+  Ok(())
+}
+
+#[test]
+fn quote_block() -> Result<()> {
+  let content = r##"
+[quote, Abraham Lincoln, Gettysburg Address]
+____
+Four score and seven years ago our fathers brought forth
+on this continent a new nation...
+____
+"##;
+  let reader = AsciidocReader::new();
+  let opts = options::Opts::parse_from(vec!["asciidoctrine", "--template", "-"]);
+  let mut env = util::Env::Cache(util::Cache::new());
+
+  let ast = reader.parse(content, &opts, &mut env)?;
+
+  let mut buf = BufWriter::new(Vec::new());
+  let mut writer = HtmlWriter::new();
+  writer.write(ast, &opts, &mut buf)?;
+
+  let output = String::from_utf8(buf.into_inner()?)?;
+  assert_eq!(
+    output,
+    r##"<div class="quoteblock">
+  <blockquote>
+    <p>Four score and seven years ago our fathers brought forth
+on this continent a new nation&#8230;&#8203;</p>
+  </blockquote>
+  <div class="attribution">
+    &#8212; Abraham Lincoln<br>
+    <cite>Gettysburg Address</cite>
+  </div>
+</div>
+"##
+  );
+
+  Ok(())
+}
+
+#[test]
+fn quote_paragraph() -> Result<()> {
+  let content = r##"
+[quote, Albert Einstein]
+A person who never made a mistake never tried anything new.
+"##;
+  let reader = AsciidocReader::new();
+  let opts = options::Opts::parse_from(vec!["asciidoctrine", "--template", "-"]);
+  let mut env = util::Env::Cache(util::Cache::new());
+
+  let ast = reader.parse(content, &opts, &mut env)?;
+
+  let mut buf = BufWriter::new(Vec::new());
+  let mut writer = HtmlWriter::new();
+  writer.write(ast, &opts, &mut buf)?;
+
+  let output = String::from_utf8(buf.into_inner()?)?;
+  assert_eq!(
+    output,
+    r##"<div class="quoteblock">
+  <blockquote>
+    <p>A person who never made a mistake never tried anything new.</p>
+  </blockquote>
+  <div class="attribution">
+    &#8212; Albert Einstein
+  </div>
+</div>
+"##
+  );
+
+  Ok(())
+}
+
+#[test]
+fn replacements() -> Result<()> {
+  let content = r##"
+Copyright (C) 2024 -- all rights reserved... The arrow -> points
+to => the result. It's a "`quoted`" and '`single`' text.
+-- Thomas Jefferson
+"##;
+  let reader = AsciidocReader::new();
+  let opts = options::Opts::parse_from(vec!["asciidoctrine", "--template", "-"]);
+  let mut env = util::Env::Cache(util::Cache::new());
+
+  let ast = reader.parse(content, &opts, &mut env)?;
+
+  let mut buf = BufWriter::new(Vec::new());
+  let mut writer = HtmlWriter::new();
+  writer.write(ast, &opts, &mut buf)?;
+
+  let output = String::from_utf8(buf.into_inner()?)?;
+  assert_eq!(
+    output,
+    r##"<p>Copyright &#169; 2024&#8201;&#8212;&#8201;all rights reserved&#8230;&#8203; The arrow &#8594; points
+to &#8658; the result. It&#8217;s a &#8220;quoted&#8221; and &#8216;single&#8217; text.
+&#8201;&#8212;&#8201;Thomas Jefferson</p>
+"##
+  );
+
+  Ok(())
+}
+
+#[test]
+fn sidebar_block() -> Result<()> {
+  let content = r##"
+.Background
+****
+Sidebars hold additional information.
+****
+"##;
+  let reader = AsciidocReader::new();
+  let opts = options::Opts::parse_from(vec!["asciidoctrine", "--template", "-"]);
+  let mut env = util::Env::Cache(util::Cache::new());
+
+  let ast = reader.parse(content, &opts, &mut env)?;
+
+  let mut buf = BufWriter::new(Vec::new());
+  let mut writer = HtmlWriter::new();
+  writer.write(ast, &opts, &mut buf)?;
+
+  let output = String::from_utf8(buf.into_inner()?)?;
+  assert_eq!(
+    output,
+    r##"<div class="sidebarblock">
+  <div class="content">
+    <div class="title">Background</div>
+    <p>Sidebars hold additional information.</p>
   </div>
 </div>
 "##
@@ -236,27 +2116,12 @@ paragraph.</p>
 }
 
 #[test]
-fn formated_table() -> Result<()> {
+fn simple_table() -> Result<()> {
   let content = r##"
-[cols="1,a"]
 |===
-|
-Here inline *markup* _text_ is rendered specially
-
-But paragraphs
-
-* or
-** Lists
-** are not handled as such
-|
-In this cell *markup* _text_ is handeled specially
-
-We can even have multiple paragraphs
-
-* List
-** with
-** multiple
-* entries
+| Col1 | Col2
+| Cel1 | Cel2
+| Cel3 | Cel4
 |===
 "##;
   let reader = AsciidocReader::new();
@@ -279,33 +2144,16 @@ We can even have multiple paragraphs
   </colgroup>
   <tbody>
     <tr>
-      <td><p>Here inline <strong>markup</strong> <em>text</em> is rendered specially
-
-But paragraphs
-
-* or
-** Lists
-** are not handled as such</p></td>
-      <td>
-        <p>In this cell <strong>markup</strong> <em>text</em> is handeled specially</p>
-        <p>We can even have multiple paragraphs</p>
-        <ul>
-          <li>
-            <p>List</p>
-            <ul>
-              <li>
-                <p>with</p>
-              </li>
-              <li>
-                <p>multiple</p>
-              </li>
-            </ul>
-          </li>
-          <li>
-            <p>entries</p>
-          </li>
-        </ul>
-      </td>
+      <td><p>Col1</p></td>
+      <td><p>Col2</p></td>
+    </tr>
+    <tr>
+      <td><p>Cel1</p></td>
+      <td><p>Cel2</p></td>
+    </tr>
+    <tr>
+      <td><p>Cel3</p></td>
+      <td><p>Cel4</p></td>
     </tr>
   </tbody>
 </table>
@@ -316,15 +2164,16 @@ But paragraphs
 }
 
 #[test]
-fn atx_headers() -> Result<()> {
+fn sourcecode_blocks() -> Result<()> {
   let content = r##"
-= This is a header
-
-== This is a subheader
-
-=== This is a subsubheader
-
-==== This is a subsubsubheader
+[source, bash]
+----
+echo "hello world!"
+----
+[source, bash]
+....
+echo "hello world!"
+....
 "##;
   let reader = AsciidocReader::new();
   let opts = options::Opts::parse_from(vec!["asciidoctrine", "--template", "-"]);
@@ -339,10 +2188,12 @@ fn atx_headers() -> Result<()> {
   let output = String::from_utf8(buf.into_inner()?)?;
   assert_eq!(
     output,
-    r##"<h1>This is a header</h1>
-<h2 id="_this_is_a_subheader">This is a subheader</h2>
-<h3 id="_this_is_a_subsubheader">This is a subsubheader</h3>
-<h4 id="_this_is_a_subsubsubheader">This is a subsubsubheader</h4>
+    r##"<div class="listingblock">
+  <pre>echo "hello world!"</pre>
+</div>
+<div class="listingblock">
+  <pre>echo "hello world!"</pre>
+</div>
 "##
   );
 
@@ -350,19 +2201,9 @@ fn atx_headers() -> Result<()> {
 }
 
 #[test]
-fn setext_headers() -> Result<()> {
+fn stem() -> Result<()> {
   let content = r##"
-This is a header
-================
-
-This is a subheader
--------------------
-
-This is a subsubheader
-~~~~~~~~~~~~~~~~~~~~~~
-
-This is a subsubsubheader
-^^^^^^^^^^^^^^^^^^^^^^^^^
+We know stem:[sqrt(4) = 2] and latexmath:[C = \alpha + \beta].
 "##;
   let reader = AsciidocReader::new();
   let opts = options::Opts::parse_from(vec!["asciidoctrine", "--template", "-"]);
@@ -377,10 +2218,7 @@ This is a subsubsubheader
   let output = String::from_utf8(buf.into_inner()?)?;
   assert_eq!(
     output,
-    r##"<h1>This is a header</h1>
-<h2 id="_this_is_a_subheader">This is a subheader</h2>
-<h3 id="_this_is_a_subsubheader">This is a subsubheader</h3>
-<h4 id="_this_is_a_subsubsubheader">This is a subsubsubheader</h4>
+    r##"<p>We know \$sqrt(4) = 2\$ and \(C = \alpha + \beta\).</p>
 "##
   );
 
@@ -388,14 +2226,20 @@ This is a subsubsubheader
 }
 
 #[test]
-fn include_macro() -> Result<()> {
+fn substitutions() -> Result<()> {
   let content = r##"
-include::included_document.adoc[]
+[subs="-replacements"]
+The arrow -> stays and *quotes* still work.
+
+[source, text, subs="+quotes"]
+----
+Some *bold* <text>
+----
 "##;
   let reader = AsciidocReader::new();
   let opts = options::Opts::parse_from(vec!["asciidoctrine", "--template", "-"]);
   let mut env = util::Env::Cache(util::Cache::new());
-  env.write("included_document.adoc", r#"Some text in another file."#)?;
+
   let ast = reader.parse(content, &opts, &mut env)?;
 
   let mut buf = BufWriter::new(Vec::new());
@@ -405,7 +2249,10 @@ include::included_document.adoc[]
   let output = String::from_utf8(buf.into_inner()?)?;
   assert_eq!(
     output,
-    r##"<p>Some text in another file.</p>
+    r##"<p>The arrow -&gt; stays and <strong>quotes</strong> still work.</p>
+<div class="listingblock">
+  <pre>Some <strong>bold</strong> &lt;text&gt;</pre>
+</div>
 "##
   );
 
@@ -413,16 +2260,25 @@ include::included_document.adoc[]
 }
 
 #[test]
-fn include_macro_nested() -> Result<()> {
+fn table_caption() -> Result<()> {
   let content = r##"
-include::nested/included_document.adoc[]
+.Values
+[[values]]
+|===
+| Col1 | Col2
+|===
+
+:table-caption: Tab.
+
+.More values
+|===
+| Col3 | Col4
+|===
 "##;
   let reader = AsciidocReader::new();
   let opts = options::Opts::parse_from(vec!["asciidoctrine", "--template", "-"]);
   let mut env = util::Env::Cache(util::Cache::new());
-  env.write("nested/included_document.adoc", r#"Some text in another file. Will include a nested file.
 
-include::nested/included_document.adoc[]"#)?;env.write("nested/nested/included_document.adoc", r#"This one is nested. It is relative to the included document."#)?;
   let ast = reader.parse(content, &opts, &mut env)?;
 
   let mut buf = BufWriter::new(Vec::new());
@@ -432,8 +2288,32 @@ include::nested/included_document.adoc[]"#)?;env.write("nested/nested/included_d
   let output = String::from_utf8(buf.into_inner()?)?;
   assert_eq!(
     output,
-    r##"<p>Some text in another file. Will include a nested file.</p>
-<p>This one is nested. It is relative to the included document.</p>
+    r##"<table id="values" class="tableblock frame-all grid-all stretch">
+  <caption class="title">Table 1. Values</caption>
+  <colgroup>
+    <col style="width: 50%;">
+    <col style="width: 50%;">
+  </colgroup>
+  <tbody>
+    <tr>
+      <td><p>Col1</p></td>
+      <td><p>Col2</p></td>
+    </tr>
+  </tbody>
+</table>
+<table class="tableblock frame-all grid-all stretch">
+  <caption class="title">Tab. 2. More values</caption>
+  <colgroup>
+    <col style="width: 50%;">
+    <col style="width: 50%;">
+  </colgroup>
+  <tbody>
+    <tr>
+      <td><p>Col3</p></td>
+      <td><p>Col4</p></td>
+    </tr>
+  </tbody>
+</table>
 "##
   );
 
@@ -441,9 +2321,10 @@ include::nested/included_document.adoc[]"#)?;env.write("nested/nested/included_d
 }
 
 #[test]
-fn inline_bold() -> Result<()> {
+fn text_anchors() -> Result<()> {
   let content = r##"
-Some text is *bold*.
+The [[start]]beginning and anchor:finish[the finish]end.
+See <<start>> and <<finish>>.
 "##;
   let reader = AsciidocReader::new();
   let opts = options::Opts::parse_from(vec!["asciidoctrine", "--template", "-"]);
@@ -458,7 +2339,8 @@ Some text is *bold*.
   let output = String::from_utf8(buf.into_inner()?)?;
   assert_eq!(
     output,
-    r##"<p>Some text is <strong>bold</strong>.</p>
+    r##"<p>The <a id="start"></a>beginning and <a id="finish"></a>end.
+See <a href="#start">start</a> and <a href="#finish">the finish</a>.</p>
 "##
   );
 
@@ -466,34 +2348,22 @@ Some text is *bold*.
 }
 
 #[test]
-fn inline_italic() -> Result<()> {
+fn toc_macro() -> Result<()> {
   let content = r##"
-Some text is _italic_.
-"##;
-  let reader = AsciidocReader::new();
-  let opts = options::Opts::parse_from(vec!["asciidoctrine", "--template", "-"]);
-  let mut env = util::Env::Cache(util::Cache::new());
+= Document
+:toc: macro
+:toclevels: 1
+:toc-title: Contents
 
-  let ast = reader.parse(content, &opts, &mut env)?;
+An introduction.
 
-  let mut buf = BufWriter::new(Vec::new());
-  let mut writer = HtmlWriter::new();
-  writer.write(ast, &opts, &mut buf)?;
+toc::[]
 
-  let output = String::from_utf8(buf.into_inner()?)?;
-  assert_eq!(
-    output,
-    r##"<p>Some text is <em>italic</em>.</p>
-"##
-  );
+== First Section
 
-  Ok(())
-}
+=== Deeper Section
 
-#[test]
-fn inline_monospaced() -> Result<()> {
-  let content = r##"
-Some text is `monospaced`.
+== Second Section
 "##;
   let reader = AsciidocReader::new();
   let opts = options::Opts::parse_from(vec!["asciidoctrine", "--template", "-"]);
@@ -508,7 +2378,18 @@ Some text is `monospaced`.
   let output = String::from_utf8(buf.into_inner()?)?;
   assert_eq!(
     output,
-    r##"<p>Some text is <code>monospaced</code>.</p>
+    r##"<h1>Document</h1>
+<p>An introduction.</p>
+<div id="toc" class="toc">
+<div id="toctitle">Contents</div>
+<ul class="sectlevel1">
+<li><a href="#_first_section">First Section</a></li>
+<li><a href="#_second_section">Second Section</a></li>
+</ul>
+</div>
+<h2 id="_first_section">First Section</h2>
+<h3 id="_deeper_section">Deeper Section</h3>
+<h2 id="_second_section">Second Section</h2>
 "##
   );
 
@@ -516,12 +2397,16 @@ Some text is `monospaced`.
 }
 
 #[test]
-fn links() -> Result<()> {
+fn toc_with_inline_markup() -> Result<()> {
   let content = r##"
-This paragraph has links. A raw link
-https://kober-systems.github.io/literate_programming_toolsuite/
-and a link to
-https://github.com/kober-systems/literate_programming_toolsuite/tree/master/asciidoctrine[Asciidocrine].
+:toc:
+
+== Kitchen <Sink> & *Co*
+
+[[two]]
+== The `Two` (C)
+
+See <<_kitchen_sink_co>> and <<two>>.
 "##;
   let reader = AsciidocReader::new();
   let opts = options::Opts::parse_from(vec!["asciidoctrine", "--template", "-"]);
@@ -536,10 +2421,16 @@ https://github.com/kober-systems/literate_programming_toolsuite/tree/master/asci
   let output = String::from_utf8(buf.into_inner()?)?;
   assert_eq!(
     output,
-    r##"<p>This paragraph has links. A raw link
-<a href="https://kober-systems.github.io/literate_programming_toolsuite/"/>
-and a link to
-<a href="https://github.com/kober-systems/literate_programming_toolsuite/tree/master/asciidoctrine">Asciidocrine</a>.</p>
+    r##"<div id="toc" class="toc">
+<div id="toctitle">Table of Contents</div>
+<ul class="sectlevel1">
+<li><a href="#_kitchen_sink_co">Kitchen &lt;Sink&gt; &amp; <strong>Co</strong></a></li>
+<li><a href="#two">The <code>Two</code> &#169;</a></li>
+</ul>
+</div>
+<h2 id="_kitchen_sink_co">Kitchen &lt;Sink&gt; &amp; <strong>Co</strong></h2>
+<h2 id="two">The <code>Two</code> &#169;</h2>
+<p>See <a href="#_kitchen_sink_co">Kitchen &lt;Sink&gt; &amp; <strong>Co</strong></a> and <a href="#two">The <code>Two</code> &#169;</a>.</p>
 "##
   );
 
@@ -547,13 +2438,10 @@ and a link to
 }
 
 #[test]
-fn numbered_list() -> Result<()> {
+fn ui_macros() -> Result<()> {
   let content = r##"
-. This
-. is
-. a
-.. nested
-. numbered list
+Press kbd:[Ctrl+S] or btn:[Save] and select
+menu:File[Export > PDF]. Use kbd:[F11] and menu:Help[].
 "##;
   let reader = AsciidocReader::new();
   let opts = options::Opts::parse_from(vec!["asciidoctrine", "--template", "-"]);
@@ -568,25 +2456,8 @@ fn numbered_list() -> Result<()> {
   let output = String::from_utf8(buf.into_inner()?)?;
   assert_eq!(
     output,
-    r##"<ol class="arabic">
-  <li>
-    <p>This</p>
-  </li>
-  <li>
-    <p>is</p>
-  </li>
-  <li>
-    <p>a</p>
-    <ol class="loweralpha" type="a">
-      <li>
-        <p>nested</p>
-      </li>
-    </ol>
-  </li>
-  <li>
-    <p>numbered list</p>
-  </li>
-</ol>
+    r##"<p>Press <span class="keyseq"><kbd>Ctrl</kbd>+<kbd>S</kbd></span> or <b class="button">Save</b> and select
+<span class="menuseq"><b class="menu">File</b>&#160;&#9656;&#32;<b class="submenu">Export</b>&#160;&#9656;&#32;<b class="menuitem">PDF</b></span>. Use <kbd>F11</kbd> and <b class="menuref">Help</b>.</p>
 "##
   );
 
@@ -594,13 +2465,13 @@ fn numbered_list() -> Result<()> {
 }
 
 #[test]
-fn simple_table() -> Result<()> {
+fn verse_block() -> Result<()> {
   let content = r##"
-|===
-| Col1 | Col2
-| Cel1 | Cel2
-| Cel3 | Cel4
-|===
+[verse, Carl Sandburg, Fog]
+____
+The fog comes
+on little cat feet.
+____
 "##;
   let reader = AsciidocReader::new();
   let opts = options::Opts::parse_from(vec!["asciidoctrine", "--template", "-"]);
@@ -615,26 +2486,14 @@ fn simple_table() -> Result<()> {
   let output = String::from_utf8(buf.into_inner()?)?;
   assert_eq!(
     output,
-    r##"<table class="tableblock frame-all grid-all stretch">
-  <colgroup>
-    <col style="width: 50%;">
-    <col style="width: 50%;">
-  </colgroup>
-  <tbody>
-    <tr>
-      <td><p>Col1</p></td>
-      <td><p>Col2</p></td>
-    </tr>
-    <tr>
-      <td><p>Cel1</p></td>
-      <td><p>Cel2</p></td>
-    </tr>
-    <tr>
-      <td><p>Cel3</p></td>
-      <td><p>Cel4</p></td>
-    </tr>
-  </tbody>
-</table>
+    r##"<div class="verseblock">
+  <pre class="content">The fog comes
+on little cat feet.</pre>
+  <div class="attribution">
+    &#8212; Carl Sandburg<br>
+    <cite>Fog</cite>
+  </div>
+</div>
 "##
   );
 
@@ -642,16 +2501,10 @@ fn simple_table() -> Result<()> {
 }
 
 #[test]
-fn sourcecode_blocks() -> Result<()> {
+fn xref_forms() -> Result<()> {
   let content = r##"
-[source, bash]
-----
-echo "hello world!"
-----
-[source, bash]
-....
-echo "hello world!"
-....
+Read <<install,the installation (first!)>> and
+xref:usage.adoc#options[the options] or <<usage.adoc#>>.
 "##;
   let reader = AsciidocReader::new();
   let opts = options::Opts::parse_from(vec!["asciidoctrine", "--template", "-"]);
@@ -666,12 +2519,8 @@ echo "hello world!"
   let output = String::from_utf8(buf.into_inner()?)?;
   assert_eq!(
     output,
-    r##"<div class="listingblock">
-  <pre>echo "hello world!"</pre>
-</div>
-<div class="listingblock">
-  <pre>echo "hello world!"</pre>
-</div>
+    r##"<p>Read <a href="#install">the installation (first!)</a> and
+<a href="usage.html#options">the options</a> or <a href="usage.html">usage.adoc</a>.</p>
 "##
   );
 
@@ -0,0 +1,429 @@
+mod common;
+
+use anyhow::Result;
+use asciidoctrine::{self, *};
+use pretty_assertions::assert_eq;
+
+/// Converts the asciidoc content to LaTeX and checks its structure
+fn to_latex_with_args(content: &str, args: &[&str]) -> Result<String> {
+  let output = common::convert_to_string(&mut LatexWriter::default(), "latex", content, args)?;
+  check_structure(&output);
+  Ok(output)
+}
+
+fn to_latex(content: &str) -> Result<String> {
+  to_latex_with_args(content, &[])
+}
+
+/// Converts the asciidoc content to the LaTeX body without a preamble
+fn to_latex_body(content: &str) -> Result<String> {
+  to_latex_with_args(content, &["--template", "-"])
+}
+
+/// Checks that the groups and the environments are balanced. The
+/// content of the verbatim environments is skipped.
+fn check_structure(tex: &str) {
+  let mut environments: Vec<String> = Vec::new();
+  let mut depth = 0;
+  let mut rest = tex;
+
+  while let Some(c) = rest.chars().next() {
+    if let Some(after) = rest.strip_prefix("\\begin{") {
+      let name = &after[..after.find('}').expect("unclosed environment name")];
+      rest = &after[name.len() + 1..];
+      if ["lstlisting", "minted", "verbatim"].contains(&name) {
+        let end = format!("\\end{{{}}}", name);
+        let index = rest.find(&end).expect("unclosed verbatim environment");
+        rest = &rest[index + end.len()..];
+      } else {
+        environments.push(name.to_string());
+      }
+      continue;
+    }
+    if let Some(after) = rest.strip_prefix("\\end{") {
+      let name = &after[..after.find('}').expect("unclosed environment name")];
+      assert_eq!(environments.pop().as_deref(), Some(name));
+      rest = &after[name.len() + 1..];
+      continue;
+    }
+    match c {
+      // An escaped character
+      '\\' => {
+        let length = rest[1..].chars().next().map_or(0, char::len_utf8);
+        rest = &rest[1 + length..];
+        continue;
+      }
+      // A comment
+      '%' => {
+        rest = &rest[rest.find('\n').unwrap_or(rest.len())..];
+        continue;
+      }
+      '{' => depth += 1,
+      '}' => {
+        assert!(depth > 0, "unbalanced braces");
+        depth -= 1;
+      }
+      _ => (),
+    }
+    rest = &rest[c.len_utf8()..];
+  }
+  assert_eq!(depth, 0, "unbalanced braces");
+  assert!(environments.is_empty(), "unclosed {:?}", environments);
+}
+
+#[test]
+fn document_with_preamble() -> Result<()> {
+  let content = r#"= Literate & Co
+Jane Doe <jane@example.org>; John Roe
+v1.0, 2024-01-02
+:lang: de
+:toc:
+
+A paragraph."#;
+  let output = to_latex(content)?;
+
+  assert!(output.starts_with("\\documentclass[a4paper]{article}\n"));
+  assert!(output.contains("\\usepackage[ngerman]{babel}\n"));
+  assert!(output.contains("\\usepackage{listings}\n"));
+  assert!(output.contains("\\setcounter{secnumdepth}{0}\n"));
+  assert!(output
+    .contains("\\title{Literate \\& Co}\n\\author{Jane Doe \\and John Roe}\n\\date{2024-01-02}\n"));
+  assert!(output.contains("\\begin{document}\n\\maketitle\n\\tableofcontents\n"));
+  assert!(output.ends_with("A paragraph.\n\n\\end{document}\n"));
+
+  Ok(())
+}
+
+#[test]
+fn custom_template() -> Result<()> {
+  let path = std::env::temp_dir().join("asciidoctrine_latex_template.tex");
+  std::fs::write(
+    &path,
+    "\\documentclass{ {{- documentclass -}} }\n\\begin{document}\n{{ body }}\\end{document}\n",
+  )?;
+  let content = r#"= Book
+:doctype: book
+
+A paragraph."#;
+  let output = to_latex_with_args(content, &["--template", path.to_str().unwrap()])?;
+
+  assert_eq!(
+    output,
+    "\\documentclass{book}\n\\begin{document}\nA paragraph.\n\\end{document}\n"
+  );
+
+  Ok(())
+}
+
+#[test]
+fn sections_with_labels() -> Result<()> {
+  let content = r#"= Document Title
+:sectnums:
+
+== First
+
+=== Nested
+
+[[custom]]
+==== Deeper
+
+See <<_first>> and <<custom,the deeper section>>."#;
+  let output = to_latex(content)?;
+  let body = to_latex_body(content)?;
+
+  assert!(output.contains("\\setcounter{secnumdepth}{3}\n"));
+  assert_eq!(
+    body,
+    r#"\section{First}
+\label{_first}
+
+\subsection{Nested}
+\label{_nested}
+
+\subsubsection{Deeper}
+\label{custom}
+
+See \nameref{_first} and \hyperref[custom]{the deeper section}.
+"#
+  );
+
+  Ok(())
+}
+
+#[test]
+fn chapters_of_a_book() -> Result<()> {
+  let content = r#"= Book
+:doctype: book
+
+== Chapter
+
+=== Section
+
+See <<_chapter>>."#;
+  let output = to_latex(content)?;
+
+  assert!(output.starts_with("\\documentclass[a4paper]{book}\n"));
+  assert!(output.contains("\\setcounter{secnumdepth}{-1}\n"));
+  assert!(output.contains("\\chapter{Chapter}\n\\label{_chapter}\n"));
+  assert!(output.contains("\\section{Section}\n"));
+  assert!(output.contains("See \\nameref{_chapter}."));
+
+  Ok(())
+}
+
+#[test]
+fn inline_formatting_and_escaping() -> Result<()> {
+  let content = r#"Some *strong*, _emphasized_ and `monospaced` text with 50% of $5, a_b, {braces}, ~, ^ and \.
+Line one +
+line two"#;
+
+  assert_eq!(
+    to_latex_body(content)?,
+    r#"Some \textbf{strong}, \emph{emphasized} and \texttt{monospaced} text with 50\% of \$5, a\_b, \{braces\}, \textasciitilde{}, \textasciicircum{} and \textbackslash{}.
+Line one\newline
+line two
+"#
+  );
+
+  Ok(())
+}
+
+#[test]
+fn replacements_without_unicode_input() -> Result<()> {
+  let content = "Wait... for it -- then go->there or<-back, A=>B<=C and self--made.";
+
+  assert_eq!(
+    to_latex_body(content)?,
+    "Wait\u{2026}\\hspace{0pt} for it\\,\u{2014}\\,then go\\ensuremath{\\rightarrow}there \
+     or\\ensuremath{\\leftarrow}back, A\\ensuremath{\\Rightarrow}B\\ensuremath{\\Leftarrow}C \
+     and self\u{2014}\\hspace{0pt}made.\n"
+  );
+
+  Ok(())
+}
+
+#[test]
+fn lists() -> Result<()> {
+  let content = r#"* One
+** Nested
+* [x] Done
+* [ ] Open
++
+continued
+
+Then
+
+[start=3]
+. Three
+. Four"#;
+
+  assert_eq!(
+    to_latex_body(content)?,
+    r#"\begin{itemize}
+\item One
+\begin{itemize}
+\item Nested
+\end{itemize}
+\item[$\boxtimes$] Done
+\item[$\square$] Open
+
+continued
+\end{itemize}
+
+Then
+
+\begin{enumerate}
+\setcounter{enumi}{2}
+\item Three
+\item Four
+\end{enumerate}
+"#
+  );
+
+  Ok(())
+}
+
+#[test]
+fn description_list() -> Result<()> {
+  let content = r#"CPU:: The brain
+[RAM]:: The memory"#;
+  let body = to_latex_body(content)?;
+
+  assert!(body.contains("\\begin{description}\n\\item[{CPU}] The brain\n"));
+  assert!(body.contains("\\item[{[RAM]}] The memory\n\\end{description}"));
+
+  Ok(())
+}
+
+#[test]
+fn source_listings() -> Result<()> {
+  let content = r#".Hello
+[[hello]]
+[source, rust]
+----
+fn main() { // <1>
+  println!("{}", 1);
+}
+----
+<1> The entry point
+
+[source, unknown]
+----
+text
+----"#;
+
+  assert_eq!(
+    to_latex_body(content)?,
+    r#"\begin{lstlisting}[language=Rust,title={Hello},label={hello}]
+fn main() { // <1>
+  println!("{}", 1);
+}
+\end{lstlisting}
+
+\begin{enumerate}
+\item The entry point
+\end{enumerate}
+
+\begin{lstlisting}
+text
+\end{lstlisting}
+"#
+  );
+
+  Ok(())
+}
+
+#[test]
+fn minted_listings() -> Result<()> {
+  let content = r#":source-highlighter: minted
+
+[source, rust]
+----
+fn main() {}
+----
+
+----
+plain
+----"#;
+  let output = to_latex(content)?;
+
+  assert!(output.contains("\\usepackage{minted}\n"));
+  assert!(!output.contains("\\usepackage{listings}"));
+  assert!(output.contains("\\begin{minted}{rust}\nfn main() {}\n\\end{minted}\n"));
+  assert!(output.contains("\\begin{minted}{text}\nplain\n\\end{minted}\n"));
+
+  Ok(())
+}
+
+#[test]
+fn table_with_column_formats() -> Result<()> {
+  let content = r#".Numbers
+[cols="1,^2a", options="header"]
+|===
+|One |Two
+|a & b |* item
+|==="#;
+
+  assert_eq!(
+    to_latex_body(content)?,
+    r#"\begin{table}[htbp]
+\centering
+\caption{Numbers}
+\begin{tabular}{|>{\raggedright\arraybackslash}p{\dimexpr 0.333\linewidth-2\tabcolsep\relax}|>{\centering\arraybackslash}p{\dimexpr 0.667\linewidth-2\tabcolsep\relax}|}
+\hline
+\textbf{One} & \textbf{Two} \\
+\hline
+\hline
+a \& b & \begin{itemize}
+\item item
+\end{itemize} \\
+\hline
+\end{tabular}
+\end{table}
+"#
+  );
+
+  Ok(())
+}
+
+#[test]
+fn table_with_aligned_columns() -> Result<()> {
+  let content = r#"[cols="<,^,>"]
+|===
+|1 |2 |3
+|4 |5
+|==="#;
+  let body = to_latex_body(content)?;
+
+  assert!(body.starts_with("\\begin{center}\n\\begin{tabular}{|l|c|r|}\n\\hline\n1 & 2 & 3 \\\\\n"));
+
+  Ok(())
+}
+
+#[test]
+fn links_footnotes_and_images() -> Result<()> {
+  let content = r#"See https://example.org[the site], https://example.org, mailto:jane@example.org[], <<other.adoc#part,elsewhere>> and a note.footnote:[The footnote.] A shared note.footnoteref:[shared,Shared note.] Again.footnoteref:[shared]
+
+image::diagram.png[A diagram, 300]"#;
+  let body = to_latex_body(content)?;
+
+  assert!(body.contains("\\href{https://example.org}{the site}"));
+  assert!(body.contains("\\url{https://example.org}"));
+  assert!(body.contains("\\href{mailto:jane@example.org}{jane@example.org}"));
+  assert!(body.contains("\\href{other.pdf\\#part}{elsewhere}"));
+  assert!(body.contains("note.\\footnote{The footnote.}"));
+  assert!(body.contains(
+    "\\footnote{Shared note.\\label{fn:shared}} Again.\\textsuperscript{\\ref{fn:shared}}"
+  ));
+  assert!(body.contains(
+    "\\begin{center}\n\\includegraphics[max width=\\linewidth,width=225pt]{diagram.png}\n\\end{center}"
+  ));
+
+  Ok(())
+}
+
+#[test]
+fn blocks_without_equivalent() -> Result<()> {
+  let content = r#"NOTE: Take note.
+
+.Side
+****
+Sidebar text.
+****
+
+[quote, Someone, A Book]
+____
+Quoted text.
+____
+
+[verse]
+____
+First line
+[second] line
+____"#;
+
+  assert_eq!(
+    to_latex_body(content)?,
+    r#"\begin{adocadmonition}[title={Note}]
+Take note.
+\end{adocadmonition}
+
+\begin{adocsidebar}[title={Side}]
+Sidebar text.
+\end{adocsidebar}
+
+\begin{quote}
+Quoted text.
+
+\hfill--- Someone, \emph{A Book}
+\end{quote}
+
+\begin{verse}
+First line\\
+{}[second] line
+\end{verse}
+"#
+  );
+
+  Ok(())
+}
@@ -0,0 +1,319 @@
+mod common;
+
+use anyhow::Result;
+use asciidoctrine::{self, *};
+use pretty_assertions::assert_eq;
+
+/// Converts the asciidoc content to a manpage and checks its structure
+fn to_manpage(content: &str) -> Result<String> {
+  let output = common::convert_to_string(&mut ManpageWriter::default(), "manpage", content, &[])?;
+  check_structure(&output);
+  Ok(output)
+}
+
+/// Converts the content of a section after the NAME section and
+/// returns it without the header
+fn to_manpage_body(content: &str) -> Result<String> {
+  let header = "= tool(1)\n\n== NAME\n\ntool - does things\n\n== DESCRIPTION\n\n";
+  let output = to_manpage(&format!("{}{}", header, content))?;
+  let start = output.find(".SH \"DESCRIPTION\"\n").unwrap() + ".SH \"DESCRIPTION\"\n".len();
+  Ok(output[start..].to_string())
+}
+
+/// Checks that indentations, fill modes and tables are balanced and
+/// that no text line could be read as a request
+fn check_structure(roff: &str) {
+  let mut indents = 0;
+  let mut table = false;
+  // The first line names the preprocessors
+  for line in roff.lines().skip(1) {
+    match line.trim_start_matches(".if n ") {
+      ".RS 4" => indents += 1,
+      ".RE" => {
+        assert!(indents > 0, "unbalanced .RE");
+        indents -= 1;
+      }
+      ".TS" => table = true,
+      ".TE" => table = false,
+      _ => (),
+    }
+    assert!(!line.is_empty(), "blank lines are printed by roff");
+    assert!(
+      table || !line.starts_with('\''),
+      "text line read as a control line: {}",
+      line
+    );
+  }
+  assert_eq!(indents, 0, "unclosed .RS");
+  assert!(!table, "unclosed table");
+}
+
+#[test]
+fn title_and_header() -> Result<()> {
+  let content = r#"= lisi(1)
+Jane Doe <jane@example.org>
+v1.0, 2024-01-02
+:doctype: manpage
+:manmanual: Lisi Manual
+:mansource: Lisi 0.3.0
+
+== NAME
+
+lisi - tangle literate programs
+
+== SYNOPSIS
+
+*lisi* [_OPTIONS_] _FILE_"#;
+
+  assert_eq!(
+    to_manpage(content)?,
+    r#"'\" t
+.\"     Title: lisi
+.\" Generator: asciidoctrine
+.\"      Date: 2024-01-02
+.\"    Manual: Lisi Manual
+.\"    Source: Lisi 0.3.0
+.\"
+.TH "LISI" "1" "2024-01-02" "Lisi 0.3.0" "Lisi Manual"
+.nh
+.ad l
+.SH "NAME"
+lisi \- tangle literate programs
+.SH "SYNOPSIS"
+.sp
+\fBlisi\fP [\fIOPTIONS\fP] \fIFILE\fP
+.SH "AUTHOR"
+.sp
+Jane Doe <jane@example.org>
+"#
+  );
+
+  Ok(())
+}
+
+#[test]
+fn name_from_attributes() -> Result<()> {
+  let content = r#"= Asciidoctrine
+:manname: asciidoctrine
+:manvolnum: 7
+:manpurpose: convert asciidoc documents
+:revdate: 2024-05-01
+
+== Description
+
+Text."#;
+  let output = to_manpage(content)?;
+
+  assert!(output.contains(".TH \"ASCIIDOCTRINE\" \"7\" \"2024-05-01\" "));
+  assert!(output
+    .contains(".SH \"NAME\"\nasciidoctrine \\- convert asciidoc documents\n.SH \"DESCRIPTION\"\n"));
+
+  Ok(())
+}
+
+#[test]
+fn escaping_special_characters() -> Result<()> {
+  let content = r#"A \backslash, --option and "quotes" -- with (C) and a
+.dot at the start of a line, a . in it and an
+'apostrophe.
+Line one +
+line two"#;
+
+  assert_eq!(
+    to_manpage_body(content)?,
+    r#".sp
+A \ebackslash, \-\-option and "quotes" \(em with \(co and a
+\&.dot at the start of a line, a . in it and an
+\&'apostrophe.
+Line one
+.br
+line two
+"#
+  );
+
+  Ok(())
+}
+
+#[test]
+fn sections_and_references() -> Result<()> {
+  let content = r#"=== Options
+
+==== Output
+
+See <<_output>> and <<_options,the options>>."#;
+
+  assert_eq!(
+    to_manpage_body(content)?,
+    r#".SS "Options"
+.SS "Output"
+.sp
+See Output and the options.
+"#
+  );
+
+  Ok(())
+}
+
+#[test]
+fn lists() -> Result<()> {
+  let content = r#"* One
+** Nested
+* [x] Done
++
+continued
+
+Then
+
+[start=9]
+. Nine
+. Ten"#;
+
+  assert_eq!(
+    to_manpage_body(content)?,
+    r#".sp
+.RS 4
+\h'-04'\(bu\h'+03'\c
+One
+.sp
+.RS 4
+\h'-04'\(bu\h'+03'\c
+Nested
+.RE
+.RE
+.sp
+.RS 4
+\h'-04'[x]\h'+01'\c
+Done
+.sp
+continued
+.RE
+.sp
+Then
+.sp
+.RS 4
+\h'-04' 9.\h'+01'\c
+Nine
+.RE
+.sp
+.RS 4
+\h'-04'10.\h'+01'\c
+Ten
+.RE
+"#
+  );
+
+  Ok(())
+}
+
+#[test]
+fn description_list() -> Result<()> {
+  let content = r#"-o, --output FILE:: Write to FILE.
+--dry-run:: Only print the files."#;
+
+  assert_eq!(
+    to_manpage_body(content)?,
+    r#".sp
+\fB\-o, \-\-output FILE\fP
+.RS 4
+Write to FILE.
+.RE
+.sp
+\fB\-\-dry\-run\fP
+.RS 4
+Only print the files.
+.RE
+"#
+  );
+
+  Ok(())
+}
+
+#[test]
+fn listings_and_admonitions() -> Result<()> {
+  let content = r#".Example
+[source, sh]
+----
+$ lisi --dry-run \
+.hidden
+----
+
+NOTE: Take note."#;
+
+  assert_eq!(
+    to_manpage_body(content)?,
+    r#".sp
+\fBExample\fP
+.sp
+.if n .RS 4
+.nf
+.fam C
+$ lisi \-\-dry\-run \e
+\&.hidden
+.fam
+.fi
+.if n .RE
+.sp
+.RS 4
+\fBNote\fP
+.br
+Take note.
+.RE
+"#
+  );
+
+  Ok(())
+}
+
+#[test]
+fn tables() -> Result<()> {
+  let content = r#"[cols="1,>1", options="header"]
+|===
+|Name |Value
+|a |b
+|c
+|==="#;
+
+  assert_eq!(
+    to_manpage_body(content)?,
+    r#".sp
+.TS
+allbox tab(:);
+ltB rtB
+lt rt.
+T{
+Name
+T}:T{
+Value
+T}
+T{
+a
+T}:T{
+b
+T}
+T{
+c
+T}:
+.TE
+.sp 1
+"#
+  );
+
+  Ok(())
+}
+
+#[test]
+fn footnotes_become_notes() -> Result<()> {
+  let content = r#"Text.footnote:[First note.] Shared.footnoteref:[shared,Shared note.] Again.footnoteref:[shared]
+
+== Bugs
+
+None."#;
+  let output = to_manpage_body(content)?;
+
+  assert!(output.contains("Text.[1] Shared.[2] Again.[2]\n"));
+  assert!(output.ends_with(
+    ".SH \"BUGS\"\n.sp\nNone.\n.SH \"NOTES\"\n.IP \" 1.\" 4\nFirst note.\n.IP \" 2.\" 4\nShared note.\n"
+  ));
+
+  Ok(())
+}
@@ -0,0 +1,330 @@
+mod common;
+
+use anyhow::Result;
+use asciidoctrine::{self, *};
+use pretty_assertions::assert_eq;
+
+fn to_markdown(content: &str) -> Result<String> {
+  common::convert_to_string(&mut MarkdownWriter::default(), "markdown", content, &[])
+}
+
+#[test]
+fn titles_and_paragraphs() -> Result<()> {
+  let content = r#"= Document Title
+Jane Doe <jane@example.org>
+
+Preamble
+
+== Section
+
+Text
+
+=== Nested
+
+More text"#;
+
+  assert_eq!(
+    to_markdown(content)?,
+    r#"# Document Title
+
+Preamble
+
+## Section
+
+Text
+
+### Nested
+
+More text
+"#
+  );
+
+  Ok(())
+}
+
+#[test]
+fn headings_are_escaped() -> Result<()> {
+  let content = r#"= Kitchen <Sink> & Co
+
+== The *bold* [section]
+
+Text"#;
+
+  assert_eq!(
+    to_markdown(content)?,
+    r#"# Kitchen \<Sink\> & Co
+
+## The **bold** \[section\]
+
+Text
+"#
+  );
+
+  Ok(())
+}
+
+#[test]
+fn inline_formatting() -> Result<()> {
+  let content = "Some *strong*, _emphasized_ and `monospaced` text with <markup>, a_b and &#169;.";
+
+  assert_eq!(
+    to_markdown(content)?,
+    "Some **strong**, *emphasized* and `monospaced` text with \\<markup\\>, a\\_b and &#169;.\n"
+  );
+
+  Ok(())
+}
+
+#[test]
+fn line_breaks_and_block_markers() -> Result<()> {
+  let content = r#"Line one +
+line two
+# not a heading
+1. not a list"#;
+
+  assert_eq!(
+    to_markdown(content)?,
+    "Line one\\\nline two\n\\# not a heading\n1\\. not a list\n"
+  );
+
+  Ok(())
+}
+
+#[test]
+fn nested_lists() -> Result<()> {
+  let content = r#"* One
+** Nested
+*** Deeper
+* Two
++
+continued
+
+Then
+
+. First
+.. Sub
+. Second"#;
+
+  assert_eq!(
+    to_markdown(content)?,
+    r#"- One
+  - Nested
+    - Deeper
+- Two
+
+  continued
+
+Then
+
+1. First
+   1. Sub
+2. Second
+"#
+  );
+
+  Ok(())
+}
+
+#[test]
+fn task_lists_and_separated_lists() -> Result<()> {
+  let content = r#"* [x] Done
+* [ ] Open
+
+Then
+
+. A
+
+[start=3]
+. B"#;
+
+  assert_eq!(
+    to_markdown(content)?,
+    r#"- [x] Done
+- [ ] Open
+
+Then
+
+1. A
+
+<!-- -->
+
+3. B
+"#
+  );
+
+  Ok(())
+}
+
+#[test]
+fn description_list_as_bullet_list() -> Result<()> {
+  let content = r#"CPU:: The brain
+RAM:: The memory"#;
+
+  assert_eq!(
+    to_markdown(content)?,
+    "- **CPU**: The brain\n- **RAM**: The memory\n"
+  );
+
+  Ok(())
+}
+
+#[test]
+fn source_listing_with_callouts() -> Result<()> {
+  let content = r#".Hello
+[source, rust]
+----
+fn main() { // <1>
+  println!("```");
+}
+----
+<1> The entry point"#;
+
+  assert_eq!(
+    to_markdown(content)?,
+    r#"**Hello**
+
+````rust
+fn main() { // <1>
+  println!("```");
+}
+````
+
+1. The entry point
+"#
+  );
+
+  Ok(())
+}
+
+#[test]
+fn table() -> Result<()> {
+  let content = r#".Numbers
+|===
+|One |Two
+|`a` |b
+|c
+|==="#;
+
+  assert_eq!(
+    to_markdown(content)?,
+    r#"**Numbers**
+
+| One | Two |
+| --- | --- |
+| `a` | b |
+| c |  |
+"#
+  );
+
+  Ok(())
+}
+
+#[test]
+fn links_xrefs_and_images() -> Result<()> {
+  let content = r#"[[intro]]
+== Getting Started
+
+See https://example.org[the site], https://example.org, <<intro>>, <<intro,the intro>>, <<other.adoc#part,elsewhere>> and image:icon.png[Icon].
+
+image::diagram.png[A diagram]"#;
+
+  assert_eq!(
+    to_markdown(content)?,
+    r#"## Getting Started
+
+See [the site](https://example.org), <https://example.org>, [Getting Started](#getting-started), [the intro](#getting-started), [elsewhere](other.md#part) and ![Icon](icon.png).
+
+![A diagram](diagram.png)
+"#
+  );
+
+  Ok(())
+}
+
+#[test]
+fn footnotes() -> Result<()> {
+  let content = r#"A note.footnote:[The  footnote.] Another.footnoteref:[shared,Shared note.] Again.footnoteref:[shared]"#;
+
+  assert_eq!(
+    to_markdown(content)?,
+    r#"A note.[^1] Another.[^shared] Again.[^shared]
+
+[^1]: The footnote.
+[^shared]: Shared note.
+"#
+  );
+
+  Ok(())
+}
+
+#[test]
+fn admonitions_and_sidebars_as_quotes() -> Result<()> {
+  let content = r#"NOTE: Take note.
+
+[WARNING]
+.Be careful
+====
+This block is a warning.
+====
+
+.Aside
+****
+Sidebar text.
+****"#;
+
+  assert_eq!(
+    to_markdown(content)?,
+    r#"> **Note:** Take note.
+
+> **Warning: Be careful**
+>
+> This block is a warning.
+
+> **Aside**
+>
+> Sidebar text.
+"#
+  );
+
+  Ok(())
+}
+
+#[test]
+fn quote_and_verse() -> Result<()> {
+  let content = r#"[quote, Someone, A Book]
+____
+Quoted text.
+____
+
+[verse]
+____
+First line
+- second line
+____"#;
+
+  assert_eq!(
+    to_markdown(content)?,
+    r#"> Quoted text.
+>
+> &#8212; Someone, *A Book*
+
+> First line\
+> \- second line
+"#
+  );
+
+  Ok(())
+}
+
+#[test]
+fn ui_macros_and_index_terms() -> Result<()> {
+  let content =
+    r#"Press kbd:[Ctrl+C] or btn:[OK] in menu:File[Save As] for ((terms))(((hidden)))."#;
+
+  assert_eq!(
+    to_markdown(content)?,
+    "Press <kbd>Ctrl</kbd>+<kbd>C</kbd> or **[OK]** in **File \u{203a} Save As** for terms.\n"
+  );
+
+  Ok(())
+}
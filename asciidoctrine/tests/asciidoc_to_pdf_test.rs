@@ -0,0 +1,378 @@
+mod common;
+
+use anyhow::Result;
+use asciidoctrine::{self, *};
+use pretty_assertions::assert_eq;
+use std::collections::HashMap;
+use std::io::Read;
+use std::path::PathBuf;
+
+fn to_pdf(content: &str) -> Result<Vec<u8>> {
+  to_pdf_with_args(content, &[])
+}
+
+fn to_pdf_with_args(content: &str, args: &[&str]) -> Result<Vec<u8>> {
+  common::convert(&mut PdfWriter::default(), "pdf", content, args)
+}
+
+/// Reads all objects of the PDF through the cross reference table
+fn objects(pdf: &[u8]) -> Result<HashMap<usize, String>> {
+  let text = String::from_utf8_lossy(pdf);
+  assert!(text.starts_with("%PDF-1.4\n"));
+  assert!(text.ends_with("%%EOF\n"));
+
+  let startxref: usize = text
+    .rsplit("startxref\n")
+    .next()
+    .and_then(|rest| rest.lines().next())
+    .and_then(|offset| offset.parse().ok())
+    .expect("startxref");
+  let xref = &pdf[startxref..];
+  assert!(xref.starts_with(b"xref\n0 "));
+
+  let mut objects = HashMap::new();
+  let table = String::from_utf8_lossy(xref);
+  let mut lines = table.lines().skip(1);
+  let count: usize = lines
+    .next()
+    .and_then(|line| line.split(' ').nth(1))
+    .and_then(|count| count.parse().ok())
+    .expect("xref size");
+  for (id, line) in lines.skip(1).take(count - 1).enumerate() {
+    let id = id + 1;
+    let offset: usize = line[..10].parse()?;
+    let object = String::from_utf8_lossy(&pdf[offset..]);
+    let header = format!("{} 0 obj\n", id);
+    assert!(object.starts_with(&header), "object {} not at offset", id);
+    let end = object.find("\nendobj").expect("endobj");
+    objects.insert(id, object[header.len()..end].to_string());
+  }
+  assert!(table.contains(&format!("trailer\n<< /Size {} /Root 1 0 R", count)));
+
+  Ok(objects)
+}
+
+/// Decompresses the content streams of the pages
+fn page_contents(pdf: &[u8]) -> Result<Vec<String>> {
+  let objects = objects(pdf)?;
+  let mut pages: Vec<usize> = objects
+    .iter()
+    .filter(|(_, object)| object.starts_with("<< /Type /Page /"))
+    .map(|(id, _)| *id)
+    .collect();
+  pages.sort();
+
+  let mut contents = Vec::new();
+  for page in pages {
+    let content: usize = objects[&page]
+      .split("/Contents ")
+      .nth(1)
+      .and_then(|rest| rest.split(' ').next())
+      .and_then(|id| id.parse().ok())
+      .expect("content stream");
+    let stream = &objects[&content];
+    let start = stream.find("stream\n").expect("stream") + "stream\n".len();
+    let data = stream_data(pdf, content, start)?;
+    let mut decoder = flate2::read::ZlibDecoder::new(&data[..]);
+    let mut content = String::new();
+    decoder.read_to_string(&mut content)?;
+    contents.push(content);
+  }
+
+  Ok(contents)
+}
+
+/// The raw bytes of a stream, which might not be valid UTF-8
+fn stream_data(pdf: &[u8], id: usize, start: usize) -> Result<Vec<u8>> {
+  let header = format!("\n{} 0 obj\n", id);
+  let position = pdf
+    .windows(header.len())
+    .position(|window| window == header.as_bytes())
+    .expect("object")
+    + header.len();
+  let object = &pdf[position..];
+  let length: usize = String::from_utf8_lossy(&object[..start])
+    .split("/Length ")
+    .nth(1)
+    .and_then(|rest| rest.split(' ').next())
+    .and_then(|length| length.parse().ok())
+    .expect("length");
+  // The offsets in the lossy string are the same up to the stream
+  Ok(object[start..start + length].to_vec())
+}
+
+/// The text shown with a font in the content streams
+fn shown_text(contents: &[String], font: &str) -> Vec<String> {
+  let marker = format!("BT /{} ", font);
+  contents
+    .iter()
+    .flat_map(|content| content.lines())
+    .filter(|line| line.starts_with(&marker))
+    .filter_map(|line| {
+      let hex = line.split('<').nth(1)?.split('>').next()?;
+      let bytes: Vec<u8> = (0..hex.len())
+        .step_by(2)
+        .map(|index| u8::from_str_radix(&hex[index..index + 2], 16).unwrap())
+        .collect();
+      Some(bytes.iter().map(|byte| *byte as char).collect())
+    })
+    .collect()
+}
+
+fn all_text(contents: &[String]) -> String {
+  (1..=6)
+    .flat_map(|font| shown_text(contents, &format!("F{}", font)))
+    .collect::<Vec<_>>()
+    .join("\n")
+}
+
+#[test]
+fn document_structure() -> Result<()> {
+  let content = r#"= Document Title
+Jane Doe <jane@example.org>
+
+A paragraph."#;
+  let pdf = to_pdf(content)?;
+  let objects = objects(&pdf)?;
+
+  assert!(objects[&1].starts_with("<< /Type /Catalog /Pages 2 0 R"));
+  assert_eq!(objects[&2], "<< /Type /Pages /Kids [10 0 R] /Count 1 >>");
+  // The title and the author are UTF-16 encoded
+  assert!(
+    objects[&3].contains("/Title <FEFF0044006F00630075006D0065006E00740020005400690074006C0065>")
+  );
+  assert!(objects[&3].contains("/Author <FEFF004A0061006E006500200044006F0065>"));
+  assert_eq!(
+    objects[&4],
+    "<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica /Encoding /WinAnsiEncoding >>"
+  );
+  assert!(objects[&9].contains("/BaseFont /Courier-Bold"));
+
+  let contents = page_contents(&pdf)?;
+  assert_eq!(shown_text(&contents, "F2"), vec!["Document Title"]);
+  assert!(shown_text(&contents, "F3").contains(&"Jane Doe <jane@example.org>".to_string()));
+  assert_eq!(shown_text(&contents, "F1"), vec!["A paragraph."]);
+
+  Ok(())
+}
+
+#[test]
+fn headings_as_bookmarks() -> Result<()> {
+  let content = r#"= Document Title
+
+== First
+
+Text
+
+=== Nested
+
+More text
+
+== Second"#;
+  let pdf = to_pdf(content)?;
+  let objects = objects(&pdf)?;
+
+  assert!(objects[&1].contains("/Outlines 10 0 R /PageMode /UseOutlines"));
+  let root = &objects[&10];
+  assert!(root.starts_with("<< /Type /Outlines"));
+  assert!(root.contains("/Count 3"));
+
+  let mut entries: Vec<(&usize, &String)> = objects
+    .iter()
+    .filter(|(_, object)| object.starts_with("<< /Title "))
+    .collect();
+  entries.sort();
+  assert_eq!(entries.len(), 3);
+  let (first, first_entry) = entries[0];
+  let (nested, nested_entry) = entries[1];
+  let (second, second_entry) = entries[2];
+  assert!(first_entry.contains("/Parent 10 0 R"));
+  assert!(first_entry.contains(&format!("/Next {} 0 R", second)));
+  assert!(first_entry.contains(&format!(
+    "/First {} 0 R /Last {} 0 R /Count 1",
+    nested, nested
+  )));
+  assert!(nested_entry.contains(&format!("/Parent {} 0 R", first)));
+  assert!(second_entry.contains(&format!("/Prev {} 0 R", first)));
+  assert!(second_entry.contains("/Dest [11 0 R /XYZ 0 "));
+
+  Ok(())
+}
+
+#[test]
+fn inline_styles() -> Result<()> {
+  let content = "Some *strong*, _emphasized_ and `monospaced` text... with Größe & (C).";
+  let pdf = to_pdf(content)?;
+  let contents = page_contents(&pdf)?;
+
+  assert_eq!(shown_text(&contents, "F2"), vec!["strong"]);
+  assert_eq!(shown_text(&contents, "F3"), vec!["emphasized"]);
+  assert_eq!(shown_text(&contents, "F5"), vec!["monospaced"]);
+  // The text is WinAnsi encoded and the replacements are resolved
+  assert!(
+    shown_text(&contents, "F1").contains(&" text\u{85} with Gr\u{f6}\u{df}e & \u{a9}.".to_string())
+  );
+
+  Ok(())
+}
+
+#[test]
+fn listing_with_callouts() -> Result<()> {
+  let content = r#"[source, rust]
+----
+fn main() { // <1>
+  println!("hello");
+}
+----
+<1> The entry point"#;
+  let pdf = to_pdf(content)?;
+  let contents = page_contents(&pdf)?;
+
+  assert_eq!(
+    shown_text(&contents, "F5"),
+    vec!["fn main() {", "  println!(\"hello\");", "}"]
+  );
+  assert_eq!(shown_text(&contents, "F6"), vec![" (1)"]);
+  assert!(shown_text(&contents, "F1").contains(&"(1)".to_string()));
+  // The listing has a gray background
+  assert!(contents[0].contains("0.95 g "));
+
+  Ok(())
+}
+
+#[test]
+fn lists() -> Result<()> {
+  let content = r#"* One
+* Two
+
+//
+
+. First
+. Second
+
+//
+
+CPU:: The brain"#;
+  let pdf = to_pdf(content)?;
+  let contents = page_contents(&pdf)?;
+  let text = shown_text(&contents, "F1");
+
+  assert_eq!(text.iter().filter(|text| *text == "\u{95}").count(), 2);
+  assert!(text.contains(&"1.".to_string()));
+  assert!(text.contains(&"2.".to_string()));
+  assert!(text.contains(&"The brain".to_string()));
+  assert_eq!(shown_text(&contents, "F2"), vec!["CPU"]);
+
+  Ok(())
+}
+
+#[test]
+fn table() -> Result<()> {
+  let content = r#"|===
+|One |Two
+|Three |Four
+|==="#;
+  let pdf = to_pdf(content)?;
+  let contents = page_contents(&pdf)?;
+
+  assert_eq!(contents[0].matches(" re S").count(), 4);
+  assert_eq!(
+    shown_text(&contents, "F1"),
+    vec!["One", "Two", "Three", "Four"]
+  );
+
+  Ok(())
+}
+
+#[test]
+fn links_xrefs_and_footnotes() -> Result<()> {
+  let content = r#"See https://example.org[the site], <<target>> and a note.footnote:[The footnote.]
+
+[[target]]
+== Target"#;
+  let pdf = to_pdf(content)?;
+  let objects = objects(&pdf)?;
+  let contents = page_contents(&pdf)?;
+
+  let links: Vec<&String> = objects
+    .values()
+    .filter(|object| object.contains("/Subtype /Link"))
+    .collect();
+  assert_eq!(links.len(), 4);
+  assert!(links
+    .iter()
+    .any(|link| link.contains("/A << /S /URI /URI (https://example.org) >>")));
+  assert_eq!(
+    links.iter().filter(|link| link.contains("/Dest [")).count(),
+    3
+  );
+  // The cross reference uses the title of the section
+  assert!(all_text(&contents).contains("Target"));
+  assert!(shown_text(&contents, "F1").contains(&"[1]".to_string()));
+  assert!(shown_text(&contents, "F1").contains(&"The footnote.".to_string()));
+
+  Ok(())
+}
+
+#[test]
+fn embedded_image() -> Result<()> {
+  let dir = std::env::temp_dir().join(format!("asciidoctrine-pdf-{}", std::process::id()));
+  std::fs::create_dir_all(&dir)?;
+  let image = std::fs::File::create(dir.join("pixel.png"))?;
+  let mut encoder = png::Encoder::new(image, 2, 1);
+  encoder.set_color(png::ColorType::Rgba);
+  encoder.set_depth(png::BitDepth::Eight);
+  encoder
+    .write_header()?
+    .write_image_data(&[255, 0, 0, 255, 0, 0, 255, 128])?;
+
+  let input: PathBuf = dir.join("document.adoc");
+  let content = "image::pixel.png[A pixel, 100]";
+  let pdf = to_pdf_with_args(content, &[input.to_str().unwrap()])?;
+  std::fs::remove_dir_all(&dir)?;
+  let objects = objects(&pdf)?;
+  let contents = page_contents(&pdf)?;
+
+  let image = objects
+    .values()
+    .find(|object| object.contains("/Subtype /Image /Width 2 /Height 1 /ColorSpace /DeviceRGB"))
+    .expect("image");
+  assert!(image.contains("/SMask"));
+  assert!(objects
+    .values()
+    .any(|object| object.contains("/ColorSpace /DeviceGray")));
+  // The width of 100px is scaled to points
+  assert!(contents[0].contains("q 75 0 0 37.5 56.69 "));
+  assert!(contents[0].contains(" cm /Im1 Do Q"));
+
+  Ok(())
+}
+
+#[test]
+fn missing_image_shows_alt_text() -> Result<()> {
+  let pdf = to_pdf("image::missing.png[Missing image]")?;
+  let contents = page_contents(&pdf)?;
+
+  assert_eq!(shown_text(&contents, "F3"), vec!["[Missing image]"]);
+
+  Ok(())
+}
+
+#[test]
+fn long_documents_break_pages() -> Result<()> {
+  let content = (1..=200)
+    .map(|number| format!("Paragraph number {}.", number))
+    .collect::<Vec<_>>()
+    .join("\n\n");
+  let pdf = to_pdf(&content)?;
+  let contents = page_contents(&pdf)?;
+
+  assert!(contents.len() > 2);
+  let text = shown_text(&contents, "F1");
+  assert_eq!(text.len(), 200);
+  assert_eq!(text[199], "Paragraph number 200.");
+  // The pages are numbered
+  assert!(contents[1].contains("(2) Tj"));
+
+  Ok(())
+}
@@ -0,0 +1,209 @@
+mod common;
+
+use anyhow::Result;
+use asciidoctrine::{self, *};
+use pretty_assertions::assert_eq;
+
+fn to_slides_with_args(content: &str, args: &[&str]) -> Result<String> {
+  common::convert_to_string(&mut RevealjsWriter::default(), "revealjs", content, args)
+}
+
+/// Converts the asciidoc content to the slides without the page
+/// around them
+fn to_slides(content: &str) -> Result<String> {
+  to_slides_with_args(content, &["--template", "-"])
+}
+
+#[test]
+fn title_slide_and_slides() -> Result<()> {
+  let content = r#"= Architecture
+Jane Doe
+
+The preamble.
+
+== Reader
+
+Parses.
+
+== Writer
+
+Writes."#;
+
+  assert_eq!(
+    to_slides(content)?,
+    r#"<section class="title" data-state="title">
+<h1>Architecture</h1>
+<div class="details">
+<span id="author" class="author">Jane Doe</span><br>
+</div>
+<p>The preamble.</p>
+</section>
+<section id="_reader">
+<h2>Reader</h2>
+<p>Parses.</p>
+</section>
+<section id="_writer">
+<h2>Writer</h2>
+<p>Writes.</p>
+</section>
+"#
+  );
+
+  Ok(())
+}
+
+#[test]
+fn vertical_slides() -> Result<()> {
+  let content = r#"== Details
+
+Intro.
+
+=== Reader
+
+==== Grammar
+
+Pest.
+
+=== Writer
+
+== Summary"#;
+  let expected = r#"<section>
+<section id="_details">
+<h2>Details</h2>
+<p>Intro.</p>
+</section>
+<section id="_reader">
+<h3>Reader</h3>
+<h4 id="_grammar">Grammar</h4>
+<p>Pest.</p>
+</section>
+<section id="_writer">
+<h3>Writer</h3>
+</section>
+</section>
+<section id="_summary">
+<h2>Summary</h2>
+</section>
+"#;
+
+  assert_eq!(to_slides(content)?, expected);
+  // The nested sections of the extension give the same slides
+  let sections = to_slides_with_args(content, &["--template", "-", "-e", "sections"])?;
+  assert!(sections.starts_with(
+    "<section>\n<section id=\"_details\">\n<h2>Details</h2>\n<p>Intro.</p>\n</section>\n<section id=\"_reader\">\n<h3>Reader</h3>\n"
+  ));
+  assert!(sections.ends_with("<section id=\"_summary\">\n<h2>Summary</h2>\n</section>\n"));
+
+  Ok(())
+}
+
+#[test]
+fn speaker_notes() -> Result<()> {
+  let content = r#"== Slide
+
+Shown.
+
+[.notes]
+--
+Mention the AST.
+
+* And the writers
+--
+
+[role=notes]
+A single paragraph."#;
+
+  assert_eq!(
+    to_slides(content)?,
+    r#"<section id="_slide">
+<h2>Slide</h2>
+<p>Shown.</p>
+<aside class="notes">
+<p>Mention the AST.</p>
+<ul>
+  <li>
+    <p>And the writers</p>
+  </li>
+</ul>
+</aside>
+<aside class="notes">
+<p>A single paragraph.</p>
+</aside>
+</section>
+"#
+  );
+
+  Ok(())
+}
+
+#[test]
+fn step_lists_become_fragments() -> Result<()> {
+  let content = r#"== Slide
+
+[%step]
+* Reader
+* Writer
+
+Then
+
+. Not stepped"#;
+  let slides = to_slides(content)?;
+
+  assert!(slides.contains(
+    "<li class=\"fragment\">\n    <p>Reader</p>\n  </li>\n  <li class=\"fragment\">\n    <p>Writer</p>"
+  ));
+  assert!(slides.contains("<li>\n    <p>Not stepped</p>"));
+
+  Ok(())
+}
+
+#[test]
+fn page_loads_revealjs() -> Result<()> {
+  let content = r#"= Architecture
+:description: The review
+
+== Slide"#;
+  let page = to_slides_with_args(content, &[])?;
+
+  assert!(page.starts_with("<!DOCTYPE html>\n<html lang=\"en\">\n"));
+  assert!(page.contains("<meta name=\"description\" content=\"The review\">"));
+  assert!(page.contains("<title>Architecture</title>"));
+  assert!(page.contains("<div class=\"reveal\">\n<div class=\"slides\">\n<section class=\"title\""));
+  assert!(page.contains(
+    "<link rel=\"stylesheet\" href=\"https://cdn.jsdelivr.net/npm/reveal.js@5.1.0/dist/theme/white.css\">"
+  ));
+  assert!(page.contains(
+    "<script src=\"https://cdn.jsdelivr.net/npm/reveal.js@5.1.0/dist/reveal.js\"></script>"
+  ));
+  assert!(page.contains("Reveal.initialize({"));
+
+  Ok(())
+}
+
+#[test]
+fn local_revealjs_and_theme() -> Result<()> {
+  let content = r#"= Architecture
+:revealjsdir: vendor/reveal.js/
+:revealjs_theme: black
+
+== Slide"#;
+  let page = to_slides_with_args(content, &[])?;
+
+  assert!(page.contains("<link rel=\"stylesheet\" href=\"vendor/reveal.js/dist/theme/black.css\">"));
+  assert!(page.contains("<script src=\"vendor/reveal.js/plugin/notes/notes.js\"></script>"));
+
+  Ok(())
+}
+
+#[test]
+fn custom_stylesheet() -> Result<()> {
+  let path = std::env::temp_dir().join("asciidoctrine_slides_theme.css");
+  std::fs::write(&path, ".reveal { color: white; }")?;
+  let page = to_slides_with_args("== Slide", &["--stylesheet", path.to_str().unwrap()])?;
+
+  assert!(page.contains("<style>\n.reveal { color: white; }\n"));
+  // The theme of reveal.js stays
+  assert!(page.contains("/dist/theme/white.css"));
+
+  Ok(())
+}
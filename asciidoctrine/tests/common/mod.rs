@@ -0,0 +1,48 @@
+//! Helpers shared by the tests of the writers. Every test crate only
+//! uses some of them.
+#![allow(dead_code)]
+
+use anyhow::Result;
+use asciidoctrine::{self, *};
+use clap::Parser;
+use std::io::Cursor;
+
+/// Converts the asciidoc content with the writer of the backend. The
+/// arguments follow the backend on the command line and can enable
+/// the `sections` extension.
+pub fn convert<W>(writer: &mut W, backend: &str, content: &str, args: &[&str]) -> Result<Vec<u8>>
+where
+  W: for<'b> Writer<&'b mut Cursor<Vec<u8>>>,
+{
+  let reader = AsciidocReader::new();
+  let mut all_args = vec!["asciidoctrine", "-w", backend];
+  all_args.extend(args);
+  let opts = options::Opts::parse_from(all_args);
+  let mut env = util::Env::Cache(util::Cache::new());
+  let mut ast = reader.parse(content, &opts, &mut env)?;
+  if opts
+    .extensions
+    .iter()
+    .any(|extension| extension == "sections")
+  {
+    ast = asciidoctrine::sections::SectionTree::new().transform(ast)?;
+  }
+
+  let mut buf = Cursor::new(Vec::new());
+  writer.write(ast, &opts, &mut buf)?;
+
+  Ok(buf.into_inner())
+}
+
+/// Converts the asciidoc content to a text format
+pub fn convert_to_string<W>(
+  writer: &mut W,
+  backend: &str,
+  content: &str,
+  args: &[&str],
+) -> Result<String>
+where
+  W: for<'b> Writer<&'b mut Cursor<Vec<u8>>>,
+{
+  Ok(String::from_utf8(convert(writer, backend, content, args)?)?)
+}
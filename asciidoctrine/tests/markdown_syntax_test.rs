@@ -7,9 +7,10 @@ use pretty_assertions::assert_eq;
 #[test]
 fn parse_empty_document() -> Result<()> {
     let ast = AST {
-        content: "",
+        content: "".into(),
         elements: Vec::new(),
         attributes: Vec::new(),
+      header: DocumentHeader::default(),
     };
 
     let reader = MarkdownReader::new();
@@ -22,9 +23,10 @@ fn parse_empty_document() -> Result<()> {
 #[test]
 fn parse_whitespace_only() -> Result<()> {
     let ast = AST {
-        content: "  ",
+        content: "  ".into(),
         elements: Vec::new(),
         attributes: Vec::new(),
+      header: DocumentHeader::default(),
     };
 
     let reader = MarkdownReader::new();
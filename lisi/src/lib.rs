@@ -240,7 +240,7 @@ impl<'a> Lisi<'a> {
 
         let content = input
           .get_attribute("content")
-          .unwrap_or(input.content);
+          .unwrap_or(&input.content);
         let mut dependencies = Vec::new();
         for dependency in codeblock_parser::get_dependencies(content).iter() {
           dependencies.push(dependency.to_string());
@@ -275,7 +275,7 @@ impl<'a> Lisi<'a> {
         }
         let content = input
           .get_attribute("content")
-          .unwrap_or(input.content);
+          .unwrap_or(&input.content);
         snippets.store(
           id.to_string(),
           Snippet {